@@ -18,7 +18,7 @@ use trust_dns_proto::{BasicDnsHandle, DnsFuture, DnsHandle, DnsStreamHandle};
 
 use client::ClientStreamHandle;
 use error::*;
-use op::{Message, MessageType, OpCode, Query, UpdateMessage};
+use op::{Message, MessageFinalizer, MessageType, OpCode, Query, UpdateMessage};
 use rr::{Name, DNSClass, IntoRecordSet, RData, Record, RecordType};
 use rr::dnssec::Signer;
 use rr::rdata::NULL;
@@ -92,6 +92,40 @@ impl<S: Stream<Item = Vec<u8>, Error = io::Error> + 'static> ClientFuture<S> {
             message_sender: dns_future_handle,
         }
     }
+
+    /// Spawns a new ClientFuture Stream, with a finalizer for requests, e.g. a `TSigner` for TSIG
+    ///
+    /// The finalizer decides which requests are finalized, and verifies the responses to them.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - A stream of bytes that can be used to send/receive DNS messages
+    ///              (see TcpClientStream or UdpClientStream)
+    /// * `stream_handle` - The handle for the `stream` on which bytes can be sent/received.
+    /// * `loop_handle` - A Handle to the Tokio reactor Core, this is the Core on which the
+    ///                   the Stream will be spawned
+    /// * `timeout_duration` - All requests may fail due to lack of response, this is the time to
+    ///                        wait for a response before canceling the request.
+    /// * `finalizer` - The finalizer for requests
+    pub fn with_finalizer<MF: MessageFinalizer + 'static>(
+        stream: Box<Future<Item = S, Error = io::Error>>,
+        stream_handle: Box<DnsStreamHandle<Error = ClientError>>,
+        loop_handle: &Handle,
+        timeout_duration: Duration,
+        finalizer: Arc<MF>,
+    ) -> BasicClientHandle {
+        let dns_future_handle = DnsFuture::with_timeout(
+            stream,
+            stream_handle,
+            loop_handle,
+            timeout_duration,
+            Some(finalizer),
+        );
+
+        BasicClientHandle {
+            message_sender: dns_future_handle,
+        }
+    }
 }
 
 /// Root ClientHandle implementaton returned by ClientFuture
@@ -338,6 +338,11 @@ impl Signer {
         &self.key
     }
 
+    /// The DNSKEY or KEY of this signer, as it is published in the zone
+    pub fn key_rdata(&self) -> &RData {
+        &self.key_rdata
+    }
+
     /// Returns the duration that this signature is valid for
     pub fn sig_duration(&self) -> Duration {
         self.sig_duration
@@ -0,0 +1,569 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! tsigner is a structure for signing and verifying messages with TSIG, RFC 8945
+
+#[cfg(feature = "openssl")]
+use openssl::hash::MessageDigest;
+#[cfg(feature = "openssl")]
+use openssl::memcmp;
+#[cfg(feature = "openssl")]
+use openssl::pkey::PKey;
+#[cfg(feature = "openssl")]
+use openssl::sign::Signer as OpenSslSigner;
+#[cfg(all(not(feature = "openssl"), feature = "ring"))]
+use ring::{digest, hmac};
+use trust_dns_proto::error::{ProtoErrorKind, ProtoResult};
+use trust_dns_proto::rr::rdata::tsig::{self, TsigAlgorithm};
+
+use op::{Message, MessageFinalizer, ResponseCode};
+use rr::{Name, RData, Record};
+use rr::rdata::TSIG;
+
+/// The fudge recommended by RFC 8945, the seconds of error permitted in the time signed
+pub const DEFAULT_TSIG_FUDGE: u16 = 300;
+
+/// The most unsigned messages in a row which are accepted in a multi-message response, RFC 8945
+///  section 5.3.1, the next message must be signed
+const MAX_UNSIGNED_MESSAGES: u8 = 99;
+
+/// Use for signing and verifying messages with a secret shared between client and server, TSIG
+///
+/// [RFC 8945, Secret Key Transaction Authentication for DNS](https://tools.ietf.org/html/rfc8945)
+///
+/// As a `MessageFinalizer`, all requests are signed, and the responses to them verified. The
+///  messages of a multi-message response, e.g. AXFR, are verified as a chain, where the final
+///  message must be signed.
+#[derive(Clone)]
+pub struct TSigner {
+    key_name: Name,
+    algorithm: TsigAlgorithm,
+    key: Vec<u8>,
+    fudge: u16,
+}
+
+impl TSigner {
+    /// Constructs a new TSigner
+    ///
+    /// # Arguments
+    ///
+    /// * `key_name` - the name of the key, shared with the server
+    /// * `algorithm` - the HMAC algorithm, only HMAC-SHA256 and HMAC-SHA512 are supported
+    /// * `key` - the shared secret
+    /// * `fudge` - the seconds of error permitted in the time signed, see `DEFAULT_TSIG_FUDGE`
+    pub fn new(
+        key_name: Name,
+        algorithm: TsigAlgorithm,
+        key: Vec<u8>,
+        fudge: u16,
+    ) -> ProtoResult<Self> {
+        if let TsigAlgorithm::Unknown(ref name) = algorithm {
+            return Err(ProtoErrorKind::Msg(format!("unsupported TSIG algorithm: {}", name)).into());
+        }
+
+        Ok(TSigner {
+            key_name,
+            algorithm,
+            key,
+            fudge,
+        })
+    }
+
+    /// The name of the key
+    pub fn key_name(&self) -> &Name {
+        &self.key_name
+    }
+
+    /// The HMAC algorithm
+    pub fn algorithm(&self) -> &TsigAlgorithm {
+        &self.algorithm
+    }
+
+    /// The seconds of error permitted in the time signed
+    pub fn fudge(&self) -> u16 {
+        self.fudge
+    }
+
+    /// Computes the MAC of the data
+    #[cfg(feature = "openssl")]
+    pub fn sign(&self, tbs: &[u8]) -> ProtoResult<Vec<u8>> {
+        let digest = match self.algorithm {
+            TsigAlgorithm::HmacSha256 => MessageDigest::sha256(),
+            TsigAlgorithm::HmacSha512 => MessageDigest::sha512(),
+            TsigAlgorithm::Unknown(_) => {
+                return Err(ProtoErrorKind::Message("unsupported TSIG algorithm").into())
+            }
+        };
+
+        PKey::hmac(&self.key)
+            .and_then(|key| {
+                let mut signer = OpenSslSigner::new(digest, &key)?;
+                signer.update(tbs)?;
+                signer.sign_to_vec()
+            })
+            .map_err(|e| ProtoErrorKind::Msg(format!("could not compute HMAC: {}", e)).into())
+    }
+
+    /// Computes the MAC of the data
+    #[cfg(all(not(feature = "openssl"), feature = "ring"))]
+    pub fn sign(&self, tbs: &[u8]) -> ProtoResult<Vec<u8>> {
+        let key = self.ring_key()?;
+        Ok(hmac::sign(&key, tbs).as_ref().to_vec())
+    }
+
+    /// Computes the MAC of the data
+    #[cfg(not(any(feature = "openssl", feature = "ring")))]
+    pub fn sign(&self, _: &[u8]) -> ProtoResult<Vec<u8>> {
+        Err(ProtoErrorKind::Message("the ring or openssl feature must be enabled for signing").into())
+    }
+
+    /// Verifies the MAC of the data, in constant time
+    #[cfg(feature = "openssl")]
+    pub fn verify(&self, tbs: &[u8], mac: &[u8]) -> ProtoResult<()> {
+        let expected = self.sign(tbs)?;
+        if expected.len() == mac.len() && memcmp::eq(&expected, mac) {
+            Ok(())
+        } else {
+            Err(ProtoErrorKind::Message("the TSIG MAC did not verify").into())
+        }
+    }
+
+    /// Verifies the MAC of the data, in constant time
+    #[cfg(all(not(feature = "openssl"), feature = "ring"))]
+    pub fn verify(&self, tbs: &[u8], mac: &[u8]) -> ProtoResult<()> {
+        let key = self.ring_key()?;
+        hmac::verify_with_own_key(&key, tbs, mac)
+            .map_err(|_| ProtoErrorKind::Message("the TSIG MAC did not verify").into())
+    }
+
+    /// Verifies the MAC of the data, in constant time
+    #[cfg(not(any(feature = "openssl", feature = "ring")))]
+    pub fn verify(&self, _: &[u8], _: &[u8]) -> ProtoResult<()> {
+        Err(ProtoErrorKind::Message("the ring or openssl feature must be enabled for verifying").into())
+    }
+
+    #[cfg(all(not(feature = "openssl"), feature = "ring"))]
+    fn ring_key(&self) -> ProtoResult<hmac::SigningKey> {
+        let digest = match self.algorithm {
+            TsigAlgorithm::HmacSha256 => &digest::SHA256,
+            TsigAlgorithm::HmacSha512 => &digest::SHA512,
+            TsigAlgorithm::Unknown(_) => {
+                return Err(ProtoErrorKind::Message("unsupported TSIG algorithm").into())
+            }
+        };
+
+        Ok(hmac::SigningKey::new(digest, &self.key))
+    }
+
+    /// Returns the TSIG record for a message
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - the message to sign, in wire format without a TSIG
+    /// * `previous` - see `tsig::message_tbs`, empty for a request
+    /// * `first` - false for the subsequent messages of a response
+    /// * `error` - the extended response code of a response, or 0
+    /// * `current_time` - the time signed
+    pub fn sign_message(
+        &self,
+        message: &[u8],
+        previous: &[u8],
+        first: bool,
+        error: u16,
+        current_time: u64,
+    ) -> ProtoResult<Record> {
+        let pre_tsig = TSIG::new(
+            self.algorithm.clone(),
+            current_time,
+            self.fudge,
+            Vec::new(),
+            message_id(message)?,
+            error,
+            Vec::new(),
+        );
+
+        self.sign_tsig(message, previous, first, pre_tsig)
+    }
+
+    /// Returns the TSIG record for a `BADTIME` response, RFC 8945 section 5.2.3
+    ///
+    /// The time signed is that of the request, so that the client can verify the response, and
+    ///  the other data is the current time of the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - the response to sign, in wire format without a TSIG
+    /// * `request_tsig` - the TSIG of the request, which was signed with this key
+    /// * `current_time` - the current time of the server
+    pub fn sign_badtime(
+        &self,
+        message: &[u8],
+        request_tsig: &TSIG,
+        current_time: u64,
+    ) -> ProtoResult<Record> {
+        let other = (0..6)
+            .rev()
+            .map(|i| (current_time >> (8 * i)) as u8)
+            .collect();
+        let pre_tsig = TSIG::new(
+            self.algorithm.clone(),
+            request_tsig.time(),
+            self.fudge,
+            Vec::new(),
+            message_id(message)?,
+            ResponseCode::BADTIME.into(),
+            other,
+        );
+
+        let previous = tsig::prefixed_mac(request_tsig.mac());
+        self.sign_tsig(message, &previous, true, pre_tsig)
+    }
+
+    fn sign_tsig(
+        &self,
+        message: &[u8],
+        previous: &[u8],
+        first: bool,
+        mut pre_tsig: TSIG,
+    ) -> ProtoResult<Record> {
+        let tbs = tsig::message_tbs(previous, message, &self.key_name, &pre_tsig, first)?;
+        pre_tsig.set_mac(self.sign(&tbs)?);
+
+        Ok(pre_tsig.into_record(self.key_name.clone()))
+    }
+
+    /// Verifies the MAC of a signed message, this does not check the time signed
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - the message, without its TSIG, see `tsig::split_message`
+    /// * `previous` - see `tsig::message_tbs`, empty for a request
+    /// * `record` - the TSIG record of the message
+    /// * `first` - false for the subsequent messages of a response
+    ///
+    /// # Return
+    ///
+    /// The TSIG of the message
+    pub fn verify_message<'r>(
+        &self,
+        message: &[u8],
+        previous: &[u8],
+        record: &'r Record,
+        first: bool,
+    ) -> ProtoResult<&'r TSIG> {
+        let tsig = match *record.rdata() {
+            RData::TSIG(ref tsig) => tsig,
+            _ => return Err(ProtoErrorKind::Message("not a TSIG record").into()),
+        };
+
+        if *record.name() != self.key_name || *tsig.algorithm() != self.algorithm {
+            return Err(ProtoErrorKind::Message("the message is signed with another key").into());
+        }
+
+        let tbs = tsig::message_tbs(previous, message, &self.key_name, tsig, first)?;
+        self.verify(&tbs, tsig.mac())?;
+        Ok(tsig)
+    }
+}
+
+/// The id of a message in wire format, the original id of its TSIG
+fn message_id(message: &[u8]) -> ProtoResult<u16> {
+    if message.len() < 2 {
+        return Err(ProtoErrorKind::Message("message is too short to sign").into());
+    }
+
+    Ok((u16::from(message[0]) << 8) | u16::from(message[1]))
+}
+
+impl MessageFinalizer for TSigner {
+    fn finalize_message(&self, message: &Message, current_time: u32) -> ProtoResult<Vec<Record>> {
+        debug!("signing message with TSIG: {:?}", message);
+        let message = message.to_vec()?;
+        let tsig = self.sign_message(&message, &[], true, 0, u64::from(current_time))?;
+
+        Ok(vec![tsig])
+    }
+
+    /// All messages are signed with TSIG
+    fn should_finalize(&self, _: &Message) -> bool {
+        true
+    }
+
+    /// The state is the count of unsigned messages since the prior signed message, followed by the
+    ///  MAC of the request or of the prior signed message, prefixed by its length, followed by
+    ///  those unsigned messages.
+    fn verify_response(
+        &self,
+        request: &Message,
+        response: &[u8],
+        state: &mut Vec<u8>,
+        is_final: bool,
+        current_time: u32,
+    ) -> ProtoResult<()> {
+        let first = state.is_empty();
+        if first {
+            match request.tsig().map(Record::rdata) {
+                Some(&RData::TSIG(ref request_tsig)) => {
+                    state.push(0);
+                    state.extend_from_slice(&tsig::prefixed_mac(request_tsig.mac()));
+                }
+                _ => return Err(ProtoErrorKind::Message("the request is not signed").into()),
+            }
+        }
+
+        let (message, record) = match tsig::split_message(response)? {
+            Some(signed) => signed,
+            None if first || is_final => {
+                return Err(ProtoErrorKind::Message("the response is not signed").into())
+            }
+            None if state[0] >= MAX_UNSIGNED_MESSAGES => {
+                return Err(ProtoErrorKind::Message("too many unsigned messages in the response").into())
+            }
+            None => {
+                // the MAC of the next signed message covers this one
+                state[0] += 1;
+                state.extend_from_slice(response);
+                return Ok(());
+            }
+        };
+
+        if let RData::TSIG(ref tsig) = *record.rdata() {
+            if tsig.error() != 0 {
+                return Err(
+                    ProtoErrorKind::Msg(format!("the TSIG was rejected, error: {}", tsig.error()))
+                        .into(),
+                );
+            }
+        }
+
+        let mac = {
+            let tsig = self.verify_message(&message, &state[1..], &record, first)?;
+            if !tsig.is_current(u64::from(current_time)) {
+                return Err(ProtoErrorKind::Message("the TSIG is not current").into());
+            }
+
+            tsig.mac().to_vec()
+        };
+
+        state.truncate(1);
+        state[0] = 0;
+        state.extend_from_slice(&tsig::prefixed_mac(&mac));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "openssl")]
+mod tests {
+    use super::*;
+    use op::{MessageType, OpCode, Query};
+    use rr::RecordType;
+    use serialize::binary::{BinEncodable, BinEncoder};
+
+    const NOW: u32 = 1_500_000_000;
+
+    fn tsigner(key: &[u8]) -> TSigner {
+        TSigner::new(
+            Name::from_ascii("key.example.com.").unwrap(),
+            TsigAlgorithm::HmacSha256,
+            key.to_vec(),
+            DEFAULT_TSIG_FUDGE,
+        ).unwrap()
+    }
+
+    fn message(message_type: MessageType) -> Message {
+        let mut message = Message::new();
+        message
+            .set_id(10)
+            .set_message_type(message_type)
+            .set_op_code(OpCode::Query)
+            .add_query(Query::query(
+                Name::from_ascii("example.com.").unwrap(),
+                RecordType::AXFR,
+            ));
+        message
+    }
+
+    /// Appends the TSIG record to the unsigned message
+    fn signed(unsigned: &[u8], tsig: &Record) -> Vec<u8> {
+        let mut signed = unsigned.to_vec();
+        {
+            let mut encoder = BinEncoder::new(&mut signed);
+            tsig.emit(&mut encoder).unwrap();
+        }
+        signed[11] += 1;
+        signed
+    }
+
+    fn mac(tsig: &Record) -> Vec<u8> {
+        match *tsig.rdata() {
+            RData::TSIG(ref tsig) => tsig.mac().to_vec(),
+            _ => panic!("not a TSIG"),
+        }
+    }
+
+    fn signed_request(signer: &TSigner) -> Message {
+        let mut request = message(MessageType::Query);
+        request.finalize(signer, NOW).unwrap();
+        request
+    }
+
+    #[test]
+    fn test_sign_and_verify_request() {
+        let signer = tsigner(b"a secret");
+        let request = signed_request(&signer);
+        assert!(request.tsig().is_some());
+
+        let (unsigned, record) = tsig::split_message(&request.to_vec().unwrap())
+            .unwrap()
+            .expect("request is signed");
+        assert!(signer.verify_message(&unsigned, &[], &record, true).is_ok());
+        assert!(
+            tsigner(b"another secret")
+                .verify_message(&unsigned, &[], &record, true)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_verify_response() {
+        let signer = tsigner(b"a secret");
+        let request = signed_request(&signer);
+        let request_mac = tsig::prefixed_mac(&mac(request.tsig().unwrap()));
+
+        let unsigned = message(MessageType::Response).to_vec().unwrap();
+        let tsig = signer
+            .sign_message(&unsigned, &request_mac, true, 0, u64::from(NOW))
+            .unwrap();
+        let response = signed(&unsigned, &tsig);
+
+        assert!(
+            signer
+                .verify_response(&request, &response, &mut Vec::new(), true, NOW + 10)
+                .is_ok()
+        );
+
+        // outside of the fudge
+        assert!(
+            signer
+                .verify_response(&request, &response, &mut Vec::new(), true, NOW + 1000)
+                .is_err()
+        );
+
+        // the MAC must cover the MAC of the request
+        let tsig = signer
+            .sign_message(&unsigned, &[], true, 0, u64::from(NOW))
+            .unwrap();
+        assert!(
+            signer
+                .verify_response(&request, &signed(&unsigned, &tsig), &mut Vec::new(), true, NOW)
+                .is_err()
+        );
+
+        // and responses must be signed
+        assert!(
+            signer
+                .verify_response(&request, &unsigned, &mut Vec::new(), true, NOW)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_sign_badtime() {
+        let signer = tsigner(b"a secret");
+        let mut request = message(MessageType::Query);
+        request.finalize(&signer, NOW - 3600).unwrap();
+        let request_tsig = match request.tsig().map(Record::rdata) {
+            Some(&RData::TSIG(ref tsig)) => tsig.clone(),
+            _ => panic!("the request is not signed"),
+        };
+
+        let unsigned = message(MessageType::Response).to_vec().unwrap();
+        let record = signer
+            .sign_badtime(&unsigned, &request_tsig, u64::from(NOW))
+            .unwrap();
+
+        let request_mac = tsig::prefixed_mac(request_tsig.mac());
+        let tsig = signer
+            .verify_message(&unsigned, &request_mac, &record, true)
+            .expect("the response did not verify");
+        assert_eq!(tsig.time(), u64::from(NOW - 3600));
+        assert_eq!(tsig.error(), u16::from(ResponseCode::BADTIME));
+        assert_eq!(tsig.other(), &[0, 0, 0x59, 0x68, 0x2F, 0x00]);
+    }
+
+    #[test]
+    fn test_verify_multi_message_response() {
+        let signer = tsigner(b"a secret");
+        let request = signed_request(&signer);
+        let request_mac = tsig::prefixed_mac(&mac(request.tsig().unwrap()));
+
+        let unsigned = message(MessageType::Response).to_vec().unwrap();
+        let first_tsig = signer
+            .sign_message(&unsigned, &request_mac, true, 0, u64::from(NOW))
+            .unwrap();
+        let first = signed(&unsigned, &first_tsig);
+
+        // the last message covers the MAC of the first, and the unsigned message in between
+        let mut previous = tsig::prefixed_mac(&mac(&first_tsig));
+        previous.extend_from_slice(&unsigned);
+        let last_tsig = signer
+            .sign_message(&unsigned, &previous, false, 0, u64::from(NOW))
+            .unwrap();
+        let last = signed(&unsigned, &last_tsig);
+
+        let mut state = Vec::new();
+        for &(message, is_final) in &[(&first, false), (&unsigned, false), (&last, true)] {
+            signer
+                .verify_response(&request, message, &mut state, is_final, NOW)
+                .expect("response did not verify");
+        }
+
+        // the final message must be signed
+        let mut state = Vec::new();
+        for &(message, is_final) in &[(&first, false), (&unsigned, false)] {
+            signer
+                .verify_response(&request, message, &mut state, is_final, NOW)
+                .expect("response did not verify");
+        }
+        assert!(
+            signer
+                .verify_response(&request, &unsigned, &mut state, true, NOW)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_verify_too_many_unsigned_messages() {
+        let signer = tsigner(b"a secret");
+        let request = signed_request(&signer);
+        let request_mac = tsig::prefixed_mac(&mac(request.tsig().unwrap()));
+
+        let unsigned = message(MessageType::Response).to_vec().unwrap();
+        let first_tsig = signer
+            .sign_message(&unsigned, &request_mac, true, 0, u64::from(NOW))
+            .unwrap();
+        let first = signed(&unsigned, &first_tsig);
+
+        let mut state = Vec::new();
+        signer
+            .verify_response(&request, &first, &mut state, false, NOW)
+            .expect("response did not verify");
+
+        // 99 unsigned messages are accepted, the 100th must be signed
+        for _ in 0..99 {
+            signer
+                .verify_response(&request, &unsigned, &mut state, false, NOW)
+                .expect("response did not verify");
+        }
+        assert!(
+            signer
+                .verify_response(&request, &unsigned, &mut state, false, NOW)
+                .is_err()
+        );
+    }
+}
@@ -18,6 +18,7 @@ use std::str::FromStr;
 
 use error::*;
 use rr::{DNSClass, IntoRecordSet, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey};
+use rr::rdata::{DNSSECRData, DNSSECRecordType};
 use serialize::txt::master_lex::{Lexer, Token};
 use serialize::txt::parse_rdata::RDataParser;
 
@@ -142,6 +143,7 @@ impl Parser {
     ) -> ParseResult<(Name, BTreeMap<RrKey, RecordSet>)> {
         let mut lexer = lexer;
         let mut records: BTreeMap<RrKey, RecordSet> = BTreeMap::new();
+        let mut rrsigs: Vec<Record> = Vec::new();
 
         let mut origin: Option<Name> = origin;
         let mut current_name: Option<Name> = None;
@@ -204,9 +206,9 @@ impl Parser {
                         // Token::Number(ref num) => ttl = Some(*num),
                         // One of Class or Type (these cannot be overlapping!)
                         Token::CharData(data) => {
-                            // if it's a number it's a ttl
+                            // if it's a number it's a ttl, e.g. DS would otherwise be read as 0 days 0 seconds
                             let result: ParseResult<u32> = Self::parse_time(&data);
-                            if result.is_ok() {
+                            if result.is_ok() && data.starts_with(|c: char| c.is_digit(10)) {
                                 ttl = result.ok();
                                 State::TtlClassType // hm, should this go to just ClassType?
                             } else {
@@ -312,6 +314,8 @@ impl Parser {
                                         );
                                     }
                                 }
+                                // the signatures of a zone signed offline, these belong to the RecordSet they cover
+                                RecordType::DNSSEC(DNSSECRecordType::RRSIG) => rrsigs.push(record),
                                 _ => {
                                     // add a Vec if it's not there, then add the record to the list
                                     let set = records.entry(key).or_insert_with(
@@ -340,6 +344,19 @@ impl Parser {
             }
         }
 
+        for rrsig in rrsigs {
+            let type_covered = match *rrsig.rdata() {
+                RData::DNSSEC(DNSSECRData::SIG(ref sig)) => sig.type_covered(),
+                _ => panic!("expected RRSIG: {:?}", rrsig), // valid panic, only RRSIGs are collected
+            };
+
+            let key = RrKey::new(LowerName::new(rrsig.name()), type_covered);
+            records
+                .entry(key)
+                .or_insert_with(|| RecordSet::new(rrsig.name(), type_covered, 0))
+                .insert_rrsig(rrsig);
+        }
+
         //
         // build the Authority and return.
         let origin = origin.ok_or_else(|| {
@@ -18,7 +18,7 @@
 
 use error::*;
 use rr::{Name, RData, RecordType};
-use rr::rdata::DNSSECRecordType;
+use rr::rdata::{DNSSECRData, DNSSECRecordType};
 use serialize::txt::rdata_parsers::*;
 
 pub trait RDataParser: Sized {
@@ -52,29 +52,37 @@ impl RDataParser for RData {
             RecordType::SOA => RData::SOA(soa::parse(tokens, origin)?),
             RecordType::SRV => RData::SRV(srv::parse(tokens, origin)?),
             RecordType::TLSA => RData::TLSA(tlsa::parse(tokens)?),
+            RecordType::TSIG => panic!("parsing TSIG doesn't make sense"), // valid panic, never should happen
             RecordType::TXT => RData::TXT(txt::parse(tokens)?),
             RecordType::DNSSEC(DNSSECRecordType::SIG) => panic!("parsing SIG doesn't make sense"), // valid panic, never should happen
+            RecordType::DNSSEC(DNSSECRecordType::CDNSKEY) => {
+                RData::DNSSEC(DNSSECRData::CDNSKEY(dnskey::parse(tokens)?))
+            }
+            RecordType::DNSSEC(DNSSECRecordType::CDS) => {
+                RData::DNSSEC(DNSSECRData::CDS(ds::parse(tokens)?))
+            }
             RecordType::DNSSEC(DNSSECRecordType::DNSKEY) => {
-                panic!("DNSKEY should be dynamically generated")
-            } // valid panic, never should happen
+                RData::DNSSEC(DNSSECRData::DNSKEY(dnskey::parse(tokens)?))
+            }
             RecordType::DNSSEC(DNSSECRecordType::KEY) => {
                 panic!("KEY should be dynamically generated")
             } // valid panic, never should happen
             RecordType::DNSSEC(DNSSECRecordType::DS) => {
-                panic!("DS should be dynamically generated")
-            } // valid panic, never should happen
+                RData::DNSSEC(DNSSECRData::DS(ds::parse(tokens)?))
+            }
             RecordType::DNSSEC(DNSSECRecordType::NSEC) => {
-                panic!("NSEC should be dynamically generated")
-            } // valid panic, never should happen
+                RData::DNSSEC(DNSSECRData::NSEC(nsec::parse(tokens, origin)?))
+            }
             RecordType::DNSSEC(DNSSECRecordType::NSEC3) => {
-                panic!("NSEC3 should be dynamically generated")
-            } // valid panic, never should happen
+                RData::DNSSEC(DNSSECRData::NSEC3(nsec3::parse(tokens)?))
+            }
             RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM) => {
-                panic!("NSEC3PARAM should be dynamically generated")
-            } // valid panic, never should happen
+                RData::DNSSEC(DNSSECRData::NSEC3PARAM(nsec3param::parse(tokens)?))
+            }
+            // zones signed offline carry their signatures
             RecordType::DNSSEC(DNSSECRecordType::RRSIG) => {
-                panic!("RRSIG should be dynamically generated")
-            } // valid panic, never should happen
+                RData::DNSSEC(DNSSECRData::SIG(sig::parse(tokens, origin)?))
+            }
             RecordType::DNSSEC(DNSSECRecordType::Unknown(code)) => {
                 panic!("Unknown dnssec record type, if you want to support this type, please file an issue against TRust-DNS: {}", code)
             } // valid panic, never should happen
@@ -0,0 +1,89 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! delegation signer record, the digest of a DNSKEY of the child zone
+
+use std::str::FromStr;
+
+use data_encoding::HEXLOWER_PERMISSIVE;
+
+use error::*;
+use rr::dnssec::{Algorithm, DigestType};
+use rr::rdata::DS;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034, DNSSEC Resource Records, March 2005](https://tools.ietf.org/html/rfc4034#section-5.3)
+///
+/// ```text
+/// 5.3.  The DS RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Key Tag field MUST be represented as an unsigned decimal integer.
+///
+///    The Algorithm field MUST be represented either as an unsigned decimal
+///    integer or as an algorithm mnemonic specified in Appendix A.1.
+///
+///    The Digest Type field MUST be represented as an unsigned decimal
+///    integer.
+///
+///    The Digest MUST be represented as a sequence of case-insensitive
+///    hexadecimal digits.  Whitespace is allowed within the hexadecimal
+///    text.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<DS> {
+    let key_tag: u16 = tokens
+        .next()
+        .ok_or_else(|| ParseErrorKind::Message("DS key tag field missing"))?
+        .parse()?;
+
+    // the number or the mnemonic of the algorithm
+    let algorithm = tokens
+        .next()
+        .ok_or_else(|| ParseErrorKind::Message("DS algorithm field missing"))?;
+    let algorithm = Algorithm::from_str(algorithm)?;
+
+    let digest_type: u8 = tokens
+        .next()
+        .ok_or_else(|| ParseErrorKind::Message("DS digest type field missing"))?
+        .parse()?;
+    let digest_type = DigestType::from_u8(digest_type)?;
+
+    let digest = tokens.collect::<String>();
+    if digest.is_empty() {
+        return Err(ParseErrorKind::Message("DS digest field missing").into());
+    }
+    let digest = HEXLOWER_PERMISSIVE.decode(digest.as_bytes())?;
+
+    Ok(DS::new(key_tag, algorithm, digest_type, digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing() {
+        let ds = parse(
+            vec![
+                "60485",
+                "5",
+                "1",
+                "2BB183AF5F22588179A53B0A",
+                "98631FAD1A292118",
+            ].into_iter(),
+        ).expect("failed to parse DS");
+
+        assert_eq!(ds.key_tag(), 60485);
+        assert_eq!(ds.algorithm(), Algorithm::RSASHA1);
+        assert_eq!(ds.digest_type(), DigestType::SHA1);
+        assert_eq!(ds.digest().len(), 20);
+
+        assert!(parse(vec!["60485", "5", "1"].into_iter()).is_err());
+    }
+}
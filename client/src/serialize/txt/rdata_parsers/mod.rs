@@ -22,9 +22,15 @@
 pub mod a;
 pub mod aaaa;
 pub mod caa;
+pub mod dnskey;
+pub mod ds;
 pub mod mx;
 pub mod name;
+pub mod nsec;
+pub mod nsec3;
+pub mod nsec3param;
 pub mod null;
+pub mod sig;
 pub mod soa;
 pub mod srv;
 pub mod tlsa;
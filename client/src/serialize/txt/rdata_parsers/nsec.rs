@@ -0,0 +1,92 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! authenticated denial of existence, the next name in the zone and the types at this one
+
+use std::str::FromStr;
+
+use error::*;
+use rr::{Name, RecordType};
+use rr::rdata::NSEC;
+use serialize::txt::rdata_parsers::name;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034, DNSSEC Resource Records, March 2005](https://tools.ietf.org/html/rfc4034#section-4.2)
+///
+/// ```text
+/// 4.2.  The NSEC RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Next Domain field is represented as a domain name.
+///
+///    The Type Bit Maps field is represented as a sequence of RR type
+///    mnemonics.  When the mnemonic is not known, the TYPE representation
+///    as described in [RFC3597], Section 5, MUST be used.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(
+    mut tokens: I,
+    origin: Option<&Name>,
+) -> ParseResult<NSEC> {
+    let next_domain_name = tokens
+        .next()
+        .ok_or_else(|| ParseErrorKind::Message("NSEC next domain name field missing"))?;
+    let next_domain_name = name::parse(Some(next_domain_name).into_iter(), origin)?;
+
+    let type_bit_maps = parse_type_bit_maps(tokens)?;
+
+    Ok(NSEC::new(next_domain_name, type_bit_maps))
+}
+
+/// The mnemonics of the types in the bit maps of the NSEC and NSEC3 records
+pub fn parse_type_bit_maps<'i, I: Iterator<Item = &'i str>>(
+    tokens: I,
+) -> ParseResult<Vec<RecordType>> {
+    let mut type_bit_maps = Vec::new();
+    for token in tokens {
+        type_bit_maps.push(RecordType::from_str(token)?);
+    }
+
+    Ok(type_bit_maps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rr::rdata::DNSSECRecordType;
+
+    #[test]
+    fn test_parsing() {
+        let origin = Name::from_str("example.com.").unwrap();
+        let nsec = parse(
+            vec!["host.example.com.", "A", "MX", "RRSIG", "NSEC"].into_iter(),
+            Some(&origin),
+        ).expect("failed to parse NSEC");
+
+        assert_eq!(
+            nsec.next_domain_name(),
+            &Name::from_str("host.example.com.").unwrap()
+        );
+        assert_eq!(
+            nsec.type_bit_maps(),
+            &[
+                RecordType::A,
+                RecordType::MX,
+                RecordType::DNSSEC(DNSSECRecordType::RRSIG),
+                RecordType::DNSSEC(DNSSECRecordType::NSEC),
+            ]
+        );
+
+        // relative to the origin
+        let nsec = parse(vec!["www", "A"].into_iter(), Some(&origin)).unwrap();
+        assert_eq!(
+            nsec.next_domain_name(),
+            &Name::from_str("www.example.com.").unwrap()
+        );
+    }
+}
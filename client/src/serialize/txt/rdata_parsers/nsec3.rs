@@ -0,0 +1,114 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! hashed authenticated denial of existence
+
+use data_encoding::BASE32_DNSSEC;
+
+use error::*;
+use rr::rdata::NSEC3;
+use serialize::txt::rdata_parsers::nsec::parse_type_bit_maps;
+use serialize::txt::rdata_parsers::nsec3param::parse_parameters;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 5155, NSEC3, March 2008](https://tools.ietf.org/html/rfc5155#section-3.3)
+///
+/// ```text
+/// 3.3.  Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    o  The Hash Algorithm field is represented as an unsigned decimal
+///       integer.  The value has a maximum of 255.
+///
+///    o  The Flags field is represented as an unsigned decimal integer.
+///       The value has a maximum of 255.
+///
+///    o  The Iterations field is represented as an unsigned decimal
+///       integer.  The value is between 0 and 65535, inclusive.
+///
+///    o  The Salt Length field is not represented.
+///
+///    o  The Salt field is represented as a sequence of case-insensitive
+///       hexadecimal digits.  Whitespace is not allowed within the
+///       sequence.  The Salt field is represented as "-" (without the
+///       quotes) when the Salt Length field has a value of 0.
+///
+///    o  The Hash Length field is not represented.
+///
+///    o  The Next Hashed Owner Name field is represented as an unpadded
+///       sequence of case-insensitive base32 digits, without whitespace.
+///
+///    o  The Type Bit Maps field is represented as a sequence of RR type
+///       mnemonics.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<NSEC3> {
+    let (hash_algorithm, opt_out, iterations, salt) = parse_parameters(&mut tokens, "NSEC3")?;
+
+    let next_hashed_owner_name = tokens.next().ok_or_else(|| {
+        ParseErrorKind::Message("NSEC3 next hashed owner name field missing")
+    })?;
+    let next_hashed_owner_name =
+        BASE32_DNSSEC.decode(next_hashed_owner_name.to_lowercase().as_bytes())?;
+
+    let type_bit_maps = parse_type_bit_maps(tokens)?;
+
+    Ok(NSEC3::new(
+        hash_algorithm,
+        opt_out,
+        iterations,
+        salt,
+        next_hashed_owner_name,
+        type_bit_maps,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rr::RecordType;
+    use rr::dnssec::Nsec3HashAlgorithm;
+    use rr::rdata::DNSSECRecordType;
+
+    #[test]
+    fn test_parsing() {
+        let nsec3 = parse(
+            vec![
+                "1",
+                "1",
+                "12",
+                "aabbccdd",
+                "2t7b4g4vsa5smi47k61mv5bv1a22bojr",
+                "MX",
+                "DNSKEY",
+                "NS",
+                "SOA",
+                "NSEC3PARAM",
+                "RRSIG",
+            ].into_iter(),
+        ).expect("failed to parse NSEC3");
+
+        assert_eq!(nsec3.hash_algorithm(), Nsec3HashAlgorithm::SHA1);
+        assert!(nsec3.opt_out());
+        assert_eq!(nsec3.iterations(), 12);
+        assert_eq!(nsec3.salt(), &[0xAA, 0xBB, 0xCC, 0xDD]);
+        assert_eq!(nsec3.next_hashed_owner_name().len(), 20);
+        assert_eq!(nsec3.type_bit_maps().len(), 6);
+        assert!(
+            nsec3
+                .type_bit_maps()
+                .contains(&RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM))
+        );
+
+        // upper case is also accepted
+        assert!(
+            parse(vec!["1", "0", "0", "-", "2T7B4G4VSA5SMI47K61MV5BV1A22BOJR", "A"].into_iter())
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! parameters of the NSEC3 chain of a zone
+
+use data_encoding::HEXLOWER_PERMISSIVE;
+
+use error::*;
+use rr::dnssec::Nsec3HashAlgorithm;
+use rr::rdata::NSEC3PARAM;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 5155, NSEC3, March 2008](https://tools.ietf.org/html/rfc5155#section-4.3)
+///
+/// ```text
+/// 4.3.  Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    o  The Hash Algorithm field is represented as an unsigned decimal
+///       integer.  The value has a maximum of 255.
+///
+///    o  The Flags field is represented as an unsigned decimal integer.
+///       The value has a maximum value of 255.
+///
+///    o  The Iterations field is represented as an unsigned decimal
+///       integer.  The value is between 0 and 65535, inclusive.
+///
+///    o  The Salt Length field is not represented.
+///
+///    o  The Salt field is represented as a sequence of case-insensitive
+///       hexadecimal digits.  Whitespace is not allowed within the
+///       sequence.  This field is represented as "-" (without the quotes)
+///       when the Salt Length field is zero.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<NSEC3PARAM> {
+    let (hash_algorithm, opt_out, iterations, salt) = parse_parameters(&mut tokens, "NSEC3PARAM")?;
+
+    Ok(NSEC3PARAM::new(hash_algorithm, opt_out, iterations, salt))
+}
+
+/// The hash algorithm, Opt-Out flag, iterations and salt, which begin both NSEC3 and NSEC3PARAM
+pub fn parse_parameters<'i, I: Iterator<Item = &'i str>>(
+    tokens: &mut I,
+    record_type: &'static str,
+) -> ParseResult<(Nsec3HashAlgorithm, bool, u16, Vec<u8>)> {
+    let missing = |field: &str| ParseErrorKind::MissingToken(format!("{} {}", record_type, field));
+
+    let hash_algorithm: u8 = tokens
+        .next()
+        .ok_or_else(|| missing("hash algorithm"))?
+        .parse()?;
+    let hash_algorithm = Nsec3HashAlgorithm::from_u8(hash_algorithm)?;
+
+    let flags: u8 = tokens.next().ok_or_else(|| missing("flags"))?.parse()?;
+
+    let iterations: u16 = tokens.next().ok_or_else(|| missing("iterations"))?.parse()?;
+
+    let salt = match tokens.next().ok_or_else(|| missing("salt"))? {
+        "-" => vec![],
+        salt => HEXLOWER_PERMISSIVE.decode(salt.as_bytes())?,
+    };
+
+    Ok((hash_algorithm, flags & 0b0000_0001 != 0, iterations, salt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing() {
+        let nsec3param = parse(vec!["1", "0", "12", "AABBCCDD"].into_iter())
+            .expect("failed to parse NSEC3PARAM");
+
+        assert_eq!(nsec3param.hash_algorithm(), Nsec3HashAlgorithm::SHA1);
+        assert!(!nsec3param.opt_out());
+        assert_eq!(nsec3param.iterations(), 12);
+        assert_eq!(nsec3param.salt(), &[0xAA, 0xBB, 0xCC, 0xDD]);
+
+        let nsec3param = parse(vec!["1", "1", "0", "-"].into_iter()).unwrap();
+        assert!(nsec3param.opt_out());
+        assert!(nsec3param.salt().is_empty());
+
+        assert!(parse(vec!["1", "0", "12"].into_iter()).is_err());
+    }
+}
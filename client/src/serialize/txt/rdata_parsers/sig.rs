@@ -0,0 +1,197 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! signature record, the RRSIG over a record set of the zone
+
+use std::str::FromStr;
+
+use chrono::{TimeZone, Utc};
+use data_encoding::BASE64;
+
+use error::*;
+use rr::{Name, RecordType};
+use rr::dnssec::Algorithm;
+use rr::rdata::SIG;
+use serialize::txt::rdata_parsers::name;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034, DNSSEC Resource Records, March 2005](https://tools.ietf.org/html/rfc4034#section-3.2)
+///
+/// ```text
+/// 3.2.  The RRSIG RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Type Covered field is represented as an RR type mnemonic.
+///
+///    The Algorithm field value MUST be represented either as an unsigned
+///    decimal integer or as an algorithm mnemonic, as specified in Appendix
+///    A.1.
+///
+///    The Labels field value MUST be represented as an unsigned decimal
+///    integer.
+///
+///    The Original TTL field value MUST be represented as an unsigned
+///    decimal integer.
+///
+///    The Signature Expiration Time and Inception Time field values MUST be
+///    represented either as an unsigned decimal integer indicating seconds
+///    since 1 January 1970 00:00:00 UTC, or in the form YYYYMMDDHHmmSS in
+///    UTC, where:
+///
+///       YYYY is the year (0001-9999, but see Section 3.1.5);
+///       MM is the month number (01-12);
+///       DD is the day of the month (01-31);
+///       HH is the hour, in 24 hour notation (00-23);
+///       mm is the minute (00-59); and
+///       SS is the second (00-59).
+///
+///    Note that it is always possible to distinguish between these two
+///    formats because the YYYYMMDDHHmmSS format will always be exactly 14
+///    digits, while the decimal representation of a 32-bit unsigned integer
+///    can never be longer than 10 digits.
+///
+///    The Key Tag field MUST be represented as an unsigned decimal integer.
+///
+///    The Signer's Name field value MUST be represented as a domain name.
+///
+///    The Signature field is represented as a Base64 encoding of the
+///    signature.  Whitespace is allowed within the Base64 text.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(
+    mut tokens: I,
+    origin: Option<&Name>,
+) -> ParseResult<SIG> {
+    let type_covered = tokens
+        .next()
+        .ok_or_else(|| ParseErrorKind::Message("RRSIG type covered field missing"))?;
+    let type_covered = RecordType::from_str(type_covered)?;
+
+    // the number or the mnemonic of the algorithm
+    let algorithm = tokens
+        .next()
+        .ok_or_else(|| ParseErrorKind::Message("RRSIG algorithm field missing"))?;
+    let algorithm = Algorithm::from_str(algorithm)?;
+
+    let num_labels: u8 = tokens
+        .next()
+        .ok_or_else(|| ParseErrorKind::Message("RRSIG labels field missing"))?
+        .parse()?;
+
+    let original_ttl: u32 = tokens
+        .next()
+        .ok_or_else(|| ParseErrorKind::Message("RRSIG original ttl field missing"))?
+        .parse()?;
+
+    let sig_expiration = parse_time(
+        tokens
+            .next()
+            .ok_or_else(|| ParseErrorKind::Message("RRSIG expiration field missing"))?,
+    )?;
+
+    let sig_inception = parse_time(
+        tokens
+            .next()
+            .ok_or_else(|| ParseErrorKind::Message("RRSIG inception field missing"))?,
+    )?;
+
+    let key_tag: u16 = tokens
+        .next()
+        .ok_or_else(|| ParseErrorKind::Message("RRSIG key tag field missing"))?
+        .parse()?;
+
+    let signer_name = tokens
+        .next()
+        .ok_or_else(|| ParseErrorKind::Message("RRSIG signer's name field missing"))?;
+    let signer_name = name::parse(Some(signer_name).into_iter(), origin)?;
+
+    let sig = tokens.collect::<String>();
+    if sig.is_empty() {
+        return Err(ParseErrorKind::Message("RRSIG signature field missing").into());
+    }
+    let sig = BASE64.decode(sig.as_bytes())?;
+
+    Ok(SIG::new(
+        type_covered,
+        algorithm,
+        num_labels,
+        original_ttl,
+        sig_expiration,
+        sig_inception,
+        key_tag,
+        signer_name,
+        sig,
+    ))
+}
+
+/// Either YYYYMMDDHHmmSS in UTC, or the seconds since the epoch
+fn parse_time(token: &str) -> ParseResult<u32> {
+    if token.len() != 14 {
+        return token.parse().map_err(ParseError::from);
+    }
+
+    Utc.datetime_from_str(token, "%Y%m%d%H%M%S")
+        .map(|time| time.timestamp() as u32)
+        .map_err(|_| ParseErrorKind::Message("RRSIG time is not in the form YYYYMMDDHHmmSS").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rr::rdata::DNSSECRecordType;
+
+    #[test]
+    fn test_parsing() {
+        let origin = Name::from_str("example.com.").unwrap();
+        let sig = parse(
+            vec![
+                "A",
+                "5",
+                "3",
+                "86400",
+                "20030322173103",
+                "1045762263",
+                "2642",
+                "example.com.",
+                "oJB1W6WNGv+ldvQ3WDG0MQkg5IEhjRip8WTr",
+                "PYGv07h108dUKGMeDPKijVCHX3DDKdfb+v6o",
+            ].into_iter(),
+            Some(&origin),
+        ).expect("failed to parse RRSIG");
+
+        assert_eq!(sig.type_covered(), RecordType::A);
+        assert_eq!(sig.algorithm(), Algorithm::RSASHA1);
+        assert_eq!(sig.num_labels(), 3);
+        assert_eq!(sig.original_ttl(), 86400);
+        assert_eq!(sig.sig_expiration(), 1048354263);
+        assert_eq!(sig.sig_inception(), 1045762263);
+        assert_eq!(sig.key_tag(), 2642);
+        assert_eq!(sig.signer_name(), &origin);
+        assert_eq!(sig.sig().len(), 54);
+
+        let sig = parse(
+            vec![
+                "NSEC3PARAM",
+                "8",
+                "2",
+                "3600",
+                "1048354263",
+                "1045762263",
+                "2642",
+                "example.com.",
+                "AwEAAQ==",
+            ].into_iter(),
+            Some(&origin),
+        ).expect("failed to parse RRSIG");
+        assert_eq!(
+            sig.type_covered(),
+            RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM)
+        );
+        assert_eq!(sig.sig_expiration(), 1048354263);
+    }
+}
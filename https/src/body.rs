@@ -0,0 +1,60 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The bodies of requests and responses, which are DNS messages
+
+use std::mem;
+
+use futures::{Async, Future, Poll, Stream};
+use h2::RecvStream;
+use http::StatusCode;
+
+use error::{self, HttpsError};
+use request::MAX_MESSAGE_LEN;
+
+/// Returns a future of the DNS message in the body
+pub(crate) fn read(body: RecvStream) -> ReadBody {
+    ReadBody {
+        body,
+        message: Vec::with_capacity(512),
+    }
+}
+
+/// Reads the DNS message in the body of a POST request or a response
+pub(crate) struct ReadBody {
+    body: RecvStream,
+    message: Vec<u8>,
+}
+
+impl Future for ReadBody {
+    type Item = Vec<u8>;
+    type Error = HttpsError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            match try_ready!(self.body.poll()) {
+                Some(bytes) => {
+                    // the data is buffered here, let the peer send more
+                    self.body.release_capacity().release_capacity(bytes.len())?;
+
+                    if self.message.len() + bytes.len() > MAX_MESSAGE_LEN {
+                        return Err(error::status(
+                            StatusCode::PAYLOAD_TOO_LARGE,
+                            format!("body exceeds {}", MAX_MESSAGE_LEN),
+                        ));
+                    }
+
+                    self.message.extend_from_slice(&bytes);
+                }
+                None if self.message.is_empty() => {
+                    return Err(error::status(StatusCode::BAD_REQUEST, "the body is empty"));
+                }
+                None => return Ok(Async::Ready(mem::replace(&mut self.message, Vec::new()))),
+            }
+        }
+    }
+}
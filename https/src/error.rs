@@ -0,0 +1,52 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Errors of DNS over HTTPS
+
+#![allow(missing_docs)]
+
+use http::StatusCode;
+
+error_chain! {
+    types {
+        HttpsError, HttpsErrorKind, HttpsChainErr, HttpsResult;
+    }
+
+    links {
+      ::trust_dns_proto::error::ProtoError, ::trust_dns_proto::error::ProtoErrorKind, Proto;
+    }
+
+    foreign_links {
+      ::std::io::Error, Io, "io error";
+      ::h2::Error, H2, "http/2 error";
+      ::http::Error, Http, "http error";
+      ::data_encoding::DecodeError, Decode, "base64url decode error";
+    }
+
+    errors {
+      Status(status: StatusCode, msg: String) {
+        description("request rejected with an http status")
+        display("{}: {}", status, msg)
+      }
+    }
+}
+
+impl HttpsError {
+    /// The status of the HTTP response to a request which failed with this error
+    pub fn status(&self) -> StatusCode {
+        match *self.kind() {
+            HttpsErrorKind::Status(status, _) => status,
+            HttpsErrorKind::Decode(..) | HttpsErrorKind::Proto(..) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Returns an error for a request which is answered with the status
+pub fn status<S: Into<String>>(status: StatusCode, msg: S) -> HttpsError {
+    HttpsErrorKind::Status(status, msg.into()).into()
+}
@@ -0,0 +1,230 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DNS over HTTPS client side, an HTTP/2 connection to the DNS query endpoint of a server
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::{future, Async, Future, Poll, Stream};
+use futures::stream::{Fuse, FuturesUnordered};
+use futures::sync::mpsc::{unbounded, UnboundedReceiver};
+use h2;
+use h2::client::SendRequest;
+use rustls::{Certificate, ClientConfig};
+use tokio_core::net::TcpStream as TokioTcpStream;
+use tokio_core::reactor::Handle;
+use tokio_rustls::ClientConfigExt;
+use trust_dns_proto::{BufDnsStreamHandle, BufStreamHandle, DnsStreamHandle};
+use trust_dns_proto::error::FromProtoError;
+
+use body;
+use error::HttpsError;
+use request;
+use response;
+use ALPN_H2;
+
+/// A DNS over HTTPS client stream, each message is sent in a POST request on its own HTTP/2
+///  stream of the connection, and responses are returned as they arrive
+///
+/// Use with `trust_dns::client::DnsFuture` impls, see `HttpsClientStreamBuilder`
+#[must_use = "futures do nothing unless polled"]
+pub struct HttpsClientStream {
+    name_server: SocketAddr,
+    name_server_name: Arc<String>,
+    endpoint: Arc<String>,
+    h2: SendRequest<Bytes>,
+    outbound_messages: Fuse<UnboundedReceiver<(Vec<u8>, SocketAddr)>>,
+    in_flight: FuturesUnordered<Box<Future<Item = Vec<u8>, Error = HttpsError>>>,
+    is_shutdown: bool,
+}
+
+impl HttpsClientStream {
+    /// Sends the message in a new request, returning the message of the response
+    fn send(&mut self, message: Vec<u8>) -> Box<Future<Item = Vec<u8>, Error = HttpsError>> {
+        let request = match request::new(&self.name_server_name, &self.endpoint, message.len()) {
+            Ok(request) => request,
+            Err(e) => return Box::new(future::err(e)),
+        };
+
+        let (response, mut stream) = match self.h2.send_request(request, false) {
+            Ok(sent) => sent,
+            Err(e) => return Box::new(future::err(e.into())),
+        };
+
+        if let Err(e) = stream.send_data(Bytes::from(message), true) {
+            return Box::new(future::err(e.into()));
+        }
+
+        Box::new(
+            response
+                .from_err()
+                .and_then(|response| {
+                    response::verify(&response)?;
+                    Ok(body::read(response.into_parts().1))
+                })
+                .flatten(),
+        )
+    }
+}
+
+impl Stream for HttpsClientStream {
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        // send the outbound messages while the connection accepts new streams
+        while !self.is_shutdown {
+            match self.h2.poll_ready() {
+                Ok(Async::Ready(())) => (),
+                Ok(Async::NotReady) => break,
+                Err(e) => return Err(h2_to_io(e)),
+            }
+
+            match self.outbound_messages
+                .poll()
+                .expect("unbounded receivers do not fail")
+            {
+                Async::Ready(Some((message, _))) => {
+                    let response = self.send(message);
+                    self.in_flight.push(response);
+                }
+                Async::Ready(None) => self.is_shutdown = true,
+                Async::NotReady => break,
+            }
+        }
+
+        loop {
+            match self.in_flight.poll() {
+                Ok(Async::Ready(Some(message))) => return Ok(Async::Ready(Some(message))),
+                Ok(Async::Ready(None)) if self.is_shutdown => return Ok(Async::Ready(None)),
+                Ok(Async::Ready(None)) | Ok(Async::NotReady) => return Ok(Async::NotReady),
+                // the request of the failed response times out, the others are unaffected
+                Err(e) => warn!("DNS over HTTPS request to {} failed: {}", self.name_server, e),
+            }
+        }
+    }
+}
+
+/// A builder for the `HttpsClientStream`
+#[derive(Clone)]
+pub struct HttpsClientStreamBuilder {
+    client_config: ClientConfig,
+    ca_chain: Vec<Certificate>,
+}
+
+impl HttpsClientStreamBuilder {
+    /// Returns a new builder which trusts only the certificate authorities which are added
+    pub fn new() -> Self {
+        Self::with_client_config(ClientConfig::new())
+    }
+
+    /// Returns a new builder with the TLS configuration, e.g. with the trusted roots of the system,
+    ///  the ALPN protocol is always set to HTTP/2
+    pub fn with_client_config(client_config: ClientConfig) -> Self {
+        HttpsClientStreamBuilder {
+            client_config,
+            ca_chain: vec![],
+        }
+    }
+
+    /// Add a custom trusted peer certificate or certificate authority.
+    pub fn add_ca(&mut self, ca: Certificate) {
+        self.ca_chain.push(ca);
+    }
+
+    /// Creates a new HttpsClientStream to the DNS query endpoint of the name_server
+    ///
+    /// [RFC 8484](https://tools.ietf.org/html/rfc8484), DNS Queries over HTTPS (DoH), October 2018
+    ///
+    /// # Arguments
+    ///
+    /// * `name_server` - IP and Port for the remote DNS server
+    /// * `dns_name` - The DNS name of the server, which the certificate of the server must match,
+    ///                and the authority of the requests
+    /// * `endpoint` - The path of the DNS query endpoint, e.g. `/dns-query`
+    /// * `loop_handle` - The reactor Core handle
+    pub fn build<E>(
+        self,
+        name_server: SocketAddr,
+        dns_name: String,
+        endpoint: String,
+        loop_handle: &Handle,
+    ) -> (
+        Box<Future<Item = HttpsClientStream, Error = io::Error>>,
+        Box<DnsStreamHandle<Error = E>>,
+    )
+    where
+        E: FromProtoError + 'static,
+    {
+        let (message_sender, outbound_messages) = unbounded();
+        let message_sender = Box::new(BufDnsStreamHandle::new(
+            name_server,
+            BufStreamHandle::new(message_sender),
+        ));
+
+        let mut client_config = self.client_config;
+        for ca in &self.ca_chain {
+            if let Err(e) = client_config.root_store.add(ca) {
+                let error = io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    format!("tls error: {:?}", e),
+                );
+                return (Box::new(future::err(error)), message_sender);
+            }
+        }
+        client_config.set_protocols(&[ALPN_H2.to_string()]);
+        let tls_connector = Arc::new(client_config);
+
+        let handle = loop_handle.clone();
+        let name_server_name = Arc::new(dns_name);
+        let endpoint = Arc::new(endpoint);
+
+        let stream = TokioTcpStream::connect(&name_server, loop_handle)
+            .and_then(move |tcp_stream| {
+                tls_connector
+                    .connect_async(&name_server_name, tcp_stream)
+                    .map_err(|e| {
+                        io::Error::new(
+                            io::ErrorKind::ConnectionRefused,
+                            format!("tls error: {}", e),
+                        )
+                    })
+                    .and_then(|tls_stream| h2::client::handshake(tls_stream).map_err(h2_to_io))
+                    .map(move |(h2, connection)| {
+                        // the connection drives the IO of all the requests
+                        handle.spawn(connection.map_err(move |e| {
+                            warn!("h2 connection to {} failed: {}", name_server, e)
+                        }));
+
+                        HttpsClientStream {
+                            name_server,
+                            name_server_name,
+                            endpoint,
+                            h2,
+                            outbound_messages: outbound_messages.fuse(),
+                            in_flight: FuturesUnordered::new(),
+                            is_shutdown: false,
+                        }
+                    })
+            });
+
+        (Box::new(stream), message_sender)
+    }
+}
+
+impl Default for HttpsClientStreamBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn h2_to_io(e: h2::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("http/2 error: {}", e))
+}
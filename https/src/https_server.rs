@@ -0,0 +1,81 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DNS over HTTPS server side, the DNS query endpoint of an HTTP/2 connection
+
+use std::io;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::{future, Future};
+use h2::RecvStream;
+use h2::server::SendResponse;
+use http::Request;
+use rustls::ServerConfig;
+use trust_dns_proto::op::Message;
+use trust_dns_rustls::tls_server::{self, Certificate, PrivateKey};
+
+use body;
+use error::{HttpsError, HttpsResult};
+use request;
+use response;
+use ALPN_H2;
+
+/// Returns the config for accepting TLS connections which negotiate HTTP/2
+///
+/// # Arguments
+///
+/// * `cert_chain` - certificate chain announced to clients, the certificate of the server first
+/// * `key` - private key of the certificate, see `trust_dns_rustls::tls_server::read_key`
+pub fn new_acceptor(cert_chain: Vec<Certificate>, key: PrivateKey) -> io::Result<Arc<ServerConfig>> {
+    let mut config = tls_server::new_config(cert_chain, key)?;
+    config.set_protocols(&[ALPN_H2.to_string()]);
+
+    Ok(Arc::new(config))
+}
+
+/// Returns the DNS message of a request for the DNS query endpoint at `path`
+///
+/// The message is read from the `dns` parameter of a GET request, or the body of a POST request.
+///  A request which is not for the endpoint, or is malformed, fails with an error which has the
+///  status of the response, see `HttpsError::status`.
+pub fn message_from(
+    path: &str,
+    request: Request<RecvStream>,
+) -> Box<Future<Item = Vec<u8>, Error = HttpsError>> {
+    match request::verify(path, &request) {
+        Ok(Some(message)) => Box::new(future::ok(message)),
+        Ok(None) => Box::new(body::read(request.into_parts().1)),
+        Err(e) => Box::new(future::err(e)),
+    }
+}
+
+/// Sends the response to a request, the DNS message or the status of the error
+///
+/// The response may be cached for the minimum TTL of the answers, see `response::max_age`.
+pub fn send_response(
+    respond: &mut SendResponse<Bytes>,
+    message: HttpsResult<Vec<u8>>,
+) -> HttpsResult<()> {
+    let message = match message {
+        Ok(message) => message,
+        Err(e) => {
+            debug!("rejecting DNS over HTTPS request: {}", e);
+            respond.send_response(response::error(e.status())?, true)?;
+            return Ok(());
+        }
+    };
+
+    let max_age = Message::from_vec(&message)
+        .ok()
+        .and_then(|message| response::max_age(&message));
+    let head = response::new(message.len(), max_age)?;
+
+    let mut stream = respond.send_response(head, false)?;
+    stream.send_data(Bytes::from(message), true)?;
+    Ok(())
+}
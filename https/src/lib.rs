@@ -0,0 +1,46 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#![warn(missing_docs)]
+
+//! DNS over HTTPS, [RFC 8484](https://tools.ietf.org/html/rfc8484), with HTTP/2 and rustls
+
+extern crate bytes;
+extern crate data_encoding;
+#[macro_use]
+extern crate error_chain;
+#[macro_use]
+extern crate futures;
+extern crate h2;
+extern crate http;
+#[macro_use]
+extern crate log;
+extern crate rustls;
+extern crate tokio_core;
+extern crate tokio_io;
+extern crate tokio_rustls;
+extern crate trust_dns_proto;
+extern crate trust_dns_rustls;
+
+mod body;
+pub mod error;
+pub mod https_client_stream;
+pub mod https_server;
+pub mod request;
+pub mod response;
+
+pub use self::error::{HttpsError, HttpsErrorKind, HttpsResult};
+pub use self::https_client_stream::{HttpsClientStream, HttpsClientStreamBuilder};
+
+/// The media type of DNS messages in requests and responses
+pub const MIME_APPLICATION_DNS: &str = "application/dns-message";
+
+/// The path of the DNS query endpoint, by convention
+pub const DNS_QUERY_PATH: &str = "/dns-query";
+
+/// The ALPN protocol identifier of HTTP/2, which DNS over HTTPS requires
+pub const ALPN_H2: &str = "h2";
@@ -0,0 +1,217 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! HTTP requests which carry DNS messages
+
+use data_encoding::BASE64URL_NOPAD;
+use http::{header, Method, Request, StatusCode};
+
+use error::{self, HttpsResult};
+use MIME_APPLICATION_DNS;
+
+/// The largest DNS message, the length of a message is two bytes in DNS over TCP
+pub const MAX_MESSAGE_LEN: usize = 65_535;
+
+/// The name of the query parameter of a GET request with the message
+pub const DNS_PARAM: &str = "dns";
+
+/// Returns the head of a POST request for the DNS query endpoint of the server, with a DNS
+///  message of `message_len` bytes as the body
+///
+/// # Arguments
+///
+/// * `name_server_name` - the name of the server, the authority of the URI
+/// * `endpoint` - the path of the DNS query endpoint, e.g. `/dns-query`
+/// * `message_len` - the length of the message in the body
+pub fn new(name_server_name: &str, endpoint: &str, message_len: usize) -> HttpsResult<Request<()>> {
+    let uri = format!("https://{}{}", name_server_name, endpoint);
+
+    Ok(Request::post(uri.as_str())
+        .header(header::CONTENT_TYPE, MIME_APPLICATION_DNS)
+        .header(header::ACCEPT, MIME_APPLICATION_DNS)
+        .header(header::CONTENT_LENGTH, message_len.to_string())
+        .body(())?)
+}
+
+/// Verifies the path, method and headers of a request for the DNS query endpoint at `path`
+///
+/// Returns the message of a GET request, which is in the `dns` parameter of the query, or `None`
+///  for a POST request, of which the message is the body.
+pub fn verify<T>(path: &str, request: &Request<T>) -> HttpsResult<Option<Vec<u8>>> {
+    if request.uri().path() != path {
+        return Err(error::status(
+            StatusCode::NOT_FOUND,
+            format!("no DNS query endpoint at: {}", request.uri().path()),
+        ));
+    }
+
+    if *request.method() == Method::GET {
+        return message_from_query(request.uri().query().unwrap_or("")).map(Some);
+    } else if *request.method() != Method::POST {
+        return Err(error::status(
+            StatusCode::METHOD_NOT_ALLOWED,
+            format!("unsupported method: {}", request.method()),
+        ));
+    }
+
+    let content_type = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    if content_type != Some(MIME_APPLICATION_DNS) {
+        return Err(error::status(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("unsupported content-type: {:?}", content_type),
+        ));
+    }
+
+    let content_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if let Some(content_length) = content_length {
+        if content_length > MAX_MESSAGE_LEN {
+            return Err(error::status(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("content-length exceeds {}: {}", MAX_MESSAGE_LEN, content_length),
+            ));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Decodes the message in the `dns` parameter of the query of a GET request
+///
+/// The message is base64url encoded without padding, though padding is tolerated.
+pub fn message_from_query(query: &str) -> HttpsResult<Vec<u8>> {
+    let param = query
+        .split('&')
+        .filter_map(|pair| {
+            let mut pair = pair.splitn(2, '=');
+            match (pair.next(), pair.next()) {
+                (Some(DNS_PARAM), Some(value)) => Some(value),
+                _ => None,
+            }
+        })
+        .next()
+        .ok_or_else(|| {
+            error::status(StatusCode::BAD_REQUEST, "the dns parameter is missing")
+        })?;
+
+    let message = BASE64URL_NOPAD.decode(param.trim_right_matches('=').as_bytes())?;
+    if message.len() > MAX_MESSAGE_LEN {
+        return Err(error::status(
+            StatusCode::URI_TOO_LONG,
+            format!("message exceeds {}: {}", MAX_MESSAGE_LEN, message.len()),
+        ));
+    }
+
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::Request;
+
+    // the example of RFC 8484, a query for www.example.com A
+    const EXAMPLE_GET: &str =
+        "/dns-query?dns=AAABAAABAAAAAAAAA3d3dwdleGFtcGxlA2NvbQAAAQAB";
+
+    fn example_message() -> Vec<u8> {
+        vec![
+            0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, b'w',
+            b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm',
+            0x00, 0x00, 0x01, 0x00, 0x01,
+        ]
+    }
+
+    #[test]
+    fn test_new() {
+        let request = new("dns.example.com", "/dns-query", 33).unwrap();
+        assert_eq!(request.method(), &Method::POST);
+        assert_eq!(request.uri().authority(), Some("dns.example.com"));
+        assert_eq!(request.uri().path(), "/dns-query");
+        assert_eq!(
+            request.headers().get(header::CONTENT_LENGTH).unwrap(),
+            "33"
+        );
+
+        // the server accepts the request
+        assert_eq!(verify("/dns-query", &request).unwrap(), None);
+    }
+
+    #[test]
+    fn test_verify_get() {
+        let request = Request::get(EXAMPLE_GET).body(()).unwrap();
+        assert_eq!(
+            verify("/dns-query", &request).unwrap(),
+            Some(example_message())
+        );
+
+        let request = Request::get("/dns-query?ct&dns=AAABAAABAAAAAAAAA3d3dwdleGFtcGxlA2NvbQAAAQAB")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            verify("/dns-query", &request).unwrap(),
+            Some(example_message())
+        );
+    }
+
+    #[test]
+    fn test_verify_post() {
+        let request = Request::post("/dns-query")
+            .header(header::CONTENT_TYPE, MIME_APPLICATION_DNS)
+            .header(header::CONTENT_LENGTH, "33")
+            .body(())
+            .unwrap();
+        assert_eq!(verify("/dns-query", &request).unwrap(), None);
+    }
+
+    #[test]
+    fn test_verify_rejects() {
+        let status = |request: Request<()>| verify("/dns-query", &request).unwrap_err().status();
+
+        assert_eq!(
+            status(Request::get("/other?dns=AAAB").body(()).unwrap()),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(Request::get("/dns-query").body(()).unwrap()),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(Request::get("/dns-query?dns=!!").body(()).unwrap()),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(Request::put("/dns-query").body(()).unwrap()),
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            status(
+                Request::post("/dns-query")
+                    .header(header::CONTENT_TYPE, "text/plain")
+                    .body(())
+                    .unwrap()
+            ),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+        assert_eq!(
+            status(
+                Request::post("/dns-query")
+                    .header(header::CONTENT_TYPE, MIME_APPLICATION_DNS)
+                    .header(header::CONTENT_LENGTH, "65536")
+                    .body(())
+                    .unwrap()
+            ),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+}
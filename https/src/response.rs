@@ -0,0 +1,174 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! HTTP responses which carry DNS messages
+
+use http::{header, Response, StatusCode};
+use trust_dns_proto::op::{Message, ResponseCode};
+use trust_dns_proto::rr::RData;
+
+use error::{self, HttpsResult};
+use MIME_APPLICATION_DNS;
+
+/// Returns the head of a response with a DNS message of `message_len` bytes as the body
+///
+/// # Arguments
+///
+/// * `message_len` - the length of the message in the body
+/// * `max_age` - how long the response may be cached, in seconds, see `max_age`
+pub fn new(message_len: usize, max_age: Option<u32>) -> HttpsResult<Response<()>> {
+    let mut builder = Response::builder();
+    builder
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, MIME_APPLICATION_DNS)
+        .header(header::CONTENT_LENGTH, message_len.to_string());
+
+    if let Some(max_age) = max_age {
+        builder.header(header::CACHE_CONTROL, format!("max-age={}", max_age));
+    }
+
+    Ok(builder.body(())?)
+}
+
+/// Returns the head of a response without a body, for requests which were rejected
+pub fn error(status: StatusCode) -> HttpsResult<Response<()>> {
+    Ok(Response::builder().status(status).body(())?)
+}
+
+/// Verifies the status and headers of a response, of which the DNS message is the body
+pub fn verify<T>(response: &Response<T>) -> HttpsResult<()> {
+    if response.status() != StatusCode::OK {
+        return Err(error::status(
+            response.status(),
+            "the server rejected the request",
+        ));
+    }
+
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    if content_type != Some(MIME_APPLICATION_DNS) {
+        return Err(format!("unsupported content-type of response: {:?}", content_type).into());
+    }
+
+    Ok(())
+}
+
+/// How long the response may be cached, the minimum TTL of the answers
+///
+/// Negative responses, without answers, may be cached for the negative TTL of the SOA in the
+///  authority section, RFC 2308. Responses which are neither positive nor negative, e.g.
+///  SERVFAIL, and those without any TTL should not be cached.
+pub fn max_age(message: &Message) -> Option<u32> {
+    match message.response_code() {
+        ResponseCode::NoError | ResponseCode::NXDomain => (),
+        _ => return None,
+    }
+
+    if !message.answers().is_empty() {
+        return message.answers().iter().map(|record| record.ttl()).min();
+    }
+
+    message
+        .name_servers()
+        .iter()
+        .filter_map(|record| match *record.rdata() {
+            RData::SOA(ref soa) => Some(record.ttl().min(soa.minimum())),
+            _ => None,
+        })
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+    use trust_dns_proto::rr::{Name, Record, RecordType};
+    use trust_dns_proto::rr::rdata::SOA;
+
+    fn a(ttl: u32) -> Record {
+        Record::from_rdata(
+            Name::from_str("www.example.com.").unwrap(),
+            ttl,
+            RecordType::A,
+            RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+        )
+    }
+
+    fn soa(ttl: u32, minimum: u32) -> Record {
+        let origin = Name::from_str("example.com.").unwrap();
+        Record::from_rdata(
+            origin.clone(),
+            ttl,
+            RecordType::SOA,
+            RData::SOA(SOA::new(origin.clone(), origin, 1, 3600, 600, 86_400, minimum)),
+        )
+    }
+
+    #[test]
+    fn test_new() {
+        let response = new(33, Some(300)).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            MIME_APPLICATION_DNS
+        );
+        assert_eq!(response.headers().get(header::CONTENT_LENGTH).unwrap(), "33");
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            "max-age=300"
+        );
+
+        let response = new(33, None).unwrap();
+        assert!(response.headers().get(header::CACHE_CONTROL).is_none());
+    }
+
+    #[test]
+    fn test_verify() {
+        assert!(verify(&new(33, Some(300)).unwrap()).is_ok());
+
+        let response = error(StatusCode::NOT_FOUND).unwrap();
+        assert_eq!(
+            verify(&response).unwrap_err().status(),
+            StatusCode::NOT_FOUND
+        );
+
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/html")
+            .body(())
+            .unwrap();
+        assert!(verify(&response).is_err());
+    }
+
+    #[test]
+    fn test_max_age_of_answers() {
+        let mut message = Message::new();
+        message.add_answer(a(300));
+        message.add_answer(a(60));
+        message.add_name_server(soa(30, 30));
+
+        assert_eq!(max_age(&message), Some(60));
+    }
+
+    #[test]
+    fn test_max_age_of_negative() {
+        let mut message = Message::new();
+        message.set_response_code(ResponseCode::NXDomain);
+        message.add_name_server(soa(3600, 120));
+        assert_eq!(max_age(&message), Some(120));
+
+        let mut message = Message::new();
+        assert_eq!(max_age(&message), None);
+
+        message.set_response_code(ResponseCode::ServFail);
+        message.add_name_server(soa(3600, 120));
+        assert_eq!(max_age(&message), None);
+    }
+}
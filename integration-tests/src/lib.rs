@@ -27,7 +27,7 @@ use trust_dns_proto::op::EncodableMessage;
 use trust_dns_proto::error::FromProtoError;
 
 use trust_dns_server::authority::{Catalog, MessageRequest};
-use trust_dns_server::server::{Request, RequestHandler, ResponseHandler};
+use trust_dns_server::server::{Protocol, Request, RequestHandler, ResponseHandler};
 
 pub mod authority;
 pub mod mock_client;
@@ -58,31 +58,56 @@ impl TestClientStream {
 
 #[derive(Clone)]
 pub struct TestResponseHandler {
-    buf: Arc<Mutex<Vec<u8>>>,
+    buf: Arc<Mutex<Vec<Vec<u8>>>>,
 }
 
 impl TestResponseHandler {
     pub fn new() -> Self {
-        let buf = Arc::new(Mutex::new(Vec::with_capacity(512)));
+        let buf = Arc::new(Mutex::new(Vec::new()));
         TestResponseHandler { buf }
     }
 
+    /// All messages sent to this handler, concatenated
     pub fn into_inner(self) -> Vec<u8> {
-        Arc::try_unwrap(self.buf).unwrap().into_inner().unwrap()
+        Arc::try_unwrap(self.buf)
+            .unwrap()
+            .into_inner()
+            .unwrap()
+            .concat()
     }
 
     pub fn into_message(self) -> Message {
-        let bytes = self.into_inner();
-        let mut decoder = BinDecoder::new(&bytes);
-        Message::read(&mut decoder).expect("could not decode message")
+        self.into_messages()
+            .into_iter()
+            .next()
+            .expect("no message was sent")
+    }
+
+    /// Each of the messages sent to this handler, in wire format
+    pub fn into_raw_messages(self) -> Vec<Vec<u8>> {
+        Arc::try_unwrap(self.buf).unwrap().into_inner().unwrap()
+    }
+
+    /// Each of the messages sent to this handler, e.g. for multi-message zone transfers
+    pub fn into_messages(self) -> Vec<Message> {
+        self.into_raw_messages()
+            .iter()
+            .map(|bytes| {
+                let mut decoder = BinDecoder::new(bytes);
+                Message::read(&mut decoder).expect("could not decode message")
+            })
+            .collect()
     }
 }
 
 impl ResponseHandler for TestResponseHandler {
     fn send<M: EncodableMessage>(self, response: M) -> io::Result<()> {
-        let buf = &mut self.buf.lock().unwrap();
-        let mut encoder = BinEncoder::new(buf);
-        response.emit(&mut encoder).expect("could not encode");
+        let mut buf = Vec::with_capacity(512);
+        {
+            let mut encoder = BinEncoder::new(&mut buf);
+            response.emit(&mut encoder).expect("could not encode");
+        }
+        self.buf.lock().unwrap().push(buf);
         Ok(())
     }
 }
@@ -108,6 +133,7 @@ impl Stream for TestClientStream {
                     src: "127.0.0.1:1234"
                         .parse()
                         .expect("cannot parse host and port"),
+                    protocol: Protocol::Udp,
                 };
 
                 let response_handler = TestResponseHandler::new();
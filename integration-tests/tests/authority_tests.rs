@@ -1,3 +1,6 @@
+extern crate chrono;
+extern crate data_encoding;
+extern crate openssl;
 extern crate rusqlite;
 extern crate trust_dns;
 extern crate trust_dns_integration;
@@ -62,6 +65,29 @@ fn test_search_www() {
     }
 }
 
+#[test]
+fn test_search_cname() {
+    let mut example = create_example();
+    let alias = Name::parse("alias.example.com.", None).unwrap();
+    let www_name = Name::parse("www.example.com.", None).unwrap();
+    example.upsert(
+        Record::from_rdata(alias.clone(), 86400, RecordType::CNAME, RData::CNAME(www_name.clone())),
+        0,
+    );
+
+    let mut query: Query = Query::new();
+    query.set_name(alias.clone());
+
+    // the alias is followed to the canonical name in the zone
+    let result = example.search(&query.into(), false, SupportedAlgorithms::new());
+    let records = result.iter().collect::<Vec<_>>();
+    assert_eq!(records.len(), 2);
+    assert_eq!(*records[0].name(), alias);
+    assert_eq!(records[0].rdata(), &RData::CNAME(www_name.clone()));
+    assert_eq!(*records[1].name(), www_name);
+    assert_eq!(records[1].rdata(), &RData::A(Ipv4Addr::new(93, 184, 216, 34)));
+}
+
 #[test]
 fn test_authority() {
     let authority: Authority = create_example();
@@ -968,6 +994,440 @@ fn test_get_nsec() {
     }
 }
 
+#[test]
+fn test_get_nsec3() {
+    let mut authority: Authority = create_secure_example();
+    authority.set_nsec3param(Some(NSEC3PARAM::new(
+        Nsec3HashAlgorithm::SHA1,
+        false,
+        1,
+        vec![0xAA, 0xBB],
+    )));
+    authority.secure_zone().expect("failed to sign zone");
+
+    let nsec3_types = |records: &[&Record]| -> Vec<Vec<RecordType>> {
+        records
+            .iter()
+            .filter_map(|r| match *r.rdata() {
+                RData::DNSSEC(DNSSECRData::NSEC3(ref nsec3)) => {
+                    assert_eq!(nsec3.iterations(), 1);
+                    assert_eq!(nsec3.salt(), &[0xAA, 0xBB]);
+                    Some(nsec3.type_bit_maps().to_vec())
+                }
+                RData::DNSSEC(DNSSECRData::NSEC(_)) => panic!("NSEC in an NSEC3 zone: {:?}", r),
+                _ => None,
+            })
+            .collect()
+    };
+
+    // the NSEC chain is replaced, one NSEC3 for each of example.com and www.example.com
+    let results = authority.lookup(
+        &authority.origin(),
+        RecordType::AXFR,
+        true,
+        SupportedAlgorithms::all(),
+    );
+    assert!(results.iter().any(|r| {
+        r.rr_type() == RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM)
+            && r.name() == &Name::from(authority.origin().clone())
+    }));
+    assert_eq!(nsec3_types(&results.iter().collect::<Vec<_>>()).len(), 2);
+
+    // no data, the NSEC3 of www.example.com
+    let name = Name::from_str("www.example.com.").unwrap();
+    let results = authority.get_nsec_records(&name.into(), true, SupportedAlgorithms::all());
+    let types = nsec3_types(&results);
+    assert_eq!(types.len(), 1);
+    assert!(types[0].contains(&RecordType::A));
+    assert!(
+        results
+            .iter()
+            .any(|r| r.rr_type() == RecordType::DNSSEC(DNSSECRecordType::RRSIG))
+    );
+
+    // name error, the closest encloser is example.com
+    let name = Name::from_str("a.b.example.com.").unwrap();
+    let results = authority.get_nsec_records(&name.into(), true, SupportedAlgorithms::all());
+    let types = nsec3_types(&results);
+    assert!(!types.is_empty());
+    assert!(types.iter().any(|types| types.contains(&RecordType::SOA)));
+}
+
+#[test]
+fn test_get_nsec3_before_first_owner() {
+    use data_encoding::BASE32_DNSSEC;
+
+    let mut authority: Authority = create_secure_example();
+    authority.set_nsec3param(Some(NSEC3PARAM::new(
+        Nsec3HashAlgorithm::SHA1,
+        false,
+        1,
+        vec![0xAA, 0xBB],
+    )));
+    authority.secure_zone().expect("failed to sign zone");
+
+    let hash = |name: &Name| {
+        Nsec3HashAlgorithm::SHA1
+            .hash(&[0xAA, 0xBB], name, 1)
+            .unwrap()
+            .as_ref()
+            .to_vec()
+    };
+    let nsec3s = |records: &[&Record]| -> Vec<(Vec<u8>, NSEC3)> {
+        records
+            .iter()
+            .filter_map(|r| match *r.rdata() {
+                RData::DNSSEC(DNSSECRData::NSEC3(ref nsec3)) => {
+                    let label = r.name().iter().next().unwrap();
+                    Some((BASE32_DNSSEC.decode(label).unwrap(), nsec3.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    };
+
+    let results = authority.lookup(
+        &authority.origin(),
+        RecordType::AXFR,
+        true,
+        SupportedAlgorithms::all(),
+    );
+    let first = nsec3s(&results.iter().collect::<Vec<_>>())
+        .into_iter()
+        .map(|(owner_hash, _)| owner_hash)
+        .min()
+        .unwrap();
+
+    // a name which hashes before the first owner, it's covered by the last NSEC3 of the chain
+    let name = (0..)
+        .map(|i| Name::from_str(&format!("n{}.example.com.", i)).unwrap())
+        .find(|name| hash(name) < first)
+        .unwrap();
+    let name_hash = hash(&name);
+
+    let results = authority.get_nsec_records(&name.into(), true, SupportedAlgorithms::all());
+    assert!(
+        nsec3s(&results)
+            .iter()
+            .any(|&(ref owner_hash, ref nsec3)| nsec3.covers(owner_hash, &name_hash))
+    );
+}
+
+#[test]
+fn test_signature_times() {
+    let mut authority: Authority = create_secure_example();
+    authority.set_signature_inception(std::time::Duration::from_secs(3600));
+    authority.set_signature_jitter(std::time::Duration::from_secs(0));
+    authority.secure_zone().expect("failed to sign zone");
+
+    let results = authority.lookup(
+        &authority.origin(),
+        RecordType::AXFR,
+        true,
+        SupportedAlgorithms::all(),
+    );
+
+    let mut rrsigs = 0;
+    for record in results.iter() {
+        if let RData::DNSSEC(DNSSECRData::SIG(ref rrsig)) = *record.rdata() {
+            // the one week validity of the key, and the back-dated hour
+            assert_eq!(rrsig.sig_expiration() - rrsig.sig_inception(), 7 * 86400 + 3600);
+            rrsigs += 1;
+        }
+    }
+    assert!(rrsigs > 0);
+
+    // none are due, the refresh window is at most half of the validity
+    assert_eq!(authority.resign_expiring().unwrap(), 0);
+    let next_refresh = authority.next_signature_refresh().unwrap();
+    assert!(next_refresh > std::time::Duration::from_secs(3 * 86400));
+    assert!(next_refresh <= std::time::Duration::from_secs(7 * 86400 / 2));
+}
+
+#[test]
+fn test_resign_expiring() {
+    use openssl::rsa::Rsa;
+
+    let mut authority: Authority = create_example();
+
+    // the signatures expire as soon as they are made
+    let key = KeyPair::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let dnskey = key.to_dnskey(Algorithm::RSASHA256).unwrap();
+    let signer = Signer::dnssec(
+        dnskey,
+        key,
+        authority.origin().clone().into(),
+        chrono::Duration::zero(),
+    );
+    authority.add_secure_key(signer).unwrap();
+    authority.secure_zone().expect("failed to sign zone");
+
+    let serial = authority.serial();
+    assert_eq!(
+        authority.next_signature_refresh(),
+        Some(std::time::Duration::from_secs(0))
+    );
+
+    assert!(authority.resign_expiring().unwrap() > 0);
+    assert_eq!(authority.serial(), serial + 1);
+
+    // the SOA, with the new serial, is signed
+    let soa = authority.soa_secure(true, SupportedAlgorithms::all());
+    assert!(soa.iter().any(|r| {
+        r.rr_type() == RecordType::DNSSEC(DNSSECRecordType::RRSIG)
+    }));
+}
+
+/// A signer for a new RSA key, a KSK if the Secure Entry Point flag is set
+fn rollover_signer(origin: &Name, is_key_signing_key: bool) -> Signer {
+    use openssl::rsa::Rsa;
+
+    let key = KeyPair::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let dnskey = DNSKEY::new(
+        true,
+        is_key_signing_key,
+        false,
+        Algorithm::RSASHA256,
+        key.to_public_bytes().unwrap(),
+    );
+    Signer::dnssec(dnskey, key, origin.clone(), chrono::Duration::weeks(1))
+}
+
+/// The record set of the type at the name in the zone, with its RRSIGs
+fn rollover_rr_set<'a>(authority: &'a Authority, name: &str, rtype: RecordType) -> &'a RecordSet {
+    let name = Name::from_str(name).unwrap();
+    authority
+        .records()
+        .get(&RrKey::new(name.into(), rtype))
+        .expect("record set not found")
+}
+
+/// The tags of the keys which signed the record set
+fn rollover_signed_by(rr_set: &RecordSet) -> Vec<u16> {
+    let mut key_tags: Vec<u16> = rr_set
+        .rrsigs()
+        .iter()
+        .filter_map(|rrsig| match *rrsig.rdata() {
+            RData::DNSSEC(DNSSECRData::SIG(ref sig)) => Some(sig.key_tag()),
+            _ => None,
+        })
+        .collect();
+    key_tags.sort();
+    key_tags
+}
+
+#[test]
+fn test_zsk_rollover() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let mut authority: Authority = create_example();
+    let origin: Name = authority.origin().clone().into();
+    let time = Arc::new(AtomicUsize::new(1000));
+    let clock = time.clone();
+    authority.set_clock(Arc::new(move || clock.load(Ordering::SeqCst) as u64));
+
+    // the old ZSK is retired when the new one, pre-published, is activated
+    let ksk = rollover_signer(&origin, true);
+    let old_zsk = rollover_signer(&origin, false);
+    let new_zsk = rollover_signer(&origin, false);
+    let ksk_tag = ksk.calculate_key_tag().unwrap();
+    let old_zsk_tag = old_zsk.calculate_key_tag().unwrap();
+    let new_zsk_tag = new_zsk.calculate_key_tag().unwrap();
+    let old_zsk_public_key = old_zsk.key().to_public_bytes().unwrap();
+
+    authority.add_secure_key(ksk).unwrap();
+    authority
+        .add_secure_key_with_timing(
+            old_zsk,
+            KeyTiming::new(None, None, Some(2000), Some(3000)).unwrap(),
+        )
+        .unwrap();
+    authority
+        .add_secure_key_with_timing(
+            new_zsk,
+            KeyTiming::new(Some(1500), Some(2000), None, None).unwrap(),
+        )
+        .unwrap();
+    authority.secure_zone().expect("failed to sign zone");
+
+    let dnskey_type = RecordType::DNSSEC(DNSSECRecordType::DNSKEY);
+    let dnskeys = rollover_rr_set(&authority, "example.com.", dnskey_type);
+    assert_eq!(dnskeys.records_without_rrsigs().len(), 2);
+    assert_eq!(rollover_signed_by(dnskeys), vec![ksk_tag]);
+    let www = rollover_rr_set(&authority, "www.example.com.", RecordType::A);
+    assert_eq!(rollover_signed_by(www), vec![old_zsk_tag]);
+
+    // the publication of the new ZSK is the next change
+    assert_eq!(
+        authority.next_signature_refresh(),
+        Some(std::time::Duration::from_secs(500))
+    );
+    assert!(!authority.roll_keys().unwrap());
+
+    // the new ZSK is published, but does not yet sign
+    time.store(1500, Ordering::SeqCst);
+    let serial = authority.serial();
+    assert!(authority.resign_expiring().unwrap() > 0);
+    assert!(authority.serial() > serial);
+    let dnskeys = rollover_rr_set(&authority, "example.com.", dnskey_type);
+    assert_eq!(dnskeys.records_without_rrsigs().len(), 3);
+    assert_eq!(rollover_signed_by(dnskeys), vec![ksk_tag]);
+    let www = rollover_rr_set(&authority, "www.example.com.", RecordType::A);
+    assert_eq!(rollover_signed_by(www), vec![old_zsk_tag]);
+
+    // the new ZSK signs, the old one is still published for the cached signatures
+    time.store(2000, Ordering::SeqCst);
+    assert!(authority.roll_keys().unwrap());
+    let dnskeys = rollover_rr_set(&authority, "example.com.", dnskey_type);
+    assert_eq!(dnskeys.records_without_rrsigs().len(), 3);
+    assert_eq!(rollover_signed_by(dnskeys), vec![ksk_tag]);
+    let www = rollover_rr_set(&authority, "www.example.com.", RecordType::A);
+    assert_eq!(rollover_signed_by(www), vec![new_zsk_tag]);
+
+    // the old ZSK is removed
+    time.store(3000, Ordering::SeqCst);
+    assert!(authority.roll_keys().unwrap());
+    let dnskeys = rollover_rr_set(&authority, "example.com.", dnskey_type);
+    assert_eq!(dnskeys.records_without_rrsigs().len(), 2);
+    assert!(dnskeys.records_without_rrsigs().iter().all(|dnskey| {
+        match *dnskey.rdata() {
+            RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey)) => {
+                dnskey.public_key() != &old_zsk_public_key[..]
+            }
+            _ => false,
+        }
+    }));
+    assert_eq!(authority.next_signature_refresh().map(|wait| wait.as_secs() > 0), Some(true));
+}
+
+#[test]
+fn test_resign_without_active_keys() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let mut authority: Authority = create_example();
+    let origin: Name = authority.origin().clone().into();
+    let time = Arc::new(AtomicUsize::new(1000));
+    let clock = time.clone();
+    authority.set_clock(Arc::new(move || clock.load(Ordering::SeqCst) as u64));
+
+    // the key is published, but not active yet
+    let zsk = rollover_signer(&origin, false);
+    let zsk_tag = zsk.calculate_key_tag().unwrap();
+    authority
+        .add_secure_key_with_timing(
+            zsk,
+            KeyTiming::new(Some(1000), Some(2000), None, None).unwrap(),
+        )
+        .unwrap();
+    authority.secure_zone().expect("failed to sign zone");
+
+    let www = rollover_rr_set(&authority, "www.example.com.", RecordType::A);
+    assert!(rollover_signed_by(www).is_empty());
+
+    // nothing is due until the key is activated
+    let serial = authority.serial();
+    assert_eq!(authority.resign_expiring().unwrap(), 0);
+    assert_eq!(authority.serial(), serial);
+    assert_eq!(
+        authority.next_signature_refresh(),
+        Some(std::time::Duration::from_secs(1000))
+    );
+
+    time.store(2000, Ordering::SeqCst);
+    assert!(authority.resign_expiring().unwrap() > 0);
+    assert!(authority.serial() > serial);
+    let www = rollover_rr_set(&authority, "www.example.com.", RecordType::A);
+    assert_eq!(rollover_signed_by(www), vec![zsk_tag]);
+}
+
+#[test]
+fn test_ksk_rollover() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let mut authority: Authority = create_example();
+    let origin: Name = authority.origin().clone().into();
+    let time = Arc::new(AtomicUsize::new(1000));
+    let clock = time.clone();
+    authority.set_clock(Arc::new(move || clock.load(Ordering::SeqCst) as u64));
+
+    // both KSKs sign the DNSKEY set while the DS at the parent is replaced
+    let zsk = rollover_signer(&origin, false);
+    let old_ksk = rollover_signer(&origin, true);
+    let new_ksk = rollover_signer(&origin, true);
+    let zsk_tag = zsk.calculate_key_tag().unwrap();
+    let old_ksk_tag = old_ksk.calculate_key_tag().unwrap();
+    let new_ksk_tag = new_ksk.calculate_key_tag().unwrap();
+
+    authority.add_secure_key(zsk).unwrap();
+    authority
+        .add_secure_key_with_timing(
+            old_ksk,
+            KeyTiming::new(None, None, Some(3000), Some(4000)).unwrap(),
+        )
+        .unwrap();
+    authority
+        .add_secure_key_with_timing(
+            new_ksk,
+            KeyTiming::new(Some(2000), None, None, None).unwrap(),
+        )
+        .unwrap();
+    authority.secure_zone().expect("failed to sign zone");
+
+    let dnskey_type = RecordType::DNSSEC(DNSSECRecordType::DNSKEY);
+    let cds_type = RecordType::DNSSEC(DNSSECRecordType::CDS);
+    let cdnskey_type = RecordType::DNSSEC(DNSSECRecordType::CDNSKEY);
+    let cds_key_tags = |authority: &Authority| -> Vec<u16> {
+        rollover_rr_set(authority, "example.com.", cds_type)
+            .records_without_rrsigs()
+            .iter()
+            .filter_map(|cds| match *cds.rdata() {
+                RData::DNSSEC(DNSSECRData::CDS(ref cds)) => Some(cds.key_tag()),
+                _ => None,
+            })
+            .collect()
+    };
+
+    // the old KSK is the only one, and it alone signs the key sets
+    let dnskeys = rollover_rr_set(&authority, "example.com.", dnskey_type);
+    assert_eq!(dnskeys.records_without_rrsigs().len(), 2);
+    assert_eq!(rollover_signed_by(dnskeys), vec![old_ksk_tag]);
+    assert_eq!(cds_key_tags(&authority), vec![old_ksk_tag]);
+    let cdnskeys = rollover_rr_set(&authority, "example.com.", cdnskey_type);
+    assert_eq!(cdnskeys.records_without_rrsigs().len(), 1);
+    assert_eq!(rollover_signed_by(cdnskeys), vec![old_ksk_tag]);
+    let www = rollover_rr_set(&authority, "www.example.com.", RecordType::A);
+    assert_eq!(rollover_signed_by(www), vec![zsk_tag]);
+
+    // double signature, the CDS and CDNSKEY ask the parent for the DS of the new KSK
+    time.store(2000, Ordering::SeqCst);
+    assert!(authority.roll_keys().unwrap());
+    let dnskeys = rollover_rr_set(&authority, "example.com.", dnskey_type);
+    assert_eq!(dnskeys.records_without_rrsigs().len(), 3);
+    let mut both_ksks = vec![old_ksk_tag, new_ksk_tag];
+    both_ksks.sort();
+    assert_eq!(rollover_signed_by(dnskeys), both_ksks);
+    assert_eq!(cds_key_tags(&authority), vec![new_ksk_tag]);
+    let www = rollover_rr_set(&authority, "www.example.com.", RecordType::A);
+    assert_eq!(rollover_signed_by(www), vec![zsk_tag]);
+
+    // the old KSK no longer signs, but is still published
+    time.store(3000, Ordering::SeqCst);
+    assert!(authority.roll_keys().unwrap());
+    let dnskeys = rollover_rr_set(&authority, "example.com.", dnskey_type);
+    assert_eq!(dnskeys.records_without_rrsigs().len(), 3);
+    assert_eq!(rollover_signed_by(dnskeys), vec![new_ksk_tag]);
+
+    // the old KSK is removed
+    time.store(4000, Ordering::SeqCst);
+    assert!(authority.roll_keys().unwrap());
+    let dnskeys = rollover_rr_set(&authority, "example.com.", dnskey_type);
+    assert_eq!(dnskeys.records_without_rrsigs().len(), 2);
+    assert_eq!(cds_key_tags(&authority), vec![new_ksk_tag]);
+}
+
 #[test]
 fn test_journal() {
     // test that this message can be inserted
@@ -1092,3 +1552,100 @@ fn test_recovery() {
             })
     }));
 }
+
+#[test]
+fn test_recovery_serial() {
+    let conn = Connection::open_in_memory().expect("could not create in memory DB");
+    let mut journal = Journal::new(conn).unwrap();
+    journal.schema_up().unwrap();
+
+    let mut authority = create_example();
+    authority.set_journal(journal);
+    authority.persist_to_journal().unwrap();
+
+    let new_record = Record::new()
+        .set_name(Name::from_str("new.example.com").unwrap())
+        .set_rdata(RData::A(Ipv4Addr::new(10, 11, 12, 13)))
+        .clone();
+    authority.update_records(&[new_record], true).unwrap();
+
+    let mut recovered_authority = Authority::new(
+        authority.origin().clone().into(),
+        BTreeMap::new(),
+        ZoneType::Master,
+        false,
+        false,
+    );
+    recovered_authority
+        .recover_with_journal(authority.journal().expect("journal not Some"))
+        .expect("recovery");
+
+    // the incremented SOA is journaled with the update
+    assert_eq!(recovered_authority.serial(), authority.serial());
+}
+
+#[test]
+fn test_journal_diffs() {
+    let conn = Connection::open_in_memory().expect("could not create in memory DB");
+    let mut journal = Journal::new(conn).unwrap();
+    journal.schema_up().unwrap();
+
+    let mut authority = create_example();
+    authority.set_journal(journal);
+    authority.persist_to_journal().unwrap();
+    let serial = authority.serial();
+
+    let new_record = Record::new()
+        .set_name(Name::from_str("new.example.com").unwrap())
+        .set_rdata(RData::A(Ipv4Addr::new(10, 11, 12, 13)))
+        .clone();
+    authority
+        .update_records(&[new_record.clone()], true)
+        .unwrap();
+
+    let delete_record = Record::new()
+        .set_name(Name::from_str("www.example.com").unwrap())
+        .set_rdata(RData::A(Ipv4Addr::new(93, 184, 216, 34)))
+        .set_dns_class(DNSClass::NONE)
+        .clone();
+    authority.update_records(&[delete_record], true).unwrap();
+    assert_eq!(authority.serial(), serial + 2);
+
+    let soa_serial = |record: &Record| {
+        if let RData::SOA(ref soa) = *record.rdata() {
+            soa.serial()
+        } else {
+            panic!("not an SOA: {:?}", record)
+        }
+    };
+
+    let diffs = authority.journal_diffs(serial).expect("no diffs");
+    assert_eq!(diffs.len(), 2);
+
+    assert_eq!(soa_serial(diffs[0].old_soa()), serial);
+    assert_eq!(soa_serial(diffs[0].new_soa()), serial + 1);
+    assert!(diffs[0].deleted().is_empty());
+    assert_eq!(diffs[0].added(), &[new_record]);
+
+    assert_eq!(soa_serial(diffs[1].old_soa()), serial + 1);
+    assert_eq!(soa_serial(diffs[1].new_soa()), serial + 2);
+    assert_eq!(diffs[1].deleted().len(), 1);
+    assert_eq!(
+        diffs[1].deleted()[0].name(),
+        &Name::from_str("www.example.com").unwrap()
+    );
+    assert_eq!(
+        diffs[1].deleted()[0].rdata(),
+        &RData::A(Ipv4Addr::new(93, 184, 216, 34))
+    );
+    assert!(diffs[1].added().is_empty());
+
+    assert_eq!(authority.journal_diffs(serial + 1).expect("no diffs").len(), 1);
+    assert!(authority.journal_diffs(serial + 2).expect("no diffs").is_empty());
+
+    // not in the history of the zone
+    assert!(authority.journal_diffs(serial - 1).is_none());
+
+    // without a journal, there is no history
+    assert!(create_example().journal_diffs(serial).is_none());
+}
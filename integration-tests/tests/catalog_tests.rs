@@ -1,16 +1,28 @@
+extern crate futures;
+extern crate rusqlite;
 extern crate trust_dns;
 extern crate trust_dns_integration;
 extern crate trust_dns_server;
 
 use std::net::*;
 use std::collections::*;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures::Stream;
+use futures::sync::mpsc::unbounded;
+use rusqlite::Connection;
 
 use trust_dns::op::*;
 use trust_dns::rr::*;
+use trust_dns::rr::dnssec::{TSigner, DEFAULT_TSIG_FUDGE};
+use trust_dns::rr::rdata::opt::{Cookie, EdnsCode, EdnsOption};
+use trust_dns::rr::rdata::tsig::{self, TsigAlgorithm};
 use trust_dns::rr::rdata::*;
 use trust_dns::serialize::binary::{BinDecodable, BinEncodable};
 
 use trust_dns_server::authority::*;
+use trust_dns_server::server::{Protocol, Request, RequestHandler, ServerCookies};
 
 use trust_dns_integration::*;
 use trust_dns_integration::authority::create_example;
@@ -403,3 +415,1105 @@ fn test_axfr() {
 
     assert_eq!(expected_set, answers);
 }
+
+fn axfr_request(origin: &Name, src: &str, protocol: Protocol) -> (Vec<u8>, SocketAddr, Protocol) {
+    let mut query: Query = Query::new();
+    query.set_name(origin.clone());
+    query.set_query_type(RecordType::AXFR);
+
+    let mut question: Message = Message::new();
+    question.add_query(query);
+
+    (
+        question.to_bytes().unwrap(),
+        src.parse().expect("cannot parse host and port"),
+        protocol,
+    )
+}
+
+fn handle(catalog: &Catalog, request: (Vec<u8>, SocketAddr, Protocol)) -> Vec<Message> {
+    handle_raw(catalog, request)
+        .iter()
+        .map(|bytes| Message::from_bytes(bytes).expect("could not decode message"))
+        .collect()
+}
+
+fn handle_raw(catalog: &Catalog, request: (Vec<u8>, SocketAddr, Protocol)) -> Vec<Vec<u8>> {
+    let (question_bytes, src, protocol) = request;
+    let request = Request {
+        message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+        src: src,
+        protocol: protocol,
+    };
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request(&request, response_handler.clone())
+        .expect("handle_request failed");
+    response_handler.into_raw_messages()
+}
+
+#[test]
+fn test_axfr_transfer() {
+    let mut test = create_test();
+    test.set_allow_transfer(vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))].into());
+    let origin: Name = test.origin().clone().into();
+    let soa = test.soa().iter().next().cloned().unwrap();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(
+        &catalog,
+        axfr_request(&origin, "127.0.0.1:1234", Protocol::Tcp),
+    );
+    assert_eq!(messages.len(), 1);
+
+    let result = &messages[0];
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.authoritative());
+
+    let answers = result.answers();
+    assert_eq!(answers.len(), 8);
+    assert_eq!(answers.first().expect("no records found?"), &soa);
+    assert_eq!(answers.last().expect("no records found?"), &soa);
+    assert_eq!(
+        answers
+            .iter()
+            .filter(|r| r.rr_type() == RecordType::SOA)
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_axfr_multiple_messages() {
+    let mut test = create_test();
+    test.set_allow_transfer(vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))].into());
+    let origin: Name = test.origin().clone().into();
+    let soa = test.soa().iter().next().cloned().unwrap();
+
+    // enough records that they can not fit in a single message
+    for i in 0..4096 {
+        let name = Name::parse(&format!("host-{}", i), Some(&origin)).unwrap();
+        test.upsert(
+            Record::new()
+                .set_name(name)
+                .set_ttl(86400)
+                .set_rr_type(RecordType::A)
+                .set_dns_class(DNSClass::IN)
+                .set_rdata(RData::A(Ipv4Addr::new(10, 0, (i / 256) as u8, (i % 256) as u8)))
+                .clone(),
+            0,
+        );
+    }
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(
+        &catalog,
+        axfr_request(&origin, "127.0.0.1:1234", Protocol::Tcp),
+    );
+    assert!(messages.len() > 1);
+
+    // only the first message needs the question
+    assert_eq!(messages.first().unwrap().queries().len(), 1);
+    assert_eq!(
+        messages
+            .first()
+            .unwrap()
+            .answers()
+            .first()
+            .expect("no records found?"),
+        &soa
+    );
+    assert_eq!(
+        messages
+            .last()
+            .unwrap()
+            .answers()
+            .last()
+            .expect("no records found?"),
+        &soa
+    );
+
+    let answers: Vec<&Record> = messages.iter().flat_map(|m| m.answers()).collect();
+    assert_eq!(answers.len(), 4096 + 8);
+    assert_eq!(
+        answers
+            .iter()
+            .filter(|r| r.rr_type() == RecordType::SOA)
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_axfr_refused_over_udp() {
+    let mut test = create_test();
+    test.set_allow_transfer(vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))].into());
+    let origin: Name = test.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(
+        &catalog,
+        axfr_request(&origin, "127.0.0.1:1234", Protocol::Udp),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::Refused);
+    assert!(messages[0].answers().is_empty());
+}
+
+#[test]
+fn test_axfr_refused_not_allowed() {
+    let mut test = create_test();
+    test.set_allow_transfer(vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))].into());
+    let origin: Name = test.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(
+        &catalog,
+        axfr_request(&origin, "127.0.0.2:1234", Protocol::Tcp),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::Refused);
+    assert!(messages[0].answers().is_empty());
+}
+
+fn query_request(name: &Name, src: &str) -> (Vec<u8>, SocketAddr, Protocol) {
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(name.clone(), RecordType::A));
+
+    (
+        question.to_bytes().unwrap(),
+        src.parse().expect("cannot parse host and port"),
+        Protocol::Udp,
+    )
+}
+
+#[test]
+fn test_query_allowed() {
+    let mut test = create_test();
+    test.set_allow_query(Acl::new(vec!["10.0.0.0/8".parse().unwrap()]));
+    let origin: Name = test.origin().clone().into();
+    let www = Name::parse("www", Some(&origin)).unwrap();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(&catalog, query_request(&www, "10.1.2.3:1234"));
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+    assert!(!messages[0].answers().is_empty());
+
+    let messages = handle(&catalog, query_request(&www, "127.0.0.1:1234"));
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::Refused);
+    assert!(messages[0].answers().is_empty());
+}
+
+#[test]
+fn test_query_allowed_by_default() {
+    let test = create_test();
+    let origin: Name = test.origin().clone().into();
+    let www = Name::parse("www", Some(&origin)).unwrap();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(&catalog, query_request(&www, "[2001:db8::1]:1234"));
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+}
+
+#[test]
+fn test_axfr_allowed_from_network() {
+    let mut test = create_test();
+    test.set_allow_transfer(Acl::new(vec!["127.0.0.0/8".parse().unwrap()]));
+    let origin: Name = test.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(
+        &catalog,
+        axfr_request(&origin, "127.0.0.2:1234", Protocol::Tcp),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+    assert_eq!(messages[0].answers().len(), 8);
+}
+
+/// An update adding an A record for `new` in the zone, signed with the TSIG key
+fn signed_update_request(
+    origin: &Name,
+    signer: &TSigner,
+    src: &str,
+) -> (Vec<u8>, SocketAddr, Protocol) {
+    let new_name = Name::parse("new", Some(origin)).unwrap();
+    let record = Record::from_rdata(
+        new_name,
+        86400,
+        RecordType::A,
+        RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+    );
+
+    let mut update: Message = Message::new();
+    update
+        .set_id(20)
+        .set_op_code(OpCode::Update)
+        .add_query(Query::query(origin.clone(), RecordType::SOA))
+        .add_name_server(record);
+    update.finalize(signer, now()).expect("could not sign");
+
+    (
+        update.to_bytes().unwrap(),
+        src.parse().expect("cannot parse host and port"),
+        Protocol::Udp,
+    )
+}
+
+#[test]
+fn test_update_allowed_from_network() {
+    let mut test = create_test();
+    test.set_allow_update(true);
+    test.set_allow_update_from(Acl::new(vec!["10.0.0.0/8".parse().unwrap()]));
+    test.add_tsig_key(tsigner(b"secret"));
+    let origin: Name = test.origin().clone().into();
+    let new_name = Name::parse("new", Some(&origin)).unwrap();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+    let signer = tsigner(b"secret");
+
+    // even signed with a key of the zone, updates from outside of the network are refused
+    let messages = handle(
+        &catalog,
+        signed_update_request(&origin, &signer, "127.0.0.1:1234"),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::Refused);
+
+    let messages = handle(&catalog, query_request(&new_name, "127.0.0.1:1234"));
+    assert_eq!(messages[0].response_code(), ResponseCode::NXDomain);
+
+    let messages = handle(
+        &catalog,
+        signed_update_request(&origin, &signer, "10.0.0.1:1234"),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+
+    let messages = handle(&catalog, query_request(&new_name, "127.0.0.1:1234"));
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+    assert_eq!(
+        *messages[0].answers()[0].rdata(),
+        RData::A(Ipv4Addr::new(192, 0, 2, 1))
+    );
+}
+
+#[test]
+fn test_update_with_transfer_key() {
+    let mut test = create_test();
+    test.set_allow_update(true);
+    test.add_transfer_tsig_key(tsigner(b"secret"));
+    let origin: Name = test.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    // the key is valid for the zone, but only for transfers
+    let messages = handle(
+        &catalog,
+        signed_update_request(&origin, &tsigner(b"secret"), "127.0.0.1:1234"),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::Refused);
+}
+
+fn now() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time went backwards")
+        .as_secs() as u32
+}
+
+fn tsigner(key: &[u8]) -> TSigner {
+    TSigner::new(
+        Name::parse("key.example.com.", None).unwrap(),
+        TsigAlgorithm::HmacSha256,
+        key.to_vec(),
+        DEFAULT_TSIG_FUDGE,
+    ).unwrap()
+}
+
+/// An AXFR request signed with the TSIG key, and the request sent
+fn signed_axfr_request(
+    origin: &Name,
+    signer: &TSigner,
+    time: u32,
+) -> (Message, (Vec<u8>, SocketAddr, Protocol)) {
+    let mut query: Query = Query::new();
+    query.set_name(origin.clone());
+    query.set_query_type(RecordType::AXFR);
+
+    let mut question: Message = Message::new();
+    question.set_id(10).add_query(query);
+    question.finalize(signer, time).expect("could not sign");
+
+    let request = (
+        question.to_bytes().unwrap(),
+        "127.0.0.2:1234".parse().unwrap(),
+        Protocol::Tcp,
+    );
+
+    (question, request)
+}
+
+/// Verifies each message of a response to a signed request
+fn verify_response(signer: &TSigner, question: &Message, responses: &[Vec<u8>]) {
+    let mut state = Vec::new();
+    for (i, response) in responses.iter().enumerate() {
+        signer
+            .verify_response(
+                question,
+                response,
+                &mut state,
+                i + 1 == responses.len(),
+                now(),
+            )
+            .expect("the response did not verify");
+    }
+}
+
+#[test]
+fn test_axfr_allowed_by_tsig() {
+    let mut test = create_test();
+    test.set_allow_transfer(Acl::new(vec!["127.0.0.0/8".parse().unwrap()]));
+    test.add_transfer_tsig_key(tsigner(b"secret"));
+    let origin: Name = test.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let signer = tsigner(b"secret");
+    let (question, request) = signed_axfr_request(&origin, &signer, now());
+    let responses = handle_raw(&catalog, request);
+    verify_response(&signer, &question, &responses);
+
+    let response = Message::from_bytes(&responses[0]).unwrap();
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert_eq!(response.answers().len(), 8);
+    assert!(response.tsig().is_some());
+}
+
+#[test]
+fn test_axfr_tsig_outside_of_network() {
+    let mut test = create_test();
+    test.set_allow_transfer(vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))].into());
+    test.add_transfer_tsig_key(tsigner(b"secret"));
+    let origin: Name = test.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    // even signed with a transfer key, transfers from outside of the network are refused
+    let (_, request) = signed_axfr_request(&origin, &tsigner(b"secret"), now());
+    let messages = handle(&catalog, request);
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::Refused);
+}
+
+#[test]
+fn test_axfr_tsig_update_key() {
+    let mut test = create_test();
+    test.set_allow_transfer(Acl::new(vec!["127.0.0.0/8".parse().unwrap()]));
+    test.add_tsig_key(tsigner(b"secret"));
+    let origin: Name = test.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    // the key is valid for the zone, but only for updates
+    let (_, request) = signed_axfr_request(&origin, &tsigner(b"secret"), now());
+    let messages = handle(&catalog, request);
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::Refused);
+}
+
+#[test]
+fn test_axfr_unsigned_with_transfer_key() {
+    let mut test = create_test();
+    test.set_allow_transfer(vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))].into());
+    test.add_transfer_tsig_key(tsigner(b"secret"));
+    let origin: Name = test.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(&catalog, axfr_request(&origin, "127.0.0.1:1234", Protocol::Tcp));
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::Refused);
+}
+
+#[test]
+fn test_axfr_multiple_messages_signed_with_tsig() {
+    let mut test = create_test();
+    test.set_allow_transfer(Acl::new(vec!["127.0.0.0/8".parse().unwrap()]));
+    test.add_transfer_tsig_key(tsigner(b"secret"));
+    let origin: Name = test.origin().clone().into();
+
+    for i in 0..4096 {
+        let name = Name::parse(&format!("host-{}", i), Some(&origin)).unwrap();
+        test.upsert(
+            Record::new()
+                .set_name(name)
+                .set_ttl(86400)
+                .set_rr_type(RecordType::A)
+                .set_dns_class(DNSClass::IN)
+                .set_rdata(RData::A(Ipv4Addr::new(10, 0, (i / 256) as u8, (i % 256) as u8)))
+                .clone(),
+            0,
+        );
+    }
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let signer = tsigner(b"secret");
+    let (question, request) = signed_axfr_request(&origin, &signer, now());
+    let responses = handle_raw(&catalog, request);
+    assert!(responses.len() > 1);
+    verify_response(&signer, &question, &responses);
+}
+
+#[test]
+fn test_tsig_bad_key() {
+    let mut test = create_test();
+    test.add_tsig_key(tsigner(b"secret"));
+    let origin: Name = test.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let signer = TSigner::new(
+        Name::parse("other.example.com.", None).unwrap(),
+        TsigAlgorithm::HmacSha256,
+        b"secret".to_vec(),
+        DEFAULT_TSIG_FUDGE,
+    ).unwrap();
+    let (_, request) = signed_axfr_request(&origin, &signer, now());
+    let messages = handle(&catalog, request);
+
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::NotAuth);
+    assert!(messages[0].answers().is_empty());
+    match messages[0].tsig().map(Record::rdata) {
+        Some(&RData::TSIG(ref tsig)) => {
+            assert_eq!(tsig.error(), u16::from(ResponseCode::BADKEY));
+            assert!(tsig.mac().is_empty());
+        }
+        _ => panic!("the error should have a TSIG"),
+    }
+}
+
+#[test]
+fn test_tsig_bad_sig() {
+    let mut test = create_test();
+    test.add_tsig_key(tsigner(b"secret"));
+    let origin: Name = test.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let (_, request) = signed_axfr_request(&origin, &tsigner(b"not the secret"), now());
+    let messages = handle(&catalog, request);
+
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::NotAuth);
+    match messages[0].tsig().map(Record::rdata) {
+        Some(&RData::TSIG(ref tsig)) => {
+            assert_eq!(tsig.error(), u16::from(ResponseCode::BADSIG))
+        }
+        _ => panic!("the error should have a TSIG"),
+    }
+}
+
+#[test]
+fn test_tsig_bad_time() {
+    let mut test = create_test();
+    test.add_tsig_key(tsigner(b"secret"));
+    let origin: Name = test.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let signer = tsigner(b"secret");
+    let (question, request) = signed_axfr_request(&origin, &signer, now() - 3600);
+    let responses = handle_raw(&catalog, request);
+    assert_eq!(responses.len(), 1);
+
+    let response = Message::from_bytes(&responses[0]).unwrap();
+    assert_eq!(response.response_code(), ResponseCode::NotAuth);
+
+    // the error is signed with the key, with the time signed of the request
+    let request_tsig = match question.tsig().map(Record::rdata) {
+        Some(&RData::TSIG(ref tsig)) => tsig.clone(),
+        _ => panic!("the request should have a TSIG"),
+    };
+    let (unsigned, record) = tsig::split_message(&responses[0])
+        .unwrap()
+        .expect("the error should have a TSIG");
+    let tsig = signer
+        .verify_message(&unsigned, &tsig::prefixed_mac(request_tsig.mac()), &record, true)
+        .expect("the error did not verify");
+    assert_eq!(tsig.error(), u16::from(ResponseCode::BADTIME));
+    assert_eq!(tsig.time(), request_tsig.time());
+    assert_eq!(tsig.other().len(), 6);
+}
+
+fn ixfr_request(
+    origin: &Name,
+    serial: u32,
+    src: &str,
+    protocol: Protocol,
+) -> (Vec<u8>, SocketAddr, Protocol) {
+    let mut query: Query = Query::new();
+    query.set_name(origin.clone());
+    query.set_query_type(RecordType::IXFR);
+
+    let soa = Record::new()
+        .set_name(origin.clone())
+        .set_rr_type(RecordType::SOA)
+        .set_dns_class(DNSClass::IN)
+        .set_rdata(RData::SOA(SOA::new(
+            Name::parse("sns.dns.icann.org.", None).unwrap(),
+            Name::parse("noc.dns.icann.org.", None).unwrap(),
+            serial,
+            7200,
+            3600,
+            1209600,
+            3600,
+        )))
+        .clone();
+
+    let mut question: Message = Message::new();
+    question.add_query(query);
+    question.add_name_server(soa);
+
+    (
+        question.to_bytes().unwrap(),
+        src.parse().expect("cannot parse host and port"),
+        protocol,
+    )
+}
+
+fn create_journaled_test() -> (Authority, u32) {
+    let conn = Connection::open_in_memory().expect("could not create in memory DB");
+    let mut journal = Journal::new(conn).unwrap();
+    journal.schema_up().unwrap();
+
+    let mut test = create_test();
+    test.set_allow_transfer(vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))].into());
+    test.set_journal(journal);
+    test.persist_to_journal().unwrap();
+    let serial = test.serial();
+
+    let new_record = Record::new()
+        .set_name(Name::parse("new.test.com.", None).unwrap())
+        .set_ttl(86400)
+        .set_dns_class(DNSClass::IN)
+        .set_rr_type(RecordType::A)
+        .set_rdata(RData::A(Ipv4Addr::new(10, 11, 12, 13)))
+        .clone();
+    test.update_records(&[new_record], true).unwrap();
+
+    let delete_record = Record::new()
+        .set_name(Name::parse("www.test.com.", None).unwrap())
+        .set_rr_type(RecordType::A)
+        .set_dns_class(DNSClass::NONE)
+        .set_rdata(RData::A(Ipv4Addr::new(94, 184, 216, 34)))
+        .clone();
+    test.update_records(&[delete_record], true).unwrap();
+
+    (test, serial)
+}
+
+#[test]
+fn test_ixfr() {
+    let (test, serial) = create_journaled_test();
+    let origin: Name = test.origin().clone().into();
+    let soa = test.soa().iter().next().cloned().unwrap();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(
+        &catalog,
+        ixfr_request(&origin, serial, "127.0.0.1:1234", Protocol::Tcp),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+
+    // current soa, two difference sequences of 2 SOAs and 1 record, current soa
+    let answers = messages[0].answers();
+    assert_eq!(answers.len(), 8);
+    assert_eq!(answers.first().unwrap(), &soa);
+    assert_eq!(answers.last().unwrap(), &soa);
+    assert_eq!(answers[3].name(), &Name::parse("new.test.com.", None).unwrap());
+    assert_eq!(answers[5].name(), &Name::parse("www.test.com.", None).unwrap());
+    assert_eq!(answers[6], soa);
+}
+
+#[test]
+fn test_ixfr_up_to_date() {
+    let (test, _) = create_journaled_test();
+    let origin: Name = test.origin().clone().into();
+    let serial = test.serial();
+    let soa = test.soa().iter().next().cloned().unwrap();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(
+        &catalog,
+        ixfr_request(&origin, serial, "127.0.0.1:1234", Protocol::Tcp),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].answers(), &[soa]);
+}
+
+#[test]
+fn test_ixfr_udp_only_soa() {
+    let (test, serial) = create_journaled_test();
+    let origin: Name = test.origin().clone().into();
+    let soa = test.soa().iter().next().cloned().unwrap();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(
+        &catalog,
+        ixfr_request(&origin, serial, "127.0.0.1:1234", Protocol::Udp),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+    assert_eq!(messages[0].answers(), &[soa]);
+}
+
+#[test]
+fn test_ixfr_unknown_serial_sends_zone() {
+    let (test, serial) = create_journaled_test();
+    let origin: Name = test.origin().clone().into();
+    let soa = test.soa().iter().next().cloned().unwrap();
+    let zone_len = test.records().values().map(|rr_set| rr_set.iter().count()).sum::<usize>();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(
+        &catalog,
+        ixfr_request(&origin, serial - 1, "127.0.0.1:1234", Protocol::Tcp),
+    );
+    assert_eq!(messages.len(), 1);
+
+    let answers = messages[0].answers();
+    assert_eq!(answers.len(), zone_len + 1);
+    assert_eq!(answers.first().unwrap(), &soa);
+    assert_eq!(answers.last().unwrap(), &soa);
+}
+
+fn notify_request(
+    origin: &Name,
+    serial: Option<u32>,
+    src: &str,
+) -> (Vec<u8>, SocketAddr, Protocol) {
+    let mut query: Query = Query::new();
+    query.set_name(origin.clone());
+    query.set_query_type(RecordType::SOA);
+
+    let mut message: Message = Message::new();
+    message.set_op_code(OpCode::Notify);
+    message.add_query(query);
+
+    if let Some(serial) = serial {
+        let soa = Record::new()
+            .set_name(origin.clone())
+            .set_rr_type(RecordType::SOA)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(RData::SOA(SOA::new(
+                Name::parse("sns.dns.icann.org.", None).unwrap(),
+                Name::parse("noc.dns.icann.org.", None).unwrap(),
+                serial,
+                7200,
+                3600,
+                1209600,
+                3600,
+            )))
+            .clone();
+        message.add_answer(soa);
+    }
+
+    (
+        message.to_bytes().unwrap(),
+        src.parse().expect("cannot parse host and port"),
+        Protocol::Udp,
+    )
+}
+
+/// A slave copy of test.com. accepting NOTIFY from 127.0.0.1, with the receiver of its refreshes
+fn create_slave_test() -> (Authority, futures::sync::mpsc::UnboundedReceiver<()>) {
+    let test = create_test();
+    let mut slave = Authority::new(
+        test.origin().clone().into(),
+        BTreeMap::new(),
+        ZoneType::Slave,
+        false,
+        false,
+    );
+    slave.upsert(test.soa().iter().next().cloned().unwrap(), 0);
+    slave.set_allow_notify(vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))].into());
+
+    let (sender, receiver) = unbounded();
+    slave.set_refresh_trigger(sender);
+    (slave, receiver)
+}
+
+#[test]
+fn test_notify_triggers_refresh() {
+    let (slave, receiver) = create_slave_test();
+    let origin: Name = slave.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), slave);
+
+    let messages = handle(
+        &catalog,
+        notify_request(&origin, Some(2015082404), "127.0.0.1:1234"),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+    assert_eq!(messages[0].op_code(), OpCode::Notify);
+    assert_eq!(messages[0].message_type(), MessageType::Response);
+    assert!(messages[0].authoritative());
+    assert_eq!(messages[0].queries().len(), 1);
+
+    // without a hint the zone is refreshed as well
+    handle(&catalog, notify_request(&origin, None, "127.0.0.1:1234"));
+
+    // dropping the catalog closes the channel
+    drop(catalog);
+    assert_eq!(receiver.wait().count(), 2);
+}
+
+#[test]
+fn test_notify_up_to_date() {
+    let (slave, receiver) = create_slave_test();
+    let origin: Name = slave.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), slave);
+
+    let messages = handle(
+        &catalog,
+        notify_request(&origin, Some(2015082403), "127.0.0.1:1234"),
+    );
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+
+    drop(catalog);
+    assert_eq!(receiver.wait().count(), 0);
+}
+
+#[test]
+fn test_notify_refused_not_allowed() {
+    let (slave, receiver) = create_slave_test();
+    let origin: Name = slave.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), slave);
+
+    let messages = handle(
+        &catalog,
+        notify_request(&origin, Some(2015082404), "127.0.0.2:1234"),
+    );
+    assert_eq!(messages[0].response_code(), ResponseCode::Refused);
+
+    drop(catalog);
+    assert_eq!(receiver.wait().count(), 0);
+}
+
+#[test]
+fn test_notify_master_not_auth() {
+    let test = create_test();
+    let origin: Name = test.origin().clone().into();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), test);
+
+    let messages = handle(
+        &catalog,
+        notify_request(&origin, Some(2015082404), "127.0.0.1:1234"),
+    );
+    assert_eq!(messages[0].response_code(), ResponseCode::NotAuth);
+}
+
+#[test]
+fn test_forward_without_forwarder() {
+    let forward = Authority::new(Name::root(), BTreeMap::new(), ZoneType::Forward, false, false);
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(Name::root().into(), forward);
+
+    let mut question: Message = Message::new();
+    let mut query: Query = Query::new();
+    query.set_name(Name::parse("www.example.com.", None).unwrap());
+    question.add_query(query);
+
+    let messages = handle(
+        &catalog,
+        (
+            question.to_bytes().unwrap(),
+            "127.0.0.1:1234".parse().unwrap(),
+            Protocol::Udp,
+        ),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::ServFail);
+}
+
+#[test]
+fn test_referral() {
+    let mut example = create_example();
+    let origin = example.origin().clone();
+
+    let child = Name::parse("sub.example.com.", None).unwrap();
+    let ns_name = Name::parse("ns.sub.example.com.", None).unwrap();
+    example.upsert(
+        Record::from_rdata(child.clone(), 86400, RecordType::NS, RData::NS(ns_name.clone())),
+        0,
+    );
+    example.upsert(
+        Record::from_rdata(
+            ns_name.clone(),
+            86400,
+            RecordType::A,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+        ),
+        0,
+    );
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), example);
+
+    let mut question: Message = Message::new();
+    let mut query: Query = Query::new();
+    query.set_name(Name::parse("www.sub.example.com.", None).unwrap());
+    question.add_query(query);
+
+    let messages = handle(
+        &catalog,
+        (
+            question.to_bytes().unwrap(),
+            "127.0.0.1:1234".parse().unwrap(),
+            Protocol::Udp,
+        ),
+    );
+    assert_eq!(messages.len(), 1);
+
+    let response = &messages[0];
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(!response.authoritative());
+    assert!(response.answers().is_empty());
+
+    assert_eq!(response.name_servers().len(), 1);
+    assert_eq!(*response.name_servers()[0].name(), child);
+    assert_eq!(*response.name_servers()[0].rdata(), RData::NS(ns_name.clone()));
+
+    assert_eq!(response.additionals().len(), 1);
+    assert_eq!(*response.additionals()[0].name(), ns_name);
+    assert_eq!(
+        *response.additionals()[0].rdata(),
+        RData::A(Ipv4Addr::new(192, 0, 2, 1))
+    );
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_ds_at_zone_cut_is_not_referred() {
+    use trust_dns::rr::dnssec::{Algorithm, DigestType};
+
+    let mut example = create_example();
+    let origin = example.origin().clone();
+
+    let child = Name::parse("sub.example.com.", None).unwrap();
+    let ds = RData::DNSSEC(DNSSECRData::DS(DS::new(
+        12345,
+        Algorithm::RSASHA256,
+        DigestType::SHA256,
+        vec![1; 32],
+    )));
+    example.upsert(
+        Record::from_rdata(
+            child.clone(),
+            86400,
+            RecordType::NS,
+            RData::NS(Name::parse("ns.sub.example.com.", None).unwrap()),
+        ),
+        0,
+    );
+    example.upsert(
+        Record::from_rdata(child.clone(), 86400, ds.to_record_type(), ds.clone()),
+        0,
+    );
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), example);
+
+    let ds_query = |name: &Name| {
+        let mut question: Message = Message::new();
+        question.add_query(Query::query(name.clone(), ds.to_record_type()));
+
+        let messages = handle(
+            &catalog,
+            (
+                question.to_bytes().unwrap(),
+                "127.0.0.1:1234".parse().unwrap(),
+                Protocol::Udp,
+            ),
+        );
+        assert_eq!(messages.len(), 1);
+        messages.into_iter().next().unwrap()
+    };
+
+    // the DS of the child is answered by the parent, authoritatively
+    let response = ds_query(&child);
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(response.authoritative());
+    assert_eq!(response.answers().len(), 1);
+    assert_eq!(*response.answers()[0].rdata(), ds);
+
+    // below the zone cut, DS queries are referred to the child
+    let response = ds_query(&Name::parse("www.sub.example.com.", None).unwrap());
+    assert!(!response.authoritative());
+    assert!(response.answers().is_empty());
+    assert_eq!(*response.name_servers()[0].name(), child);
+}
+
+fn cookie_request(
+    name: &Name,
+    cookie: EdnsOption,
+    protocol: Protocol,
+) -> (Vec<u8>, SocketAddr, Protocol) {
+    let mut edns = Edns::new();
+    edns.set_option(cookie);
+
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(name.clone(), RecordType::A));
+    question.set_edns(edns);
+
+    (
+        question.to_bytes().unwrap(),
+        "192.0.2.1:1234".parse().unwrap(),
+        protocol,
+    )
+}
+
+fn response_cookie(response: &Message) -> Cookie {
+    match response.edns().and_then(|edns| edns.option(&EdnsCode::Cookie)) {
+        Some(&EdnsOption::Cookie(ref cookie)) => cookie.clone(),
+        option => panic!("expected a cookie: {:?}", option),
+    }
+}
+
+fn cookie_catalog() -> (Catalog, Name) {
+    let test = create_test();
+    let origin: Name = test.origin().clone().into();
+    let www = Name::parse("www", Some(&origin)).unwrap();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.into(), test);
+    catalog.set_server_cookies(Arc::new(ServerCookies::new(vec![1; 16]).unwrap()));
+
+    (catalog, www)
+}
+
+#[test]
+fn test_cookie_minted() {
+    let (catalog, www) = cookie_catalog();
+
+    // a client cookie alone is answered with a server cookie
+    let client_cookie = Cookie::new([7; 8], Vec::new());
+    let messages = handle(
+        &catalog,
+        cookie_request(&www, EdnsOption::Cookie(client_cookie), Protocol::Udp),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+    assert!(!messages[0].answers().is_empty());
+
+    let cookie = response_cookie(&messages[0]);
+    assert_eq!(cookie.client(), &[7; 8]);
+    assert!(cookie.has_server());
+
+    // which is then accepted
+    let messages = handle(
+        &catalog,
+        cookie_request(&www, EdnsOption::Cookie(cookie), Protocol::Udp),
+    );
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+    assert!(!messages[0].answers().is_empty());
+    assert_eq!(response_cookie(&messages[0]).client(), &[7; 8]);
+}
+
+#[test]
+fn test_bad_cookie() {
+    let (catalog, www) = cookie_catalog();
+
+    // a server cookie minted with another secret
+    let other = ServerCookies::new(vec![2; 16]).unwrap();
+    let cookie = other
+        .mint(&[7; 8], "192.0.2.1".parse().unwrap(), now())
+        .unwrap();
+
+    let messages = handle(
+        &catalog,
+        cookie_request(&www, EdnsOption::Cookie(cookie.clone()), Protocol::Udp),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::BADCOOKIE);
+    assert!(messages[0].answers().is_empty());
+
+    // the fresh cookie is valid
+    let fresh = response_cookie(&messages[0]);
+    assert_eq!(fresh.client(), &[7; 8]);
+    let messages = handle(
+        &catalog,
+        cookie_request(&www, EdnsOption::Cookie(fresh), Protocol::Udp),
+    );
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+
+    // TCP requests are answered regardless
+    let messages = handle(
+        &catalog,
+        cookie_request(&www, EdnsOption::Cookie(cookie), Protocol::Tcp),
+    );
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+    assert!(!messages[0].answers().is_empty());
+}
+
+#[test]
+fn test_malformed_cookie() {
+    let (catalog, www) = cookie_catalog();
+
+    let messages = handle(
+        &catalog,
+        cookie_request(&www, EdnsOption::Unknown(10, vec![7; 12]), Protocol::Udp),
+    );
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].response_code(), ResponseCode::FormErr);
+}
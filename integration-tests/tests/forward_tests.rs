@@ -0,0 +1,217 @@
+extern crate futures;
+extern crate tokio_core;
+extern crate trust_dns;
+extern crate trust_dns_integration;
+extern crate trust_dns_proto;
+extern crate trust_dns_server;
+
+use std::collections::BTreeMap;
+use std::io;
+use std::net::*;
+use std::time::Duration;
+
+use futures::Stream;
+use futures::sync::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+
+use trust_dns::op::*;
+use trust_dns::rr::*;
+use trust_dns::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder};
+use trust_dns_proto::op::EncodableMessage;
+
+use trust_dns_server::ServerFuture;
+use trust_dns_server::authority::*;
+use trust_dns_server::server::{Protocol, Request, RequestHandler, ResponseHandler};
+
+use trust_dns_integration::authority::create_example;
+
+/// Sends the responses over a channel, so that they can be awaited on the reactor
+#[derive(Clone)]
+struct ChannelResponseHandler(UnboundedSender<Vec<u8>>);
+
+impl ResponseHandler for ChannelResponseHandler {
+    fn send<M: EncodableMessage>(self, response: M) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(512);
+        {
+            let mut encoder = BinEncoder::new(&mut buf);
+            response.emit(&mut encoder).expect("could not encode");
+        }
+
+        self.0
+            .unbounded_send(buf)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "receiver dropped"))
+    }
+}
+
+/// An upstream serving the example.com. zone over UDP and TCP on loopback
+fn upstream() -> (ServerFuture<Catalog>, SocketAddr) {
+    let example = create_example();
+    let mut catalog = Catalog::new();
+    catalog.upsert(example.origin().clone(), example);
+
+    let udp_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = udp_socket.local_addr().unwrap();
+    let tcp_listener = TcpListener::bind(addr).unwrap();
+
+    let server = ServerFuture::new(catalog).expect("error creating ServerFuture");
+    server.register_socket(udp_socket);
+    server
+        .register_listener(tcp_listener, Duration::from_secs(30))
+        .expect("could not register TCP listener");
+
+    (server, addr)
+}
+
+/// A catalog forwarding the root, i.e. everything, to the upstream
+fn forwarding(upstream: &mut ServerFuture<Catalog>, addr: SocketAddr) -> Catalog {
+    let mut root = Authority::new(Name::root(), BTreeMap::new(), ZoneType::Forward, false, false);
+    root.set_forwarder(Forwarder::new(&[addr], &upstream.tokio_core().handle()));
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(Name::root().into(), root);
+    catalog
+}
+
+fn query(name: &str, record_type: RecordType) -> Vec<u8> {
+    let mut query: Query = Query::new();
+    query.set_name(Name::parse(name, None).unwrap());
+    query.set_query_type(record_type);
+
+    let mut message: Message = Message::new();
+    message.set_id(10);
+    message.set_recursion_desired(true);
+    message.add_query(query);
+    message.to_bytes().unwrap()
+}
+
+/// Handles the request with the catalog, and runs the upstream until the response is sent
+fn resolve(
+    upstream: &mut ServerFuture<Catalog>,
+    catalog: &Catalog,
+    question_bytes: &[u8],
+) -> Message {
+    let request = Request {
+        message: MessageRequest::from_bytes(question_bytes).unwrap(),
+        src: "127.0.0.1:1234".parse().unwrap(),
+        protocol: Protocol::Udp,
+    };
+
+    let (sender, receiver): (_, UnboundedReceiver<Vec<u8>>) = unbounded();
+    catalog
+        .handle_request(&request, ChannelResponseHandler(sender))
+        .expect("handle_request failed");
+
+    let (response, _) = upstream
+        .tokio_core()
+        .run(receiver.into_future())
+        .ok()
+        .unwrap();
+    let response = response.expect("no response was sent");
+
+    let mut decoder = BinDecoder::new(&response);
+    Message::read(&mut decoder).expect("could not decode message")
+}
+
+#[test]
+fn test_forward() {
+    let (mut upstream, addr) = upstream();
+    let catalog = forwarding(&mut upstream, addr);
+
+    let response = resolve(
+        &mut upstream,
+        &catalog,
+        &query("www.example.com.", RecordType::A),
+    );
+
+    assert_eq!(response.id(), 10);
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert_eq!(response.message_type(), MessageType::Response);
+    assert!(!response.authoritative());
+    assert!(response.recursion_desired());
+    assert!(response.recursion_available());
+    assert_eq!(response.queries().len(), 1);
+
+    assert_eq!(response.answers().len(), 1);
+    let answer = &response.answers()[0];
+    assert_eq!(*answer.name(), Name::parse("www.example.com.", None).unwrap());
+    assert_eq!(*answer.rdata(), RData::A(Ipv4Addr::new(93, 184, 216, 34)));
+    assert!(answer.ttl() > 0 && answer.ttl() <= 86400);
+}
+
+#[test]
+fn test_forward_cached() {
+    let (mut upstream, addr) = upstream();
+    let catalog = forwarding(&mut upstream, addr);
+
+    let question = query("www.example.com.", RecordType::A);
+    let first = resolve(&mut upstream, &catalog, &question);
+
+    // the second answer comes from the cache, with the remaining TTL
+    let second = resolve(&mut upstream, &catalog, &question);
+    assert_eq!(second.response_code(), ResponseCode::NoError);
+    assert_eq!(second.answers()[0].rdata(), first.answers()[0].rdata());
+    assert!(second.answers()[0].ttl() <= first.answers()[0].ttl());
+}
+
+#[test]
+fn test_forward_no_records() {
+    let (mut upstream, addr) = upstream();
+    let catalog = forwarding(&mut upstream, addr);
+
+    let response = resolve(
+        &mut upstream,
+        &catalog,
+        &query("nx.example.com.", RecordType::A),
+    );
+
+    assert_eq!(response.response_code(), ResponseCode::NXDomain);
+    assert!(response.recursion_available());
+    assert!(response.answers().is_empty());
+
+    // the SOA of the zone, for negative caching
+    assert_eq!(response.name_servers().len(), 1);
+    let soa = &response.name_servers()[0];
+    assert_eq!(soa.rr_type(), RecordType::SOA);
+    assert_eq!(*soa.name(), Name::parse("example.com.", None).unwrap());
+}
+
+#[test]
+fn test_forward_no_data() {
+    let (mut upstream, addr) = upstream();
+    let catalog = forwarding(&mut upstream, addr);
+
+    let response = resolve(
+        &mut upstream,
+        &catalog,
+        &query("www.example.com.", RecordType::MX),
+    );
+
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(response.answers().is_empty());
+    assert_eq!(response.name_servers().len(), 1);
+    assert_eq!(response.name_servers()[0].rr_type(), RecordType::SOA);
+}
+
+#[test]
+fn test_forward_authoritative_zone_is_not_forwarded() {
+    let (mut upstream, addr) = upstream();
+    let mut catalog = forwarding(&mut upstream, addr);
+
+    // a local, more specific, zone is answered authoritatively
+    let origin = Name::parse("test.com.", None).unwrap();
+    let mut soa = create_example().soa().iter().next().cloned().unwrap();
+    soa.set_name(origin.clone());
+
+    let mut test = Authority::new(origin.clone(), BTreeMap::new(), ZoneType::Master, false, false);
+    test.upsert(soa, 0);
+    catalog.upsert(origin.into(), test);
+
+    let response = resolve(
+        &mut upstream,
+        &catalog,
+        &query("test.com.", RecordType::SOA),
+    );
+
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(response.authoritative());
+    assert!(!response.recursion_available());
+}
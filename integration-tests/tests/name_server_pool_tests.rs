@@ -1,3 +1,4 @@
+extern crate futures;
 extern crate tokio_core;
 extern crate trust_dns;
 extern crate trust_dns_integration;
@@ -6,15 +7,18 @@ extern crate trust_dns_resolver;
 
 use std::net::*;
 use std::str::FromStr;
+use std::time::Duration;
 
-use tokio_core::reactor::{Core, Handle};
+use futures::{Future, IntoFuture};
+use tokio_core::reactor::{Core, Handle, Timeout};
 
 use trust_dns::op::{Message, Query};
 use trust_dns::rr::{Name, RecordType};
-use trust_dns_proto::DnsHandle;
+use trust_dns_proto::{CookieJar, DnsHandle};
 use trust_dns_resolver::config::*;
 use trust_dns_resolver::error::*;
-use trust_dns_resolver::name_server_pool::{ConnectionProvider, NameServer, NameServerPool};
+use trust_dns_resolver::name_server_pool::{ConnectionProvider, NameServer, NameServerPool,
+                                           NameServerRank};
 use trust_dns_integration::mock_client::*;
 
 #[derive(Clone)]
@@ -23,7 +27,12 @@ struct MockConnProvider {}
 impl ConnectionProvider for MockConnProvider {
     type ConnHandle = MockClientHandle<ResolveError>;
 
-    fn new_connection(_: &NameServerConfig, _: &ResolverOpts, _: &Handle) -> Self::ConnHandle {
+    fn new_connection(
+        _: &NameServerConfig,
+        _: &ResolverOpts,
+        _: &CookieJar,
+        _: &Handle,
+    ) -> Self::ConnHandle {
         MockClientHandle::mock(vec![])
     }
 }
@@ -144,3 +153,164 @@ fn test_datagram_fails_to_stream() {
     let response = reactor.run(future).unwrap();
     assert_eq!(response.answers()[0], tcp_record);
 }
+
+/// A name server which echoes the requests after a delay
+#[derive(Clone)]
+struct DelayedClientHandle {
+    delay: Duration,
+    reactor: Handle,
+}
+
+impl DnsHandle for DelayedClientHandle {
+    type Error = ResolveError;
+
+    fn send(&mut self, message: Message) -> Box<Future<Item = Message, Error = Self::Error>> {
+        Box::new(
+            Timeout::new(self.delay, &self.reactor)
+                .into_future()
+                .flatten()
+                .map(move |_| message)
+                .map_err(ResolveError::from),
+        )
+    }
+}
+
+#[derive(Clone)]
+struct DelayedConnProvider {}
+
+impl ConnectionProvider for DelayedConnProvider {
+    type ConnHandle = DelayedClientHandle;
+
+    fn new_connection(
+        _: &NameServerConfig,
+        _: &ResolverOpts,
+        _: &CookieJar,
+        reactor: &Handle,
+    ) -> Self::ConnHandle {
+        DelayedClientHandle {
+            delay: Duration::from_millis(0),
+            reactor: reactor.clone(),
+        }
+    }
+}
+
+fn delayed_nameserver(
+    ip: Ipv4Addr,
+    delay: Duration,
+    reactor: &Handle,
+) -> NameServer<DelayedClientHandle, DelayedConnProvider> {
+    NameServer::from_conn(
+        NameServerConfig {
+            socket_addr: SocketAddr::new(ip.into(), 53),
+            protocol: Protocol::Udp,
+        },
+        ResolverOpts::default(),
+        DelayedClientHandle {
+            delay: delay,
+            reactor: reactor.clone(),
+        },
+        reactor,
+    )
+}
+
+fn rank_of(stats: &[NameServerRank], ip: Ipv4Addr) -> &NameServerRank {
+    stats
+        .iter()
+        .find(|rank| rank.config().socket_addr.ip() == IpAddr::from(ip))
+        .expect("name server not in the stats")
+}
+
+#[test]
+fn test_lowest_latency_preferred() {
+    let query = Query::query(
+        Name::from_str("www.example.com.").unwrap(),
+        RecordType::A,
+    );
+    let request = message::<ResolveError>(query, vec![], vec![], vec![]).unwrap();
+
+    let mut reactor = Core::new().unwrap();
+    let near = Ipv4Addr::new(127, 0, 0, 1);
+    let far = Ipv4Addr::new(127, 0, 0, 2);
+    let near_nameserver = delayed_nameserver(near, Duration::from_millis(5), &reactor.handle());
+    let far_nameserver = delayed_nameserver(far, Duration::from_millis(50), &reactor.handle());
+
+    let mut pool = NameServerPool::from_nameservers(
+        &ResolverOpts::default(),
+        vec![far_nameserver, near_nameserver],
+        vec![],
+    );
+
+    // each is measured once, and then the near one is preferred
+    for _ in 0..10 {
+        reactor.run(pool.send(request.clone())).unwrap();
+    }
+
+    let stats = pool.stats();
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0].config().socket_addr.ip(), IpAddr::from(near));
+    assert!(stats[0].srtt().unwrap() < stats[1].srtt().unwrap());
+    assert_eq!(rank_of(&stats, near).successes(), 9);
+    assert_eq!(rank_of(&stats, far).successes(), 1);
+    assert!(stats.iter().all(|rank| !rank.is_failed()));
+
+    // the far one is probed from time to time, its response is only measured
+    for _ in 10..32 {
+        reactor.run(pool.send(request.clone())).unwrap();
+    }
+    reactor
+        .run(Timeout::new(Duration::from_millis(200), &reactor.handle()).unwrap())
+        .unwrap();
+
+    let stats = pool.stats();
+    assert_eq!(stats[0].config().socket_addr.ip(), IpAddr::from(near));
+    assert_eq!(rank_of(&stats, near).successes(), 31);
+    assert_eq!(rank_of(&stats, far).successes(), 2);
+}
+
+#[test]
+fn test_failed_ranked_last() {
+    let query = Query::query(
+        Name::from_str("www.example.com.").unwrap(),
+        RecordType::A,
+    );
+    let request = message::<ResolveError>(query.clone(), vec![], vec![], vec![]).unwrap();
+    let response = message(query, vec![], vec![], vec![]);
+    let failure = Err(ResolveErrorKind::Msg(format!("Forced Testing Error")).into());
+
+    let mut reactor = Core::new().unwrap();
+    let failing = NameServer::from_conn(
+        NameServerConfig {
+            socket_addr: SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 53),
+            protocol: Protocol::Udp,
+        },
+        ResolverOpts::default(),
+        MockClientHandle::mock(vec![failure]),
+        &reactor.handle(),
+    );
+    let working = NameServer::from_conn(
+        NameServerConfig {
+            socket_addr: SocketAddr::new(Ipv4Addr::new(127, 0, 0, 2).into(), 53),
+            protocol: Protocol::Udp,
+        },
+        ResolverOpts::default(),
+        MockClientHandle::mock(vec![response.clone(), response]),
+        &reactor.handle(),
+    );
+
+    let mut pool = mock_nameserver_pool(vec![failing, working], vec![]);
+
+    // both are tried, the one which is not yet used is preferred
+    for _ in 0..2 {
+        let _ = reactor.run(pool.send(request.clone()));
+    }
+
+    let stats = pool.stats();
+    assert_eq!(stats.len(), 2);
+    assert!(!stats[0].is_failed());
+    assert_eq!(stats[0].successes(), 1);
+    assert!(stats[1].is_failed());
+    assert_eq!(stats[1].failures(), 1);
+
+    // the failure counts as a round trip of the whole timeout
+    assert_eq!(stats[1].srtt(), Some(ResolverOpts::default().timeout));
+}
@@ -0,0 +1,358 @@
+// the name servers of the hierarchy listen on distinct loopback addresses, with the same port
+#![cfg(target_os = "linux")]
+
+extern crate futures;
+extern crate tokio_core;
+extern crate trust_dns;
+extern crate trust_dns_proto;
+extern crate trust_dns_resolver;
+extern crate trust_dns_server;
+
+use std::collections::BTreeMap;
+use std::io;
+use std::net::*;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use futures::Stream;
+use futures::sync::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::sync::oneshot;
+use tokio_core::reactor::Core;
+
+use trust_dns::op::*;
+use trust_dns::rr::*;
+use trust_dns::rr::rdata::SOA;
+use trust_dns::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder};
+use trust_dns_proto::op::EncodableMessage;
+use trust_dns_resolver::Recursor;
+use trust_dns_resolver::config::ResolverOpts;
+use trust_dns_resolver::error::ResolveErrorKind;
+
+use trust_dns_server::ServerFuture;
+use trust_dns_server::authority::*;
+use trust_dns_server::server::{Protocol, Request, RequestHandler, ResponseHandler};
+
+fn name(name: &str) -> Name {
+    Name::parse(name, None).unwrap()
+}
+
+fn record(owner: &str, rdata: RData) -> Record {
+    Record::from_rdata(name(owner), 86400, rdata.to_record_type(), rdata)
+}
+
+fn zone(origin: &str, records: Vec<Record>) -> Authority {
+    let origin = name(origin);
+    let mut authority = Authority::new(
+        origin.clone(),
+        BTreeMap::new(),
+        ZoneType::Master,
+        false,
+        false,
+    );
+
+    let soa = RData::SOA(SOA::new(
+        name("ns.com."),
+        name("hostmaster.com."),
+        1,
+        7200,
+        3600,
+        1209600,
+        3600,
+    ));
+    authority.upsert(Record::from_rdata(origin, 3600, RecordType::SOA, soa), 0);
+
+    for record in records {
+        authority.upsert(record, 0);
+    }
+
+    authority
+}
+
+/// The root, delegates com. with glue, and net. to a name server without glue
+fn root() -> Vec<Authority> {
+    vec![
+        zone(
+            ".",
+            vec![
+                record(".", RData::NS(name("ns.root."))),
+                record("ns.root.", RData::A(Ipv4Addr::new(127, 0, 0, 2))),
+                record("com.", RData::NS(name("ns.com."))),
+                record("ns.com.", RData::A(Ipv4Addr::new(127, 0, 0, 3))),
+                record("net.", RData::NS(name("ns.example.com."))),
+            ],
+        ),
+    ]
+}
+
+/// com., delegates example.com. and has an alias into it
+fn com() -> Vec<Authority> {
+    vec![
+        zone(
+            "com.",
+            vec![
+                record("com.", RData::NS(name("ns.com."))),
+                record("ns.com.", RData::A(Ipv4Addr::new(127, 0, 0, 3))),
+                record("example.com.", RData::NS(name("ns.example.com."))),
+                record("ns.example.com.", RData::A(Ipv4Addr::new(127, 0, 0, 4))),
+                record("www.alias.com.", RData::CNAME(name("www.example.com."))),
+            ],
+        ),
+    ]
+}
+
+/// example.com. and net.
+fn example() -> Vec<Authority> {
+    vec![
+        zone(
+            "example.com.",
+            vec![
+                record("example.com.", RData::NS(name("ns.example.com."))),
+                record("ns.example.com.", RData::A(Ipv4Addr::new(127, 0, 0, 4))),
+                record("www.example.com.", RData::A(Ipv4Addr::new(93, 184, 216, 34))),
+                record("mail.example.com.", RData::A(Ipv4Addr::new(93, 184, 216, 35))),
+            ],
+        ),
+        zone(
+            "net.",
+            vec![
+                record("net.", RData::NS(name("ns.example.com."))),
+                record("www.example.net.", RData::A(Ipv4Addr::new(93, 184, 216, 36))),
+            ],
+        ),
+    ]
+}
+
+/// A name server, listening over UDP and TCP until it is shutdown
+struct NameServer {
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl NameServer {
+    fn spawn(addr: SocketAddr, zones: fn() -> Vec<Authority>) -> (Self, SocketAddr) {
+        let udp_socket = UdpSocket::bind(addr).unwrap();
+        let addr = udp_socket.local_addr().unwrap();
+        let tcp_listener = TcpListener::bind(addr).unwrap();
+        let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+
+        let thread = thread::Builder::new()
+            .name(format!("name_server:{}", addr))
+            .spawn(move || {
+                let mut catalog = Catalog::new();
+                for authority in zones() {
+                    catalog.upsert(authority.origin().clone(), authority);
+                }
+
+                let mut server = ServerFuture::new(catalog).expect("error creating ServerFuture");
+                server.register_socket(udp_socket);
+                server
+                    .register_listener(tcp_listener, Duration::from_secs(30))
+                    .expect("could not register TCP listener");
+
+                server.tokio_core().run(shutdown_signal).ok();
+            })
+            .unwrap();
+
+        let name_server = NameServer {
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        };
+
+        (name_server, addr)
+    }
+}
+
+impl Drop for NameServer {
+    fn drop(&mut self) {
+        self.shutdown.take().unwrap().send(()).ok();
+        self.thread.take().unwrap().join().ok();
+    }
+}
+
+/// The hierarchy of name servers, and a recursor with the root as its hint
+fn hierarchy(io_loop: &Core) -> (Vec<NameServer>, Recursor) {
+    let (root, root_addr) = NameServer::spawn("127.0.0.2:0".parse().unwrap(), root);
+    let port = root_addr.port();
+    let (com, _) = NameServer::spawn(SocketAddr::new([127, 0, 0, 3].into(), port), com);
+    let (example, _) = NameServer::spawn(SocketAddr::new([127, 0, 0, 4].into(), port), example);
+
+    let mut options = ResolverOpts::default();
+    options.timeout = Duration::from_secs(1);
+
+    let mut recursor = Recursor::new(vec![root_addr], options, &io_loop.handle());
+    recursor.set_name_server_port(port);
+
+    (vec![root, com, example], recursor)
+}
+
+#[test]
+fn test_recursor() {
+    let mut io_loop = Core::new().unwrap();
+    let (_name_servers, recursor) = hierarchy(&io_loop);
+
+    let records = io_loop
+        .run(recursor.resolve(Query::query(name("www.example.com."), RecordType::A)))
+        .expect("resolution failed");
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(*records[0].name(), name("www.example.com."));
+    assert_eq!(*records[0].rdata(), RData::A(Ipv4Addr::new(93, 184, 216, 34)));
+    assert!(records[0].ttl() > 0 && records[0].ttl() <= 86400);
+}
+
+#[test]
+fn test_recursor_cname_across_zones() {
+    let mut io_loop = Core::new().unwrap();
+    let (_name_servers, recursor) = hierarchy(&io_loop);
+
+    let records = io_loop
+        .run(recursor.resolve(Query::query(name("www.alias.com."), RecordType::A)))
+        .expect("resolution failed");
+
+    assert_eq!(records.len(), 2);
+    assert_eq!(*records[0].name(), name("www.alias.com."));
+    assert_eq!(*records[0].rdata(), RData::CNAME(name("www.example.com.")));
+    assert_eq!(*records[1].name(), name("www.example.com."));
+    assert_eq!(*records[1].rdata(), RData::A(Ipv4Addr::new(93, 184, 216, 34)));
+}
+
+#[test]
+fn test_recursor_name_server_without_glue() {
+    let mut io_loop = Core::new().unwrap();
+    let (_name_servers, recursor) = hierarchy(&io_loop);
+
+    let records = io_loop
+        .run(recursor.resolve(Query::query(name("www.example.net."), RecordType::A)))
+        .expect("resolution failed");
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(*records[0].rdata(), RData::A(Ipv4Addr::new(93, 184, 216, 36)));
+}
+
+#[test]
+fn test_recursor_caches_delegations() {
+    let mut io_loop = Core::new().unwrap();
+    let (mut name_servers, recursor) = hierarchy(&io_loop);
+
+    io_loop
+        .run(recursor.resolve(Query::query(name("www.example.com."), RecordType::A)))
+        .expect("resolution failed");
+
+    // without the root and com. name servers, only the cached delegation leads to example.com.
+    let _example = name_servers.pop();
+    drop(name_servers);
+
+    let records = io_loop
+        .run(recursor.resolve(Query::query(name("mail.example.com."), RecordType::A)))
+        .expect("resolution failed");
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(*records[0].rdata(), RData::A(Ipv4Addr::new(93, 184, 216, 35)));
+}
+
+#[test]
+fn test_recursor_nx_domain() {
+    let mut io_loop = Core::new().unwrap();
+    let (_name_servers, recursor) = hierarchy(&io_loop);
+
+    let error = io_loop
+        .run(recursor.resolve(Query::query(name("nx.example.com."), RecordType::A)))
+        .expect_err("nx.example.com. should not exist");
+
+    match *error.kind() {
+        ResolveErrorKind::NoRecordsFound(ref query, Some(ref soa), ResponseCode::NXDomain) => {
+            assert_eq!(*query.name(), name("nx.example.com."));
+            assert_eq!(*soa.name(), name("example.com."));
+        }
+        ref kind => panic!("unexpected error: {:?}", kind),
+    }
+}
+
+#[test]
+fn test_recursor_caches_nx_domain() {
+    let mut io_loop = Core::new().unwrap();
+    let (name_servers, recursor) = hierarchy(&io_loop);
+
+    io_loop
+        .run(recursor.resolve(Query::query(name("nx.example.com."), RecordType::A)))
+        .expect_err("nx.example.com. should not exist");
+
+    // without any name servers, the name does not exist for all types
+    drop(name_servers);
+
+    let error = io_loop
+        .run(recursor.resolve(Query::query(name("nx.example.com."), RecordType::MX)))
+        .expect_err("nx.example.com. should not exist");
+
+    match *error.kind() {
+        ResolveErrorKind::NoRecordsFound(ref query, Some(ref soa), ResponseCode::NXDomain) => {
+            assert_eq!(query.query_type(), RecordType::MX);
+            assert!(soa.ttl() <= 3600);
+        }
+        ref kind => panic!("unexpected error: {:?}", kind),
+    }
+}
+
+/// Sends the responses over a channel, so that they can be awaited on the reactor
+#[derive(Clone)]
+struct ChannelResponseHandler(UnboundedSender<Vec<u8>>);
+
+impl ResponseHandler for ChannelResponseHandler {
+    fn send<M: EncodableMessage>(self, response: M) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(512);
+        {
+            let mut encoder = BinEncoder::new(&mut buf);
+            response.emit(&mut encoder).expect("could not encode");
+        }
+
+        self.0
+            .unbounded_send(buf)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "receiver dropped"))
+    }
+}
+
+#[test]
+fn test_catalog_hint_zone() {
+    let mut io_loop = Core::new().unwrap();
+    let (_name_servers, recursor) = hierarchy(&io_loop);
+
+    // the root Hint zone, as named configures it, resolves everything with the recursor
+    let mut hint = Authority::new(Name::root(), BTreeMap::new(), ZoneType::Hint, false, false);
+    hint.set_forwarder(Forwarder::recursive(recursor, &io_loop.handle()));
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(Name::root().into(), hint);
+
+    let mut question: Message = Message::new();
+    question.set_id(10);
+    question.set_recursion_desired(true);
+    question.add_query(Query::query(name("www.example.com."), RecordType::A));
+    let question_bytes = question.to_bytes().unwrap();
+
+    let request = Request {
+        message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+        src: "127.0.0.1:1234".parse().unwrap(),
+        protocol: Protocol::Udp,
+    };
+
+    let (sender, receiver): (_, UnboundedReceiver<Vec<u8>>) = unbounded();
+    catalog
+        .handle_request(&request, ChannelResponseHandler(sender))
+        .expect("handle_request failed");
+
+    let (response, _) = io_loop.run(receiver.into_future()).ok().unwrap();
+    let response = response.expect("no response was sent");
+    let response = Message::read(&mut BinDecoder::new(&response)).expect("could not decode");
+
+    assert_eq!(response.id(), 10);
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(!response.authoritative());
+    assert!(response.recursion_available());
+
+    assert_eq!(response.answers().len(), 1);
+    assert_eq!(*response.answers()[0].name(), name("www.example.com."));
+    assert_eq!(
+        *response.answers()[0].rdata(),
+        RData::A(Ipv4Addr::new(93, 184, 216, 34))
+    );
+}
@@ -0,0 +1,152 @@
+#![cfg(feature = "dnssec")]
+
+extern crate openssl;
+extern crate tokio_core;
+extern crate trust_dns;
+extern crate trust_dns_integration;
+extern crate trust_dns_proto;
+extern crate trust_dns_resolver;
+extern crate trust_dns_server;
+
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpListener};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use openssl::rsa::Rsa;
+use tokio_core::reactor::Core;
+
+use trust_dns::rr::dnssec::KeyPair;
+use trust_dns_proto::rr::{Name, RData, RecordType};
+use trust_dns_proto::rr::dnssec::TrustAnchor;
+use trust_dns_resolver::ResolverFuture;
+use trust_dns_resolver::config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
+use trust_dns_resolver::error::ResolveErrorKind;
+use trust_dns_server::ServerFuture;
+use trust_dns_server::authority::{Authority, Catalog};
+
+use trust_dns_integration::authority::create_secure_example;
+
+#[test]
+fn test_resolver_validates_secure_zone() {
+    let authority = create_secure_example();
+    let trust_anchor = trust_anchor_of(&authority);
+
+    with_server(authority, |addr| {
+        let mut io_loop = Core::new().unwrap();
+        let resolver = ResolverFuture::with_trust_anchor(
+            resolver_config(addr),
+            ResolverOpts::default(),
+            trust_anchor,
+            &io_loop.handle(),
+        );
+
+        let lookup = io_loop
+            .run(resolver.lookup("www.example.com.", RecordType::A))
+            .expect("lookup failed");
+        assert!(lookup.is_secure());
+        assert_eq!(
+            lookup.iter().next(),
+            Some(&RData::A(Ipv4Addr::new(93, 184, 216, 34)))
+        );
+
+        // the second lookup is from the cache, and still secure
+        let lookup = io_loop
+            .run(resolver.lookup("www.example.com.", RecordType::A))
+            .expect("lookup failed");
+        assert!(lookup.is_secure());
+    });
+}
+
+#[test]
+fn test_resolver_rejects_bogus_zone() {
+    let authority = create_secure_example();
+
+    // a key which did not sign the zone
+    let trust_anchor = {
+        let key = KeyPair::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut trust_anchor = TrustAnchor::new();
+        trust_anchor.insert_trust_anchor(&key.to_public_key().unwrap());
+        trust_anchor
+    };
+
+    with_server(authority, |addr| {
+        let mut io_loop = Core::new().unwrap();
+        let resolver = ResolverFuture::with_trust_anchor(
+            resolver_config(addr),
+            ResolverOpts::default(),
+            trust_anchor,
+            &io_loop.handle(),
+        );
+
+        let error = io_loop
+            .run(resolver.lookup("www.example.com.", RecordType::A))
+            .expect_err("lookup should fail validation");
+        match *error.kind() {
+            ResolveErrorKind::Bogus(ref query, _) => {
+                assert_eq!(query.name(), &Name::from_str("www.example.com.").unwrap())
+            }
+            ref kind => panic!("expected Bogus, got: {}", kind),
+        }
+    });
+}
+
+fn trust_anchor_of(authority: &Authority) -> TrustAnchor {
+    let public_key = authority
+        .secure_keys()
+        .first()
+        .expect("expected a key in the authority")
+        .key()
+        .to_public_key()
+        .expect("could not convert keypair to public_key");
+
+    let mut trust_anchor = TrustAnchor::new();
+    trust_anchor.insert_trust_anchor(&public_key);
+    trust_anchor
+}
+
+fn resolver_config(addr: SocketAddr) -> ResolverConfig {
+    ResolverConfig::from_parts(
+        None,
+        vec![],
+        vec![
+            NameServerConfig {
+                socket_addr: addr,
+                protocol: Protocol::Tcp,
+            },
+        ],
+    )
+}
+
+fn with_server<F: FnOnce(SocketAddr)>(authority: Authority, test: F) {
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let tcp_listener = TcpListener::bind(&addr).unwrap();
+    let addr = tcp_listener.local_addr().unwrap();
+
+    let server_continue = Arc::new(AtomicBool::new(true));
+    let server_continue2 = server_continue.clone();
+
+    let server_thread = thread::Builder::new()
+        .name("test_resolver_dnssec:server".to_string())
+        .spawn(move || {
+            let mut catalog = Catalog::new();
+            catalog.upsert(authority.origin().clone().into(), authority);
+
+            let mut server = ServerFuture::new(catalog).expect("new tcp server failed");
+            server
+                .register_listener(tcp_listener, Duration::from_secs(30))
+                .expect("tcp registration failed");
+
+            while server_continue2.load(Ordering::Relaxed) {
+                server.tokio_core().turn(Some(Duration::from_millis(10)));
+            }
+        })
+        .unwrap();
+
+    test(addr);
+
+    server_continue.store(false, Ordering::Relaxed);
+    server_thread.join().unwrap();
+}
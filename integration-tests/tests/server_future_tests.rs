@@ -1,7 +1,11 @@
 extern crate futures;
 extern crate openssl;
 extern crate rustls;
+#[cfg(feature = "https")]
+extern crate tokio_core;
 extern crate trust_dns;
+#[cfg(feature = "https")]
+extern crate trust_dns_https;
 extern crate trust_dns_integration;
 extern crate trust_dns_openssl;
 extern crate trust_dns_rustls;
@@ -174,6 +178,92 @@ fn test_server_www_tls() {
     server_thread.join().unwrap();
 }
 
+#[cfg(feature = "https")]
+#[test]
+fn test_server_www_https() {
+    use std::path::Path;
+    use tokio_core::reactor::Core;
+    use trust_dns_https::HttpsClientStreamBuilder;
+    use trust_dns_rustls::tls_server::{read_cert, read_key};
+
+    let dns_name = "ns.example.com";
+
+    let server_path = env::var("TDNS_SERVER_SRC_ROOT").unwrap_or("../server".to_owned());
+    println!("using server src path: {}", server_path);
+
+    let ca_der = read_file(&format!("{}/../tests/ca.der", server_path));
+    let cert_chain = read_cert(Path::new(&format!("{}/../tests/cert.pem", server_path)))
+        .expect("failed to read cert");
+    let key = read_key(Path::new(&format!("{}/../tests/cert-key.pem", server_path)))
+        .expect("failed to read key");
+
+    // Server address
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let https_listener = TcpListener::bind(&addr).unwrap();
+
+    let ipaddr = https_listener.local_addr().unwrap();
+    println!("https_listener on port: {}", ipaddr);
+    let server_continue = Arc::new(AtomicBool::new(true));
+    let server_continue2 = server_continue.clone();
+
+    let server_thread = thread::Builder::new()
+        .name("test_server:https:server".to_string())
+        .spawn(move || {
+            let catalog = new_catalog();
+            let mut server = ServerFuture::new(catalog).expect("new https server failed");
+            server
+                .register_https_listener(
+                    https_listener,
+                    Duration::from_secs(30),
+                    cert_chain,
+                    key,
+                    "/dns-query".to_string(),
+                )
+                .expect("https registration failed");
+
+            while server_continue2.load(Ordering::Relaxed) {
+                server.tokio_core().turn(Some(Duration::from_millis(10)));
+            }
+        })
+        .unwrap();
+
+    let client_thread = thread::Builder::new()
+        .name("test_server:https:client".to_string())
+        .spawn(move || {
+            let mut io_loop = Core::new().unwrap();
+            let mut builder = HttpsClientStreamBuilder::new();
+            builder.add_ca(Certificate(ca_der));
+            let (stream, sender) = builder.build(
+                ipaddr,
+                dns_name.to_string(),
+                "/dns-query".to_string(),
+                &io_loop.handle(),
+            );
+            let mut client = ClientFuture::new(stream, sender, &io_loop.handle(), None);
+
+            // requests are multiplexed on the connection
+            let name = Name::from_str("www.example.com").unwrap();
+            for _ in 0..2 {
+                let response = io_loop
+                    .run(client.query(name.clone(), DNSClass::IN, RecordType::A))
+                    .expect("error querying");
+
+                assert_eq!(response.response_code(), ResponseCode::NoError);
+                assert_eq!(
+                    response.answers()[0].rdata(),
+                    &RData::A(Ipv4Addr::new(93, 184, 216, 34))
+                );
+            }
+        })
+        .unwrap();
+
+    let client_result = client_thread.join();
+
+    assert!(client_result.is_ok(), "client failed: {:?}", client_result);
+    server_continue.store(false, Ordering::Relaxed);
+    server_thread.join().unwrap();
+}
+
 fn lazy_udp_client(ipaddr: SocketAddr) -> UdpClientConnection {
     UdpClientConnection::new(ipaddr).unwrap()
 }
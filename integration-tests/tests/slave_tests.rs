@@ -0,0 +1,246 @@
+extern crate rusqlite;
+extern crate tokio_core;
+extern crate trust_dns;
+extern crate trust_dns_integration;
+extern crate trust_dns_server;
+
+use std::collections::BTreeMap;
+use std::net::*;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use rusqlite::Connection;
+use tokio_core::reactor::Timeout;
+
+use trust_dns::rr::*;
+use trust_dns::rr::rdata::SOA;
+
+use trust_dns_server::ServerFuture;
+use trust_dns_server::authority::*;
+
+use trust_dns_integration::authority::create_example;
+
+fn journal() -> Journal {
+    let conn = Connection::open_in_memory().expect("could not create in memory DB");
+    let mut journal = Journal::new(conn).unwrap();
+    journal.schema_up().unwrap();
+    journal
+}
+
+/// A primary serving the example.com. zone over TCP on loopback
+fn primary() -> (ServerFuture<Catalog>, Arc<RwLock<Authority>>, SocketAddr) {
+    let mut example = create_example();
+    example.set_allow_transfer(vec![IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))].into());
+    example.set_journal(journal());
+    example.persist_to_journal().unwrap();
+
+    let origin = example.origin().clone();
+    let mut catalog = Catalog::new();
+    catalog.upsert(origin.clone(), example);
+    let authority = catalog.authority(&origin).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = ServerFuture::new(catalog).expect("error creating ServerFuture");
+    server
+        .register_listener(listener, Duration::from_secs(30))
+        .expect("could not register TCP listener");
+
+    (server, authority, addr)
+}
+
+fn slave(masters: Vec<SocketAddr>) -> (SlaveZone, Arc<RwLock<Authority>>) {
+    let mut authority = Authority::new(
+        Name::parse("example.com.", None).unwrap(),
+        BTreeMap::new(),
+        ZoneType::Slave,
+        false,
+        false,
+    );
+    authority.set_journal(journal());
+
+    let authority = Arc::new(RwLock::new(authority));
+    (SlaveZone::new(authority.clone(), masters), authority)
+}
+
+fn www() -> LowerName {
+    LowerName::new(&Name::parse("www.example.com.", None).unwrap())
+}
+
+#[test]
+fn test_slave_axfr() {
+    let (mut primary, _, addr) = primary();
+    let (slave, slave_authority) = slave(vec![addr]);
+
+    let handle = primary.tokio_core().handle();
+    let refresh = primary.tokio_core().run(slave.refresh(&handle)).unwrap();
+    assert_eq!(refresh, Refresh::Full(2015082403));
+
+    {
+        let authority = slave_authority.read().unwrap();
+        assert_eq!(authority.serial(), 2015082403);
+        assert_eq!(
+            *authority
+                .lookup(&www(), RecordType::A, false, Default::default())
+                .iter()
+                .next()
+                .unwrap()
+                .rdata(),
+            RData::A(Ipv4Addr::new(93, 184, 216, 34))
+        );
+        assert_eq!(
+            authority.records().len(),
+            create_example().records().len()
+        );
+    }
+
+    // nothing has changed
+    let refresh = primary.tokio_core().run(slave.refresh(&handle)).unwrap();
+    assert_eq!(refresh, Refresh::UpToDate);
+}
+
+#[test]
+fn test_slave_ixfr() {
+    let (mut primary, primary_authority, addr) = primary();
+    let (slave, slave_authority) = slave(vec![addr]);
+
+    let handle = primary.tokio_core().handle();
+    primary.tokio_core().run(slave.refresh(&handle)).unwrap();
+
+    let new_record = Record::new()
+        .set_name(Name::parse("new.example.com.", None).unwrap())
+        .set_ttl(86400)
+        .set_dns_class(DNSClass::IN)
+        .set_rr_type(RecordType::A)
+        .set_rdata(RData::A(Ipv4Addr::new(10, 11, 12, 13)))
+        .clone();
+    primary_authority
+        .write()
+        .unwrap()
+        .update_records(&[new_record.clone()], true)
+        .unwrap();
+
+    let refresh = primary.tokio_core().run(slave.refresh(&handle)).unwrap();
+    assert_eq!(refresh, Refresh::Incremental(2015082404));
+
+    let authority = slave_authority.read().unwrap();
+    assert_eq!(authority.serial(), 2015082404);
+    assert_eq!(
+        authority
+            .lookup(
+                &LowerName::new(new_record.name()),
+                RecordType::A,
+                false,
+                Default::default()
+            )
+            .iter()
+            .next(),
+        Some(&new_record)
+    );
+}
+
+#[test]
+fn test_slave_local_copy() {
+    let (mut primary, _, addr) = primary();
+    let (slave, slave_authority) = slave(vec![addr]);
+
+    let handle = primary.tokio_core().handle();
+    primary.tokio_core().run(slave.refresh(&handle)).unwrap();
+
+    // as on a restart, the zone is recovered from the journal
+    let authority = slave_authority.read().unwrap();
+    let mut recovered = Authority::new(
+        Name::parse("example.com.", None).unwrap(),
+        BTreeMap::new(),
+        ZoneType::Slave,
+        false,
+        false,
+    );
+    recovered
+        .recover_with_journal(authority.journal().unwrap())
+        .unwrap();
+
+    assert_eq!(recovered.serial(), 2015082403);
+    assert_eq!(
+        recovered
+            .records()
+            .values()
+            .flat_map(|rr_set| rr_set.iter())
+            .collect::<Vec<_>>(),
+        authority
+            .records()
+            .values()
+            .flat_map(|rr_set| rr_set.iter())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_slave_tries_next_master() {
+    // nothing is listening on this address
+    let unused = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let (mut primary, _, addr) = primary();
+    let (slave, _) = slave(vec![unused, addr]);
+
+    let handle = primary.tokio_core().handle();
+    let refresh = primary.tokio_core().run(slave.refresh(&handle)).unwrap();
+    assert_eq!(refresh, Refresh::Full(2015082403));
+}
+
+#[test]
+fn test_slave_expires() {
+    let unused = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    // the local copy of the zone expires after a second
+    let mut example = create_example();
+    let mut soa = example.soa().iter().next().cloned().unwrap();
+    soa.set_rdata(RData::SOA(SOA::new(
+        Name::parse("sns.dns.icann.org.", None).unwrap(),
+        Name::parse("noc.dns.icann.org.", None).unwrap(),
+        2015082404,
+        7200,
+        1,
+        1,
+        3600,
+    )));
+    example.upsert(soa, 0);
+
+    let authority = Arc::new(RwLock::new(example));
+    let slave = SlaveZone::new(authority.clone(), vec![unused]);
+
+    let (mut primary, _, _) = primary();
+    let handle = primary.tokio_core().handle();
+    slave.spawn(&handle);
+    assert!(!authority.read().unwrap().is_expired());
+
+    let wait = Timeout::new(Duration::from_secs(3), &handle).unwrap();
+    primary.tokio_core().run(wait).unwrap();
+    assert!(authority.read().unwrap().is_expired());
+}
+
+#[test]
+fn test_slave_without_copy_is_expired() {
+    let unused = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let (mut primary, _, _) = primary();
+    let (slave, slave_authority) = slave(vec![unused]);
+
+    let handle = primary.tokio_core().handle();
+    slave.spawn(&handle);
+    assert!(slave_authority.read().unwrap().is_expired());
+
+    let wait = Timeout::new(Duration::from_millis(100), &handle).unwrap();
+    primary.tokio_core().run(wait).unwrap();
+    assert!(slave_authority.read().unwrap().is_expired());
+}
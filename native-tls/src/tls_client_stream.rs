@@ -19,8 +19,8 @@ use tokio_core::reactor::Handle;
 use tokio_tls::TlsStream as TokioTlsStream;
 
 use trust_dns::tcp::TcpClientStream;
-use trust_dns::error::ClientError;
 use trust_dns_proto::{BufDnsStreamHandle, DnsStreamHandle};
+use trust_dns_proto::error::FromProtoError;
 
 use TlsStreamBuilder;
 
@@ -58,16 +58,19 @@ impl TlsClientStreamBuilder {
     /// * `name_server` - IP and Port for the remote DNS resolver
     /// * `dns_name` - The DNS name, Subject Public Key Info (SPKI) name, as associated to a certificate
     /// * `loop_handle` - The reactor Core handle
-    pub fn build(
+    pub fn build<E>(
         self,
         name_server: SocketAddr,
         dns_name: String,
         loop_handle: &Handle,
     ) -> (
         Box<Future<Item = TlsClientStream, Error = io::Error>>,
-        Box<DnsStreamHandle<Error = ClientError>>,
-    ) {
-        let (stream_future, sender) = self.0.build(name_server, dns_name, loop_handle);
+        Box<DnsStreamHandle<Error = E>>,
+    )
+    where
+        E: FromProtoError + 'static,
+    {
+        let (stream_future, sender) = self.0.build_with_error(name_server, dns_name, loop_handle);
 
         let new_future: Box<Future<Item = TlsClientStream, Error = io::Error>> =
             Box::new(stream_future.map(move |tls_stream| TcpClientStream::from_stream(tls_stream)));
@@ -0,0 +1,145 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The DNS Cookies of a client, [RFC 7873](https://tools.ietf.org/html/rfc7873)
+
+use std::sync::{Arc, Mutex};
+
+use rand;
+
+use op::Message;
+use rr::rdata::opt::{Cookie, EdnsCode, EdnsOption};
+
+/// The client cookie sent to an upstream server, and the server cookie last returned by it
+///
+/// Clones share the cookies, so that the server cookie outlives any one connection to the server.
+///  A jar should only be used with a single server, the server cookie is meaningless to others.
+#[derive(Clone, Debug)]
+pub struct CookieJar {
+    cookie: Arc<Mutex<Cookie>>,
+}
+
+impl CookieJar {
+    /// Returns a new jar with a random client cookie and no server cookie
+    pub fn new() -> Self {
+        Self::with_client_cookie(rand::random())
+    }
+
+    /// Returns a new jar with the client cookie and no server cookie
+    pub fn with_client_cookie(client: [u8; 8]) -> Self {
+        CookieJar {
+            cookie: Arc::new(Mutex::new(Cookie::new(client, Vec::new()))),
+        }
+    }
+
+    /// The cookie to send in a request, with the server cookie once one has been received
+    pub fn cookie(&self) -> Cookie {
+        self.cookie.lock().expect("cookie jar poisoned").clone()
+    }
+
+    /// Adds the cookie to the request, if it is an EDNS request
+    pub fn add_to_request(&self, request: &mut Message) {
+        if request.edns().is_some() {
+            let cookie = self.cookie();
+            request.edns_mut().set_option(EdnsOption::Cookie(cookie));
+        }
+    }
+
+    /// Checks the cookie of a response, and remembers the server cookie
+    ///
+    /// Returns false if the response carries a cookie for another client cookie, or a malformed
+    ///  cookie, such a response is most likely spoofed and should be discarded. Responses without
+    ///  a cookie, from servers which do not support cookies, are accepted.
+    pub fn receive(&self, response: &Message) -> bool {
+        let option = match response.edns().and_then(|edns| edns.option(&EdnsCode::Cookie)) {
+            Some(option) => option,
+            None => return true,
+        };
+
+        let received = match *option {
+            EdnsOption::Cookie(ref cookie) => cookie,
+            _ => return false,
+        };
+
+        let mut cookie = self.cookie.lock().expect("cookie jar poisoned");
+        if received.client() != cookie.client() {
+            return false;
+        }
+
+        if received.has_server() {
+            *cookie = received.clone();
+        }
+
+        true
+    }
+}
+
+impl Default for CookieJar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use op::Edns;
+
+    fn response(cookie: Option<EdnsOption>) -> Message {
+        let mut edns = Edns::new();
+        if let Some(cookie) = cookie {
+            edns.set_option(cookie);
+        }
+
+        let mut message = Message::new();
+        message.set_edns(edns);
+        message
+    }
+
+    #[test]
+    fn test_remembers_server_cookie() {
+        let jar = CookieJar::with_client_cookie([1; 8]);
+        assert!(!jar.cookie().has_server());
+
+        let server = Cookie::new([1; 8], vec![2; 16]);
+        assert!(jar.receive(&response(Some(EdnsOption::Cookie(server.clone())))));
+        assert_eq!(jar.cookie(), server);
+
+        // clones share the cookie
+        assert_eq!(jar.clone().cookie(), server);
+
+        // responses without a cookie leave it as is
+        assert!(jar.receive(&response(None)));
+        assert_eq!(jar.cookie(), server);
+    }
+
+    #[test]
+    fn test_rejects_other_client_cookie() {
+        let jar = CookieJar::with_client_cookie([1; 8]);
+
+        let other = Cookie::new([3; 8], vec![2; 16]);
+        assert!(!jar.receive(&response(Some(EdnsOption::Cookie(other)))));
+        assert!(!jar.receive(&response(Some(EdnsOption::Unknown(10, vec![1; 12])))));
+        assert!(!jar.cookie().has_server());
+    }
+
+    #[test]
+    fn test_add_to_request() {
+        let jar = CookieJar::with_client_cookie([1; 8]);
+
+        let mut request = Message::new();
+        jar.add_to_request(&mut request);
+        assert!(request.edns().is_none());
+
+        let mut request = response(None);
+        jar.add_to_request(&mut request);
+        assert_eq!(
+            request.edns().unwrap().option(&EdnsCode::Cookie),
+            Some(&EdnsOption::Cookie(Cookie::new([1; 8], Vec::new())))
+        );
+    }
+}
@@ -22,8 +22,10 @@ use rand;
 use rand::Rand;
 use tokio_core::reactor::{Handle, Timeout};
 
+use cookie_jar::CookieJar;
 use error::*;
-use op::{Message, MessageFinalizer, MessageType, OpCode, Query};
+use op::{Message, MessageFinalizer, MessageType, OpCode, Query, ResponseCode};
+use rr::{RData, RecordType};
 
 // TODO: this should be configurable
 const MAX_PAYLOAD_LEN: u16 = 1500 - 40 - 8; // 1500 (general MTU) - 40 (ipv6 header) - 8 (udp header)
@@ -91,7 +93,13 @@ where
     stream_handle: Box<DnsStreamHandle<Error = E>>,
     new_receiver: Peekable<StreamFuse<UnboundedReceiver<(Message, Complete<Result<Message, E>>)>>>,
     active_requests: HashMap<u16, (Complete<Result<Message, E>>, Timeout)>,
+    active_transfers: HashMap<u16, ZoneTransfer>,
+    // finalized requests, and the state of the verification of their responses
+    active_verifications: HashMap<u16, (Message, Vec<u8>)>,
+    // requests sent with a cookie, and the request to resend if the server answers BADCOOKIE
+    active_cookies: HashMap<u16, Option<Message>>,
     signer: Option<Arc<MF>>,
+    cookies: Option<CookieJar>,
 }
 
 impl<S, E, MF> DnsFuture<S, E, MF>
@@ -143,6 +151,42 @@ where
         loop_handle: &Handle,
         timeout_duration: Duration,
         signer: Option<Arc<MF>>,
+    ) -> BasicDnsHandle<E> {
+        Self::with_cookies(
+            stream,
+            stream_handle,
+            loop_handle,
+            timeout_duration,
+            signer,
+            Some(CookieJar::new()),
+        )
+    }
+
+    /// Spawns a new DnsFuture Stream, which sends DNS Cookies from the jar.
+    ///
+    /// EDNS requests carry the cookie of the jar, and responses with a cookie for another client
+    ///  are discarded. A request answered with `BADCOOKIE` is sent once more with the new server
+    ///  cookie, unless it was finalized by the signer.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - A stream of bytes that can be used to send/receive DNS messages
+    ///              (see TcpClientStream or UdpClientStream)
+    /// * `loop_handle` - A Handle to the Tokio reactor Core, this is the Core on which the
+    ///                   the Stream will be spawned
+    /// * `timeout_duration` - All requests may fail due to lack of response, this is the time to
+    ///                        wait for a response before canceling the request.
+    /// * `stream_handle` - The handle for the `stream` on which bytes can be sent/received.
+    /// * `signer` - An optional signer for requests, needed for Updates with Sig0, otherwise not needed
+    /// * `cookies` - The cookies for the server, shared with other connections to the same server,
+    ///               `None` to send no cookies
+    pub fn with_cookies(
+        stream: Box<Future<Item = S, Error = io::Error>>,
+        stream_handle: Box<DnsStreamHandle<Error = E>>,
+        loop_handle: &Handle,
+        timeout_duration: Duration,
+        signer: Option<Arc<MF>>,
+        cookies: Option<CookieJar>,
     ) -> BasicDnsHandle<E> {
         let (sender, rx) = unbounded();
 
@@ -157,7 +201,11 @@ where
                         stream_handle: stream_handle,
                         new_receiver: rx.fuse().peekable(),
                         active_requests: HashMap::new(),
+                        active_transfers: HashMap::new(),
+                        active_verifications: HashMap::new(),
+                        active_cookies: HashMap::new(),
                         signer: signer,
+                        cookies: cookies,
                     }),
                     Err(stream_error) => ClientStreamOrError::Errored(ClientStreamErrored {
                         error_msg: format!(
@@ -205,6 +253,9 @@ where
 
         // drop all the canceled requests
         for id in canceled {
+            self.active_transfers.remove(&id);
+            self.active_verifications.remove(&id);
+            self.active_cookies.remove(&id);
             if let Some((req, _)) = self.active_requests.remove(&id) {
                 // TODO, perhaps there is a different reason timeout? but there shouldn't be...
                 //  being lazy and always returning timeout in this case (if it was canceled then the
@@ -284,9 +335,19 @@ where
                         .as_secs();
                     let now = now as u32; // XXX: truncates u64 to u32.
 
-                    // update messages need to be signed.
-                    if let OpCode::Update = message.op_code() {
-                        if let Some(ref signer) = self.signer {
+                    // the cookie is part of what is signed
+                    let has_cookie = match self.cookies {
+                        Some(ref cookies) if message.edns().is_some() => {
+                            cookies.add_to_request(&mut message);
+                            true
+                        }
+                        _ => false,
+                    };
+
+                    // update messages need to be signed, other messages as the signer requires, e.g. TSIG
+                    let mut finalized = false;
+                    if let Some(ref signer) = self.signer {
+                        if signer.should_finalize(&message) {
                             if let Err(e) = message.finalize::<MF>(signer.borrow(), now) {
                                 warn!("could not sign message: {}", e);
                                 complete
@@ -294,6 +355,7 @@ where
                                     .expect("error notifying wait, possible future leak");
                                 continue; // to the next message...
                             }
+                            finalized = true;
                         }
                     }
 
@@ -318,6 +380,27 @@ where
                             //  we ended up returning from the send.
                             self.active_requests
                                 .insert(message.id(), (complete, timeout));
+
+                            // zone transfers may span many response messages
+                            if let Some(transfer) = ZoneTransfer::for_request(&message) {
+                                self.active_transfers.insert(message.id(), transfer);
+                            }
+
+                            // the responses to finalized requests are verified by the signer
+                            // a signed request can not be sent again with another cookie
+                            if has_cookie {
+                                let resend = if finalized {
+                                    None
+                                } else {
+                                    Some(message.clone())
+                                };
+                                self.active_cookies.insert(message.id(), resend);
+                            }
+
+                            if finalized {
+                                self.active_verifications
+                                    .insert(message.id(), (message, Vec::new()));
+                            }
                         }
                         Err(e) => {
                             debug!("error message id: {} error: {}", query_id, e);
@@ -340,6 +423,10 @@ where
         //   by having a max we will guarantee that the client can't be DOSed in this loop
         // TODO: make the QoS configurable
         let mut messages_received = 0;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| "Current time is before the Unix epoch.".into())?
+            .as_secs() as u32;
         for i in 0..QOS_MAX_RECEIVE_MSGS {
             match self.stream.poll().map_err(|e| E::from(e.into()))? {
                 Async::Ready(Some(buffer)) => {
@@ -347,12 +434,76 @@ where
 
                     //   deserialize or log decode_error
                     match Message::from_vec(&buffer) {
-                        Ok(message) => match self.active_requests.remove(&message.id()) {
-                            Some((complete, _)) => complete
-                                .send(Ok(message))
-                                .expect("error notifying wait, possible future leak"),
-                            None => debug!("unexpected request_id: {}", message.id()),
-                        },
+                        Ok(message) => {
+                            let id = message.id();
+
+                            if let Some(resend) = self.active_cookies.get_mut(&id) {
+                                let cookies = self.cookies
+                                    .as_ref()
+                                    .expect("cookies are only sent from a jar");
+                                if !cookies.receive(&message) {
+                                    // likely spoofed, keep waiting for the real response
+                                    warn!("discarding response id: {} with a bad cookie", id);
+                                    continue;
+                                }
+
+                                if message.response_code() == ResponseCode::BADCOOKIE {
+                                    if let Some(mut request) = resend.take() {
+                                        cookies.add_to_request(&mut request);
+                                        match request.to_vec() {
+                                            Ok(buffer) => {
+                                                debug!("resending id: {} with new cookie", id);
+                                                self.stream_handle.send(buffer)?;
+                                                continue;
+                                            }
+                                            Err(e) => warn!("could not resend id: {}: {}", id, e),
+                                        }
+                                    }
+                                }
+                            }
+
+                            let message = match self.active_transfers.get_mut(&id) {
+                                Some(transfer) => transfer.receive(message),
+                                None => Some(message),
+                            };
+
+                            // each message of the response to a finalized request is verified
+                            let verified = match (
+                                self.active_verifications.get_mut(&id),
+                                self.signer.as_ref(),
+                            ) {
+                                (Some(&mut (ref request, ref mut state)), Some(signer)) => signer
+                                    .verify_response(
+                                        request,
+                                        &buffer,
+                                        state,
+                                        message.is_some(),
+                                        now,
+                                    ),
+                                _ => Ok(()),
+                            };
+
+                            let response = match verified {
+                                Ok(()) => match message {
+                                    Some(message) => Ok(message),
+                                    None => continue, // more messages to come
+                                },
+                                Err(e) => {
+                                    warn!("could not verify response id: {}: {}", id, e);
+                                    Err(e.into())
+                                }
+                            };
+                            self.active_transfers.remove(&id);
+                            self.active_verifications.remove(&id);
+                            self.active_cookies.remove(&id);
+
+                            match self.active_requests.remove(&id) {
+                                Some((complete, _)) => complete
+                                    .send(response)
+                                    .expect("error notifying wait, possible future leak"),
+                                None => debug!("unexpected request_id: {}", id),
+                            }
+                        }
                         // TODO: return src address for diagnostics
                         Err(e) => debug!("error decoding message: {}", e),
                     }
@@ -386,6 +537,117 @@ where
     }
 }
 
+/// Collects the messages of an AXFR or IXFR response into a single Message
+///
+/// [RFC 5936](https://tools.ietf.org/html/rfc5936), DNS Zone Transfer Protocol (AXFR), June 2010
+///
+/// ```text
+/// 2.2.  AXFR Response
+///
+///    The AXFR response will consist of one or more messages.  The special
+///    case of a server closing the TCP connection without sending an AXFR
+///    response is covered in Section 2.3.
+///
+///    An AXFR response that is transferring the zone's contents will
+///    consist of a series (which could be a series of length 1) of DNS
+///    messages.  In such a series, the first message MUST begin with the
+///    SOA resource record of the zone, and the last message MUST conclude
+///    with the same SOA resource record.
+/// ```
+///
+/// An IXFR response either begins with the SOA followed by the old SOA of the first difference
+///  sequence, or it is a full zone in AXFR format; a lone SOA means the requester is up to date.
+struct ZoneTransfer {
+    is_ixfr: bool,
+    message: Option<Message>,
+    record_count: usize,
+    first_serial: u32,
+    incremental: bool,
+    soa_count: usize,
+    complete: bool,
+}
+
+impl ZoneTransfer {
+    /// Returns a new ZoneTransfer if the message is an AXFR or IXFR query
+    fn for_request(message: &Message) -> Option<Self> {
+        let is_ixfr = match message.queries().first().map(|q| q.query_type()) {
+            Some(RecordType::AXFR) => false,
+            Some(RecordType::IXFR) => true,
+            _ => return None,
+        };
+
+        Some(ZoneTransfer {
+            is_ixfr: is_ixfr,
+            message: None,
+            record_count: 0,
+            first_serial: 0,
+            incremental: false,
+            soa_count: 0,
+            complete: false,
+        })
+    }
+
+    /// Adds the response to the transfer, returns the merged Message once the transfer is complete
+    fn receive(&mut self, mut message: Message) -> Option<Message> {
+        // errors end the transfer, there is nothing more to wait for
+        if message.response_code() != ResponseCode::NoError {
+            return Some(message);
+        }
+
+        let answers = message.take_answers();
+        for record in &answers {
+            let soa_serial = if let RData::SOA(ref soa) = *record.rdata() {
+                Some(soa.serial())
+            } else {
+                None
+            };
+
+            match (self.record_count, soa_serial) {
+                // the response always starts with the SOA of the zone
+                (0, Some(serial)) => self.first_serial = serial,
+                (0, None) => self.complete = true,
+                (1, _) if self.is_ixfr && soa_serial != Some(self.first_serial) => {
+                    self.incremental = soa_serial.is_some();
+                    self.soa_count += soa_serial.map_or(0, |_| 1);
+                }
+                (_, Some(serial)) => {
+                    self.soa_count += 1;
+
+                    // incremental responses alternate between old and new SOAs, the final SOA is
+                    //  the odd one out
+                    if !self.incremental || (self.soa_count % 2 == 1 && serial == self.first_serial)
+                    {
+                        self.complete = true;
+                    }
+                }
+                (_, None) => (),
+            }
+
+            self.record_count += 1;
+        }
+
+        // a single SOA in the IXFR response means there is nothing to transfer
+        if self.is_ixfr && self.message.is_none() && self.record_count == 1 {
+            self.complete = true;
+        }
+
+        if self.message.is_none() {
+            self.message = Some(message);
+        }
+
+        self.message
+            .as_mut()
+            .expect("message was set above")
+            .add_answers(answers);
+
+        if self.complete {
+            self.message.take()
+        } else {
+            None
+        }
+    }
+}
+
 /// Always returns the specified io::Error to the remote Sender
 struct ClientStreamErrored<E>
 where
@@ -62,6 +62,11 @@ error_chain! {
     // the same as `quick_error!`, but the `from()` and `cause()`
     // syntax is not supported.
     errors {
+      Bogus(reason: String) {
+        description("dnssec validation failed")
+        display("dnssec validation failed: {}", reason)
+      }
+
       Canceled(c: ::futures::sync::oneshot::Canceled) {
         description("future was canceled")
         display("future was canceled: {:?}", c)
@@ -216,6 +221,7 @@ impl Clone for ProtoErrorKind {
     fn clone(&self) -> Self {
         match *self {
             ProtoErrorKind::AddrParseError => ProtoErrorKind::AddrParseError,
+            ProtoErrorKind::Bogus(ref reason) => ProtoErrorKind::Bogus(reason.clone()),
             ProtoErrorKind::Canceled(ref c) => ProtoErrorKind::Canceled(*c),
             ProtoErrorKind::CharacterDataTooLong(len) => ProtoErrorKind::CharacterDataTooLong(len),
             ProtoErrorKind::DnsKeyProtocolNot3(value) => ProtoErrorKind::DnsKeyProtocolNot3(value),
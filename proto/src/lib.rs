@@ -40,6 +40,7 @@ use std::net::SocketAddr;
 
 use futures::sync::mpsc::{SendError, UnboundedSender};
 
+mod cookie_jar;
 mod dns_handle;
 pub mod error;
 pub mod op;
@@ -51,6 +52,7 @@ pub mod serialize;
 pub mod tcp;
 pub mod udp;
 
+pub use cookie_jar::CookieJar;
 pub use dns_handle::{BasicDnsHandle, DnsFuture, DnsHandle, DnsStreamHandle, StreamHandle};
 pub use retry_dns_handle::RetryDnsHandle;
 #[cfg(feature = "dnssec")]
@@ -391,6 +391,15 @@ impl Message {
         self
     }
 
+    /// Add a TSIG record, i.e. sign this message with a shared secret
+    ///
+    /// Like SIG(0), this must be done only after all records have been associated, the TSIG is the final record of the message
+    pub fn add_tsig(&mut self, record: Record) -> &mut Self {
+        assert_eq!(RecordType::TSIG, record.rr_type());
+        self.sig0.push(record);
+        self
+    }
+
     /// see `Header::id()`
     pub fn id(&self) -> u16 {
         self.header.id()
@@ -572,6 +581,14 @@ impl Message {
         &self.sig0
     }
 
+    /// The TSIG, i.e. the transaction signature, of the message, RFC 8945
+    pub fn tsig(&self) -> Option<&Record> {
+        match self.sig0.last() {
+            Some(record) if record.rr_type() == RecordType::TSIG => Some(record),
+            _ => None,
+        }
+    }
+
     // TODO: only necessary in tests, should it be removed?
     /// this is necessary to match the counts in the header from the record sections
     ///  this happens implicitly on write_to, so no need to call before write_to
@@ -624,6 +641,11 @@ impl Message {
                         saw_sig0 = true;
                         sig0s.push(record);
                     }
+                    // the TSIG, like SIG(0), must be last, RFC 8945
+                    RecordType::TSIG => {
+                        saw_sig0 = true;
+                        sig0s.push(record);
+                    }
                     RecordType::OPT => {
                         if saw_sig0 {
                             return Err(
@@ -702,6 +724,7 @@ impl Message {
                 // SIG0's are special, and come at the very end of the message
                 #[cfg(feature = "dnssec")]
                 RecordType::DNSSEC(DNSSECRecordType::SIG) => self.add_sig0(fin),
+                RecordType::TSIG => self.add_tsig(fin),
                 _ => self.add_additional(fin),
             };
         }
@@ -727,6 +750,32 @@ pub trait MessageFinalizer {
     ///
     /// A vector to append to the additionals section of the message, sorted in the order as they should appear in the message.
     fn finalize_message(&self, message: &Message, current_time: u32) -> ProtoResult<Vec<Record>>;
+
+    /// Returns true if the message should be finalized before it is sent, by default only updates are
+    fn should_finalize(&self, message: &Message) -> bool {
+        message.op_code() == OpCode::Update
+    }
+
+    /// Verifies a message of the response to a finalized request, by default all responses are accepted
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the request, as it was finalized and sent
+    /// * `response` - the message of the response, as it was received
+    /// * `state` - the state of the verification of a response spanning many messages, e.g. a zone
+    ///             transfer, this is empty for the first message of the response
+    /// * `is_final` - true if this is the final message of the response
+    /// * `current_time` - the current time as specified by the system
+    fn verify_response(
+        &self,
+        _request: &Message,
+        _response: &[u8],
+        _state: &mut Vec<u8>,
+        _is_final: bool,
+        _current_time: u32,
+    ) -> ProtoResult<()> {
+        Ok(())
+    }
 }
 
 /// A MessageFinalizer which does nothing
@@ -15,6 +15,7 @@
  */
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 use serialize::binary::*;
 use error::*;
@@ -157,6 +158,37 @@ impl Algorithm {
     }
 }
 
+impl FromStr for Algorithm {
+    type Err = ProtoError;
+
+    /// Convert the mnemonic of the algorithm, see `to_str()`, or its number to `Algorithm`, the
+    ///  presentation formats of [RFC 4034](https://tools.ietf.org/html/rfc4034#appendix-A.1)
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use trust_dns_proto::rr::dnssec::Algorithm;
+    ///
+    /// assert_eq!(Algorithm::from_str("RSASHA256").unwrap(), Algorithm::RSASHA256);
+    /// assert_eq!(Algorithm::from_str("8").unwrap(), Algorithm::RSASHA256);
+    /// ```
+    fn from_str(str: &str) -> ProtoResult<Self> {
+        if let Ok(value) = str.parse::<u8>() {
+            return Algorithm::from_u8(value);
+        }
+
+        match str.to_uppercase().as_str() {
+            "RSASHA1" => Ok(Algorithm::RSASHA1),
+            "RSASHA1-NSEC3-SHA1" => Ok(Algorithm::RSASHA1NSEC3SHA1),
+            "RSASHA256" => Ok(Algorithm::RSASHA256),
+            "RSASHA512" => Ok(Algorithm::RSASHA512),
+            "ECDSAP256SHA256" => Ok(Algorithm::ECDSAP256SHA256),
+            "ECDSAP384SHA384" => Ok(Algorithm::ECDSAP384SHA384),
+            "ED25519" => Ok(Algorithm::ED25519),
+            _ => Err(format!("unknown algorithm: {}", str).into()),
+        }
+    }
+}
+
 impl BinEncodable for Algorithm {
     fn emit(&self, encoder: &mut BinEncoder) -> ProtoResult<()> {
         encoder.emit(u8::from(*self))
@@ -215,6 +247,29 @@ fn test_into() {
     }
 }
 
+#[test]
+fn test_from_str() {
+    for algorithm in &[
+        Algorithm::RSASHA1,
+        Algorithm::RSASHA256,
+        Algorithm::RSASHA1NSEC3SHA1,
+        Algorithm::RSASHA512,
+        Algorithm::ECDSAP256SHA256,
+        Algorithm::ECDSAP384SHA384,
+        Algorithm::ED25519,
+    ] {
+        assert_eq!(*algorithm, Algorithm::from_str(algorithm.to_str()).unwrap());
+        assert_eq!(
+            *algorithm,
+            Algorithm::from_str(&u8::from(*algorithm).to_string()).unwrap()
+        );
+    }
+
+    assert_eq!(Algorithm::from_str("rsasha256").unwrap(), Algorithm::RSASHA256);
+    assert!(Algorithm::from_str("RSAMD5").is_err());
+    assert!(Algorithm::from_str("1").is_err());
+}
+
 #[test]
 fn test_order() {
     let mut algorithms = [
@@ -27,6 +27,8 @@ pub mod nsec3;
 pub mod nsec3param;
 pub mod sig;
 
+use std::str::FromStr;
+
 use error::*;
 use serialize::binary::*;
 use rr::rdata::NULL;
@@ -43,9 +45,11 @@ pub use self::sig::SIG;
 /// The type of the resource record, for DNSSEC-specific records.
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum DNSSECRecordType {
-    //  CDS,        //	59	RFC 7344	Child DS
-    //  CDNSKEY,    //	60	RFC 7344	Child DNSKEY
     //  DLV,        //	32769	RFC 4431	DNSSEC Lookaside Validation record
+    /// RFC 7344	Child DNSKEY, the DNSKEY a child zone would have its parent trust
+    CDNSKEY,
+    /// RFC 7344	Child DS, the DS a child zone would have its parent publish
+    CDS,
     /// RFC 4034	DNS Key record: RSASHA256 and RSASHA512, RFC5702
     DNSKEY,
     /// RFC 4034	Delegation signer: RSASHA256 and RSASHA512, RFC5702
@@ -73,6 +77,8 @@ pub enum DNSSECRecordType {
 impl From<u16> for DNSSECRecordType {
     fn from(value: u16) -> Self {
         match value {
+            60 => DNSSECRecordType::CDNSKEY,
+            59 => DNSSECRecordType::CDS,
             48 => DNSSECRecordType::DNSKEY,
             43 => DNSSECRecordType::DS,
             25 => DNSSECRecordType::KEY,
@@ -86,9 +92,40 @@ impl From<u16> for DNSSECRecordType {
     }
 }
 
+impl FromStr for DNSSECRecordType {
+    type Err = ProtoError;
+
+    /// Convert `&str` to `DNSSECRecordType`
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use trust_dns_proto::rr::dnssec::rdata::DNSSECRecordType;
+    ///
+    /// let var: DNSSECRecordType = DNSSECRecordType::from_str("RRSIG").unwrap();
+    /// assert_eq!(DNSSECRecordType::RRSIG, var);
+    /// ```
+    fn from_str(str: &str) -> ProtoResult<Self> {
+        match str {
+            "CDNSKEY" => Ok(DNSSECRecordType::CDNSKEY),
+            "CDS" => Ok(DNSSECRecordType::CDS),
+            "DNSKEY" => Ok(DNSSECRecordType::DNSKEY),
+            "DS" => Ok(DNSSECRecordType::DS),
+            "KEY" => Ok(DNSSECRecordType::KEY),
+            "NSEC" => Ok(DNSSECRecordType::NSEC),
+            "NSEC3" => Ok(DNSSECRecordType::NSEC3),
+            "NSEC3PARAM" => Ok(DNSSECRecordType::NSEC3PARAM),
+            "RRSIG" => Ok(DNSSECRecordType::RRSIG),
+            "SIG" => Ok(DNSSECRecordType::SIG),
+            _ => Err(ProtoErrorKind::UnknownRecordTypeStr(str.to_string()).into()),
+        }
+    }
+}
+
 impl From<DNSSECRecordType> for &'static str {
     fn from(rt: DNSSECRecordType) -> &'static str {
         match rt {
+            DNSSECRecordType::CDNSKEY => "CDNSKEY",
+            DNSSECRecordType::CDS => "CDS",
             DNSSECRecordType::DNSKEY => "DNSKEY",
             DNSSECRecordType::DS => "DS",
             DNSSECRecordType::KEY => "KEY",
@@ -105,6 +142,8 @@ impl From<DNSSECRecordType> for &'static str {
 impl From<DNSSECRecordType> for u16 {
     fn from(rt: DNSSECRecordType) -> Self {
         match rt {
+            DNSSECRecordType::CDNSKEY => 60,
+            DNSSECRecordType::CDS => 59,
             DNSSECRecordType::KEY => 25,
             DNSSECRecordType::DNSKEY => 48,
             DNSSECRecordType::DS => 43,
@@ -121,6 +160,40 @@ impl From<DNSSECRecordType> for u16 {
 /// Record data enum variants for DNSSEC-specific records.
 #[derive(Debug, PartialEq, Clone, Eq)]
 pub enum DNSSECRData {
+    /// ```text
+    /// RFC 7344              Delegation Trust Maintenance        September 2014
+    ///
+    /// 3.2.  CDNSKEY Resource Record Format
+    ///
+    ///    The wire and presentation format of the CDNSKEY ("Child DNSKEY")
+    ///    resource record is identical to the DNSKEY record.  IANA has
+    ///    allocated RR code 60 for the CDNSKEY resource record via Expert
+    ///    Review.  The CDNSKEY RR uses the same registries as DNSKEY for its
+    ///    fields.
+    ///
+    ///    No special processing is performed by authoritative servers or by
+    ///    resolvers, when serving or resolving.  For all practical purposes,
+    ///    CDNSKEY is a regular RR type.
+    /// ```
+    CDNSKEY(DNSKEY),
+
+    /// ```text
+    /// RFC 7344              Delegation Trust Maintenance        September 2014
+    ///
+    /// 3.1.  CDS Resource Record Format
+    ///
+    ///    The wire and presentation format of the Child DS (CDS) resource
+    ///    record is identical to the DS record [RFC4034].  IANA has allocated
+    ///    RR code 59 for the CDS resource record via Expert Review
+    ///    [DNS-TRANSPORT].  The CDS RR uses the same registries as DS for its
+    ///    fields.
+    ///
+    ///    No special processing is performed by authoritative servers or by
+    ///    resolvers, when serving or resolving.  For all practical purposes,
+    ///    CDS is a regular RR type.
+    /// ```
+    CDS(DS),
+
     /// ```text
     /// RFC 4034                DNSSEC Resource Records               March 2005
     ///
@@ -462,6 +535,14 @@ impl DNSSECRData {
         rdata_length: u16,
     ) -> ProtoResult<Self> {
         match record_type {
+            DNSSECRecordType::CDNSKEY => {
+                debug!("reading CDNSKEY");
+                dnskey::read(decoder, rdata_length).map(DNSSECRData::CDNSKEY)
+            }
+            DNSSECRecordType::CDS => {
+                debug!("reading CDS");
+                ds::read(decoder, rdata_length).map(DNSSECRData::CDS)
+            }
             DNSSECRecordType::DNSKEY => {
                 debug!("reading DNSKEY");
                 dnskey::read(decoder, rdata_length).map(DNSSECRData::DNSKEY)
@@ -503,6 +584,8 @@ impl DNSSECRData {
 
     pub(crate) fn emit(&self, encoder: &mut BinEncoder) -> ProtoResult<()> {
         match *self {
+            DNSSECRData::CDNSKEY(ref cdnskey) => dnskey::emit(encoder, cdnskey),
+            DNSSECRData::CDS(ref cds) => ds::emit(encoder, cds),
             DNSSECRData::DS(ref ds) => ds::emit(encoder, ds),
             DNSSECRData::KEY(ref key) => key::emit(encoder, key),
             DNSSECRData::DNSKEY(ref dnskey) => dnskey::emit(encoder, dnskey),
@@ -516,6 +599,8 @@ impl DNSSECRData {
 
     pub(crate) fn to_record_type(&self) -> DNSSECRecordType {
         match *self {
+            DNSSECRData::CDNSKEY(..) => DNSSECRecordType::CDNSKEY,
+            DNSSECRData::CDS(..) => DNSSECRecordType::CDS,
             DNSSECRData::DS(..) => DNSSECRecordType::DS,
             DNSSECRData::KEY(..) => DNSSECRecordType::KEY,
             DNSSECRData::DNSKEY(..) => DNSSECRecordType::DNSKEY,
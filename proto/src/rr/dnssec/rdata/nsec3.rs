@@ -226,6 +226,25 @@ impl NSEC3 {
         &self.next_hashed_owner_name
     }
 
+    /// Returns true if the hash falls between the hash in the owner name of this NSEC3 and the
+    ///  Next Hashed Owner Name, i.e. there is no name with that hash in the zone
+    ///
+    /// The last NSEC3 of the chain points back to the first, so it covers the hashes after its
+    ///  owner as well as those before the first owner.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner_hash` - the hash in the first label of the owner name of this NSEC3
+    /// * `hash` - the hash of the name, with the parameters of this NSEC3
+    pub fn covers(&self, owner_hash: &[u8], hash: &[u8]) -> bool {
+        let next = self.next_hashed_owner_name();
+        if next <= owner_hash {
+            owner_hash < hash || hash < next
+        } else {
+            owner_hash < hash && hash < next
+        }
+    }
+
     /// [RFC 5155, NSEC3, March 2008](https://tools.ietf.org/html/rfc5155#section-3.1.8)
     ///
     /// ```text
@@ -533,3 +552,28 @@ pub fn test_dups() {
     );
     assert_eq!(rdata_wo, read_rdata.unwrap());
 }
+
+#[test]
+pub fn test_covers() {
+    let nsec3 = |next: Vec<u8>| NSEC3::new(Nsec3HashAlgorithm::SHA1, false, 0, vec![], next, vec![]);
+
+    // within the chain
+    let middle = nsec3(vec![5]);
+    assert!(middle.covers(&[3], &[4]));
+    assert!(!middle.covers(&[3], &[3]));
+    assert!(!middle.covers(&[3], &[5]));
+    assert!(!middle.covers(&[3], &[2]));
+
+    // the last wraps around to the first, and covers the hashes before it
+    let last = nsec3(vec![1]);
+    assert!(last.covers(&[8], &[9]));
+    assert!(last.covers(&[8], &[0]));
+    assert!(!last.covers(&[8], &[1]));
+    assert!(!last.covers(&[8], &[5]));
+
+    // the only one covers all but its owner
+    let only = nsec3(vec![8]);
+    assert!(only.covers(&[8], &[0]));
+    assert!(only.covers(&[8], &[9]));
+    assert!(!only.covers(&[8], &[8]));
+}
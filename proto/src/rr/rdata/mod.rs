@@ -29,6 +29,7 @@ pub mod opt;
 pub mod soa;
 pub mod srv;
 pub mod tlsa;
+pub mod tsig;
 pub mod txt;
 
 pub use self::caa::CAA;
@@ -38,4 +39,5 @@ pub use self::opt::OPT;
 pub use self::srv::SRV;
 pub use self::soa::SOA;
 pub use self::tlsa::TLSA;
+pub use self::tsig::TSIG;
 pub use self::txt::TXT;
@@ -381,6 +381,9 @@ pub enum EdnsOption {
     #[cfg(feature = "dnssec")]
     N3U(SupportedAlgorithms),
 
+    /// [RFC 7873, DNS Cookies](https://tools.ietf.org/html/rfc7873)
+    Cookie(Cookie),
+
     /// Unknown, used to deal with unknown or unsupported codes
     Unknown(u16, Vec<u8>),
 }
@@ -393,6 +396,7 @@ impl EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.len(),
+            EdnsOption::Cookie(ref cookie) => cookie.len(),
             EdnsOption::Unknown(_, ref data) => data.len() as u16, // TODO: should we verify?
         }
     }
@@ -404,6 +408,7 @@ impl EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.is_empty(),
+            EdnsOption::Cookie(..) => false,
             EdnsOption::Unknown(_, ref data) => data.is_empty(),
         }
     }
@@ -416,6 +421,7 @@ impl BinEncodable for EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.emit(encoder),
+            EdnsOption::Cookie(ref cookie) => cookie.emit(encoder),
             EdnsOption::Unknown(_, ref data) => encoder.emit_vec(data), // gah, clone needed or make a crazy api.
         }
     }
@@ -431,6 +437,11 @@ impl<'a> From<(EdnsCode, &'a [u8])> for EdnsOption {
             EdnsCode::DHU => EdnsOption::DHU(value.1.into()),
             #[cfg(feature = "dnssec")]
             EdnsCode::N3U => EdnsOption::N3U(value.1.into()),
+            // malformed cookies are kept as they are, for the server to answer with FORMERR
+            EdnsCode::Cookie => match Cookie::from_bytes(value.1) {
+                Ok(cookie) => EdnsOption::Cookie(cookie),
+                Err(_) => EdnsOption::Unknown(value.0.into(), value.1.to_vec()),
+            },
             _ => EdnsOption::Unknown(value.0.into(), value.1.to_vec()),
         }
     }
@@ -443,6 +454,11 @@ impl<'a> From<&'a EdnsOption> for Vec<u8> {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.into(),
+            EdnsOption::Cookie(ref cookie) => {
+                let mut bytes = cookie.client().to_vec();
+                bytes.extend_from_slice(cookie.server());
+                bytes
+            }
             EdnsOption::Unknown(_, ref data) => data.clone(), // gah, clone needed or make a crazy api.
         }
     }
@@ -457,11 +473,94 @@ impl<'a> From<&'a EdnsOption> for EdnsCode {
             EdnsOption::DHU(..) => EdnsCode::DHU,
             #[cfg(feature = "dnssec")]
             EdnsOption::N3U(..) => EdnsCode::N3U,
-            EdnsOption::Unknown(code, _) => EdnsCode::Unknown(code),
+            EdnsOption::Cookie(..) => EdnsCode::Cookie,
+            EdnsOption::Unknown(code, _) => code.into(),
         }
     }
 }
 
+const COOKIE_CLIENT_LEN: usize = 8;
+const COOKIE_MIN_SERVER_LEN: usize = 8;
+const COOKIE_MAX_SERVER_LEN: usize = 32;
+
+/// The client and server cookies of the COOKIE option
+///
+/// [RFC 7873, Domain Name System (DNS) Cookies, May 2016](https://tools.ietf.org/html/rfc7873#section-4)
+///
+/// ```text
+/// 4.  DNS Cookies
+///
+///    The DNS Cookie mechanism is implemented with a new EDNS(0) option,
+///    COOKIE, which contains a fixed-size 8-byte Client Cookie followed by
+///    an optional Server Cookie of 8 to 32 bytes.  ...  When it is present in a
+///    request, the Server Cookie is the one the server last returned to the
+///    client, and is used by the server to authenticate the client.
+/// ```
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash)]
+pub struct Cookie {
+    client: [u8; 8],
+    server: Vec<u8>,
+}
+
+impl Cookie {
+    /// Returns a new cookie
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - the client cookie
+    /// * `server` - the server cookie, empty if the client does not yet know one, otherwise 8 to
+    ///              32 bytes
+    pub fn new(client: [u8; 8], server: Vec<u8>) -> Self {
+        Cookie {
+            client: client,
+            server: server,
+        }
+    }
+
+    /// Reads the cookie from the data of the option, the client cookie and optional server cookie
+    pub fn from_bytes(bytes: &[u8]) -> ProtoResult<Self> {
+        let server_len = bytes.len().saturating_sub(COOKIE_CLIENT_LEN);
+        if bytes.len() < COOKIE_CLIENT_LEN
+            || (server_len != 0
+                && (server_len < COOKIE_MIN_SERVER_LEN || server_len > COOKIE_MAX_SERVER_LEN))
+        {
+            return Err(ProtoErrorKind::Msg(format!("bad cookie length: {}", bytes.len())).into());
+        }
+
+        let mut client = [0_u8; 8];
+        client.copy_from_slice(&bytes[..COOKIE_CLIENT_LEN]);
+
+        Ok(Cookie::new(client, bytes[COOKIE_CLIENT_LEN..].to_vec()))
+    }
+
+    /// The client cookie, chosen by the client
+    pub fn client(&self) -> &[u8; 8] {
+        &self.client
+    }
+
+    /// The server cookie, minted by the server for the client, empty if there is none
+    pub fn server(&self) -> &[u8] {
+        &self.server
+    }
+
+    /// Returns true if the cookie carries a server cookie
+    pub fn has_server(&self) -> bool {
+        !self.server.is_empty()
+    }
+
+    /// The length in bytes of the option data
+    fn len(&self) -> u16 {
+        (COOKIE_CLIENT_LEN + self.server.len()) as u16
+    }
+}
+
+impl BinEncodable for Cookie {
+    fn emit(&self, encoder: &mut BinEncoder) -> ProtoResult<()> {
+        encoder.emit_vec(&self.client)?;
+        encoder.emit_vec(&self.server)
+    }
+}
+
 #[test]
 #[cfg(feature = "dnssec")]
 pub fn test() {
@@ -483,3 +582,39 @@ pub fn test() {
     );
     assert_eq!(rdata, read_rdata.unwrap());
 }
+
+#[test]
+pub fn test_cookie() {
+    let mut rdata = OPT::default();
+    rdata.insert(EdnsOption::Cookie(Cookie::new(
+        [1, 2, 3, 4, 5, 6, 7, 8],
+        vec![9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24],
+    )));
+
+    let mut bytes = Vec::new();
+    let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+    assert!(emit(&mut encoder, &rdata).is_ok());
+    let bytes = encoder.into_bytes();
+
+    let mut decoder: BinDecoder = BinDecoder::new(bytes);
+    let read_rdata = read(&mut decoder, bytes.len() as u16).expect("error decoding");
+    assert_eq!(rdata, read_rdata);
+}
+
+#[test]
+pub fn test_cookie_lengths() {
+    // only the client cookie
+    let cookie = Cookie::from_bytes(&[1; 8]).unwrap();
+    assert_eq!(cookie.client(), &[1; 8]);
+    assert!(!cookie.has_server());
+
+    assert!(Cookie::from_bytes(&[1; 7]).is_err());
+    assert!(Cookie::from_bytes(&[1; 15]).is_err());
+    assert!(Cookie::from_bytes(&[1; 16]).is_ok());
+    assert!(Cookie::from_bytes(&[1; 40]).is_ok());
+    assert!(Cookie::from_bytes(&[1; 41]).is_err());
+
+    // malformed cookies are not dropped
+    let option = EdnsOption::from((EdnsCode::Cookie, &[1_u8; 12] as &[u8]));
+    assert_eq!(option, EdnsOption::Unknown(10, vec![1; 12]));
+}
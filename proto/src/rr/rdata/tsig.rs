@@ -0,0 +1,515 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TSIG records for the transaction signatures of messages, signed with a shared secret
+
+use serialize::binary::*;
+use error::*;
+use op::{Header, Query};
+use rr::{DNSClass, Name, RData, Record, RecordType};
+
+/// [RFC 8945, Secret Key Transaction Authentication for DNS](https://tools.ietf.org/html/rfc8945#section-4.2)
+///
+/// ```text
+/// 4.2.  TSIG Record Format
+///
+///    The fields of the TSIG RR are described below.  All multi-octet
+///    integers in the record are sent in network byte order (see
+///    Section 2.3.2 of [RFC1035]).
+///
+///    NAME:  The name of the key used, in domain name syntax.
+///
+///    TYPE:  This MUST be TSIG (250: Transaction SIGnature).
+///
+///    CLASS:  This MUST be ANY.
+///
+///    TTL:  This MUST be 0.
+///
+///                         1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+///     0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    /                         Algorithm Name                        /
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |                                                               |
+///    |          Time Signed          +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |                               |            Fudge              |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |          MAC Size             |                               /
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+             MAC               /
+///    /                                                               /
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |          Original ID          |            Error              |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |          Other Len            |                               /
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+           Other Data          /
+///    /                                                               /
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct TSIG {
+    algorithm: TsigAlgorithm,
+    time: u64,
+    fudge: u16,
+    mac: Vec<u8>,
+    oid: u16,
+    error: u16,
+    other: Vec<u8>,
+}
+
+/// [RFC 8945, Secret Key Transaction Authentication for DNS](https://tools.ietf.org/html/rfc8945#section-6)
+///
+/// ```text
+/// 6.  Algorithms and Identifiers
+///
+///    The only message digest algorithm specified in the first version of
+///    these specifications [RFC2845] was "HMAC-MD5" (see [RFC1321] and
+///    [RFC2104]).  ...
+///
+///      +==========================+================+=================+
+///      | Algorithm Name           | Implementation | Use             |
+///      +==========================+================+=================+
+///      | HMAC-MD5.SIG-ALG.REG.INT | MAY            | MUST NOT        |
+///      +--------------------------+----------------+-----------------+
+///      | gss-tsig                 | MAY            | MAY             |
+///      +--------------------------+----------------+-----------------+
+///      | hmac-sha1                | MUST           | NOT RECOMMENDED |
+///      +--------------------------+----------------+-----------------+
+///      | hmac-sha224              | MAY            | MAY             |
+///      +--------------------------+----------------+-----------------+
+///      | hmac-sha256              | MUST           | RECOMMENDED     |
+///      +--------------------------+----------------+-----------------+
+///      | hmac-sha256-128          | MAY            | MAY             |
+///      +--------------------------+----------------+-----------------+
+///      | hmac-sha384              | MAY            | MAY             |
+///      +--------------------------+----------------+-----------------+
+///      | hmac-sha384-192          | MAY            | MAY             |
+///      +--------------------------+----------------+-----------------+
+///      | hmac-sha512              | MAY            | MAY             |
+///      +--------------------------+----------------+-----------------+
+///      | hmac-sha512-256          | MAY            | MAY             |
+///      +--------------------------+----------------+-----------------+
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum TsigAlgorithm {
+    /// HMAC-SHA256, hmac-sha256.
+    HmacSha256,
+    /// HMAC-SHA512, hmac-sha512.
+    HmacSha512,
+    /// Any other algorithm, by name
+    Unknown(Name),
+}
+
+impl TsigAlgorithm {
+    /// Returns the name of the algorithm, as used in the TSIG record
+    pub fn to_name(&self) -> Name {
+        match *self {
+            TsigAlgorithm::HmacSha256 => Name::from_ascii("hmac-sha256.").unwrap(),
+            TsigAlgorithm::HmacSha512 => Name::from_ascii("hmac-sha512.").unwrap(),
+            TsigAlgorithm::Unknown(ref name) => name.clone(),
+        }
+    }
+
+    /// Returns the algorithm for the name, names are case-insensitive
+    pub fn from_name(name: Name) -> Self {
+        if name == TsigAlgorithm::HmacSha256.to_name() {
+            TsigAlgorithm::HmacSha256
+        } else if name == TsigAlgorithm::HmacSha512.to_name() {
+            TsigAlgorithm::HmacSha512
+        } else {
+            TsigAlgorithm::Unknown(name)
+        }
+    }
+}
+
+impl TSIG {
+    /// Constructs a new TSIG
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - the algorithm of the MAC
+    /// * `time` - the time the message was signed, seconds since the epoch, only the lower 48 bits are used
+    /// * `fudge` - the seconds of error permitted in `time`
+    /// * `mac` - the MAC of the message
+    /// * `oid` - the original id of the message
+    /// * `error` - the extended response code, e.g. `BADSIG`, `BADKEY` or `BADTIME`, or 0
+    /// * `other` - the other data, the time of the server for `BADTIME`, or empty
+    pub fn new(
+        algorithm: TsigAlgorithm,
+        time: u64,
+        fudge: u16,
+        mac: Vec<u8>,
+        oid: u16,
+        error: u16,
+        other: Vec<u8>,
+    ) -> Self {
+        TSIG {
+            algorithm,
+            time: time & 0xFFFF_FFFF_FFFF,
+            fudge,
+            mac,
+            oid,
+            error,
+            other,
+        }
+    }
+
+    /// The algorithm of the MAC
+    pub fn algorithm(&self) -> &TsigAlgorithm {
+        &self.algorithm
+    }
+
+    /// The time the message was signed, seconds since the epoch
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// The seconds of error permitted in the time signed
+    pub fn fudge(&self) -> u16 {
+        self.fudge
+    }
+
+    /// The MAC of the message
+    pub fn mac(&self) -> &[u8] {
+        &self.mac
+    }
+
+    /// Sets the MAC of the message
+    pub fn set_mac(&mut self, mac: Vec<u8>) -> &mut Self {
+        self.mac = mac;
+        self
+    }
+
+    /// The original id of the message
+    pub fn oid(&self) -> u16 {
+        self.oid
+    }
+
+    /// The extended response code, 0 if there is none
+    pub fn error(&self) -> u16 {
+        self.error
+    }
+
+    /// The other data
+    pub fn other(&self) -> &[u8] {
+        &self.other
+    }
+
+    /// Returns true if `now` is within the fudge of the time signed
+    pub fn is_current(&self, now: u64) -> bool {
+        let now = now & 0xFFFF_FFFF_FFFF;
+        let delta = if now > self.time {
+            now - self.time
+        } else {
+            self.time - now
+        };
+
+        delta <= u64::from(self.fudge)
+    }
+
+    /// Returns the TSIG record for the key, the final record of a signed message
+    pub fn into_record(self, key_name: Name) -> Record {
+        let mut record = Record::from_rdata(key_name, 0, RecordType::TSIG, RData::TSIG(self));
+        record.set_dns_class(DNSClass::ANY);
+        record
+    }
+}
+
+/// Read the RData from the given Decoder
+pub fn read(decoder: &mut BinDecoder, rdata_length: u16) -> ProtoResult<TSIG> {
+    let start_idx = decoder.index();
+
+    let algorithm = TsigAlgorithm::from_name(Name::read(decoder)?);
+    let time = (u64::from(decoder.read_u16()?) << 32) | u64::from(decoder.read_u32()?);
+    let fudge = decoder.read_u16()?;
+    let mac_size = decoder.read_u16()?;
+    let mac = decoder.read_vec(mac_size as usize)?;
+    let oid = decoder.read_u16()?;
+    let error = decoder.read_u16()?;
+    let other_len = decoder.read_u16()?;
+    let other = decoder.read_vec(other_len as usize)?;
+
+    let read = decoder.index() - start_idx;
+    if read != rdata_length as usize {
+        return Err(ProtoErrorKind::IncorrectRDataLengthRead(read, rdata_length as usize).into());
+    }
+
+    Ok(TSIG {
+        algorithm,
+        time,
+        fudge,
+        mac,
+        oid,
+        error,
+        other,
+    })
+}
+
+/// Write the RData from the given Decoder
+pub fn emit(encoder: &mut BinEncoder, tsig: &TSIG) -> ProtoResult<()> {
+    // the algorithm name is never compressed
+    tsig.algorithm
+        .to_name()
+        .to_lowercase()
+        .emit_as_canonical(encoder, true)?;
+    emit_time(encoder, tsig.time)?;
+    encoder.emit_u16(tsig.fudge)?;
+    encoder.emit_u16(tsig.mac.len() as u16)?;
+    encoder.emit_vec(&tsig.mac)?;
+    encoder.emit_u16(tsig.oid)?;
+    encoder.emit_u16(tsig.error)?;
+    encoder.emit_u16(tsig.other.len() as u16)?;
+    encoder.emit_vec(&tsig.other)?;
+    Ok(())
+}
+
+/// The time signed is a 48 bit unsigned integer
+fn emit_time(encoder: &mut BinEncoder, time: u64) -> ProtoResult<()> {
+    encoder.emit_u16((time >> 32) as u16)?;
+    encoder.emit_u32(time as u32)
+}
+
+/// Splits a signed message, as it was received, into the message covered by the MAC and the TSIG record
+///
+/// [RFC 8945, Secret Key Transaction Authentication for DNS](https://tools.ietf.org/html/rfc8945#section-4.3.1)
+///
+/// ```text
+/// 4.3.1.  DNS Message
+///
+///    A whole and complete DNS message in wire format, before the TSIG RR
+///    has been added to the Additional section and before the DNS Message
+///    Header's ARCOUNT field has been incremented to contain the TSIG RR.
+///    If the message ID differs from the original message ID, the original
+///    message ID is substituted for the message ID.
+/// ```
+///
+/// # Returns
+///
+/// None if the message is not signed, i.e. its final record is not a TSIG
+pub fn split_message(message: &[u8]) -> ProtoResult<Option<(Vec<u8>, Record)>> {
+    let mut decoder = BinDecoder::new(message);
+    let header = Header::read(&mut decoder)?;
+
+    for _ in 0..header.query_count() {
+        Query::read(&mut decoder)?;
+    }
+
+    let record_count = u32::from(header.answer_count()) + u32::from(header.name_server_count())
+        + u32::from(header.additional_count());
+    let mut last = None;
+    for _ in 0..record_count {
+        let start = decoder.index();
+        last = Some((start, Record::read(&mut decoder)?));
+    }
+
+    let (start, record) = match last {
+        Some((start, record)) if record.rr_type() == RecordType::TSIG => (start, record),
+        _ => return Ok(None),
+    };
+
+    let oid = match *record.rdata() {
+        RData::TSIG(ref tsig) => tsig.oid(),
+        _ => return Err(ProtoErrorKind::Message("TSIG record without TSIG rdata").into()),
+    };
+
+    let mut unsigned = message[..start].to_vec();
+    let additional_count = header.additional_count() - 1;
+    unsigned[0] = (oid >> 8) as u8;
+    unsigned[1] = oid as u8;
+    unsigned[10] = (additional_count >> 8) as u8;
+    unsigned[11] = additional_count as u8;
+
+    Ok(Some((unsigned, record)))
+}
+
+/// Returns the data covered by the MAC of a message
+///
+/// [RFC 8945, Secret Key Transaction Authentication for DNS](https://tools.ietf.org/html/rfc8945#section-4.3)
+///
+/// ```text
+/// 4.3.  Generation of TSIG on Requests
+///
+///    Once the outgoing record has been constructed, the client performs
+///    the following operations:
+///
+///    1.  compute the MAC using the algorithm and key, over the request
+///        MAC for responses, the DNS message, and the TSIG variables:
+///        the key name and algorithm name in canonical wire format, class
+///        ANY, TTL 0, time signed, fudge, error, other len and other data
+/// ```
+///
+/// # Arguments
+///
+/// * `previous` - the length prefixed MAC of the request for a response, or of the prior signed
+///                message for the subsequent messages of a response, followed by any unsigned
+///                messages since, empty for a request
+/// * `message` - the message, without its TSIG, see `split_message`
+/// * `key_name` - the name of the key
+/// * `tsig` - the TSIG of the message, the MAC is ignored
+/// * `first` - false for the subsequent messages of a response, for which only the time signed
+///             and fudge of the TSIG variables are covered, RFC 8945 section 5.3.1
+pub fn message_tbs(
+    previous: &[u8],
+    message: &[u8],
+    key_name: &Name,
+    tsig: &TSIG,
+    first: bool,
+) -> ProtoResult<Vec<u8>> {
+    let mut tbs: Vec<u8> = Vec::with_capacity(previous.len() + message.len() + 64);
+    {
+        let mut encoder = BinEncoder::with_mode(&mut tbs, EncodeMode::Signing);
+        encoder.emit_vec(previous)?;
+        encoder.emit_vec(message)?;
+
+        if first {
+            key_name.to_lowercase().emit_as_canonical(&mut encoder, true)?;
+            DNSClass::ANY.emit(&mut encoder)?;
+            encoder.emit_u32(0)?;
+            tsig.algorithm
+                .to_name()
+                .to_lowercase()
+                .emit_as_canonical(&mut encoder, true)?;
+            emit_time(&mut encoder, tsig.time)?;
+            encoder.emit_u16(tsig.fudge)?;
+            encoder.emit_u16(tsig.error)?;
+            encoder.emit_u16(tsig.other.len() as u16)?;
+            encoder.emit_vec(&tsig.other)?;
+        } else {
+            emit_time(&mut encoder, tsig.time)?;
+            encoder.emit_u16(tsig.fudge)?;
+        }
+    }
+
+    Ok(tbs)
+}
+
+/// Returns the MAC prefixed with its length, as it's covered by the MAC of the following message
+pub fn prefixed_mac(mac: &[u8]) -> Vec<u8> {
+    let mut prefixed = Vec::with_capacity(mac.len() + 2);
+    prefixed.push((mac.len() >> 8) as u8);
+    prefixed.push(mac.len() as u8);
+    prefixed.extend_from_slice(mac);
+    prefixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use op::{Message, MessageType, OpCode};
+
+    fn tsig() -> TSIG {
+        TSIG::new(
+            TsigAlgorithm::HmacSha256,
+            1_500_000_000,
+            300,
+            vec![1, 2, 3, 4],
+            0x1234,
+            0,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_algorithm_names() {
+        for algorithm in &[TsigAlgorithm::HmacSha256, TsigAlgorithm::HmacSha512] {
+            assert_eq!(*algorithm, TsigAlgorithm::from_name(algorithm.to_name()));
+        }
+
+        assert_eq!(
+            TsigAlgorithm::HmacSha256,
+            TsigAlgorithm::from_name(Name::from_ascii("HMAC-SHA256.").unwrap())
+        );
+
+        let unknown = Name::from_ascii("hmac-md5.sig-alg.reg.int.").unwrap();
+        assert_eq!(
+            TsigAlgorithm::Unknown(unknown.clone()),
+            TsigAlgorithm::from_name(unknown)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let rdata = TSIG::new(
+            TsigAlgorithm::HmacSha512,
+            0xFFFF_0000_0001,
+            300,
+            vec![0xAB; 64],
+            7,
+            18,
+            vec![0, 0, 1, 2, 3, 4],
+        );
+
+        let mut bytes = Vec::new();
+        {
+            let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+            assert!(emit(&mut encoder, &rdata).is_ok());
+        }
+
+        let mut decoder: BinDecoder = BinDecoder::new(&bytes);
+        let read_rdata = read(&mut decoder, bytes.len() as u16).expect("failed to read TSIG");
+        assert_eq!(read_rdata, rdata);
+    }
+
+    #[test]
+    fn test_is_current() {
+        let tsig = tsig();
+        assert!(tsig.is_current(1_500_000_000));
+        assert!(tsig.is_current(1_500_000_300));
+        assert!(tsig.is_current(1_499_999_700));
+        assert!(!tsig.is_current(1_500_000_301));
+        assert!(!tsig.is_current(1_499_999_699));
+    }
+
+    #[test]
+    fn test_split_message() {
+        let mut message = Message::new();
+        message
+            .set_id(0x4321)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .add_query(Query::query(
+                Name::from_ascii("www.example.com.").unwrap(),
+                RecordType::A,
+            ));
+
+        let mut unsigned = message.to_vec().unwrap();
+        // the original id is restored
+        unsigned[0] = 0x12;
+        unsigned[1] = 0x34;
+
+        assert!(split_message(&message.to_vec().unwrap()).unwrap().is_none());
+
+        let key_name = Name::from_ascii("key.example.com.").unwrap();
+        message.add_tsig(tsig().into_record(key_name.clone()));
+        let (split, record) = split_message(&message.to_vec().unwrap())
+            .unwrap()
+            .expect("message is signed");
+
+        assert_eq!(split, unsigned);
+        assert_eq!(*record.name(), key_name);
+        assert_eq!(record.dns_class(), DNSClass::ANY);
+        assert_eq!(*record.rdata(), RData::TSIG(tsig()));
+    }
+
+    #[test]
+    fn test_message_tbs() {
+        let key_name = Name::from_ascii("Key.Example.com.").unwrap();
+        let tsig = tsig();
+
+        let first = message_tbs(&[], &[0xFF], &key_name, &tsig, true).unwrap();
+        assert_eq!(first[0], 0xFF);
+        // the key name is lowercased
+        assert_eq!(&first[1..5], b"\x03key");
+        assert_eq!(first.len(), 1 + 17 + 2 + 4 + 13 + 6 + 2 + 2 + 2);
+
+        let prefix = prefixed_mac(tsig.mac());
+        assert_eq!(prefix, vec![0, 4, 1, 2, 3, 4]);
+
+        let subsequent = message_tbs(&prefix, &[0xFF], &key_name, &tsig, false).unwrap();
+        assert_eq!(&subsequent[..7], &[0, 4, 1, 2, 3, 4, 0xFF]);
+        assert_eq!(subsequent.len(), 7 + 6 + 2);
+    }
+}
@@ -26,7 +26,7 @@ use serialize::binary::*;
 use super::domain::Name;
 use super::record_type::RecordType;
 use super::rdata;
-use super::rdata::{CAA, MX, NULL, OPT, SOA, SRV, TLSA, TXT};
+use super::rdata::{CAA, MX, NULL, OPT, SOA, SRV, TLSA, TSIG, TXT};
 
 #[cfg(feature = "dnssec")]
 use super::dnssec::rdata::DNSSECRData;
@@ -362,6 +362,11 @@ pub enum RData {
     /// ```
     TLSA(TLSA),
 
+    /// [RFC 8945, Secret Key Transaction Authentication for DNS](https://tools.ietf.org/html/rfc8945#section-4.2)
+    ///
+    /// The transaction signature of a message, see `TSIG` for the wire format
+    TSIG(TSIG),
+
     /// ```text
     /// 3.3.14. TXT RDATA format
     ///
@@ -474,6 +479,10 @@ impl RData {
                 debug!("reading TLSA");
                 rdata::tlsa::read(decoder, rdata_length).map(RData::TLSA)
             }
+            RecordType::TSIG => {
+                debug!("reading TSIG");
+                rdata::tsig::read(decoder, rdata_length).map(RData::TSIG)
+            }
             RecordType::TXT => {
                 debug!("reading TXT");
                 rdata::txt::read(decoder, rdata_length).map(RData::TXT)
@@ -534,6 +543,7 @@ impl RData {
             // to_lowercase for rfc4034 and rfc6840
             RData::SRV(ref srv) => rdata::srv::emit(encoder, srv),
             RData::TLSA(ref tlsa) => rdata::tlsa::emit(encoder, tlsa),
+            RData::TSIG(ref tsig) => rdata::tsig::emit(encoder, tsig),
             RData::TXT(ref txt) => rdata::txt::emit(encoder, txt),
             #[cfg(feature = "dnssec")]
             RData::DNSSEC(ref rdata) => rdata.emit(encoder),
@@ -556,6 +566,7 @@ impl RData {
             RData::SOA(..) => RecordType::SOA,
             RData::SRV(..) => RecordType::SRV,
             RData::TLSA(..) => RecordType::TLSA,
+            RData::TSIG(..) => RecordType::TSIG,
             RData::TXT(..) => RecordType::TXT,
             #[cfg(feature = "dnssec")]
             RData::DNSSEC(ref rdata) => RecordType::DNSSEC(DNSSECRData::to_record_type(rdata)),
@@ -912,6 +923,7 @@ mod tests {
             RData::SOA(..) => RecordType::SOA,
             RData::SRV(..) => RecordType::SRV,
             RData::TLSA(..) => RecordType::TLSA,
+            RData::TSIG(..) => RecordType::TSIG,
             RData::TXT(..) => RecordType::TXT,
             #[cfg(feature = "dnssec")]
             RData::DNSSEC(ref rdata) => RecordType::DNSSEC(rdata.to_record_type()),
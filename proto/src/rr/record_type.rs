@@ -78,7 +78,8 @@ pub enum RecordType {
     //  TKEY,       //	249	RFC 2930	Secret key record
     ///	RFC 6698	TLSA certificate association
     TLSA,
-    //  TSIG,       //	250	RFC 2845	Transaction Signature
+    /// RFC 8945	Transaction Signature
+    TSIG,
     /// RFC 1035[1]	Text record
     TXT,
 
@@ -141,9 +142,14 @@ impl FromStr for RecordType {
             "SOA" => Ok(RecordType::SOA),
             "SRV" => Ok(RecordType::SRV),
             "TLSA" => Ok(RecordType::TLSA),
+            "TSIG" => Ok(RecordType::TSIG),
             "TXT" => Ok(RecordType::TXT),
             "ANY" | "*" => Ok(RecordType::ANY),
             "AXFR" => Ok(RecordType::AXFR),
+            "IXFR" => Ok(RecordType::IXFR),
+            #[cfg(feature = "dnssec")]
+            _ => DNSSECRecordType::from_str(str).map(RecordType::DNSSEC),
+            #[cfg(not(feature = "dnssec"))]
             _ => Err(ProtoErrorKind::UnknownRecordTypeStr(str.to_string()).into()),
         }
     }
@@ -164,6 +170,7 @@ impl From<u16> for RecordType {
             28 => RecordType::AAAA,
             255 => RecordType::ANY,
             252 => RecordType::AXFR,
+            251 => RecordType::IXFR,
             257 => RecordType::CAA,
             5 => RecordType::CNAME,
             0 => RecordType::ZERO,
@@ -175,8 +182,11 @@ impl From<u16> for RecordType {
             6 => RecordType::SOA,
             33 => RecordType::SRV,
             52 => RecordType::TLSA,
+            250 => RecordType::TSIG,
             16 => RecordType::TXT,
             #[cfg(feature = "dnssec")]
+            60/*CDNSKEY*/ |
+            59/*CDS*/ |
             48/*DNSKEY*/ |
             43/*DS*/ |
             25/*KEY*/ |
@@ -238,6 +248,7 @@ impl From<RecordType> for &'static str {
             RecordType::SOA => "SOA",
             RecordType::SRV => "SRV",
             RecordType::TLSA => "TLSA",
+            RecordType::TSIG => "TSIG",
             RecordType::TXT => "TXT",
             #[cfg(feature = "dnssec")]
             RecordType::DNSSEC(rt) => rt.into(),
@@ -274,6 +285,7 @@ impl From<RecordType> for u16 {
             RecordType::SOA => 6,
             RecordType::SRV => 33,
             RecordType::TLSA => 52,
+            RecordType::TSIG => 250,
             RecordType::TXT => 16,
             #[cfg(feature = "dnssec")]
             RecordType::DNSSEC(rt) => rt.into(),
@@ -342,3 +354,28 @@ fn test_order() {
 
     assert_eq!(ordered, unordered);
 }
+
+#[test]
+fn test_round_trip() {
+    for rt in &[RecordType::AXFR, RecordType::IXFR, RecordType::TSIG] {
+        assert_eq!(*rt, RecordType::from(u16::from(*rt)));
+        assert_eq!(*rt, RecordType::from_str((*rt).into()).unwrap());
+    }
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_dnssec_round_trip() {
+    for rt in &[
+        DNSSECRecordType::CDNSKEY,
+        DNSSECRecordType::CDS,
+        DNSSECRecordType::DNSKEY,
+        DNSSECRecordType::DS,
+        DNSSECRecordType::NSEC3PARAM,
+        DNSSECRecordType::RRSIG,
+    ] {
+        let rt = RecordType::DNSSEC(*rt);
+        assert_eq!(rt, RecordType::from(u16::from(rt)));
+        assert_eq!(rt, RecordType::from_str(rt.into()).unwrap());
+    }
+}
@@ -14,12 +14,12 @@ use futures::*;
 
 use DnsHandle;
 use error::*;
-use op::{Message, OpCode, Query};
+use op::{Message, OpCode, Query, ResponseCode};
 use rr::{Name, DNSClass, RData, Record, RecordType};
 #[cfg(feature = "dnssec")]
 use rr::dnssec::Verifier;
 use rr::dnssec::{Algorithm, SupportedAlgorithms, TrustAnchor};
-use rr::dnssec::rdata::{DNSSECRData, DNSSECRecordType, DNSKEY, SIG};
+use rr::dnssec::rdata::{DNSSECRData, DNSSECRecordType, DNSKEY, NSEC, NSEC3, SIG};
 use rr::rdata::opt::EdnsOption;
 
 #[derive(Debug)]
@@ -152,41 +152,63 @@ where
                 .queries()
                 .first()
                 .map_or(DNSClass::IN, |q| q.query_class());
-
-            return Box::new(
-                self.handle
-                    .send(message)
-                    .and_then(move |message_response| {
-                        // group the record sets by name and type
-                        //  each rrset type needs to validated independently
-                        debug!("validating message_response: {}", message_response.id());
-                        verify_rrsets(&handle, message_response, dns_class)
-                    })
-                    .and_then(move |verified_message| {
+            let request_depth = self.request_depth;
+
+            return Box::new(self.handle.send(message).and_then(move |message_response| {
+                // group the record sets by name and type
+                //  each rrset type needs to validated independently
+                debug!("validating message_response: {}", message_response.id());
+                // the RRSIGs do not remain after verification, wildcard expansions are found now
+                let wildcards = wildcard_expansions(&message_response);
+                verify_rrsets(&handle, message_response, dns_class)
+                    .and_then(move |mut verified_message| {
                         // at this point all of the message is verified.
-                        //  This is where NSEC (and possibly NSEC3) validation occurs
-                        // As of now, only NSEC is supported.
+                        //  This is where NSEC and NSEC3 validation occurs
                         if verified_message.answers().is_empty() {
-                            let nsecs = verified_message
-                                .name_servers()
-                                .iter()
-                                .filter(|rr| is_dnssec(rr, DNSSECRecordType::NSEC))
-                                .collect::<Vec<_>>();
+                            let nxdomain =
+                                verified_message.response_code() == ResponseCode::NXDomain;
 
-                            if !verify_nsec(&query, nsecs.as_slice()) {
+                            if !verify_denial(&query, nxdomain, verified_message.name_servers()) {
                                 // TODO change this to remove the NSECs, like we do for the others?
                                 return Err(E::from(
                                     ProtoErrorKind::Message(
-                                        "could not validate nxdomain \
-                                         with NSEC",
+                                        "could not validate negative response \
+                                         with NSEC or NSEC3",
                                     ).into(),
                                 ));
                             }
                         }
 
+                        // answers synthesized from a wildcard are only valid if the name does not
+                        //  exist in the zone, RFC 4035 5.3.4 and RFC 5155 8.8
+                        for &(ref name, ref encloser) in &wildcards {
+                            if !verify_wildcard_answer(
+                                name,
+                                encloser,
+                                verified_message.name_servers(),
+                            ) {
+                                return Err(E::from(
+                                    ProtoErrorKind::Message(
+                                        "could not validate wildcard expansion \
+                                         with NSEC or NSEC3",
+                                    ).into(),
+                                ));
+                            }
+                        }
+
+                        // only the verified records remain, the response is authentic
+                        verified_message.set_authentic_data(true);
                         Ok(verified_message)
-                    }),
-            );
+                    })
+                    .map_err(move |e| {
+                        // failures of the inner validations are reported once, for the request
+                        if request_depth == 0 {
+                            E::from(ProtoErrorKind::Bogus(e.to_string()).into())
+                        } else {
+                            e
+                        }
+                    })
+            }));
         }
 
         self.handle.send(message)
@@ -222,9 +244,12 @@ where
                              //   this means that only DNSKEY and DS are intersting at that point.
                              //   this protects against looping over things like NS records and DNSKEYs in responses.
                              // TODO: is there a cleaner way to prevent cycles in the evaluations?
+                             //   NSEC and NSEC3 are needed to prove the absence of a DS.
                                           (handle.request_depth <= 1 ||
                                            is_dnssec(rr, DNSSECRecordType::DNSKEY) ||
-                                           is_dnssec(rr, DNSSECRecordType::DS))
+                                           is_dnssec(rr, DNSSECRecordType::DS) ||
+                                           is_dnssec(rr, DNSSECRecordType::NSEC) ||
+                                           is_dnssec(rr, DNSSECRecordType::NSEC3))
         })
         .map(|rr| (rr.name().clone(), rr.rr_type()))
     {
@@ -779,6 +804,110 @@ fn verify_rrset_with_dnskey(_: &DNSKEY, _: &SIG, _: &Rrset) -> ProtoResult<()> {
 }
 
 
+/// RFC 9276, NSEC3 records with more iterations are not used to prove denial of existence
+const MAX_NSEC3_ITERATIONS: u16 = 150;
+
+/// Returns the owner names of the answers synthesized from a wildcard, with their closest encloser
+///
+/// The Labels field of the RRSIG is less than the labels of the owner for wildcard expansions,
+///  RFC 4035 5.3.4.
+fn wildcard_expansions(message: &Message) -> Vec<(Name, Name)> {
+    message
+        .answers()
+        .iter()
+        .filter_map(|rr| {
+            if let RData::DNSSEC(DNSSECRData::SIG(ref rrsig)) = *rr.rdata() {
+                if rrsig.num_labels() < rr.name().num_labels() {
+                    let encloser = rr.name().trim_to(rrsig.num_labels() as usize);
+                    return Some((rr.name().clone(), encloser));
+                }
+            }
+
+            None
+        })
+        .collect()
+}
+
+/// Verifies that the NSEC or NSEC3 records prove the query has no answer
+///
+/// A NoError response is accepted with a proof that the name does not exist, it denies the
+///  records just as well.
+fn verify_denial(query: &Query, nxdomain: bool, records: &[Record]) -> bool {
+    let nsecs = nsec_records(records);
+    if !nsecs.is_empty() {
+        return verify_nsec(query, nxdomain, &nsecs);
+    }
+
+    let nsec3s = nsec3_records(records);
+    if !nsec3s.is_empty() {
+        return verify_nsec3(query, nxdomain, &nsec3s);
+    }
+
+    false
+}
+
+/// Verifies that the name of a wildcard expansion does not exist
+///
+/// For NSEC the name must be covered, for NSEC3 the next closer name of the closest encloser.
+fn verify_wildcard_answer(name: &Name, encloser: &Name, records: &[Record]) -> bool {
+    let nsecs = nsec_records(records);
+    if !nsecs.is_empty() {
+        return nsecs
+            .iter()
+            .any(|&(owner, nsec)| nsec_covers(owner, nsec.next_domain_name(), name));
+    }
+
+    let next_closer = name.trim_to(encloser.num_labels() as usize + 1);
+    nsec3_covering(&next_closer, &nsec3_records(records)).is_some()
+}
+
+fn nsec_records(records: &[Record]) -> Vec<(&Name, &NSEC)> {
+    records
+        .iter()
+        .filter_map(|rr| {
+            if let RData::DNSSEC(DNSSECRData::NSEC(ref nsec)) = *rr.rdata() {
+                Some((rr.name(), nsec))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn nsec3_records(records: &[Record]) -> Vec<(&Name, &NSEC3)> {
+    records
+        .iter()
+        .filter_map(|rr| {
+            if let RData::DNSSEC(DNSSECRData::NSEC3(ref nsec3)) = *rr.rdata() {
+                if nsec3.iterations() > MAX_NSEC3_ITERATIONS {
+                    debug!(
+                        "ignoring NSEC3 with {} iterations: {}",
+                        nsec3.iterations(),
+                        rr.name()
+                    );
+                    return None;
+                }
+
+                Some((rr.name(), nsec3))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// A denial of the type is not valid if the type, or a CNAME, exists at the name
+fn has_type_or_cname(type_bit_maps: &[RecordType], record_type: RecordType) -> bool {
+    type_bit_maps.contains(&record_type) || type_bit_maps.contains(&RecordType::CNAME)
+}
+
+/// The wildcard which would be expanded below the closest encloser
+fn wildcard_of(encloser: &Name) -> Name {
+    Name::from_labels(vec!["*"])
+        .expect("* is a valid label")
+        .append_name(encloser)
+}
+
 /// Verifies NSEC records
 ///
 /// ```text
@@ -831,38 +960,446 @@ fn verify_rrset_with_dnskey(_: &DNSKEY, _: &SIG, _: &Rrset) -> ProtoResult<()> {
 ///  corresponding RRSIG RR, a validator MUST ignore the settings of the
 ///  NSEC and RRSIG bits in an NSEC RR.
 /// ```
-fn verify_nsec(query: &Query, nsecs: &[&Record]) -> bool {
+fn verify_nsec(query: &Query, nxdomain: bool, nsecs: &[(&Name, &NSEC)]) -> bool {
+    let name = query.name();
+
     // first look for a record with the same name
-    //  if they are, then the query_type should not exist in the NSEC record.
-    //  if we got an NSEC record of the same name, but it is listed in the NSEC types,
-    //    WTF? is that bad server, bad record
-    if nsecs.iter().any(|r| {
-        query.name() == r.name() && {
-            if let RData::DNSSEC(DNSSECRData::NSEC(ref rdata)) = *r.rdata() {
-                !rdata.type_bit_maps().contains(&query.query_type())
-            } else {
-                panic!("expected NSEC was {:?}", r.rr_type()) // valid panic, never should happen
-            }
+    //  if there is one, then the name exists and the query_type should not be in the NSEC record.
+    if let Some(&(_, nsec)) = nsecs.iter().find(|&&(owner, _)| owner == name) {
+        return !nxdomain && !has_type_or_cname(nsec.type_bit_maps(), query.query_type());
+    }
+
+    // otherwise the name must be covered
+    let (owner, next) = match nsecs
+        .iter()
+        .find(|&&(owner, nsec)| nsec_covers(owner, nsec.next_domain_name(), name))
+    {
+        Some(&(owner, nsec)) => (owner, nsec.next_domain_name()),
+        None => return false,
+    };
+
+    // a name below this one exists, it's an empty non-terminal
+    if name.zone_of(next) {
+        return !nxdomain;
+    }
+
+    // the closest encloser is the longest ancestor shared with either end of the covering NSEC
+    let encloser = {
+        let from_owner = common_ancestor(name, owner);
+        let from_next = common_ancestor(name, next);
+        if from_next.num_labels() > from_owner.num_labels() {
+            from_next
+        } else {
+            from_owner
         }
-    }) {
+    };
+
+    // no wildcard could have matched, RFC 4035 5.4
+    let wildcard = wildcard_of(&encloser);
+    if nsecs
+        .iter()
+        .any(|&(owner, nsec)| nsec_covers(owner, nsec.next_domain_name(), &wildcard))
+    {
         return true;
     }
 
-    // based on the WTF? above, we will ignore any NSEC records of the same name
-    if nsecs.iter().filter(|r| query.name() != r.name()).any(|r| {
-        query.name() > r.name() && {
-            if let RData::DNSSEC(DNSSECRData::NSEC(ref rdata)) = *r.rdata() {
-                query.name() < rdata.next_domain_name()
-            } else {
-                panic!("expected NSEC was {:?}", r.rr_type()) // valid panic, never should happen
-            }
-        }
-    }) {
+    // the wildcard matched, but doesn't have the type, RFC 4035 3.1.3.4
+    !nxdomain && nsecs.iter().any(|&(owner, nsec)| {
+        owner == &wildcard && !has_type_or_cname(nsec.type_bit_maps(), query.query_type())
+    })
+}
+
+/// Returns true if the name is between the owner and the next name of the NSEC
+fn nsec_covers(owner: &Name, next: &Name, name: &Name) -> bool {
+    // the last NSEC of the zone points back to the apex
+    owner < name && (name < next || next <= owner)
+}
+
+/// Returns the longest ancestor of both names
+fn common_ancestor(name: &Name, other: &Name) -> Name {
+    let labels = name.iter()
+        .rev()
+        .zip(other.iter().rev())
+        .take_while(|&(label, other_label)| label.eq_ignore_ascii_case(other_label))
+        .count();
+
+    name.trim_to(labels)
+}
+
+/// Verifies NSEC3 records
+///
+/// ```text
+/// RFC 5155                         NSEC3                        March 2008
+///
+/// 8.3.  Closest Encloser Proof
+///
+///    For some NSEC3 responses, the validator MUST verify a closest
+///    encloser proof.
+///
+///    The validator MUST verify that there is an NSEC3 RR that matches
+///    the closest encloser and that there is an NSEC3 RR that covers the
+///    next closer name.
+///
+/// 8.4.  Validating Name Error Responses
+///
+///    A validator MUST verify that there is a closest encloser proof for
+///    QNAME present in the response and that there is an NSEC3 RR that
+///    covers the wildcard at the closest encloser (i.e., the name formed by
+///    prepending the asterisk label to the closest encloser).
+///
+/// 8.5.  Validating No Data Responses, QTYPE is not DS
+///
+///    The validator MUST verify that an NSEC3 RR that matches QNAME is
+///    present and that both the QTYPE and the CNAME type are not set in its
+///    Type Bit Maps field.
+///
+/// 8.6.  Validating No Data Responses, QTYPE is DS
+///
+///    If there is an NSEC3 RR that matches QNAME present in the response,
+///    then that NSEC3 RR MUST NOT have the bits corresponding to DS and
+///    CNAME set in its Type Bit Maps field.
+///
+///    If there is no such NSEC3 RR, then the validator MUST verify a
+///    closest provable encloser proof for QNAME.  The validator MUST verify
+///    that the NSEC3 RR that covers the "next closer" name has the Opt-Out
+///    bit set.
+///
+/// 8.7.  Validating Wildcard No Data Responses
+///
+///    The validator MUST verify a closest encloser for QNAME.  The
+///    validator MUST find a matching NSEC3 RR for the wildcard at the
+///    closest encloser.  The validator MUST verify that both the QTYPE and
+///    the CNAME type are not set in the Type Bit Maps field of the NSEC3
+///    RR.
+/// ```
+#[cfg(any(feature = "openssl", feature = "ring"))]
+fn verify_nsec3(query: &Query, nxdomain: bool, nsec3s: &[(&Name, &NSEC3)]) -> bool {
+    let name = query.name();
+
+    // the name exists, RFC 5155 8.5 and 8.6
+    if let Some(nsec3) = nsec3_matching(name, nsec3s) {
+        return !nxdomain && !has_type_or_cname(nsec3.type_bit_maps(), query.query_type());
+    }
+
+    let (encloser, next_closer) = match nsec3_closest_encloser(name, nsec3s) {
+        Some(proof) => proof,
+        None => return false,
+    };
+
+    // unsigned delegations are not in the chain of an Opt-Out zone, RFC 5155 8.6
+    if !nxdomain && query.query_type() == RecordType::DNSSEC(DNSSECRecordType::DS)
+        && next_closer.opt_out()
+    {
+        return true;
+    }
+
+    // no wildcard could have matched, RFC 5155 8.4
+    let wildcard = wildcard_of(&encloser);
+    if nsec3_covering(&wildcard, nsec3s).is_some() {
         return true;
     }
 
-    // TODO: need to validate ANY or *.domain record existance, which doesn't make sense since
-    //  that would have been returned in the request
-    // if we got here, then there are no matching NSEC records, no validation
+    // the wildcard matched, but doesn't have the type, RFC 5155 8.7
+    !nxdomain && nsec3_matching(&wildcard, nsec3s).map_or(false, |nsec3| {
+        !has_type_or_cname(nsec3.type_bit_maps(), query.query_type())
+    })
+}
+
+/// Will always return false. To validate NSEC3 compile with the openssl or ring feature.
+#[cfg(not(any(feature = "openssl", feature = "ring")))]
+fn verify_nsec3(_: &Query, _: bool, _: &[(&Name, &NSEC3)]) -> bool {
     false
 }
+
+/// Finds the closest encloser of the name, and the NSEC3 covering the next closer name
+#[cfg(any(feature = "openssl", feature = "ring"))]
+fn nsec3_closest_encloser<'a>(
+    name: &Name,
+    nsec3s: &[(&Name, &'a NSEC3)],
+) -> Option<(Name, &'a NSEC3)> {
+    for labels in (0..name.iter().count()).rev() {
+        let encloser = name.trim_to(labels);
+        if let Some(nsec3) = nsec3_matching(&encloser, nsec3s) {
+            // a delegation or DNAME can not be the closest encloser of names in this zone
+            let types = nsec3.type_bit_maps();
+            if types.contains(&RecordType::DNAME)
+                || (types.contains(&RecordType::NS) && !types.contains(&RecordType::SOA))
+            {
+                return None;
+            }
+
+            let next_closer = name.trim_to(labels + 1);
+            return nsec3_covering(&next_closer, nsec3s).map(|nsec3| (encloser, nsec3));
+        }
+    }
+
+    None
+}
+
+/// Returns the NSEC3 whose owner is the hash of the name
+#[cfg(any(feature = "openssl", feature = "ring"))]
+fn nsec3_matching<'a>(name: &Name, nsec3s: &[(&Name, &'a NSEC3)]) -> Option<&'a NSEC3> {
+    nsec3s
+        .iter()
+        .find(|&&(owner, nsec3)| match nsec3_hashes(owner, name, nsec3) {
+            Some((owner_hash, hash)) => owner_hash == hash,
+            None => false,
+        })
+        .map(|&(_, nsec3)| nsec3)
+}
+
+/// Returns the NSEC3 whose owner and next hashed owner name are around the hash of the name
+#[cfg(any(feature = "openssl", feature = "ring"))]
+fn nsec3_covering<'a>(name: &Name, nsec3s: &[(&Name, &'a NSEC3)]) -> Option<&'a NSEC3> {
+    nsec3s
+        .iter()
+        .find(|&&(owner, nsec3)| match nsec3_hashes(owner, name, nsec3) {
+            Some((owner_hash, hash)) => nsec3.covers(&owner_hash, &hash),
+            None => false,
+        })
+        .map(|&(_, nsec3)| nsec3)
+}
+
+/// Will always return None. To validate NSEC3 compile with the openssl or ring feature.
+#[cfg(not(any(feature = "openssl", feature = "ring")))]
+fn nsec3_covering<'a>(_: &Name, _: &[(&Name, &'a NSEC3)]) -> Option<&'a NSEC3> {
+    None
+}
+
+/// Returns the hash in the owner name of the NSEC3, and the hash of the name with its parameters
+///
+/// None if the name is not in the zone of the NSEC3, or if either hash is not valid.
+#[cfg(any(feature = "openssl", feature = "ring"))]
+fn nsec3_hashes(owner: &Name, name: &Name, nsec3: &NSEC3) -> Option<(Vec<u8>, Vec<u8>)> {
+    use data_encoding::BASE32_DNSSEC;
+
+    if !owner.base_name().zone_of(name) {
+        return None;
+    }
+
+    let owner_hash = match owner.iter().next().map(|label| BASE32_DNSSEC.decode(label)) {
+        Some(Ok(owner_hash)) => owner_hash,
+        _ => return None,
+    };
+
+    nsec3
+        .hash_algorithm()
+        .hash(nsec3.salt(), name, nsec3.iterations())
+        .ok()
+        .map(|hash| (owner_hash, hash.as_ref().to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    fn query(name: &str, record_type: RecordType) -> Query {
+        Query::query(Name::from_str(name).unwrap(), record_type)
+    }
+
+    /// example.com. with a wildcard below b, and d as an empty non-terminal
+    fn zone() -> Vec<(Name, Vec<RecordType>)> {
+        vec![
+            (name("example.com."), vec![RecordType::SOA, RecordType::NS]),
+            (name("a.example.com."), vec![RecordType::A]),
+            (name("*.b.example.com."), vec![RecordType::TXT]),
+            (name("c.d.example.com."), vec![RecordType::A]),
+            (name("www.example.com."), vec![RecordType::A]),
+        ]
+    }
+
+    fn nsec_chain() -> Vec<Record> {
+        let mut names = zone();
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+
+        (0..names.len())
+            .map(|i| {
+                let next = names[(i + 1) % names.len()].0.clone();
+                Record::from_rdata(
+                    names[i].0.clone(),
+                    3600,
+                    RecordType::DNSSEC(DNSSECRecordType::NSEC),
+                    RData::DNSSEC(DNSSECRData::NSEC(NSEC::new(next, names[i].1.clone()))),
+                )
+            })
+            .collect()
+    }
+
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    fn nsec3_chain(iterations: u16, opt_out: bool) -> Vec<Record> {
+        use data_encoding::BASE32_DNSSEC;
+        use rr::dnssec::Nsec3HashAlgorithm;
+
+        let salt = vec![0xAA, 0xBB, 0xCC, 0xDD];
+        let mut names = zone();
+        // empty non-terminals have an NSEC3
+        names.push((name("b.example.com."), vec![]));
+        names.push((name("d.example.com."), vec![]));
+
+        let mut hashes = names
+            .into_iter()
+            .map(|(name, types)| {
+                let hash = Nsec3HashAlgorithm::SHA1
+                    .hash(&salt, &name, iterations)
+                    .unwrap();
+                (hash.as_ref().to_vec(), types)
+            })
+            .collect::<Vec<_>>();
+        hashes.sort_by(|a, b| a.0.cmp(&b.0));
+
+        (0..hashes.len())
+            .map(|i| {
+                let owner = Name::from_labels(vec![BASE32_DNSSEC.encode(&hashes[i].0).as_str()])
+                    .unwrap()
+                    .append_domain(&name("example.com."));
+                let next = hashes[(i + 1) % hashes.len()].0.clone();
+                Record::from_rdata(
+                    owner,
+                    3600,
+                    RecordType::DNSSEC(DNSSECRecordType::NSEC3),
+                    RData::DNSSEC(DNSSECRData::NSEC3(NSEC3::new(
+                        Nsec3HashAlgorithm::SHA1,
+                        opt_out,
+                        iterations,
+                        salt.clone(),
+                        next,
+                        hashes[i].1.clone(),
+                    ))),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_nsec_nxdomain() {
+        let chain = nsec_chain();
+        assert!(verify_denial(&query("none.example.com.", RecordType::A), true, &chain));
+        assert!(!verify_denial(&query("a.example.com.", RecordType::AAAA), true, &chain));
+
+        // the NSEC covering the name, without the one covering the wildcard
+        let covering = chain
+            .iter()
+            .filter(|rr| rr.name() == &name("c.d.example.com."))
+            .cloned()
+            .collect::<Vec<_>>();
+        assert!(!verify_denial(&query("none.example.com.", RecordType::A), true, &covering));
+    }
+
+    #[test]
+    fn test_nsec_nodata() {
+        let chain = nsec_chain();
+        assert!(verify_denial(&query("a.example.com.", RecordType::AAAA), false, &chain));
+        assert!(!verify_denial(&query("a.example.com.", RecordType::A), false, &chain));
+
+        // empty non-terminal
+        assert!(verify_denial(&query("d.example.com.", RecordType::A), false, &chain));
+        assert!(!verify_denial(&query("d.example.com.", RecordType::A), true, &chain));
+    }
+
+    #[test]
+    fn test_nsec_wildcard() {
+        let chain = nsec_chain();
+        assert!(verify_denial(&query("x.b.example.com.", RecordType::MX), false, &chain));
+        assert!(!verify_denial(&query("x.b.example.com.", RecordType::TXT), false, &chain));
+
+        assert!(verify_wildcard_answer(
+            &name("x.b.example.com."),
+            &name("b.example.com."),
+            &chain
+        ));
+        assert!(!verify_wildcard_answer(
+            &name("a.example.com."),
+            &name("example.com."),
+            &chain
+        ));
+    }
+
+    #[test]
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    fn test_nsec3_nxdomain() {
+        let chain = nsec3_chain(12, false);
+        assert!(verify_denial(&query("none.example.com.", RecordType::A), true, &chain));
+        assert!(verify_denial(&query("x.none.example.com.", RecordType::A), true, &chain));
+        assert!(!verify_denial(&query("a.example.com.", RecordType::AAAA), true, &chain));
+    }
+
+    #[test]
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    fn test_nsec3_nxdomain_before_first_owner() {
+        let chain = nsec3_chain(12, false);
+        let nsec3s = nsec3_records(&chain);
+        let (owner, nsec3) = nsec3s[0];
+        let hash = |name: &Name| nsec3_hashes(owner, name, nsec3).unwrap().1;
+
+        // the hash in the owner name of the first NSEC3 of the chain
+        let first = nsec3s
+            .iter()
+            .map(|&(owner, nsec3)| nsec3_hashes(owner, owner, nsec3).unwrap().0)
+            .min()
+            .unwrap();
+
+        // a name which hashes before the first owner, it's covered by the last NSEC3 of the chain
+        let before = (0..)
+            .map(|i| name(&format!("n{}.example.com.", i)))
+            .find(|name| hash(name) < first)
+            .unwrap();
+        assert!(verify_denial(&Query::query(before.clone(), RecordType::A), true, &chain));
+
+        // as the next closer name
+        let below = Name::from_labels(vec!["x"]).unwrap().append_domain(&before);
+        assert!(verify_denial(&Query::query(below, RecordType::A), true, &chain));
+    }
+
+    #[test]
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    fn test_nsec3_iterations_cap() {
+        let chain = nsec3_chain(MAX_NSEC3_ITERATIONS + 1, false);
+        assert!(!verify_denial(&query("none.example.com.", RecordType::A), true, &chain));
+    }
+
+    #[test]
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    fn test_nsec3_nodata() {
+        let chain = nsec3_chain(12, false);
+        assert!(verify_denial(&query("a.example.com.", RecordType::AAAA), false, &chain));
+        assert!(!verify_denial(&query("a.example.com.", RecordType::A), false, &chain));
+
+        // empty non-terminal
+        assert!(verify_denial(&query("d.example.com.", RecordType::A), false, &chain));
+        assert!(!verify_denial(&query("d.example.com.", RecordType::A), true, &chain));
+    }
+
+    #[test]
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    fn test_nsec3_opt_out() {
+        let chain = nsec3_chain(12, true);
+        let ds = RecordType::DNSSEC(DNSSECRecordType::DS);
+        assert!(verify_denial(&query("sub.example.com.", ds), false, &chain));
+        assert!(!verify_denial(&query("a.example.com.", RecordType::A), false, &chain));
+    }
+
+    #[test]
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    fn test_nsec3_wildcard() {
+        let chain = nsec3_chain(12, false);
+        assert!(verify_denial(&query("x.b.example.com.", RecordType::MX), false, &chain));
+        assert!(!verify_denial(&query("x.b.example.com.", RecordType::TXT), false, &chain));
+
+        assert!(verify_wildcard_answer(
+            &name("x.b.example.com."),
+            &name("b.example.com."),
+            &chain
+        ));
+        assert!(!verify_wildcard_answer(
+            &name("a.example.com."),
+            &name("example.com."),
+            &chain
+        ));
+    }
+}
@@ -87,6 +87,69 @@ impl ResolverConfig {
     pub fn name_servers(&self) -> &[NameServerConfig] {
         &self.name_servers
     }
+
+    /// Creates a configuration using DNS over TLS with Cloudflare, `1.1.1.1`, `1.0.0.1` and `2606:4700:4700::1111`, `2606:4700:4700::1001`.
+    ///
+    /// Please see Cloudflare's [privacy statement](https://developers.cloudflare.com/1.1.1.1/commitment-to-privacy/privacy-policy/privacy-policy) for important information about what they track.
+    #[cfg(feature = "tls")]
+    pub fn cloudflare_tls() -> Self {
+        Self::tls(
+            &[
+                IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)),
+                IpAddr::V4(Ipv4Addr::new(1, 0, 0, 1)),
+                IpAddr::V6(Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111)),
+                IpAddr::V6(Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1001)),
+            ],
+            "cloudflare-dns.com",
+        )
+    }
+
+    /// Creates a configuration using DNS over TLS with Quad9, `9.9.9.9`, `149.112.112.112` and `2620:fe::fe`, `2620:fe::9`.
+    ///
+    /// Please see Quad9's [privacy statement](https://www.quad9.net/privacy/policy/) for important information about what they track.
+    #[cfg(feature = "tls")]
+    pub fn quad9_tls() -> Self {
+        Self::tls(
+            &[
+                IpAddr::V4(Ipv4Addr::new(9, 9, 9, 9)),
+                IpAddr::V4(Ipv4Addr::new(149, 112, 112, 112)),
+                IpAddr::V6(Ipv6Addr::new(0x2620, 0x00fe, 0, 0, 0, 0, 0, 0x00fe)),
+                IpAddr::V6(Ipv6Addr::new(0x2620, 0x00fe, 0, 0, 0, 0, 0, 0x0009)),
+            ],
+            "dns.quad9.net",
+        )
+    }
+
+    /// Creates a configuration using DNS over TLS with Google, `8.8.8.8`, `8.8.4.4` and `2001:4860:4860::8888`, `2001:4860:4860::8844`.
+    ///
+    /// Please see Google's [privacy statement](https://developers.google.com/speed/public-dns/privacy) for important information about what they track.
+    #[cfg(feature = "tls")]
+    pub fn google_tls() -> Self {
+        Self::tls(
+            &[
+                IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)),
+                IpAddr::V4(Ipv4Addr::new(8, 8, 4, 4)),
+                IpAddr::V6(Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8888)),
+                IpAddr::V6(Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8844)),
+            ],
+            "dns.google",
+        )
+    }
+
+    /// All the addresses of the provider on the DNS over TLS port, 853, with the name in its certificate
+    #[cfg(feature = "tls")]
+    fn tls(ips: &[IpAddr], dns_name: &str) -> Self {
+        let name_servers = ips.iter()
+            .map(|ip| NameServerConfig {
+                socket_addr: SocketAddr::new(*ip, 853),
+                protocol: Protocol::Tls {
+                    dns_name: dns_name.to_string(),
+                },
+            })
+            .collect();
+
+        Self::from_parts(None, vec![], name_servers)
+    }
 }
 
 impl Default for ResolverConfig {
@@ -148,14 +211,28 @@ impl Default for ResolverConfig {
 }
 
 /// The protocol on which a NameServer should be communicated with
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Protocol {
     /// UDP is the traditional DNS port, this is generally the correct choice
     Udp,
     /// TCP can be used for large queries, but not all NameServers support it
     Tcp,
     // TODO: add client certificate for mTLS?
-    // Tls,
+    /// DNS over TLS, RFC 7858, the server is trusted with the roots of webpki with `tls-ring`, or of
+    ///  the system with `tls-native`
+    #[cfg(feature = "tls")]
+    Tls {
+        /// The name of the server, which its certificate must match
+        dns_name: String,
+    },
+    /// DNS over HTTPS, RFC 8484, with HTTP/2, the server is trusted with the roots of webpki
+    #[cfg(feature = "https")]
+    Https {
+        /// The name of the server, which its certificate must match
+        dns_name: String,
+        /// The path of the DNS query endpoint of the server, e.g. `/dns-query`
+        endpoint: String,
+    },
 }
 
 impl Protocol {
@@ -164,6 +241,10 @@ impl Protocol {
         match *self {
             Protocol::Udp => true,
             Protocol::Tcp => false,
+            #[cfg(feature = "tls")]
+            Protocol::Tls { .. } => false,
+            #[cfg(feature = "https")]
+            Protocol::Https { .. } => false,
         }
     }
 
@@ -174,7 +255,7 @@ impl Protocol {
 }
 
 /// Configuration for the NameServer
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct NameServerConfig {
     /// The address which the DNS NameServer is registered at.
     pub socket_addr: SocketAddr,
@@ -232,6 +313,22 @@ pub struct ResolverOpts {
     pub cache_size: usize,
     /// Check /ect/hosts file before dns requery (only works for unix like OS)
     pub use_hosts_file: bool,
+    /// Refresh cached records in the background when they are looked up in the last `prefetch`
+    ///  percent of their TTL, so that frequently used names do not expire from the cache. Only
+    ///  with the `ResolverFuture`, defaults to None, no prefetch. Values above 100 are treated as
+    ///  100, the records are refreshed on every hit
+    pub prefetch: Option<u8>,
+    /// Serve expired records, for at most this long past their TTL, when the name servers fail,
+    ///  see [RFC 8767](https://tools.ietf.org/html/rfc8767). The records are handed out with a
+    ///  TTL of 30 seconds. Defaults to None, expired records are never served
+    pub serve_stale: Option<Duration>,
+    /// The minimum time negative responses, NXDomain and NoData, are cached for, regardless of the
+    ///  SOA of the zone. Defaults to None, no minimum
+    pub negative_min_ttl: Option<Duration>,
+    /// The maximum time negative responses are cached for, otherwise the minimum of the TTL and
+    ///  the MINIMUM field of the SOA, see [RFC 2308](https://tools.ietf.org/html/rfc2308#section-5).
+    ///  Defaults to None, no maximum
+    pub negative_max_ttl: Option<Duration>,
 }
 
 impl Default for ResolverOpts {
@@ -250,6 +347,49 @@ impl Default for ResolverOpts {
             ip_strategy: LookupIpStrategy::default(),
             cache_size: 32,
             use_hosts_file: true,
+            prefetch: None,
+            serve_stale: None,
+            negative_min_ttl: None,
+            negative_max_ttl: None,
         }
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "tls")]
+mod tests {
+    use super::*;
+
+    fn assert_tls(config: &ResolverConfig, dns_name: &str) {
+        assert_eq!(config.name_servers().len(), 4);
+        for name_server in config.name_servers() {
+            assert_eq!(name_server.socket_addr.port(), 853);
+            assert_eq!(
+                name_server.protocol,
+                Protocol::Tls {
+                    dns_name: dns_name.to_string(),
+                }
+            );
+        }
+
+        // both address families
+        assert!(config.name_servers().iter().any(|ns| ns.socket_addr.is_ipv4()));
+        assert!(config.name_servers().iter().any(|ns| ns.socket_addr.is_ipv6()));
+    }
+
+    #[test]
+    fn test_tls_presets() {
+        assert_tls(&ResolverConfig::cloudflare_tls(), "cloudflare-dns.com");
+        assert_tls(&ResolverConfig::quad9_tls(), "dns.quad9.net");
+        assert_tls(&ResolverConfig::google_tls(), "dns.google");
+    }
+
+    #[test]
+    fn test_tls_is_stream() {
+        let tls = Protocol::Tls {
+            dns_name: "dns.google".to_string(),
+        };
+        assert!(tls.is_stream());
+        assert!(!tls.is_datagram());
+    }
+}
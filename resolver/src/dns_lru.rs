@@ -10,9 +10,10 @@
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use trust_dns_proto::op::Query;
-use trust_dns_proto::rr::RData;
+use trust_dns_proto::op::{Query, ResponseCode};
+use trust_dns_proto::rr::{DNSClass, Name, RData, Record};
 
+use config::ResolverOpts;
 use error::*;
 use lookup::Lookup;
 use lru_cache::LruCache;
@@ -20,32 +21,131 @@ use lru_cache::LruCache;
 /// Maximum TTL as defined in https://tools.ietf.org/html/rfc2181
 pub const MAX_TTL: u32 = 2147483647_u32;
 
+/// The TTL of expired records when they are served, https://tools.ietf.org/html/rfc8767#section-4
+pub const STALE_TTL: u32 = 30;
+
+/// A cached negative response, https://tools.ietf.org/html/rfc2308
+#[derive(Clone, Debug)]
+struct Negative {
+    // the SOA from the authority section of the response
+    soa: Record,
+    // NXDomain if the name does not exist, NoError if there are no records of the type
+    response_code: ResponseCode,
+}
+
 #[derive(Debug)]
 struct LruValue {
-    // In the None case, this represents an NXDomain
-    lookup: Option<Lookup>,
+    // In the Err case, this represents an NXDomain or NoData
+    lookup: Result<Lookup, Negative>,
     ttl_until: Instant,
+    // the TTL the value was cached with
+    ttl: Duration,
+    // a refresh of the value has been started
+    is_prefetching: bool,
 }
 
 impl LruValue {
+    fn new(lookup: Result<Lookup, Negative>, ttl: Duration, now: Instant) -> Self {
+        LruValue {
+            lookup,
+            ttl_until: now + ttl,
+            ttl,
+            is_prefetching: false,
+        }
+    }
+
     /// Returns true if this set of ips is still valid
     fn is_current(&self, now: Instant) -> bool {
         now <= self.ttl_until
     }
+
+    /// Returns true if this set of ips is valid, or expired for no longer than `max_stale`
+    fn is_stale_servable(&self, now: Instant, max_stale: Option<Duration>) -> bool {
+        max_stale.map_or(false, |max_stale| now <= self.ttl_until + max_stale)
+    }
+
+    /// Returns true if this is still valid, but in the last `percent` of its TTL, and it is not
+    ///  already being refreshed
+    fn is_prefetch_due(&self, now: Instant, percent: u8) -> bool {
+        if self.is_prefetching || !self.is_current(now) {
+            return false;
+        }
+
+        self.ttl_until.duration_since(now) <= self.ttl * u32::from(percent) / 100
+    }
+
+    /// The cached records, or the error of the negative response, with the SOA's TTL set to the
+    ///  time remaining in the cache
+    fn to_result(&self, query: &Query, now: Instant) -> ResolveResult<Lookup> {
+        match self.lookup {
+            Ok(ref lookup) => Ok(lookup.clone()),
+            Err(ref negative) => {
+                let mut soa = negative.soa.clone();
+                let remaining = if self.ttl_until > now {
+                    self.ttl_until.duration_since(now).as_secs()
+                } else {
+                    0
+                };
+                soa.set_ttl(remaining as u32);
+
+                Err(
+                    ResolveErrorKind::NoRecordsFound(
+                        query.clone(),
+                        Some(soa),
+                        negative.response_code,
+                    ).into(),
+                )
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
-pub(crate) struct DnsLru(LruCache<Query, LruValue>);
+pub(crate) struct DnsLru {
+    cache: LruCache<Query, LruValue>,
+    // the names which do not exist, NXDomain covers the queries of all types of the name
+    nx_domains: LruCache<(Name, DNSClass), LruValue>,
+    // how long past their TTL records are kept, to be served when the name servers fail
+    max_stale: Option<Duration>,
+    // the last percent of the TTL in which hits refresh the records
+    prefetch: Option<u8>,
+    // the bounds of the TTL of negative responses
+    negative_min_ttl: Option<Duration>,
+    negative_max_ttl: Option<Duration>,
+}
 
 impl DnsLru {
     pub(crate) fn new(capacity: usize) -> Self {
-        DnsLru(LruCache::new(capacity))
+        DnsLru {
+            cache: LruCache::new(capacity),
+            nx_domains: LruCache::new(capacity),
+            max_stale: None,
+            prefetch: None,
+            negative_min_ttl: None,
+            negative_max_ttl: None,
+        }
     }
 
+    /// A cache of `options.cache_size`, with the `serve_stale`, `prefetch` and negative TTL bounds
+    ///  of the options
+    pub(crate) fn with_opts(options: &ResolverOpts) -> Self {
+        DnsLru {
+            cache: LruCache::new(options.cache_size),
+            nx_domains: LruCache::new(options.cache_size),
+            max_stale: options.serve_stale,
+            // the percent of the TTL, anything above is the whole TTL
+            prefetch: options.prefetch.map(|percent| percent.min(100)),
+            negative_min_ttl: options.negative_min_ttl,
+            negative_max_ttl: options.negative_max_ttl,
+        }
+    }
+
+    /// Caches the records, `secure` if they were validated with DNSSEC
     pub(crate) fn insert(
         &mut self,
         query: Query,
         rdatas_and_ttl: Vec<(RData, u32)>,
+        secure: bool,
         now: Instant,
     ) -> Lookup {
         let len = rdatas_and_ttl.len();
@@ -63,14 +163,9 @@ impl DnsLru {
         let ttl_until = now + ttl;
 
         // insert into the LRU
-        let lookup = Lookup::new(Arc::new(rdatas));
-        self.0.insert(
-            query,
-            LruValue {
-                lookup: Some(lookup.clone()),
-                ttl_until,
-            },
-        );
+        let lookup = Lookup::new_with_deadline(Arc::new(rdatas), ttl_until).into_secure(secure);
+        self.cache
+            .insert(query, LruValue::new(Ok(lookup.clone()), ttl, now));
 
         lookup
     }
@@ -85,62 +180,156 @@ impl DnsLru {
     ) -> Lookup {
         let ttl = Duration::from_secs(ttl as u64);
         let ttl_until = now + ttl;
+        let lookup = lookup.with_deadline(ttl_until);
 
-        self.0.insert(
-            query,
-            LruValue {
-                lookup: Some(lookup.clone()),
-                ttl_until,
-            },
-        );
+        self.cache
+            .insert(query, LruValue::new(Ok(lookup.clone()), ttl, now));
 
         lookup
     }
 
+    /// The error for a name which does not exist, for which there is no SOA
     pub(crate) fn nx_error(query: Query) -> ResolveError {
-        ResolveErrorKind::NoRecordsFound(query).into()
+        ResolveErrorKind::NoRecordsFound(query, None, ResponseCode::NXDomain).into()
     }
 
-    pub(crate) fn negative(&mut self, query: Query, ttl: u32, now: Instant) -> ResolveError {
-        // TODO: if we are getting a negative response, should we instead fallback to cache?
-        //   this would cache indefinitely, probably not correct
+    /// Caches a negative response, NXDomain or NoData per the `response_code`, see
+    ///  https://tools.ietf.org/html/rfc2308#section-5
+    ///
+    /// The response is cached for the minimum of the TTL and the MINIMUM field of the SOA, within
+    ///  the `ResolverOpts::negative_min_ttl` and `negative_max_ttl`. Without an SOA it is not
+    ///  cached. NXDomain is cached for the name, and answers the queries of all types.
+    pub(crate) fn negative(
+        &mut self,
+        query: Query,
+        soa: Option<Record>,
+        response_code: ResponseCode,
+        now: Instant,
+    ) -> ResolveError {
+        let soa = match soa {
+            Some(soa) => soa,
+            None => return ResolveErrorKind::NoRecordsFound(query, None, response_code).into(),
+        };
 
-        let ttl = Duration::from_secs(ttl as u64);
-        let ttl_until = now + ttl;
+        let ttl = match *soa.rdata() {
+            RData::SOA(ref rdata) => soa.ttl().min(rdata.minimum()),
+            _ => return ResolveErrorKind::NoRecordsFound(query, None, response_code).into(),
+        };
 
-        self.0.insert(
-            query.clone(),
-            LruValue {
-                lookup: None,
-                ttl_until,
-            },
-        );
+        let mut ttl = Duration::from_secs(u64::from(ttl));
+        if let Some(min_ttl) = self.negative_min_ttl {
+            ttl = ttl.max(min_ttl);
+        }
+        if let Some(max_ttl) = self.negative_max_ttl {
+            ttl = ttl.min(max_ttl);
+        }
 
-        Self::nx_error(query)
+        let value = LruValue::new(Err(Negative { soa, response_code }), ttl, now);
+        let error = value.to_result(&query, now).err().expect("negative is an error");
+        if response_code == ResponseCode::NXDomain {
+            self.nx_domains.insert(Self::name_key(&query), value);
+        } else {
+            self.cache.insert(query, value);
+        }
+
+        error
+    }
+
+    /// The key under which NXDomain is cached, for all the types of the name
+    fn name_key(query: &Query) -> (Name, DNSClass) {
+        (query.name().clone(), query.query_class())
     }
 
+    /// Returns the cached records, or the error of a cached negative response
+    ///
     /// This needs to be mut b/c it's an LRU, meaning the ordering of elements will potentially change on retrieval...
-    pub(crate) fn get(&mut self, query: &Query, now: Instant) -> Option<Lookup> {
+    pub(crate) fn get(&mut self, query: &Query, now: Instant) -> Option<ResolveResult<Lookup>> {
+        if let Some(result) = self.get_current(query, now) {
+            return Some(result);
+        }
+
+        // a name which does not exist has no records of any type
+        let name_key = Self::name_key(query);
         let mut out_of_date = false;
-        let lookup = self.0.get_mut(query).and_then(|value| {
+        let error = self.nx_domains.get_mut(&name_key).and_then(|value| {
             if value.is_current(now) {
-                out_of_date = false;
-                value.lookup.clone()
+                Some(value.to_result(query, now))
             } else {
+                // negative responses are never served stale
                 out_of_date = true;
                 None
             }
         });
 
+        if out_of_date {
+            self.nx_domains.remove(&name_key);
+        }
+
+        error
+    }
+
+    fn get_current(&mut self, query: &Query, now: Instant) -> Option<ResolveResult<Lookup>> {
+        let mut out_of_date = false;
+        let max_stale = self.max_stale;
+        let lookup = self.cache.get_mut(query).and_then(|value| {
+            if value.is_current(now) {
+                out_of_date = false;
+                Some(value.to_result(query, now))
+            } else {
+                // expired values are kept while they may be served stale, see get_stale()
+                out_of_date = !value.is_stale_servable(now, max_stale);
+                None
+            }
+        });
+
         // in this case, we can preemtively remove out of data elements
         // this assumes time is always moving forward, this would only not be true in contrived situations where now
         //  is not current time, like tests...
         if out_of_date {
-            self.0.remove(query);
+            self.cache.remove(query);
         }
 
         lookup
     }
+
+    /// Returns expired records, to be served when the name servers fail, see
+    ///  https://tools.ietf.org/html/rfc8767
+    ///
+    /// Records are only kept past their TTL, for at most `ResolverOpts::serve_stale`, if that is
+    ///  set. The lookup is valid for the `STALE_TTL`.
+    pub(crate) fn get_stale(&mut self, query: &Query, now: Instant) -> Option<Lookup> {
+        let max_stale = self.max_stale;
+        self.cache.get_mut(query).and_then(|value| {
+            if value.is_stale_servable(now, max_stale) {
+                value.lookup.as_ref().ok().map(|lookup| {
+                    lookup
+                        .clone()
+                        .with_deadline(now + Duration::from_secs(STALE_TTL as u64))
+                })
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns true if the records of the query should be refreshed, once per cached value
+    ///
+    /// This is the case if they are hit in the last `ResolverOpts::prefetch` percent of their TTL,
+    ///  if that is set.
+    pub(crate) fn prefetch(&mut self, query: &Query, now: Instant) -> bool {
+        let percent = match self.prefetch {
+            Some(percent) => percent,
+            None => return false,
+        };
+
+        match self.cache.get_mut(query) {
+            Some(ref mut value) if value.is_prefetch_due(now, percent) => {
+                value.is_prefetching = true;
+                true
+            }
+            _ => false,
+        }
+    }
 }
 
 // see also the lookup_tests.rs in integration-tests crate
@@ -151,7 +340,8 @@ mod tests {
     use std::time::*;
 
     use trust_dns_proto::op::Query;
-    use trust_dns_proto::rr::{Name, RecordType};
+    use trust_dns_proto::rr::RecordType;
+    use trust_dns_proto::rr::rdata::SOA;
 
     use super::*;
  
@@ -162,10 +352,9 @@ mod tests {
         let future = now + Duration::from_secs(5);
         let past_the_future = now + Duration::from_secs(6);
 
-        let value = LruValue {
-            lookup: None,
-            ttl_until: future,
-        };
+        let lookup = Lookup::new_with_deadline(Arc::new(vec![]), future);
+        let value = LruValue::new(Ok(lookup), Duration::from_secs(5), now);
+        assert_eq!(value.ttl_until, future);
 
         assert!(value.is_current(now));
         assert!(value.is_current(not_the_future));
@@ -181,10 +370,10 @@ mod tests {
         let ips = vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))];
         let mut lru = DnsLru::new(1);
 
-        let rc_ips = lru.insert(name.clone(), ips_ttl, now);
+        let rc_ips = lru.insert(name.clone(), ips_ttl, false, now);
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
 
-        let rc_ips = lru.get(&name, now).unwrap();
+        let rc_ips = lru.get(&name, now).unwrap().unwrap();
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
     }
 
@@ -203,14 +392,280 @@ mod tests {
         ];
         let mut lru = DnsLru::new(1);
 
-        lru.insert(name.clone(), ips_ttl, now);
+        lru.insert(name.clone(), ips_ttl, false, now);
 
         // still valid
-        let rc_ips = lru.get(&name, now + Duration::from_secs(1)).unwrap();
+        let rc_ips = lru.get(&name, now + Duration::from_secs(1)).unwrap().unwrap();
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
 
         // 2 should be one too far
         let rc_ips = lru.get(&name, now + Duration::from_secs(2));
         assert!(rc_ips.is_none());
     }
+
+    #[test]
+    fn test_insert_valid_until() {
+        let now = Instant::now();
+        let name = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+        let ips_ttl = vec![
+            (RData::A(Ipv4Addr::new(127, 0, 0, 1)), 10),
+            (RData::A(Ipv4Addr::new(127, 0, 0, 2)), 20),
+        ];
+        let mut lru = DnsLru::new(2);
+
+        // the lookup is valid for the minimum TTL
+        let lookup = lru.insert(name.clone(), ips_ttl, false, now);
+        assert_eq!(lookup.valid_until(), now + Duration::from_secs(10));
+
+        let lookup = lru.get(&name, now).unwrap().unwrap();
+        assert_eq!(lookup.valid_until(), now + Duration::from_secs(10));
+
+        // duplicates carry their own TTL
+        let alias = Query::query(Name::from_str("alias.example.com.").unwrap(), RecordType::A);
+        let lookup = lru.duplicate(alias.clone(), lookup, 5, now);
+        assert_eq!(lookup.valid_until(), now + Duration::from_secs(5));
+        assert_eq!(
+            lru.get(&alias, now).unwrap().unwrap().valid_until(),
+            now + Duration::from_secs(5)
+        );
+    }
+
+    #[test]
+    fn test_serve_stale() {
+        let now = Instant::now();
+        let name = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+        let ips_ttl = vec![(RData::A(Ipv4Addr::new(127, 0, 0, 1)), 10)];
+        let mut lru = DnsLru::with_opts(&ResolverOpts {
+            serve_stale: Some(Duration::from_secs(60)),
+            ..ResolverOpts::default()
+        });
+
+        lru.insert(name.clone(), ips_ttl, false, now);
+
+        // expired, but kept to be served stale
+        let expired = now + Duration::from_secs(11);
+        assert!(lru.get(&name, expired).is_none());
+        let stale = lru.get_stale(&name, expired).unwrap();
+        assert_eq!(
+            *stale.iter().next().unwrap(),
+            RData::A(Ipv4Addr::new(127, 0, 0, 1))
+        );
+        assert_eq!(
+            stale.valid_until(),
+            expired + Duration::from_secs(STALE_TTL as u64)
+        );
+
+        // too old to be served
+        let too_old = now + Duration::from_secs(71);
+        assert!(lru.get(&name, too_old).is_none());
+        assert!(lru.get_stale(&name, now).is_none());
+
+        // without serve_stale, nothing is served after the TTL
+        let mut lru = DnsLru::new(1);
+        let ips_ttl = vec![(RData::A(Ipv4Addr::new(127, 0, 0, 1)), 10)];
+        lru.insert(name.clone(), ips_ttl, false, now);
+        assert!(lru.get_stale(&name, expired).is_none());
+    }
+
+    #[test]
+    fn test_prefetch() {
+        let now = Instant::now();
+        let name = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+        let ips_ttl = vec![(RData::A(Ipv4Addr::new(127, 0, 0, 1)), 100)];
+        let mut lru = DnsLru::with_opts(&ResolverOpts {
+            prefetch: Some(10),
+            ..ResolverOpts::default()
+        });
+
+        lru.insert(name.clone(), ips_ttl.clone(), false, now);
+
+        // before the last 10% of the TTL
+        assert!(!lru.prefetch(&name, now + Duration::from_secs(89)));
+
+        // only once in the last 10%
+        assert!(lru.prefetch(&name, now + Duration::from_secs(90)));
+        assert!(!lru.prefetch(&name, now + Duration::from_secs(95)));
+        assert!(lru.get(&name, now + Duration::from_secs(95)).is_some());
+
+        // the refreshed records can be prefetched again
+        let later = now + Duration::from_secs(95);
+        lru.insert(name.clone(), ips_ttl, false, later);
+        assert!(!lru.prefetch(&name, later));
+        assert!(lru.prefetch(&name, later + Duration::from_secs(90)));
+
+        // not after they expire
+        assert!(!lru.prefetch(&name, later + Duration::from_secs(101)));
+    }
+
+    #[test]
+    fn test_prefetch_above_100_percent() {
+        let now = Instant::now();
+        let name = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+        let ips_ttl = vec![(RData::A(Ipv4Addr::new(127, 0, 0, 1)), 100)];
+        let mut lru = DnsLru::with_opts(&ResolverOpts {
+            prefetch: Some(200),
+            ..ResolverOpts::default()
+        });
+
+        lru.insert(name.clone(), ips_ttl, false, now);
+
+        // treated as 100%, due from the first hit
+        assert!(lru.prefetch(&name, now));
+    }
+
+    fn soa(ttl: u32, minimum: u32) -> Record {
+        let origin = Name::from_str("example.com.").unwrap();
+        Record::from_rdata(
+            origin.clone(),
+            ttl,
+            RecordType::SOA,
+            RData::SOA(SOA::new(
+                origin,
+                Name::from_str("admin.example.com.").unwrap(),
+                1,
+                3600,
+                600,
+                86400,
+                minimum,
+            )),
+        )
+    }
+
+    #[test]
+    fn test_negative_ttl() {
+        let now = Instant::now();
+        let name = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::MX);
+        let mut lru = DnsLru::new(2);
+
+        // the minimum of the SOA's TTL and MINIMUM
+        lru.negative(name.clone(), Some(soa(300, 60)), ResponseCode::NoError, now);
+        assert!(lru.get(&name, now + Duration::from_secs(60)).unwrap().is_err());
+        assert!(lru.get(&name, now + Duration::from_secs(61)).is_none());
+
+        lru.negative(name.clone(), Some(soa(30, 60)), ResponseCode::NoError, now);
+        assert!(lru.get(&name, now + Duration::from_secs(30)).unwrap().is_err());
+        assert!(lru.get(&name, now + Duration::from_secs(31)).is_none());
+
+        // without an SOA nothing is cached
+        let other = Query::query(Name::from_str("other.example.com.").unwrap(), RecordType::MX);
+        lru.negative(other.clone(), None, ResponseCode::NoError, now);
+        assert!(lru.get(&other, now).is_none());
+
+        // within the floor and the ceiling
+        let mut lru = DnsLru::with_opts(&ResolverOpts {
+            negative_min_ttl: Some(Duration::from_secs(10)),
+            negative_max_ttl: Some(Duration::from_secs(100)),
+            ..ResolverOpts::default()
+        });
+
+        lru.negative(name.clone(), Some(soa(0, 0)), ResponseCode::NoError, now);
+        assert!(lru.get(&name, now + Duration::from_secs(10)).unwrap().is_err());
+        assert!(lru.get(&name, now + Duration::from_secs(11)).is_none());
+
+        lru.negative(name.clone(), Some(soa(3600, 3600)), ResponseCode::NoError, now);
+        assert!(lru.get(&name, now + Duration::from_secs(100)).unwrap().is_err());
+        assert!(lru.get(&name, now + Duration::from_secs(101)).is_none());
+    }
+
+    #[test]
+    fn test_negative_soa() {
+        let now = Instant::now();
+        let name = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::MX);
+        let mut lru = DnsLru::new(2);
+
+        let error = lru.negative(name.clone(), Some(soa(300, 60)), ResponseCode::NoError, now);
+        match *error.kind() {
+            ResolveErrorKind::NoRecordsFound(ref query, Some(ref soa), ResponseCode::NoError) => {
+                assert_eq!(*query, name);
+                assert_eq!(*soa.name(), Name::from_str("example.com.").unwrap());
+                assert_eq!(soa.ttl(), 60);
+            }
+            ref kind => panic!("unexpected error: {:?}", kind),
+        }
+
+        // the SOA from the cache has the remaining TTL
+        let error = lru.get(&name, now + Duration::from_secs(20))
+            .unwrap()
+            .unwrap_err();
+        match *error.kind() {
+            ResolveErrorKind::NoRecordsFound(_, Some(ref soa), ResponseCode::NoError) => {
+                assert_eq!(soa.ttl(), 40)
+            }
+            ref kind => panic!("unexpected error: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_nx_domain_all_types() {
+        let now = Instant::now();
+        let name = Name::from_str("nx.example.com.").unwrap();
+        let mut lru = DnsLru::new(2);
+
+        lru.negative(
+            Query::query(name.clone(), RecordType::A),
+            Some(soa(300, 60)),
+            ResponseCode::NXDomain,
+            now,
+        );
+
+        // the name does not exist, for any type
+        for record_type in &[RecordType::A, RecordType::AAAA, RecordType::MX, RecordType::ANY] {
+            let error = lru.get(&Query::query(name.clone(), *record_type), now)
+                .unwrap()
+                .unwrap_err();
+            match *error.kind() {
+                ResolveErrorKind::NoRecordsFound(ref query, Some(_), ResponseCode::NXDomain) => {
+                    assert_eq!(query.query_type(), *record_type)
+                }
+                ref kind => panic!("unexpected error: {:?}", kind),
+            }
+        }
+
+        // NoData only covers the type
+        let other = Name::from_str("www.example.com.").unwrap();
+        lru.negative(
+            Query::query(other.clone(), RecordType::MX),
+            Some(soa(300, 60)),
+            ResponseCode::NoError,
+            now,
+        );
+        assert!(
+            lru.get(&Query::query(other.clone(), RecordType::MX), now)
+                .unwrap()
+                .is_err()
+        );
+        assert!(
+            lru.get(&Query::query(other, RecordType::A), now)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_nx_domain_keeps_any_records() {
+        let now = Instant::now();
+        let name = Name::from_str("www.example.com.").unwrap();
+        let any = Query::query(name.clone(), RecordType::ANY);
+        let mut lru = DnsLru::new(2);
+
+        lru.insert(
+            any.clone(),
+            vec![(RData::A(Ipv4Addr::new(127, 0, 0, 1)), 100)],
+            false,
+            now,
+        );
+        lru.negative(
+            Query::query(name.clone(), RecordType::A),
+            Some(soa(300, 60)),
+            ResponseCode::NXDomain,
+            now,
+        );
+
+        // the NXDomain is kept apart from the records of the ANY query
+        assert!(lru.get(&any, now).unwrap().is_ok());
+        assert!(
+            lru.get(&Query::query(name, RecordType::AAAA), now)
+                .unwrap()
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,153 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A cache of the chain of trust fetched during DNSSEC validation
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::{future, Future};
+use lru_cache::LruCache;
+
+use trust_dns_proto::DnsHandle;
+use trust_dns_proto::op::{Message, Query, ResponseCode};
+use trust_dns_proto::rr::RecordType;
+use trust_dns_proto::rr::dnssec::rdata::DNSSECRecordType;
+
+use error::*;
+
+/// Caches the responses to the DNSKEY and DS queries of the `SecureDnsHandle`, so that the chain
+///  of trust is not fetched again for each validated response.
+///
+/// The responses are cached with their signatures, and are validated again on each use.
+#[derive(Clone)]
+#[doc(hidden)]
+pub struct DnssecCache<H: DnsHandle<Error = ResolveError>> {
+    handle: H,
+    cache: Arc<Mutex<LruCache<Query, (Message, Instant)>>>,
+}
+
+impl<H: DnsHandle<Error = ResolveError>> DnssecCache<H> {
+    /// Returns a new cache of up to `max_size` responses in front of the handle
+    pub(crate) fn new(max_size: usize, handle: H) -> Self {
+        DnssecCache {
+            handle,
+            cache: Arc::new(Mutex::new(LruCache::new(max_size))),
+        }
+    }
+
+    fn get(&self, query: &Query, now: Instant) -> Option<Message> {
+        let mut cache = self.cache.lock().expect("dnssec cache poisoned");
+        let current = cache
+            .get_mut(query)
+            .map(|&mut (ref message, valid_until)| (message.clone(), now <= valid_until));
+
+        match current {
+            Some((message, true)) => Some(message),
+            Some((_, false)) => {
+                cache.remove(query);
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+impl<H: DnsHandle<Error = ResolveError>> DnsHandle for DnssecCache<H> {
+    type Error = ResolveError;
+
+    fn is_verifying_dnssec(&self) -> bool {
+        self.handle.is_verifying_dnssec()
+    }
+
+    fn send(&mut self, message: Message) -> Box<Future<Item = Message, Error = Self::Error>> {
+        let query = match message.queries().first() {
+            Some(query) if is_chain_of_trust(query.query_type()) => query.clone(),
+            _ => return self.handle.send(message),
+        };
+
+        let now = Instant::now();
+        if let Some(mut response) = self.get(&query, now) {
+            debug!("dnssec cache hit: {}", query);
+            response.set_id(message.id());
+            return Box::new(future::ok(response));
+        }
+
+        let cache = Arc::clone(&self.cache);
+        Box::new(self.handle.send(message).map(move |response| {
+            let ttl = response.answers().iter().map(|r| r.ttl()).min();
+            if let (ResponseCode::NoError, Some(ttl)) = (response.response_code(), ttl) {
+                let valid_until = now + Duration::from_secs(u64::from(ttl));
+                cache
+                    .lock()
+                    .expect("dnssec cache poisoned")
+                    .insert(query, (response.clone(), valid_until));
+            }
+
+            response
+        }))
+    }
+}
+
+fn is_chain_of_trust(record_type: RecordType) -> bool {
+    match record_type {
+        RecordType::DNSSEC(DNSSECRecordType::DNSKEY) | RecordType::DNSSEC(DNSSECRecordType::DS) => {
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use trust_dns_proto::rr::{Name, RData, Record};
+    use trust_dns_proto::rr::dnssec::rdata::{DNSSECRData, DNSKEY};
+    use trust_dns_proto::rr::dnssec::Algorithm;
+
+    use lookup::tests::*;
+    use super::*;
+
+    fn dnskey_message() -> ResolveResult<Message> {
+        let mut message = Message::new();
+        message.insert_answers(vec![
+            Record::from_rdata(
+                Name::root(),
+                86400,
+                RecordType::DNSSEC(DNSSECRecordType::DNSKEY),
+                RData::DNSSEC(DNSSECRData::DNSKEY(DNSKEY::new(
+                    true,
+                    true,
+                    false,
+                    Algorithm::RSASHA256,
+                    vec![1, 2, 3],
+                ))),
+            ),
+        ]);
+        Ok(message)
+    }
+
+    #[test]
+    fn test_caches_chain_of_trust() {
+        // the second response is an error, it must come from the cache
+        let mut handle = DnssecCache::new(2, mock(vec![error(), dnskey_message()]));
+
+        let query = Query::query(Name::root(), RecordType::DNSSEC(DNSSECRecordType::DNSKEY));
+        for _ in 0..2 {
+            let response = handle.lookup(query.clone()).wait().expect("lookup failed");
+            assert_eq!(response.answers().len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_does_not_cache_others() {
+        let mut handle = DnssecCache::new(2, mock(vec![error(), v4_message()]));
+
+        let query = Query::query(Name::root(), RecordType::A);
+        assert!(handle.lookup(query.clone()).wait().is_ok());
+        assert!(handle.lookup(query).wait().is_err());
+    }
+}
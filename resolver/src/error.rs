@@ -8,7 +8,8 @@
 #![allow(missing_docs)]
 
 use std::io;
-use trust_dns_proto::op::Query;
+use trust_dns_proto::op::{Query, ResponseCode};
+use trust_dns_proto::rr::Record;
 
 error_chain! {
     // The type defined for this error. These are the conventional
@@ -42,12 +43,25 @@ error_chain! {
     // the same as `quick_error!`, but the `from()` and `cause()`
     // syntax is not supported.
     errors {
+        Bogus(query: Query, reason: String) {
+            description("dnssec validation failed")
+            display("dnssec validation failed for {}: {}", query, reason)
+        }
+
         Message(msg: &'static str) {
             description(msg)
             display("{}", msg)
         }
 
-        NoRecordsFound(query: Query) {
+        // The name server responded with an error other than NXDomain, e.g. ServFail or Refused
+        ResponseError(query: Query, response_code: ResponseCode) {
+            description("the name server responded with an error")
+            display("the name server responded with {} for {}", response_code, query)
+        }
+
+        // The records do not exist, the `ResponseCode` is NXDomain if the name does not exist,
+        //  otherwise NoError. The SOA of the zone is present if the response carried one.
+        NoRecordsFound(query: Query, soa: Option<Record>, response_code: ResponseCode) {
             description("no record found for name")
             display("no record found for {}", query)
         }
@@ -57,13 +71,19 @@ error_chain! {
 impl Clone for ResolveErrorKind {
     fn clone(&self) -> Self {
         match self {
+            &ResolveErrorKind::Bogus(ref query, ref reason) => {
+                ResolveErrorKind::Bogus(query.clone(), reason.clone())
+            }
             &ResolveErrorKind::Io => ResolveErrorKind::Io,
             &ResolveErrorKind::Message(ref string) => ResolveErrorKind::Message(string),
             &ResolveErrorKind::Msg(ref string) => ResolveErrorKind::Msg(string.clone()),
-            &ResolveErrorKind::NoRecordsFound(ref query) => {
-                ResolveErrorKind::NoRecordsFound(query.clone())
+            &ResolveErrorKind::NoRecordsFound(ref query, ref soa, response_code) => {
+                ResolveErrorKind::NoRecordsFound(query.clone(), soa.clone(), response_code)
             }
             &ResolveErrorKind::Proto(ref kind) => ResolveErrorKind::Proto(kind.clone()),
+            &ResolveErrorKind::ResponseError(ref query, response_code) => {
+                ResolveErrorKind::ResponseError(query.clone(), response_code)
+            }
         }
     }
 }
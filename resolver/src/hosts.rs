@@ -1,30 +1,50 @@
 //! Hosts result from a configuration of `/etc/hosts`
 
 use std::collections::HashMap;
+use std::fs;
 use std::io::{self, BufRead, BufReader};
 use std::fs::File;
 use std::net::IpAddr;
 use std::str::FromStr;
-use std::path::Path;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
 
 use trust_dns_proto::rr::{Name, RData, RecordType};
 use trust_dns_proto::op::Query;
 use lookup::Lookup;
 
+/// How often the hosts file is checked for modifications, in seconds
+const RELOAD_INTERVAL_SECS: u64 = 5;
+
 #[derive(Debug, Default)]
 struct LookupType {
     /// represents the A record type
     a: Option<Lookup>,
     /// represents the AAAA record type
     aaaa: Option<Lookup>,
+    /// represents the PTR record type, of the reverse name of an address
+    ptr: Option<Lookup>,
+}
+
+/// The file the hosts were read from
+#[derive(Debug)]
+struct HostsFile {
+    path: PathBuf,
+    /// when the file was last checked, and its modification time and length then
+    checked: Mutex<(Instant, Option<(SystemTime, u64)>)>,
 }
 
 /// Configuration for the local `/etc/hosts`
+///
+/// Hosts read from a file are reloaded when the file is modified, it is checked at most every
+///  5 seconds on lookup.
 #[derive(Debug, Default)]
 pub struct Hosts {
-    /// Name -> RDatas map
-    by_name: HashMap<Name, LookupType>,
+    /// Name -> RDatas map, the reverse names of the addresses map to their PTRs
+    by_name: RwLock<HashMap<Name, LookupType>>,
+    /// the file to reload
+    file: Option<HostsFile>,
 }
 
 impl Hosts {
@@ -35,12 +55,19 @@ impl Hosts {
     }
 
     /// lookup_static_host looks up the addresses for the given host from /etc/hosts.
+    ///
+    /// PTR queries for the reverse names of the addresses, see `Name::from(IpAddr)`, return the
+    ///  hosts of the address.
     pub fn lookup_static_host(&self, query: &Query) -> Option<Lookup> {
-        if !self.by_name.is_empty() {
-            if let Some(val) = self.by_name.get(query.name()) {
+        self.refresh_if_due();
+
+        let by_name = self.by_name.read().unwrap(); // poison errors should panic
+        if !by_name.is_empty() {
+            if let Some(val) = by_name.get(query.name()) {
                 let result = match query.query_type() {
                     RecordType::A => val.a.clone(),
                     RecordType::AAAA => val.aaaa.clone(),
+                    RecordType::PTR => val.ptr.clone(),
                     _ => None,
                 };
 
@@ -50,40 +77,127 @@ impl Hosts {
         None
     }
 
-    /// Insert a new Lookup for the associated `Name` and `RecordType`
+    /// Insert a new Lookup for the associated `Name` and `RecordType`, only A, AAAA and PTR are
+    ///  supported, other types are ignored with a warning
     pub fn insert(&mut self, name: Name, record_type: RecordType, lookup: Lookup) {
-        assert!(record_type == RecordType::A || record_type == RecordType::AAAA);
-
-        let lookup_type = self
-            .by_name
-            .entry(name.clone())
-            .or_insert_with(|| LookupType::default());
-
-        let new_lookup = {
-            let mut old_lookup = match &record_type {
-                &RecordType::A => lookup_type.a.get_or_insert_with(|| Lookup::new(Arc::new(vec![]))),
-                &RecordType::AAAA => lookup_type.aaaa.get_or_insert_with(|| Lookup::new(Arc::new(vec![]))),
-                _ => { warn!("unsupported IP type from Hosts file: {:#?}", record_type); return },
-            };
+        let by_name = self.by_name.get_mut().unwrap(); // poison errors should panic
+        insert(by_name, name, record_type, lookup);
+    }
 
-            old_lookup.append(lookup)
+    /// Reads the file again, if it was modified since it was last read
+    ///
+    /// # Returns
+    ///
+    /// True if the hosts were reloaded, an error if the modified file could not be read, the
+    ///  previous hosts are kept then.
+    pub fn refresh(&self) -> io::Result<bool> {
+        let file = match self.file {
+            Some(ref file) => file,
+            None => return Ok(false),
         };
-                
-        // replace the appended version
-        match &record_type {
-            &RecordType::A => lookup_type.a = Some(new_lookup),
-            &RecordType::AAAA => lookup_type.aaaa = Some(new_lookup),
-            _ => warn!("unsupported IP type from Hosts file"),
+
+        let mut checked = file.checked.lock().unwrap(); // poison errors should panic
+        checked.0 = Instant::now();
+
+        let modified = modification(&file.path);
+        if modified == checked.1 {
+            return Ok(false);
+        }
+
+        let by_name = parse_hosts_conf(&file.path)?;
+        *self.by_name.write().unwrap() = by_name; // poison errors should panic
+        checked.1 = modified;
+
+        info!("reloaded hosts from: {}", file.path.display());
+        Ok(true)
+    }
+
+    fn refresh_if_due(&self) {
+        let is_due = match self.file {
+            Some(ref file) => {
+                let checked = file.checked.lock().unwrap(); // poison errors should panic
+                checked.0.elapsed() >= Duration::from_secs(RELOAD_INTERVAL_SECS)
+            }
+            None => false,
+        };
+
+        if is_due {
+            if let Err(error) = self.refresh() {
+                warn!("could not reload hosts: {}", error);
+            }
         }
     }
 }
 
+fn insert(
+    by_name: &mut HashMap<Name, LookupType>,
+    name: Name,
+    record_type: RecordType,
+    lookup: Lookup,
+) {
+    match record_type {
+        RecordType::A | RecordType::AAAA | RecordType::PTR => (),
+        _ => {
+            warn!("unsupported record type for Hosts, ignoring: {:?}", record_type);
+            return;
+        }
+    }
+
+    let lookup_type = by_name
+        .entry(name.clone())
+        .or_insert_with(|| LookupType::default());
+
+    let new_lookup = {
+        let mut old_lookup = match &record_type {
+            &RecordType::A => lookup_type.a.get_or_insert_with(|| Lookup::new(Arc::new(vec![]))),
+            &RecordType::AAAA => lookup_type.aaaa.get_or_insert_with(|| Lookup::new(Arc::new(vec![]))),
+            &RecordType::PTR => lookup_type.ptr.get_or_insert_with(|| Lookup::new(Arc::new(vec![]))),
+            _ => { warn!("unsupported IP type from Hosts file: {:#?}", record_type); return },
+        };
+
+        old_lookup.append(lookup)
+    };
+            
+    // replace the appended version
+    match &record_type {
+        &RecordType::A => lookup_type.a = Some(new_lookup),
+        &RecordType::AAAA => lookup_type.aaaa = Some(new_lookup),
+        &RecordType::PTR => lookup_type.ptr = Some(new_lookup),
+        _ => warn!("unsupported IP type from Hosts file"),
+    }
+}
+
+/// The modification time and length of the file, to detect changes
+fn modification(path: &Path) -> Option<(SystemTime, u64)> {
+    fs::metadata(path).ok().and_then(|metadata| {
+        metadata
+            .modified()
+            .ok()
+            .map(|modified| (modified, metadata.len()))
+    })
+}
+
 /// parse configuration from `/etc/hosts`
 #[cfg(unix)]
 pub fn read_hosts_conf<P: AsRef<Path>>(path: P) -> io::Result<Hosts> {
-    let mut hosts = Hosts {
-        by_name: HashMap::new(),
-    };
+    let path = path.as_ref();
+
+    // checked before reading, a modification while it is read is picked up by the next refresh
+    let modified = modification(path);
+    let by_name = parse_hosts_conf(path)?;
+
+    Ok(Hosts {
+        by_name: RwLock::new(by_name),
+        file: Some(HostsFile {
+            path: path.to_path_buf(),
+            checked: Mutex::new((Instant::now(), modified)),
+        }),
+    })
+}
+
+#[cfg(unix)]
+fn parse_hosts_conf(path: &Path) -> io::Result<HashMap<Name, LookupType>> {
+    let mut by_name = HashMap::new();
 
     // lines in the file should have the form `addr host1 host2 host3 ...`
     // line starts with `#` will be regarded with comments and ignored,
@@ -118,18 +232,21 @@ pub fn read_hosts_conf<P: AsRef<Path>>(path: P) -> io::Result<Hosts> {
         for domain in fields.iter().skip(1).map(|domain| domain.to_lowercase()) {
             if let Ok(name) = Name::from_str(&domain) {
                 let lookup = Lookup::new(Arc::new(vec![addr.clone()]));
-                match &addr {
-                       &RData::A(..) => hosts.insert(name.clone(), RecordType::A, lookup),
-                       &RData::AAAA(..) =>  hosts.insert(name.clone(), RecordType::AAAA, lookup),
+                let (record_type, ip) = match &addr {
+                       &RData::A(ip) => (RecordType::A, IpAddr::V4(ip)),
+                       &RData::AAAA(ip) => (RecordType::AAAA, IpAddr::V6(ip)),
                        _ => { warn!("unsupported IP type from Hosts file: {:#?}", addr); continue },
                 };
+                insert(&mut by_name, name.clone(), record_type, lookup);
 
-                // TODO: insert reverse lookup as well.
+                // the reverse lookup, the hosts of the address in the order of the file
+                let ptr = Lookup::new(Arc::new(vec![RData::PTR(name)]));
+                insert(&mut by_name, Name::from(ip), RecordType::PTR, ptr);
             };
         }
     }
 
-    Ok(hosts)
+    Ok(by_name)
 }
 
 #[cfg(not(unix))]
@@ -140,6 +257,14 @@ pub fn read_hosts_conf<P: AsRef<Path>>(path: P) -> io::Result<Hosts> {
     ))
 }
 
+#[cfg(not(unix))]
+fn parse_hosts_conf(path: &Path) -> io::Result<HashMap<Name, LookupType>> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "Non-Posix systems currently not supported".to_string(),
+    ))
+}
+
 /// parse &str to RData::A or RData::AAAA
 pub fn parse_literal_ip(addr: &str) -> Option<RData> {
     match IpAddr::from_str(addr) {
@@ -154,6 +279,7 @@ pub fn parse_literal_ip(addr: &str) -> Option<RData> {
 mod tests {
     use super::*;
     use std::env;
+    use std::process;
     use std::net::{Ipv4Addr, Ipv6Addr};
 
     fn tests_dir() -> String {
@@ -245,5 +371,92 @@ mod tests {
             .map(|r| r.to_owned())
             .collect::<Vec<RData>>();
         assert_eq!(rdatas, vec![RData::A(Ipv4Addr::new(10, 0, 1, 111))]);
+
+        // the reverse lookups, in the order of the hosts
+        let name = Name::from(IpAddr::V4(Ipv4Addr::new(10, 0, 1, 111)));
+        let rdatas = hosts
+            .lookup_static_host(&Query::query(name, RecordType::PTR))
+            .unwrap()
+            .iter()
+            .map(|r| r.to_owned())
+            .collect::<Vec<RData>>();
+        assert_eq!(
+            rdatas,
+            vec![
+                RData::PTR(Name::from_str("a.example.com").unwrap()),
+                RData::PTR(Name::from_str("b.example.com").unwrap()),
+            ]
+        );
+
+        let name = Name::from(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)));
+        let rdatas = hosts
+            .lookup_static_host(&Query::query(name, RecordType::PTR))
+            .unwrap()
+            .iter()
+            .map(|r| r.to_owned())
+            .collect::<Vec<RData>>();
+        assert_eq!(rdatas, vec![RData::PTR(Name::from_str("localhost").unwrap())]);
+    }
+
+    #[test]
+    fn test_insert_unsupported_type() {
+        let mut hosts = Hosts::default();
+        let name = Name::from_str("example.com").unwrap();
+        let lookup = Lookup::new(Arc::new(vec![RData::A(Ipv4Addr::new(10, 0, 1, 102))]));
+
+        hosts.insert(name.clone(), RecordType::MX, lookup);
+        assert!(
+            hosts
+                .lookup_static_host(&Query::query(name, RecordType::MX))
+                .is_none()
+        );
+        assert!(hosts.by_name.read().unwrap().is_empty());
+    }
+
+    fn write_hosts(path: &Path, hosts: &str) {
+        use std::io::Write;
+        File::create(path)
+            .unwrap()
+            .write_all(hosts.as_bytes())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_refresh() {
+        // unique to the process and the test, tests may run concurrently
+        let path = env::temp_dir().join(format!(
+            "trust_dns_resolver_{}_test_refresh_hosts",
+            process::id()
+        ));
+        write_hosts(&path, "10.0.1.102 example.com\n");
+
+        let hosts = read_hosts_conf(&path).unwrap();
+        let name = Name::from_str("example.com").unwrap();
+        let query = Query::query(name.clone(), RecordType::A);
+        let reverse = Query::query(
+            Name::from(IpAddr::V4(Ipv4Addr::new(10, 0, 1, 103))),
+            RecordType::PTR,
+        );
+
+        // unmodified
+        assert!(!hosts.refresh().unwrap());
+        assert!(hosts.lookup_static_host(&reverse).is_none());
+
+        write_hosts(&path, "10.0.1.103 example.com www.example.com\n");
+        assert!(hosts.refresh().unwrap());
+
+        let rdatas = hosts
+            .lookup_static_host(&query)
+            .unwrap()
+            .iter()
+            .map(|r| r.to_owned())
+            .collect::<Vec<RData>>();
+        assert_eq!(rdatas, vec![RData::A(Ipv4Addr::new(10, 0, 1, 103))]);
+        assert!(hosts.lookup_static_host(&reverse).is_some());
+
+        // the previous hosts are kept if the file is gone
+        fs::remove_file(&path).unwrap();
+        assert!(hosts.refresh().is_err());
+        assert!(hosts.lookup_static_host(&query).is_some());
     }
 }
@@ -136,22 +136,36 @@ extern crate lazy_static;
 extern crate log;
 extern crate lru_cache;
 extern crate resolv_conf;
+#[cfg(any(feature = "https", feature = "tls-ring"))]
+extern crate rustls;
 extern crate tokio_core;
+#[cfg(feature = "https")]
+extern crate trust_dns_https;
+#[cfg(feature = "tls-native")]
+extern crate trust_dns_native_tls;
 extern crate trust_dns_proto;
+#[cfg(feature = "tls-ring")]
+extern crate trust_dns_rustls;
+#[cfg(any(feature = "https", feature = "tls-ring"))]
+extern crate webpki_roots;
 
 pub mod config;
 mod dns_lru;
+#[cfg(feature = "dnssec")]
+mod dnssec_cache;
 pub mod error;
 pub mod lookup_ip;
 pub mod lookup;
 pub mod lookup_state;
 #[doc(hidden)]
 pub mod name_server_pool;
+mod recursor;
 mod resolver;
 pub mod system_conf;
 mod resolver_future;
 mod hosts;
 
+pub use recursor::Recursor;
 pub use resolver::Resolver;
 pub use resolver_future::ResolverFuture;
 pub use hosts::Hosts;
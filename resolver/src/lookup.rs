@@ -12,16 +12,22 @@ use std::net::{Ipv4Addr, Ipv6Addr};
 use std::mem;
 use std::slice::Iter;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use futures::{future, task, Async, Future, Poll};
 
 use trust_dns_proto::{DnsHandle, RetryDnsHandle};
 #[cfg(feature = "dnssec")]
 use trust_dns_proto::SecureDnsHandle;
+#[cfg(feature = "dnssec")]
+use trust_dns_proto::error::ProtoErrorKind;
 use trust_dns_proto::op::{Message, Query};
 use trust_dns_proto::rr::{Name, RData, RecordType};
 use trust_dns_proto::rr::rdata;
 
+use dns_lru::MAX_TTL;
+#[cfg(feature = "dnssec")]
+use dnssec_cache::DnssecCache;
 use error::*;
 use lookup_state::CachingClient;
 use name_server_pool::{ConnectionProvider, NameServerPool, StandardConnection};
@@ -33,12 +39,31 @@ use resolver_future::BasicResolverHandle;
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Lookup {
     rdatas: Arc<Vec<RData>>,
+    valid_until: Instant,
+    secure: bool,
 }
 
 impl Lookup {
-    /// Return new instance with given rdatas
+    /// Return new instance with given rdatas, valid for the maximum TTL
     pub fn new(rdatas: Arc<Vec<RData>>) -> Self {
-        Lookup { rdatas }
+        let valid_until = Instant::now() + Duration::from_secs(u64::from(MAX_TTL));
+        Self::new_with_deadline(rdatas, valid_until)
+    }
+
+    /// Return new instance with given rdatas, which is valid until the deadline, i.e. the minimum
+    ///  TTL of the records
+    pub fn new_with_deadline(rdatas: Arc<Vec<RData>>, valid_until: Instant) -> Self {
+        Lookup {
+            rdatas,
+            valid_until,
+            secure: false,
+        }
+    }
+
+    /// Marks the records as validated with DNSSEC
+    pub(crate) fn into_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
     }
 
     /// Returns a borrowed iterator of the returned IPs
@@ -46,6 +71,23 @@ impl Lookup {
         LookupIter(self.rdatas.iter())
     }
 
+    /// Returns the `Instant` at which this lookup is no longer valid, e.g. for calculating the
+    ///  remaining TTL of the records
+    pub fn valid_until(&self) -> Instant {
+        self.valid_until
+    }
+
+    /// Returns true if the records were validated with DNSSEC up to a trust anchor, see
+    ///  `ResolverOpts::validate`
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    /// Returns a copy of this lookup, valid until the new deadline
+    pub(crate) fn with_deadline(&self, valid_until: Instant) -> Self {
+        Self::new_with_deadline(self.rdatas.clone(), valid_until).into_secure(self.secure)
+    }
+
     pub(crate) fn is_empty(&self) -> bool {
         self.rdatas.is_empty()
     }
@@ -60,7 +102,10 @@ impl Lookup {
         rdatas.extend_from_slice(&*self.rdatas);
         rdatas.extend_from_slice(&*other.rdatas);
 
-        Self::new(Arc::new(rdatas))
+        // the combined lookup is only valid, and secure, as long as both are
+        let valid_until = self.valid_until.min(other.valid_until);
+        Self::new_with_deadline(Arc::new(rdatas), valid_until)
+            .into_secure(self.secure && other.secure)
     }
 }
 
@@ -89,7 +134,8 @@ pub enum LookupEither<
     P: ConnectionProvider<ConnHandle = C> + 'static,
 > {
     Retry(RetryDnsHandle<NameServerPool<C, P>>),
-    #[cfg(feature = "dnssec")] Secure(SecureDnsHandle<RetryDnsHandle<NameServerPool<C, P>>>),
+    #[cfg(feature = "dnssec")]
+    Secure(SecureDnsHandle<DnssecCache<RetryDnsHandle<NameServerPool<C, P>>>>),
 }
 
 impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<ConnHandle = C>> DnsHandle
@@ -108,7 +154,15 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<ConnHandle = C>>
         match *self {
             LookupEither::Retry(ref mut c) => c.send(message),
             #[cfg(feature = "dnssec")]
-            LookupEither::Secure(ref mut c) => c.send(message),
+            LookupEither::Secure(ref mut c) => {
+                let query = message.queries().first().cloned().unwrap_or_else(Query::new);
+                Box::new(c.send(message).map_err(move |e| {
+                    if let ResolveErrorKind::Proto(ProtoErrorKind::Bogus(ref reason)) = *e.kind() {
+                        return ResolveErrorKind::Bogus(query, reason.clone()).into();
+                    }
+                    e
+                }))
+            }
         }
     }
 }
@@ -172,6 +226,15 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> InnerLookupFuture<C> {
         }
     }
 
+    pub(crate) fn ok(client_cache: CachingClient<C>, lookup: Lookup) -> Self {
+        InnerLookupFuture {
+            client_cache,
+            names: vec![],
+            record_type: RecordType::NULL,
+            future: Box::new(future::ok(lookup)),
+        }
+    }
+
     pub(crate) fn error<E: StdError>(client_cache: CachingClient<C>, error: E) -> Self {
         return InnerLookupFuture {
             // errors on names don't need to be cheap... i.e. this clone is unfortunate in this case.
@@ -308,7 +371,7 @@ pub mod tests {
 
     use futures::{future, Future};
 
-    use trust_dns_proto::op::Message;
+    use trust_dns_proto::op::{Message, ResponseCode};
     use trust_dns_proto::rr::{Name, RData, Record, RecordType};
 
     use super::*;
@@ -393,7 +456,11 @@ pub mod tests {
             ).wait()
                 .unwrap_err()
                 .kind(),
-            &ResolveErrorKind::NoRecordsFound(Query::query(Name::root(), RecordType::A))
+            &ResolveErrorKind::NoRecordsFound(
+                Query::query(Name::root(), RecordType::A),
+                None,
+                ResponseCode::NoError,
+            )
         );
     }
 }
@@ -9,16 +9,21 @@
 
 use std::borrow::Cow;
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
 use std::mem;
 use std::net::{Ipv4Addr, Ipv6Addr};
 use std::sync::{Arc, Mutex, TryLockError};
 use std::time::Instant;
 
 use futures::{task, future, Async, Future, Poll};
+use futures::future::Shared;
+use tokio_core::reactor::Handle;
 
 use trust_dns_proto::DnsHandle;
+use trust_dns_proto::error::ProtoErrorKind;
 use trust_dns_proto::op::{Message, Query, ResponseCode};
-use trust_dns_proto::rr::{DNSClass, Name, RData, RecordType};
+use trust_dns_proto::rr::{DNSClass, Name, RData, Record, RecordType};
 use trust_dns_proto::rr::domain::usage::{ResolverUsage, DEFAULT, LOCALHOST as LOCALHOST_usage, IN_ADDR_ARPA_127, IP6_ARPA_1, INVALID};
 
 use dns_lru;
@@ -46,6 +51,10 @@ pub struct CachingClient<C: DnsHandle<Error = ResolveError>> {
     // TODO: switch to FuturesMutex (Mutex will have some undesireable locking)
     lru: Arc<Mutex<DnsLru>>,
     client: C,
+    // the prefetches of the records are spawned on the reactor, without one there is no prefetch
+    reactor: Option<Handle>,
+    // identical queries share the lookup in progress, not for the lookups of CNAME targets
+    in_flight: Option<InFlight>,
 }
 
 impl<C: DnsHandle<Error = ResolveError> + 'static> CachingClient<C> {
@@ -55,7 +64,23 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> CachingClient<C> {
     }
 
     pub(crate) fn with_cache(lru: Arc<Mutex<DnsLru>>, client: C) -> Self {
-        CachingClient { lru, client }
+        CachingClient {
+            lru,
+            client,
+            reactor: None,
+            in_flight: Some(InFlight::default()),
+        }
+    }
+
+    /// A caching client which refreshes the records in the background on the reactor, see
+    ///  `ResolverOpts::prefetch`
+    pub(crate) fn with_prefetch(lru: Arc<Mutex<DnsLru>>, client: C, reactor: &Handle) -> Self {
+        CachingClient {
+            lru,
+            client,
+            reactor: Some(reactor.clone()),
+            in_flight: Some(InFlight::default()),
+        }
     }
 
     /// Perform a lookup against this caching client, looking first in the cache for a result
@@ -88,25 +113,127 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> CachingClient<C> {
                    RecordType::A => return Box::new(future::ok(LOCALHOST_V4.clone())),
                    RecordType::AAAA => return Box::new(future::ok(LOCALHOST_V6.clone())),
                    RecordType::PTR => return Box::new(future::ok(LOCALHOST.clone())),
-                   _ => return Box::new(future::err(DnsLru::nx_error(query))), // Are there any other types we can use?
+                   // Are there any other types we can use?
+                   _ => return Box::new(future::err(
+                       ResolveErrorKind::NoRecordsFound(query, None, ResponseCode::NoError).into(),
+                   )),
                },
                ResolverUsage::NxDomain => return Box::new(future::err(DnsLru::nx_error(query))),
                ResolverUsage::Normal => (),
             }
         }
 
-        Box::new(
-            QueryState::lookup(query, &mut self.client, self.lru.clone()).then(|f| {
-                QUERY_DEPTH.with(|c| *c.borrow_mut() -= 1);
-                f
-            }),
-        )
+        let lookup = match self.in_flight.clone() {
+            Some(in_flight) => {
+                let mut client = self.clone();
+                in_flight.join(query, move |query| client.query(query))
+            }
+            None => self.query(query),
+        };
+
+        Box::new(lookup.then(|f| {
+            QUERY_DEPTH.with(|c| *c.borrow_mut() -= 1);
+            f
+        }))
+    }
+
+    fn query(&mut self, query: Query) -> Box<Future<Item = Lookup, Error = ResolveError>> {
+        Box::new(QueryState::lookup(
+            query,
+            &mut self.client,
+            self.lru.clone(),
+            self.reactor.clone(),
+        ))
+    }
+}
+
+type SharedLookup = Shared<Box<Future<Item = Lookup, Error = ResolveError>>>;
+
+/// The lookups in progress, by their query
+#[derive(Clone, Default)]
+struct InFlight(Arc<Mutex<HashMap<Query, SharedLookup>>>);
+
+impl InFlight {
+    /// Joins the lookup in progress for the query, otherwise starts it with `lookup`
+    ///
+    /// All the waiters get the result, or the error, of the one lookup, which is removed once it
+    ///  completes.
+    fn join<F>(&self, query: Query, lookup: F) -> Box<Future<Item = Lookup, Error = ResolveError>>
+    where
+        F: FnOnce(Query) -> Box<Future<Item = Lookup, Error = ResolveError>>,
+    {
+        let mut lookups = self.0.lock().unwrap(); // poison errors should panic
+        let in_progress = lookups.get(&query).cloned();
+
+        let shared = match in_progress {
+            Some(shared) => {
+                debug!("joining lookup in progress: {} {}", query.name(), query.query_type());
+                shared
+            }
+            None => {
+                let in_flight = self.clone();
+                let key = query.clone();
+                let lookup: Box<Future<Item = Lookup, Error = ResolveError>> =
+                    Box::new(lookup(query.clone()).then(move |result| {
+                        in_flight.0.lock().unwrap().remove(&key); // poison errors should panic
+                        result
+                    }));
+
+                let shared = lookup.shared();
+                lookups.insert(query, shared.clone());
+                shared
+            }
+        };
+
+        Box::new(shared.then(|result| match result {
+            Ok(lookup) => Ok((*lookup).clone()),
+            Err(error) => Err((*error).clone()),
+        }))
+    }
+}
+
+impl fmt::Debug for InFlight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0.try_lock() {
+            Ok(lookups) => write!(f, "InFlight({} lookups)", lookups.len()),
+            Err(_) => write!(f, "InFlight"),
+        }
     }
 }
 
 struct FromCache {
     query: Query,
     cache: Arc<Mutex<DnsLru>>,
+    reactor: Option<Handle>,
+    /// the records were found, and are due to be refreshed, see `DnsLru::prefetch()`
+    is_prefetch: bool,
+}
+
+impl FromCache {
+    /// Refreshes the records of the query in the background, bypassing the cache
+    fn prefetch<C: DnsHandle<Error = ResolveError> + 'static>(&self, client: C) {
+        let reactor = match self.reactor {
+            Some(ref reactor) => reactor,
+            None => return,
+        };
+
+        debug!("prefetching: {} {}", self.query.name(), self.query.query_type());
+        let mut refresh = QueryState::lookup(
+            self.query.clone(),
+            &mut client.clone(),
+            self.cache.clone(),
+            self.reactor.clone(),
+        );
+        refresh.query_after_cache();
+
+        let name = self.query.name().clone();
+        reactor.spawn(refresh.then(move |result| {
+            if let Err(error) = result {
+                debug!("prefetch of: {} failed: {}", name, error);
+            }
+            Ok::<_, ()>(())
+        }));
+    }
 }
 
 impl Future for FromCache {
@@ -126,7 +253,16 @@ impl Future for FromCache {
                 Err(ResolveErrorKind::Msg(format!("poisoned: {}", poison)).into())
             }
             Ok(mut lru) => {
-                return Ok(Async::Ready(lru.get(&self.query, Instant::now())));
+                let now = Instant::now();
+                let lookup = match lru.get(&self.query, now) {
+                    Some(Ok(lookup)) => Some(lookup),
+                    // the negative response is cached
+                    Some(Err(error)) => return Err(error),
+                    None => None,
+                };
+                self.is_prefetch =
+                    lookup.is_some() && self.reactor.is_some() && lru.prefetch(&self.query, now);
+                return Ok(Async::Ready(lookup));
             }
         }
     }
@@ -145,8 +281,11 @@ struct QueryFuture<C: DnsHandle<Error = ResolveError> + 'static> {
 enum Records {
     /// The records exists, a vec of rdata with ttl
     Exists(Vec<(RData, u32)>),
-    /// Records do not exist, ttl for negative caching
-    NoData { ttl: Option<u32> },
+    /// Records do not exist, the SOA for negative caching, and NXDomain or NoError for NoData
+    NoData {
+        soa: Option<Record>,
+        response_code: ResponseCode,
+    },
     /// Future lookup for recursive cname records
     CnameChain {
         next: Box<Future<Item = Lookup, Error = ResolveError>>,
@@ -236,7 +375,7 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> QueryFuture<C> {
                 self.next_query(next_query, cname_ttl, message),
             ))
         } else {
-            // TODO: review See https://tools.ietf.org/html/rfc2308 for NoData section
+            // NoData, see https://tools.ietf.org/html/rfc2308#section-2.2
             // Note on DNSSec, in secure_client_hanle, if verify_nsec fails then the request fails.
             //   this will mean that no unverified negative caches will make it to this point and be stored
             Ok(Async::Ready(self.handle_nxdomain(message, true)))
@@ -249,31 +388,29 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> QueryFuture<C> {
     ///  and a record for the name, regardless of CNAME presence, what have you
     ///  ultimately does not exist.
     ///
-    /// This also handles empty responses in the same way. When performing DNSSec enabled queries, we should
-    ///  never enter here, and should never cache unless verified requests.
+    /// This also handles empty responses, NoData, which keep the NoError response code. When performing
+    ///  DNSSec enabled queries, we should never enter here, and should never cache unless verified requests.
     ///
     /// # Arguments
     ///
     /// * `message` - message to extract SOA, etc, from for caching failed requests
     /// * `valid_nsec` - species that in DNSSec mode, this request is safe to cache
     fn handle_nxdomain(&self, mut message: Message, valid_nsec: bool) -> Records {
+        let response_code = message.response_code();
         if valid_nsec || !self.dnssec {
             //  if there were validated NSEC records
+            //  responses without an SOA are not cached, https://tools.ietf.org/html/rfc2308#section-5
             let soa = message
                 .take_name_servers()
                 .into_iter()
                 .find(|r| r.rr_type() == RecordType::SOA);
 
-            let ttl = if let Some(RData::SOA(soa)) = soa.map(|r| r.unwrap_rdata()) {
-                Some(soa.minimum())
-            } else {
-                // TODO: figure out a looping lookup to get SOA
-                None
-            };
-
-            Records::NoData { ttl }
+            Records::NoData { soa, response_code }
         } else {
-            Records::NoData { ttl: None }
+            Records::NoData {
+                soa: None,
+                response_code,
+            }
         }
     }
 }
@@ -294,7 +431,7 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> Future for QueryFuture<C> {
                         false, /* false b/c DNSSec should not cache NXDomain */
                     ))),
                     ResponseCode::NoError => self.handle_noerror(message),
-                    r @ _ => Err(ResolveErrorKind::Msg(format!("DNS Error: {}", r)).into()),
+                    r @ _ => Err(ResolveErrorKind::ResponseError(self.query.clone(), r).into()),
                 }
             }
             Ok(Async::NotReady) => Ok(Async::NotReady),
@@ -307,6 +444,8 @@ struct InsertCache {
     rdatas: Records,
     query: Query,
     cache: Arc<Mutex<DnsLru>>,
+    /// the records were validated with DNSSEC, chained lookups carry their own
+    secure: bool,
 }
 
 impl Future for InsertCache {
@@ -328,11 +467,17 @@ impl Future for InsertCache {
             Ok(mut lru) => {
                 // this will put this object into an inconsistent state, but no one should call poll again...
                 let query = mem::replace(&mut self.query, Query::new());
-                let rdata = mem::replace(&mut self.rdatas, Records::NoData { ttl: None });
+                let rdata = mem::replace(
+                    &mut self.rdatas,
+                    Records::NoData {
+                        soa: None,
+                        response_code: ResponseCode::NoError,
+                    },
+                );
 
                 match rdata {
                     Records::Exists(rdata) => {
-                        Ok(Async::Ready(lru.insert(query, rdata, Instant::now())))
+                        Ok(Async::Ready(lru.insert(query, rdata, self.secure, Instant::now())))
                     }
                     Records::Chained {
                         cached: lookup,
@@ -340,12 +485,10 @@ impl Future for InsertCache {
                     } => Ok(Async::Ready(
                         lru.duplicate(query, lookup, ttl, Instant::now()),
                     )),
-                    Records::NoData { ttl: Some(ttl) } => {
-                        Err(lru.negative(query, ttl, Instant::now()))
-                    }
-                    Records::NoData { ttl: None } | Records::CnameChain { .. } => {
-                        Err(DnsLru::nx_error(query))
+                    Records::NoData { soa, response_code } => {
+                        Err(lru.negative(query, soa, response_code, Instant::now()))
                     }
+                    Records::CnameChain { .. } => Err(DnsLru::nx_error(query)),
                 }
             }
         }
@@ -371,8 +514,21 @@ enum QueryState<C: DnsHandle<Error = ResolveError> + 'static> {
 }
 
 impl<C: DnsHandle<Error = ResolveError> + 'static> QueryState<C> {
-    pub(crate) fn lookup(query: Query, client: &mut C, cache: Arc<Mutex<DnsLru>>) -> QueryState<C> {
-        QueryState::FromCache(FromCache { query, cache }, client.clone())
+    pub(crate) fn lookup(
+        query: Query,
+        client: &mut C,
+        cache: Arc<Mutex<DnsLru>>,
+        reactor: Option<Handle>,
+    ) -> QueryState<C> {
+        QueryState::FromCache(
+            FromCache {
+                query,
+                cache,
+                reactor,
+                is_prefetch: false,
+            },
+            client.clone(),
+        )
     }
 
     /// Query after a failed cache lookup
@@ -396,7 +552,13 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> QueryState<C> {
                         query,
                         cache: cache.clone(),
                         dnssec: client.is_verifying_dnssec(),
-                        client: CachingClient::with_cache(cache, client),
+                        // a CNAME loop would otherwise wait on itself
+                        client: CachingClient {
+                            lru: cache,
+                            client,
+                            reactor: from_cache.reactor,
+                            in_flight: None,
+                        },
                     }),
                 );
             }
@@ -434,7 +596,7 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> QueryState<C> {
                 message_future: _,
                 query,
                 cache,
-                dnssec: _,
+                dnssec,
                 client: _,
             }) => {
                 match rdatas {
@@ -449,6 +611,8 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> QueryState<C> {
                                 rdatas,
                                 query,
                                 cache,
+                                // a validating client fails on any records which are not secure
+                                secure: dnssec,
                             }),
                         );
                     }
@@ -467,6 +631,7 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> QueryState<C> {
                                 rdatas,
                                 query,
                                 cache,
+                                secure: false,
                             }),
                         );
                     }
@@ -485,11 +650,16 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> Future for QueryState<C> {
         // first transition any polling that is needed (mutable refs...)
         let records: Option<Records>;
         match *self {
-            QueryState::FromCache(ref mut from_cache, ..) => {
+            QueryState::FromCache(ref mut from_cache, ref client) => {
                 match from_cache.poll() {
                     // need to query since it wasn't in the cache
                     Ok(Async::Ready(None)) => (), // handled below
-                    Ok(Async::Ready(Some(ips))) => return Ok(Async::Ready(ips)),
+                    Ok(Async::Ready(Some(ips))) => {
+                        if from_cache.is_prefetch {
+                            from_cache.prefetch(client.clone());
+                        }
+                        return Ok(Async::Ready(ips));
+                    }
                     Ok(Async::NotReady) => return Ok(Async::NotReady),
                     Err(error) => return Err(error),
                 };
@@ -504,6 +674,21 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> Future for QueryState<C> {
                     }
                     Ok(Async::Ready(rdatas)) => records = Some(rdatas), // handled in next match
                     Err(e) => {
+                        // the name servers failed, expired records may be served, RFC 8767
+                        if !is_stale_allowed(&e) {
+                            return Err(e);
+                        }
+
+                        let stale = query
+                            .cache
+                            .lock()
+                            .ok()
+                            .and_then(|mut lru| lru.get_stale(&query.query, Instant::now()));
+                        if let Some(stale) = stale {
+                            warn!("serving stale records for: {}: {}", query.query.name(), e);
+                            return Ok(Async::Ready(stale));
+                        }
+
                         return Err(e);
                     }
                 }
@@ -558,6 +743,21 @@ impl<C: DnsHandle<Error = ResolveError> + 'static> Future for QueryState<C> {
     }
 }
 
+/// Returns true if stale records may be served for the error, RFC 8767 section 4: the name
+///  servers timed out, the connection to them failed, or they answered SERVFAIL or REFUSED
+///
+/// Stale records are never served for other errors, e.g. responses which failed DNSSEC validation.
+fn is_stale_allowed(error: &ResolveError) -> bool {
+    match *error.kind() {
+        ResolveErrorKind::Io
+        | ResolveErrorKind::Proto(ProtoErrorKind::Io)
+        | ResolveErrorKind::Proto(ProtoErrorKind::Timeout)
+        | ResolveErrorKind::ResponseError(_, ResponseCode::ServFail)
+        | ResolveErrorKind::ResponseError(_, ResponseCode::Refused) => true,
+        _ => false,
+    }
+}
+
 // see also the lookup_tests.rs in integration-tests crate
 #[cfg(test)]
 mod tests {
@@ -572,6 +772,7 @@ mod tests {
     use trust_dns_proto::rr::rdata::SRV;
 
     use super::*;
+    use config::ResolverOpts;
     use lookup_ip::tests::*;
 
     #[test]
@@ -580,11 +781,11 @@ mod tests {
         let mut client = mock(vec![empty()]);
 
         assert_eq!(
-            QueryState::lookup(Query::new(), &mut client, cache)
+            QueryState::lookup(Query::new(), &mut client, cache, None)
                 .wait()
                 .unwrap_err()
                 .kind(),
-            &ResolveErrorKind::NoRecordsFound(Query::new())
+            &ResolveErrorKind::NoRecordsFound(Query::new(), None, ResponseCode::NoError)
         );
     }
 
@@ -594,12 +795,13 @@ mod tests {
         cache.lock().unwrap().insert(
             Query::new(),
             vec![(RData::A(Ipv4Addr::new(127, 0, 0, 1)), u32::max_value())],
+            false,
             Instant::now(),
         );
 
         let mut client = mock(vec![empty()]);
 
-        let ips = QueryState::lookup(Query::new(), &mut client, cache)
+        let ips = QueryState::lookup(Query::new(), &mut client, cache, None)
             .wait()
             .unwrap();
 
@@ -615,7 +817,7 @@ mod tests {
         // first should come from client...
         let mut client = mock(vec![v4_message()]);
 
-        let ips = QueryState::lookup(Query::new(), &mut client, cache.clone())
+        let ips = QueryState::lookup(Query::new(), &mut client, cache.clone(), None)
             .wait()
             .unwrap();
 
@@ -627,7 +829,63 @@ mod tests {
         // next should come from cache...
         let mut client = mock(vec![empty()]);
 
-        let ips = QueryState::lookup(Query::new(), &mut client, cache)
+        let ips = QueryState::lookup(Query::new(), &mut client, cache, None)
+            .wait()
+            .unwrap();
+
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+    }
+
+    /// A cache which serves stale records, with an A record which expired ten seconds ago
+    fn stale_cache() -> Arc<Mutex<DnsLru>> {
+        let cache = Arc::new(Mutex::new(DnsLru::with_opts(&ResolverOpts {
+            serve_stale: Some(Duration::from_secs(60)),
+            ..ResolverOpts::default()
+        })));
+
+        cache.lock().unwrap().insert(
+            Query::new(),
+            vec![(RData::A(Ipv4Addr::new(127, 0, 0, 2)), 1)],
+            false,
+            Instant::now() - Duration::from_secs(11),
+        );
+        cache
+    }
+
+    fn timeout() -> ResolveResult<Message> {
+        Err(ResolveErrorKind::Proto(ProtoErrorKind::Timeout).into())
+    }
+
+    fn response_code(response_code: ResponseCode) -> ResolveResult<Message> {
+        let mut message = Message::new();
+        message.set_response_code(response_code);
+        Ok(message)
+    }
+
+    #[test]
+    fn test_serve_stale() {
+        let cache = stale_cache();
+
+        // the name servers time out, the expired records are served with a short TTL
+        let mut client = mock(vec![timeout()]);
+        let ips = QueryState::lookup(Query::new(), &mut client, cache.clone(), None)
+            .wait()
+            .unwrap();
+
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 2))]
+        );
+        assert!(
+            ips.valid_until() <= Instant::now() + Duration::from_secs(dns_lru::STALE_TTL as u64)
+        );
+
+        // the name servers answer again
+        let mut client = mock(vec![v4_message()]);
+        let ips = QueryState::lookup(Query::new(), &mut client, cache, None)
             .wait()
             .unwrap();
 
@@ -637,6 +895,114 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_serve_stale_on_server_errors() {
+        for code in &[ResponseCode::ServFail, ResponseCode::Refused] {
+            let mut client = mock(vec![response_code(*code)]);
+            let ips = QueryState::lookup(Query::new(), &mut client, stale_cache(), None)
+                .wait()
+                .unwrap();
+
+            assert_eq!(
+                ips.iter().cloned().collect::<Vec<_>>(),
+                vec![RData::A(Ipv4Addr::new(127, 0, 0, 2))]
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_stale_on_other_errors() {
+        let bogus = Err(ResolveErrorKind::Bogus(Query::new(), "test".to_string()).into());
+        let errors = vec![bogus, response_code(ResponseCode::FormErr), error()];
+
+        for error in errors {
+            let mut client = mock(vec![error]);
+            assert!(
+                QueryState::lookup(Query::new(), &mut client, stale_cache(), None)
+                    .wait()
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_in_flight() {
+        // there is one answer, the cache keeps nothing
+        let mut client = CachingClient::new(0, mock(vec![empty(), v4_message()]));
+
+        // identical queries share the one lookup
+        let lookups = future::join_all(vec![
+            client.lookup(Query::new()),
+            client.lookup(Query::new()),
+            client.lookup(Query::new()),
+        ]).wait()
+            .unwrap();
+
+        for lookup in lookups {
+            assert_eq!(
+                lookup.iter().cloned().collect::<Vec<_>>(),
+                vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+            );
+        }
+
+        // once complete, the next query is sent
+        assert!(client.lookup(Query::new()).wait().is_err());
+    }
+
+    #[test]
+    fn test_in_flight_error() {
+        let mut client = CachingClient::new(0, mock(vec![v4_message(), error()]));
+
+        // all get the error
+        let first = client.lookup(Query::new());
+        let second = client.lookup(Query::new());
+        assert!(first.wait().is_err());
+        assert!(second.wait().is_err());
+    }
+
+    #[test]
+    fn test_prefetch() {
+        use tokio_core::reactor::Core;
+
+        let mut io_loop = Core::new().unwrap();
+        let cache = Arc::new(Mutex::new(DnsLru::with_opts(&ResolverOpts {
+            prefetch: Some(50),
+            ..ResolverOpts::default()
+        })));
+
+        // in the last half of the TTL
+        cache.lock().unwrap().insert(
+            Query::new(),
+            vec![(RData::A(Ipv4Addr::new(127, 0, 0, 2)), 10)],
+            false,
+            Instant::now() - Duration::from_secs(6),
+        );
+
+        // the cached records are returned, and refreshed in the background
+        let client = mock(vec![v4_message()]);
+        let mut client = CachingClient::with_prefetch(cache.clone(), client, &io_loop.handle());
+        let ips = io_loop.run(client.lookup(Query::new())).unwrap();
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 2))]
+        );
+
+        for _ in 0..10 {
+            io_loop.turn(Some(Duration::from_millis(1)));
+        }
+
+        let ips = cache
+            .lock()
+            .unwrap()
+            .get(&Query::new(), Instant::now())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+    }
+
     pub fn cname_message() -> ResolveResult<Message> {
         let mut message = Message::new();
         message.insert_answers(vec![
@@ -678,6 +1044,7 @@ mod tests {
             Query::query(Name::from_str("www.example.com.").unwrap(), query_type),
             &mut client,
             cache.clone(),
+            None,
         ).wait()
             .expect("lookup failed");
 
@@ -711,6 +1078,7 @@ mod tests {
             ),
             &mut client,
             cache.clone(),
+            None,
         ).wait()
             .expect("lookup failed");
 
@@ -786,7 +1154,7 @@ mod tests {
     fn test_early_return_localhost() {
         let cache = Arc::new(Mutex::new(DnsLru::new(0)));
         let client = mock(vec![empty()]);
-        let mut client = CachingClient{lru: cache, client: client};
+        let mut client = CachingClient{lru: cache, client: client, reactor: None};
         
         assert_eq!(
             client.lookup(Query::query(Name::from_ascii("localhost.").unwrap(), RecordType::A))
@@ -839,7 +1207,7 @@ mod tests {
     fn test_early_return_invalid() {
         let cache = Arc::new(Mutex::new(DnsLru::new(0)));
         let client = mock(vec![empty()]);
-        let mut client = CachingClient{lru: cache, client: client};
+        let mut client = CachingClient{lru: cache, client: client, reactor: None};
         
         assert!(
             client.lookup(Query::query(Name::from_ascii("horrible.invalid.").unwrap(), RecordType::A))
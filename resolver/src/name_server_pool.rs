@@ -10,15 +10,26 @@ use std::collections::BinaryHeap;
 use std::marker::PhantomData;
 use std::mem;
 use std::sync::{Arc, Mutex, TryLockError};
+use std::sync::atomic::{self, AtomicUsize};
 use std::time::{Duration, Instant};
 
 use futures::{future, task, Async, Future, Poll};
 use tokio_core::reactor::Handle;
 
-use trust_dns_proto::{DnsFuture, DnsHandle};
+use trust_dns_proto::{CookieJar, DnsFuture, DnsHandle};
 use trust_dns_proto::op::{Edns, Message, NoopMessageFinalizer, ResponseCode};
 use trust_dns_proto::udp::UdpClientStream;
 use trust_dns_proto::tcp::TcpClientStream;
+#[cfg(feature = "https")]
+use trust_dns_https::HttpsClientStreamBuilder;
+#[cfg(feature = "tls-ring")]
+use trust_dns_rustls::TlsClientStreamBuilder;
+#[cfg(all(feature = "tls-native", not(feature = "tls-ring")))]
+use trust_dns_native_tls::TlsClientStreamBuilder;
+#[cfg(any(feature = "https", feature = "tls-ring"))]
+use rustls::ClientConfig;
+#[cfg(any(feature = "https", feature = "tls-ring"))]
+use webpki_roots;
 
 use config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
 use error::*;
@@ -26,6 +37,11 @@ use resolver_future::BasicResolverHandle;
 
 const MIN_RETRY_DELAY_MS: u64 = 500;
 const MAX_RETRY_DELAY_S: u64 = 360;
+/// Each new round trip time is weighted 1/SRTT_WEIGHT in the smoothed RTT, as for TCP, RFC 6298
+const SRTT_WEIGHT: u32 = 8;
+/// Every PROBE_INTERVAL requests through a pool, the request is also sent to one of the other
+///  name servers, so that the SRTT of a slower or recovered name server is kept up to date
+const PROBE_INTERVAL: usize = 32;
 
 /// State of a connection with a remote NameServer.
 #[derive(Clone, Debug)]
@@ -78,26 +94,44 @@ struct NameServerStats {
     state: NameServerState,
     successes: usize,
     failures: usize,
+    /// smoothed round trip time, None until the first response
+    srtt: Option<Duration>,
 }
 
 impl Default for NameServerStats {
     fn default() -> Self {
-        Self::init(None, 0, 0)
+        Self::init(None, 0, 0, None)
     }
 }
 
 impl NameServerStats {
-    fn init(send_edns: Option<Edns>, successes: usize, failures: usize) -> Self {
+    fn init(
+        send_edns: Option<Edns>,
+        successes: usize,
+        failures: usize,
+        srtt: Option<Duration>,
+    ) -> Self {
         NameServerStats {
-            state: NameServerState::Init { send_edns },
-            successes,
-            failures,
-            // TODO: incorporate latency
+            state: NameServerState::Init {
+                send_edns: send_edns,
+            },
+            successes: successes,
+            failures: failures,
+            srtt: srtt,
         }
     }
 
-    fn next_success(&mut self, remote_edns: Option<Edns>) {
+    /// Folds the round trip time into the smoothed RTT, an exponentially weighted moving average
+    fn next_rtt(&mut self, rtt: Duration) {
+        self.srtt = Some(match self.srtt {
+            Some(srtt) => srtt * (SRTT_WEIGHT - 1) / SRTT_WEIGHT + rtt / SRTT_WEIGHT,
+            None => rtt,
+        });
+    }
+
+    fn next_success(&mut self, remote_edns: Option<Edns>, rtt: Duration) {
         self.successes += 1;
+        self.next_rtt(rtt);
 
         // update current state
 
@@ -121,8 +155,11 @@ impl NameServerStats {
         };
     }
 
-    fn next_failure(&mut self, error: ResolveError, when: Instant) {
+    /// A failure counts as a round trip of the whole `timeout`, the SRTT of a failing name server
+    ///  decays towards it
+    fn next_failure(&mut self, error: ResolveError, when: Instant, timeout: Duration) {
         self.failures += 1;
+        self.next_rtt(timeout);
 
         // update current state
         mem::replace(&mut self.state, NameServerState::Failed { error, when });
@@ -145,15 +182,25 @@ impl Ord for NameServerStats {
             }
         }
 
-        // TODO: track latency and use lowest latency connection...
+        // the lowest latency connection, those not yet measured are tried first
+        match (self.srtt, other.srtt) {
+            (Some(srtt), Some(other_srtt)) => match other_srtt.cmp(&srtt) {
+                Ordering::Equal => (),
+                o @ _ => return o,
+            },
+            (None, Some(_)) => return Ordering::Greater,
+            (Some(_), None) => return Ordering::Less,
+            (None, None) => (),
+        }
 
         // invert failure comparison
-        if self.failures <= other.failures {
-            return Ordering::Greater;
+        match other.failures.cmp(&self.failures) {
+            Ordering::Equal => (),
+            o @ _ => return o,
         }
 
         // at this point we'll go with the lesser of successes to make sure there is ballance
-        self.successes.cmp(&other.successes)
+        other.successes.cmp(&self.successes)
     }
 }
 
@@ -163,13 +210,69 @@ impl PartialOrd for NameServerStats {
     }
 }
 
+/// The performance of a name server in a `NameServerPool`, see `NameServerPool::stats()`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameServerRank {
+    config: NameServerConfig,
+    is_failed: bool,
+    srtt: Option<Duration>,
+    successes: usize,
+    failures: usize,
+}
+
+impl NameServerRank {
+    fn new(config: &NameServerConfig, stats: &NameServerStats) -> Self {
+        let is_failed = if let NameServerState::Failed { .. } = stats.state {
+            true
+        } else {
+            false
+        };
+
+        NameServerRank {
+            config: config.clone(),
+            is_failed: is_failed,
+            srtt: stats.srtt,
+            successes: stats.successes,
+            failures: stats.failures,
+        }
+    }
+
+    /// The address and protocol of the name server
+    pub fn config(&self) -> &NameServerConfig {
+        &self.config
+    }
+
+    /// True if the last request to the name server failed, it is retried after a backoff
+    pub fn is_failed(&self) -> bool {
+        self.is_failed
+    }
+
+    /// The smoothed round trip time of the requests to the name server, failures count as the
+    ///  full timeout, None if it has not been measured
+    pub fn srtt(&self) -> Option<Duration> {
+        self.srtt
+    }
+
+    /// The number of successful requests to the name server
+    pub fn successes(&self) -> usize {
+        self.successes
+    }
+
+    /// The number of failed requests to the name server
+    pub fn failures(&self) -> usize {
+        self.failures
+    }
+}
+
 #[doc(hidden)]
 pub trait ConnectionProvider: Clone {
     type ConnHandle;
 
+    /// Returns a new connection to the name server, which sends the cookies of the jar
     fn new_connection(
         config: &NameServerConfig,
         options: &ResolverOpts,
+        cookies: &CookieJar,
         reactor: &Handle,
     ) -> Self::ConnHandle;
 }
@@ -184,33 +287,106 @@ impl ConnectionProvider for StandardConnection {
     fn new_connection(
         config: &NameServerConfig,
         options: &ResolverOpts,
+        cookies: &CookieJar,
         reactor: &Handle,
     ) -> Self::ConnHandle {
         let dns_handle = match config.protocol {
             Protocol::Udp => {
                 let (stream, handle) = UdpClientStream::new(config.socket_addr, reactor);
                 // TODO: need config for Signer...
-                DnsFuture::with_timeout(stream, handle, reactor, options.timeout, NoopMessageFinalizer::new())
+                DnsFuture::with_cookies(
+                    stream,
+                    handle,
+                    reactor,
+                    options.timeout,
+                    NoopMessageFinalizer::new(),
+                    Some(cookies.clone()),
+                )
             }
             Protocol::Tcp => {
                 let (stream, handle) =
                     TcpClientStream::with_timeout(config.socket_addr, reactor, options.timeout);
                 // TODO: need config for Signer...
-                DnsFuture::with_timeout(stream, handle, reactor, options.timeout, NoopMessageFinalizer::new())
+                DnsFuture::with_cookies(
+                    stream,
+                    handle,
+                    reactor,
+                    options.timeout,
+                    NoopMessageFinalizer::new(),
+                    Some(cookies.clone()),
+                )
+            }
+            #[cfg(feature = "tls")]
+            Protocol::Tls { ref dns_name } => {
+                let (stream, handle) =
+                    tls_builder().build(config.socket_addr, dns_name.clone(), reactor);
+                // TODO: need config for Signer...
+                DnsFuture::with_cookies(
+                    stream,
+                    handle,
+                    reactor,
+                    options.timeout,
+                    NoopMessageFinalizer::new(),
+                    Some(cookies.clone()),
+                )
+            }
+            #[cfg(feature = "https")]
+            Protocol::Https {
+                ref dns_name,
+                ref endpoint,
+            } => {
+                let (stream, handle) = HttpsClientStreamBuilder::with_client_config(
+                    webpki_config(),
+                ).build(
+                    config.socket_addr,
+                    dns_name.clone(),
+                    endpoint.clone(),
+                    reactor,
+                );
+                DnsFuture::with_cookies(
+                    stream,
+                    handle,
+                    reactor,
+                    options.timeout,
+                    NoopMessageFinalizer::new(),
+                    Some(cookies.clone()),
+                )
             }
-            // TODO: Protocol::Tls => TlsClientStream::new(config.socket_addr, reactor),
         };
 
         BasicResolverHandle::new(dns_handle)
     }
 }
 
+/// The TLS configuration which trusts the roots of webpki
+#[cfg(any(feature = "https", feature = "tls-ring"))]
+fn webpki_config() -> ClientConfig {
+    let mut client_config = ClientConfig::new();
+    client_config
+        .root_store
+        .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+    client_config
+}
+
+#[cfg(feature = "tls-ring")]
+fn tls_builder() -> TlsClientStreamBuilder {
+    TlsClientStreamBuilder::with_client_config(webpki_config())
+}
+
+/// native-tls trusts the roots of the system
+#[cfg(all(feature = "tls-native", not(feature = "tls-ring")))]
+fn tls_builder() -> TlsClientStreamBuilder {
+    TlsClientStreamBuilder::new()
+}
+
 #[derive(Clone)]
 #[doc(hidden)]
 pub struct NameServer<C: DnsHandle, P: ConnectionProvider<ConnHandle = C>> {
     config: NameServerConfig,
     options: ResolverOpts,
     client: C,
+    // the cookies of the server outlive the connections
+    cookies: CookieJar,
     // TODO: switch to FuturesMutex? (Mutex will have some undesireable locking)
     stats: Arc<Mutex<NameServerStats>>,
     reactor: Handle,
@@ -223,13 +399,15 @@ impl<C: DnsHandle, P: ConnectionProvider<ConnHandle = C>> NameServer<C, P> {
         options: ResolverOpts,
         reactor: &Handle,
     ) -> NameServer<BasicResolverHandle, StandardConnection> {
-        let client = StandardConnection::new_connection(&config, &options, reactor);
+        let cookies = CookieJar::new();
+        let client = StandardConnection::new_connection(&config, &options, &cookies, reactor);
 
         // TODO: setup EDNS
         NameServer {
             config,
             options,
             client,
+            cookies,
             stats: Arc::new(Mutex::new(NameServerStats::default())),
             reactor: reactor.clone(),
             phantom: PhantomData,
@@ -247,6 +425,7 @@ impl<C: DnsHandle, P: ConnectionProvider<ConnHandle = C>> NameServer<C, P> {
             config,
             options,
             client,
+            cookies: CookieJar::new(),
             stats: Arc::new(Mutex::new(NameServerStats::default())),
             reactor: reactor.clone(),
             phantom: PhantomData,
@@ -257,11 +436,17 @@ impl<C: DnsHandle, P: ConnectionProvider<ConnHandle = C>> NameServer<C, P> {
     ///  will check the last falure time, and if the retry period is acceptable,
     ///  then reconnect.
     fn try_reconnect(&mut self) -> ResolveResult<()> {
-        let error_opt: Option<(ResolveError, Instant, usize, usize)> = self.stats
+        let error_opt: Option<(ResolveError, Instant, usize, usize, Option<Duration>)> = self.stats
             .lock()
             .map(|stats| {
                 if let NameServerState::Failed { ref error, when } = stats.state {
-                    Some((error.clone(), when, stats.successes, stats.failures))
+                    Some((
+                        error.clone(),
+                        when,
+                        stats.successes,
+                        stats.failures,
+                        stats.srtt,
+                    ))
                 } else {
                     None
                 }
@@ -271,7 +456,7 @@ impl<C: DnsHandle, P: ConnectionProvider<ConnHandle = C>> NameServer<C, P> {
             })?;
 
         // if this is in a failure state
-        if let Some((error, when, successes, failures)) = error_opt {
+        if let Some((error, when, successes, failures, srtt)) = error_opt {
             // Backoff is based on successes vs. failures...
             let max_delay = Duration::from_secs(MAX_RETRY_DELAY_S);
             let min_delay = Duration::from_millis(MIN_RETRY_DELAY_MS);
@@ -292,13 +477,19 @@ impl<C: DnsHandle, P: ConnectionProvider<ConnHandle = C>> NameServer<C, P> {
             if Instant::now().duration_since(when) > retry_delay {
                 debug!("reconnecting: {:?}", self.config);
                 // establish a new connection
-                let client = P::new_connection(&self.config, &self.options, &self.reactor);
+                let client =
+                    P::new_connection(&self.config, &self.options, &self.cookies, &self.reactor);
                 mem::replace(&mut self.client, client);
 
                 // reinitialize the mutex (in case it was poisoned before)
                 mem::replace(
                     &mut self.stats,
-                    Arc::new(Mutex::new(NameServerStats::init(None, successes, failures))),
+                    Arc::new(Mutex::new(NameServerStats::init(
+                        None,
+                        successes,
+                        failures,
+                        srtt,
+                    ))),
                 );
                 Ok(())
             } else {
@@ -333,6 +524,8 @@ where
         // grab a reference to the stats for this NameServer
         let mutex1 = self.stats.clone();
         let mutex2 = self.stats.clone();
+        let timeout = self.options.timeout;
+        let sent = Instant::now();
         Box::new(
             self.client
                 .send(message)
@@ -344,7 +537,7 @@ where
                     let response = mutex1
                         .lock()
                         .and_then(|mut stats| {
-                            stats.next_success(remote_edns);
+                            stats.next_success(remote_edns, sent.elapsed());
                             Ok(response)
                         })
                         .map_err(|e| {
@@ -358,7 +551,7 @@ where
                     mutex2
                         .lock()
                         .and_then(|mut stats| {
-                            stats.next_failure(error.clone(), Instant::now());
+                            stats.next_failure(error.clone(), Instant::now(), timeout);
                             Ok(())
                         })
                         .or_else(|e| {
@@ -415,6 +608,8 @@ pub struct NameServerPool<C: DnsHandle + 'static, P: ConnectionProvider<ConnHand
     // TODO: switch to FuturesMutex (Mutex will have some undesireable locking)
     datagram_conns: Arc<Mutex<BinaryHeap<NameServer<C, P>>>>, /* All NameServers must be the same type */
     stream_conns: Arc<Mutex<BinaryHeap<NameServer<C, P>>>>, /* All NameServers must be the same type */
+    // the number of requests sent through the pool, for the probes of the other NameServers
+    sends: Arc<AtomicUsize>,
     options: ResolverOpts,
     phantom: PhantomData<P>,
 }
@@ -455,6 +650,7 @@ impl<C: DnsHandle + 'static, P: ConnectionProvider<ConnHandle = C> + 'static> Na
         NameServerPool {
             datagram_conns: Arc::new(Mutex::new(datagram_conns)),
             stream_conns: Arc::new(Mutex::new(stream_conns)),
+            sends: Arc::new(AtomicUsize::new(0)),
             options: options.clone(),
             phantom: PhantomData,
         }
@@ -469,18 +665,52 @@ impl<C: DnsHandle + 'static, P: ConnectionProvider<ConnHandle = C> + 'static> Na
         NameServerPool {
             datagram_conns: Arc::new(Mutex::new(datagram_conns.into_iter().collect())),
             stream_conns: Arc::new(Mutex::new(stream_conns.into_iter().collect())),
+            sends: Arc::new(AtomicUsize::new(0)),
             options: options.clone(),
             phantom: PhantomData,
         }
     }
 
+    /// The performance of each of the NameServers, the datagram and then the stream NameServers,
+    ///  each from the most to the least preferred
+    ///
+    /// NameServers are ranked by their state, failed ones last, and then by their smoothed round
+    ///  trip time, see `NameServerRank::srtt()`.
+    pub fn stats(&self) -> Vec<NameServerRank> {
+        let mut stats = Self::ranking(&self.datagram_conns);
+        stats.extend(Self::ranking(&self.stream_conns));
+        stats
+    }
+
+    fn ranking(conns: &Mutex<BinaryHeap<NameServer<C, P>>>) -> Vec<NameServerRank> {
+        let conns = conns.lock().expect("poisoned lock in NameServerPool::stats");
+        let mut ranking: Vec<(NameServerStats, &NameServerConfig)> = conns
+            .iter()
+            .map(|conn| {
+                let stats = conn.stats
+                    .lock()
+                    .expect("poisoned lock in NameServerPool::stats");
+                (stats.clone(), &conn.config)
+            })
+            .collect();
+
+        // most preferred first
+        ranking.sort_by(|a, b| b.0.cmp(&a.0));
+        ranking
+            .iter()
+            .map(|&(ref stats, config)| NameServerRank::new(config, stats))
+            .collect()
+    }
+
     fn try_send(
         conns: Arc<Mutex<BinaryHeap<NameServer<C, P>>>>,
         message: Message,
+        probe: Option<usize>,
     ) -> TrySend<C, P> {
         TrySend::Lock {
-            conns,
+            conns: conns,
             message: Some(message),
+            probe: probe,
         }
     }
 }
@@ -500,17 +730,25 @@ where
         let tcp_message1 = message.clone();
         let tcp_message2 = message.clone();
 
+        // every so often one of the other NameServers is probed, in turn
+        let sends = self.sends.fetch_add(1, atomic::Ordering::Relaxed) + 1;
+        let probe = if sends % PROBE_INTERVAL == 0 {
+            Some(sends / PROBE_INTERVAL)
+        } else {
+            None
+        };
+
         Box::new(
-            Self::try_send(datagram_conns, message)
+            Self::try_send(datagram_conns, message, probe)
                 .and_then(move |response| {
                     // handling promotion from datagram to stream base on truncation in message
                     if ResponseCode::NoError == response.response_code() && response.truncated() {
-                        future::Either::A(Self::try_send(stream_conns1, tcp_message1))
+                        future::Either::A(Self::try_send(stream_conns1, tcp_message1, None))
                     } else {
                         future::Either::B(future::ok(response))
                     }
                 })
-                .or_else(move |_| Self::try_send(stream_conns2, tcp_message2)),
+                .or_else(move |_| Self::try_send(stream_conns2, tcp_message2, probe)),
         )
     }
 }
@@ -519,6 +757,8 @@ enum TrySend<C: DnsHandle + 'static, P: ConnectionProvider<ConnHandle = C> + 'st
     Lock {
         conns: Arc<Mutex<BinaryHeap<NameServer<C, P>>>>,
         message: Option<Message>,
+        // the turn of the probe to send along with the message, see `PROBE_INTERVAL`
+        probe: Option<usize>,
     },
     DoSend(Box<Future<Item = Message, Error = ResolveError>>),
 }
@@ -538,6 +778,7 @@ where
             TrySend::Lock {
                 ref conns,
                 ref mut message,
+                probe,
             } => {
                 // pull a lock on the shared connections, lock releases at the end of the method
                 let conns = conns.try_lock();
@@ -548,6 +789,17 @@ where
                     }
                     Err(TryLockError::WouldBlock) => return Ok(Async::NotReady),
                     Ok(mut conns) => {
+                        // the stats of the NameServers change as their responses arrive, after
+                        //  they were placed in the heap, restore its order
+                        let heap = mem::replace(&mut *conns, BinaryHeap::new());
+                        *conns = BinaryHeap::from(heap.into_vec());
+
+                        let message = mem::replace(message, None)
+                            .expect("bad state, mesage should never be None");
+                        if let Some(probe) = probe {
+                            probe_other(&mut conns, probe, message.clone());
+                        }
+
                         // select the highest priority connection
                         let conn = conns.peek_mut();
 
@@ -557,9 +809,7 @@ where
                             );
                         }
 
-                        let message = mem::replace(message, None);
-                        future = conn.unwrap()
-                            .send(message.expect("bad state, mesage should never be None"));
+                        future = conn.unwrap().send(message);
                     }
                 }
             }
@@ -574,6 +824,37 @@ where
     }
 }
 
+/// Sends the message to one of the NameServers other than the most preferred, in the background,
+///  to measure it; the responses are dropped
+///
+/// The probes go to each of the other NameServers in turn.
+fn probe_other<C, P>(conns: &mut BinaryHeap<NameServer<C, P>>, probe: usize, message: Message)
+where
+    C: DnsHandle<Error = ResolveError> + 'static,
+    P: ConnectionProvider<ConnHandle = C> + 'static,
+{
+    if conns.len() < 2 {
+        return;
+    }
+
+    // least preferred first, the most preferred is last
+    let mut sorted = mem::replace(conns, BinaryHeap::new()).into_sorted_vec();
+    let others = sorted.len() - 1;
+    {
+        let conn = &mut sorted[probe % others];
+        debug!("probing: {:?}", conn.config);
+        let probe = conn.send(message).then(|response| {
+            if let Err(error) = response {
+                debug!("probe failed: {}", error);
+            }
+            Ok::<_, ()>(())
+        });
+        conn.reactor.spawn(probe);
+    }
+
+    *conns = sorted.into_iter().collect();
+}
+
 #[cfg(test)]
 mod tests {
     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
@@ -593,12 +874,14 @@ mod tests {
             state: NameServerState::Init { send_edns: None },
             successes: 0,
             failures: 0,
+            srtt: None,
         };
 
         let established = NameServerStats {
             state: NameServerState::Established { remote_edns: None },
             successes: 0,
             failures: 0,
+            srtt: None,
         };
 
         let failed = NameServerStats {
@@ -608,18 +891,21 @@ mod tests {
             },
             successes: 0,
             failures: 0,
+            srtt: None,
         };
 
         let established_successes = NameServerStats {
             state: NameServerState::Established { remote_edns: None },
             successes: 1,
             failures: 0,
+            srtt: None,
         };
 
         let established_failed = NameServerStats {
             state: NameServerState::Established { remote_edns: None },
             successes: 0,
             failures: 1,
+            srtt: None,
         };
 
 
@@ -630,6 +916,54 @@ mod tests {
         assert_eq!(established.cmp(&established_failed), Ordering::Greater);
     }
 
+    #[test]
+    fn test_srtt_cmp() {
+        let established = |srtt: Option<Duration>| NameServerStats {
+            state: NameServerState::Established { remote_edns: None },
+            successes: 1,
+            failures: 0,
+            srtt: srtt,
+        };
+
+        let near = established(Some(Duration::from_millis(10)));
+        let far = established(Some(Duration::from_millis(100)));
+        let unmeasured = established(None);
+
+        assert_eq!(near.cmp(&far), Ordering::Greater);
+        assert_eq!(far.cmp(&near), Ordering::Less);
+        assert_eq!(unmeasured.cmp(&near), Ordering::Greater);
+
+        // the state still comes first
+        let mut failed = near.clone();
+        failed.next_failure(
+            ResolveErrorKind::Msg("test".to_string()).into(),
+            Instant::now(),
+            Duration::from_millis(10),
+        );
+        assert_eq!(far.cmp(&failed), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_srtt() {
+        let mut stats = NameServerStats::default();
+        assert_eq!(stats.srtt, None);
+
+        // the first round trip is taken as it is, then weighted in
+        stats.next_success(None, Duration::from_millis(80));
+        assert_eq!(stats.srtt, Some(Duration::from_millis(80)));
+        stats.next_success(None, Duration::from_millis(160));
+        assert_eq!(stats.srtt, Some(Duration::from_millis(90)));
+
+        // a failure counts as the timeout
+        stats.next_failure(
+            ResolveErrorKind::Msg("test".to_string()).into(),
+            Instant::now(),
+            Duration::from_millis(890),
+        );
+        assert_eq!(stats.srtt, Some(Duration::from_millis(190)));
+        assert_eq!(stats.failures, 1);
+    }
+
     #[test]
     fn test_name_server() {
         let config = NameServerConfig {
@@ -650,6 +984,63 @@ mod tests {
         assert_eq!(response.response_code(), ResponseCode::NoError);
     }
 
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_tls_name_server() {
+        let config = ResolverConfig::cloudflare_tls().name_servers()[0].clone();
+        let mut io_loop = Core::new().unwrap();
+        let mut name_server = NameServer::<_, StandardConnection>::new(
+            config,
+            ResolverOpts::default(),
+            &io_loop.handle(),
+        );
+
+        // the second query reuses the connection of the first
+        let name = Name::parse("www.example.com.", None).unwrap();
+        for _ in 0..2 {
+            let response = io_loop
+                .run(name_server.lookup(Query::query(name.clone(), RecordType::A)))
+                .expect("query failed");
+            assert_eq!(response.response_code(), ResponseCode::NoError);
+        }
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_tls_pool_uses_streams() {
+        let io_loop = Core::new().unwrap();
+        let mut config = ResolverConfig::cloudflare_tls();
+        config.add_name_server(NameServerConfig {
+            socket_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)), 53),
+            protocol: Protocol::Udp,
+        });
+
+        let pool = NameServerPool::<BasicResolverHandle, StandardConnection>::from_config(
+            &config,
+            &ResolverOpts::default(),
+            &io_loop.handle(),
+        );
+
+        // the TLS name servers are only tried with the streams, never over UDP
+        let datagram_conns = pool.datagram_conns.lock().unwrap();
+        assert_eq!(datagram_conns.len(), 1);
+        assert!(
+            datagram_conns
+                .iter()
+                .all(|conn| conn.config.protocol == Protocol::Udp)
+        );
+
+        let stream_conns = pool.stream_conns.lock().unwrap();
+        assert_eq!(stream_conns.len(), 4);
+        for conn in stream_conns.iter() {
+            assert_eq!(conn.config.socket_addr.port(), 853);
+            match conn.config.protocol {
+                Protocol::Tls { ref dns_name } => assert_eq!(dns_name, "cloudflare-dns.com"),
+                ref protocol => panic!("expected TLS: {:?}", protocol),
+            }
+        }
+    }
+
     #[test]
     fn test_failed_name_server() {
         let mut options = ResolverOpts::default();
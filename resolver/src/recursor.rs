@@ -0,0 +1,551 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! An iterative resolver, which follows the referrals from the root name servers
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use futures::{future, Future};
+use futures::future::Loop;
+use lru_cache::LruCache;
+use tokio_core::reactor::Handle;
+
+use trust_dns_proto::DnsHandle;
+use trust_dns_proto::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use trust_dns_proto::rr::{Name, RData, Record, RecordType};
+
+use config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
+use dns_lru::{DnsLru, MAX_TTL};
+use error::*;
+use lookup::Lookup;
+use name_server_pool::{NameServerPool, StandardConnection};
+use resolver_future::BasicResolverHandle;
+
+/// Maximum number of nested resolutions, i.e. of aliases and of name servers without glue
+const MAX_DEPTH: u8 = 8;
+/// Maximum number of referrals followed in the resolution of a single name
+const MAX_REFERRALS: usize = 16;
+/// Number of connection pools, to the name servers of the zones, which are kept open
+const POOL_CACHE_SIZE: usize = 64;
+/// Payload size advertised with EDNS, referrals from the root are larger than 512 bytes
+const MAX_PAYLOAD_LEN: u16 = 1500 - 40 - 8;
+
+type Pool = NameServerPool<BasicResolverHandle, StandardConnection>;
+
+/// A recursive resolver, starting from the root hints, the addresses of the root name servers
+///
+/// Names are resolved iteratively, see [RFC 1034](https://tools.ietf.org/html/rfc1034#section-5.3.3)
+///
+/// ```text
+/// 5.3.3. Algorithm
+///
+/// The top level algorithm has four steps:
+///
+///    1. See if the answer is in local information, and if so return
+///       it to the client.
+///
+///    2. Find the best servers to ask.
+///
+///    3. Send them queries until one returns a response.
+///
+///    4. Analyze the response, either:
+///
+///          a. if the response answers the question or contains a name
+///             error, cache the data as well as returning it back to
+///             the client.
+///
+///          b. if the response contains a better delegation to other
+///             servers, cache the delegation information, and go to
+///             step 2.
+///
+///          c. if the response shows a CNAME and that is not the
+///             answer itself, cache the CNAME, change the SNAME to the
+///             canonical name in the CNAME RR and go to step 1.
+///
+///          d. if the response shows a servers failure or other
+///             bizarre contents, delete the server from the SLIST and
+///             go back to step 3.
+/// ```
+///
+/// The delegations, i.e. the NS records and their glue, are cached along with the answers. Only
+///  records from the zone of the name server which was queried are accepted.
+#[derive(Clone)]
+pub struct Recursor {
+    hints: Arc<Vec<SocketAddr>>,
+    options: ResolverOpts,
+    cache: Arc<Mutex<DnsLru>>,
+    pools: Arc<Mutex<LruCache<Name, (Vec<SocketAddr>, Pool)>>>,
+    port: u16,
+    reactor: Handle,
+}
+
+/// The outcome of a query to the name servers of a zone
+enum Step {
+    /// The records of the answer, and the alias at which it continues outside of the zone
+    Answer(Vec<Record>, Option<Name>),
+    /// The name is delegated to a zone with other name servers
+    Referral(Name, Vec<SocketAddr>),
+}
+
+impl Recursor {
+    /// Construct a new Recursor
+    ///
+    /// # Arguments
+    ///
+    /// * `hints` - addresses of the root name servers, e.g. from a root hints file
+    /// * `options` - the timeout, attempts and cache_size are used for the queries
+    /// * `reactor` - the [`tokio_core::Core`] to use with this future
+    pub fn new(hints: Vec<SocketAddr>, options: ResolverOpts, reactor: &Handle) -> Self {
+        Recursor {
+            hints: Arc::new(hints),
+            cache: Arc::new(Mutex::new(DnsLru::with_opts(&options))),
+            options,
+            pools: Arc::new(Mutex::new(LruCache::new(POOL_CACHE_SIZE))),
+            port: 53,
+            reactor: reactor.clone(),
+        }
+    }
+
+    /// Sets the port on which the name servers, learned from NS records and glue, are queried
+    ///
+    /// This is 53 by default, other ports are only useful for testing.
+    pub fn set_name_server_port(&mut self, port: u16) {
+        self.port = port;
+    }
+
+    /// Resolves the query, returning the records of the answer
+    ///
+    /// If the name is an alias, the answer starts with the CNAME records leading to the canonical
+    ///  name. The TTLs of the records are those remaining in the cache.
+    pub fn resolve(&self, query: Query) -> Box<Future<Item = Vec<Record>, Error = ResolveError>> {
+        self.resolve_depth(query, 0)
+    }
+
+    fn resolve_depth(
+        &self,
+        query: Query,
+        depth: u8,
+    ) -> Box<Future<Item = Vec<Record>, Error = ResolveError>> {
+        if depth > MAX_DEPTH {
+            return Box::new(future::err(
+                ResolveErrorKind::Message("maximum depth of recursion exceeded").into(),
+            ));
+        }
+
+        if let Some(records) = self.from_cache(&query) {
+            return Box::new(future::result(records));
+        }
+
+        // an alias is followed to the canonical name
+        if query.query_type() != RecordType::CNAME {
+            let alias = Query::query(query.name().clone(), RecordType::CNAME);
+            if let Some(Ok(cnames)) = self.from_cache(&alias) {
+                let target = match cnames.first().map(Record::rdata) {
+                    Some(&RData::CNAME(ref target)) => Some(target.clone()),
+                    _ => None,
+                };
+
+                if let Some(target) = target {
+                    let target = Query::query(target, query.query_type());
+                    return Box::new(self.resolve_depth(target, depth + 1).map(
+                        move |records| cnames.into_iter().chain(records).collect(),
+                    ));
+                }
+            }
+        }
+
+        let (zone, name_servers) = self.closest_delegation(query.name());
+        debug!("resolving: {} {:?} from: {}", query.name(), query.query_type(), zone);
+
+        let recursor = self.clone();
+        let next = self.clone();
+        let query_type = query.query_type();
+        Box::new(
+            future::loop_fn(
+                (zone, name_servers, 0),
+                move |(zone, name_servers, referrals)| {
+                    if referrals > MAX_REFERRALS {
+                        return future::Either::A(future::err(
+                            ResolveErrorKind::Message("maximum referrals exceeded").into(),
+                        ));
+                    }
+
+                    future::Either::B(
+                        recursor
+                            .step(query.clone(), zone, name_servers, depth)
+                            .map(move |step| match step {
+                                Step::Answer(records, alias) => Loop::Break((records, alias)),
+                                Step::Referral(zone, name_servers) => {
+                                    Loop::Continue((zone, name_servers, referrals + 1))
+                                }
+                            }),
+                    )
+                },
+            ).and_then(move |(records, alias)| match alias {
+                // the answer continues in another zone
+                Some(alias) => future::Either::A(
+                    next.resolve_depth(Query::query(alias, query_type), depth + 1)
+                        .map(move |more| records.into_iter().chain(more).collect()),
+                ),
+                None => future::Either::B(future::ok(records)),
+            }),
+        )
+    }
+
+    /// Queries the name servers of the zone, and analyzes the response
+    fn step(
+        &self,
+        query: Query,
+        zone: Name,
+        name_servers: Vec<SocketAddr>,
+        depth: u8,
+    ) -> Box<Future<Item = Step, Error = ResolveError>> {
+        let mut message = Message::new();
+        message
+            .add_query(query.clone())
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(false);
+        {
+            let edns = message.edns_mut();
+            edns.set_max_payload(MAX_PAYLOAD_LEN);
+            edns.set_version(0);
+        }
+
+        let recursor = self.clone();
+        Box::new(
+            self.pool(&zone, name_servers)
+                .send(message)
+                .and_then(move |response| recursor.analyze(query, zone, response, depth)),
+        )
+    }
+
+    fn analyze(
+        &self,
+        query: Query,
+        zone: Name,
+        response: Message,
+        depth: u8,
+    ) -> Box<Future<Item = Step, Error = ResolveError>> {
+        match response.response_code() {
+            ResponseCode::NoError => (),
+            ResponseCode::NXDomain => return Box::new(future::err(self.negative(query, &response))),
+            code => {
+                return Box::new(future::err(
+                    ResolveErrorKind::Msg(format!("DNS Error: {} from: {}", code, zone)).into(),
+                ))
+            }
+        }
+
+        if let Some((records, alias)) = self.answer(&query, &zone, &response) {
+            return Box::new(future::ok(Step::Answer(records, alias)));
+        }
+
+        // a delegation must be to a zone below this one, which contains the name
+        let child = response
+            .name_servers()
+            .iter()
+            .find(|r| r.rr_type() == RecordType::NS)
+            .map(|r| r.name().clone());
+
+        let child = match child {
+            Some(ref child)
+                if *child != zone && zone.zone_of(child) && child.zone_of(query.name()) =>
+            {
+                child.clone()
+            }
+            _ => return Box::new(future::err(self.negative(query, &response))),
+        };
+
+        let ns_names = self.delegation(&zone, &child, &response);
+        let glue = self.name_servers(&ns_names);
+        debug!("referral to: {} from: {} glue: {:?}", child, zone, glue);
+
+        if !glue.is_empty() {
+            return Box::new(future::ok(Step::Referral(child, glue)));
+        }
+
+        // the name servers must be resolved, trying each in turn
+        let mut addresses: Box<Future<Item = Vec<SocketAddr>, Error = ResolveError>> =
+            Box::new(future::err(
+                ResolveErrorKind::Message("no addresses for name servers").into(),
+            ));
+        for ns_name in ns_names {
+            let recursor = self.clone();
+            addresses =
+                Box::new(addresses.or_else(move |_| recursor.addresses(ns_name, depth + 1)));
+        }
+
+        Box::new(addresses.map(move |addresses| Step::Referral(child, addresses)))
+    }
+
+    /// Returns the records answering the query in the response, with the alias at which the answer
+    ///  continues if the chain of CNAMEs leaves the zone
+    fn answer(
+        &self,
+        query: &Query,
+        zone: &Name,
+        response: &Message,
+    ) -> Option<(Vec<Record>, Option<Name>)> {
+        let mut name = query.name().clone();
+        let mut records = Vec::new();
+
+        for _ in 0..MAX_DEPTH {
+            // only records of the zone which was queried are trusted
+            if !zone.zone_of(&name) {
+                break;
+            }
+
+            let found = response
+                .answers()
+                .iter()
+                .filter(|r| *r.name() == name && r.rr_type() == query.query_type())
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if !found.is_empty() {
+                self.insert(Query::query(name, query.query_type()), &found);
+                records.extend(found);
+                return Some((records, None));
+            }
+
+            let cname = response
+                .answers()
+                .iter()
+                .find(|r| *r.name() == name && r.rr_type() == RecordType::CNAME)
+                .cloned();
+
+            match cname {
+                Some(cname) => {
+                    self.insert(Query::query(name, RecordType::CNAME), &[cname.clone()]);
+                    name = match *cname.rdata() {
+                        RData::CNAME(ref target) => target.clone(),
+                        _ => break,
+                    };
+                    records.push(cname);
+                }
+                None => break,
+            }
+        }
+
+        if records.is_empty() {
+            None
+        } else {
+            Some((records, Some(name)))
+        }
+    }
+
+    /// Caches the delegation to the child zone, and its glue, returning the names of the name
+    ///  servers
+    fn delegation(&self, zone: &Name, child: &Name, response: &Message) -> Vec<Name> {
+        let ns_records = response
+            .name_servers()
+            .iter()
+            .filter(|r| r.name() == child && r.rr_type() == RecordType::NS)
+            .cloned()
+            .collect::<Vec<_>>();
+        self.insert(Query::query(child.clone(), RecordType::NS), &ns_records);
+
+        let ns_names = ns_records
+            .iter()
+            .filter_map(|r| match *r.rdata() {
+                RData::NS(ref ns_name) => Some(ns_name.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // glue is only accepted from the zone of the name server which was queried
+        for ns_name in ns_names.iter().filter(|ns_name| zone.zone_of(ns_name)) {
+            for record_type in &[RecordType::A, RecordType::AAAA] {
+                let glue = response
+                    .additionals()
+                    .iter()
+                    .filter(|r| r.name() == ns_name && r.rr_type() == *record_type)
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                if !glue.is_empty() {
+                    self.insert(Query::query(ns_name.clone(), *record_type), &glue);
+                }
+            }
+        }
+
+        ns_names
+    }
+
+    /// Resolves the addresses of a name server
+    fn addresses(
+        &self,
+        ns_name: Name,
+        depth: u8,
+    ) -> Box<Future<Item = Vec<SocketAddr>, Error = ResolveError>> {
+        let recursor = self.clone();
+        let aaaa = Query::query(ns_name.clone(), RecordType::AAAA);
+        let port = self.port;
+
+        Box::new(
+            self.resolve_depth(Query::query(ns_name, RecordType::A), depth)
+                .or_else(move |_| recursor.resolve_depth(aaaa, depth))
+                .and_then(|records| {
+                    let addresses = records
+                        .iter()
+                        .filter_map(|r| to_socket_addr(r.rdata(), port))
+                        .collect::<Vec<_>>();
+
+                    if addresses.is_empty() {
+                        Err(ResolveErrorKind::Message("no addresses for name server").into())
+                    } else {
+                        Ok(addresses)
+                    }
+                }),
+        )
+    }
+
+    /// Finds the closest enclosing zone of the name, for which the name servers are known
+    fn closest_delegation(&self, name: &Name) -> (Name, Vec<SocketAddr>) {
+        let mut zone = name.clone();
+        loop {
+            if zone.is_root() {
+                return (Name::root(), self.hints.to_vec());
+            }
+
+            let ns_names = self.cached(&Query::query(zone.clone(), RecordType::NS))
+                .map(|lookup| {
+                    lookup
+                        .iter()
+                        .filter_map(|rdata| match *rdata {
+                            RData::NS(ref ns_name) => Some(ns_name.clone()),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            let name_servers = self.name_servers(&ns_names);
+            if !name_servers.is_empty() {
+                return (zone, name_servers);
+            }
+
+            zone = zone.base_name();
+        }
+    }
+
+    /// Returns the cached addresses of the name servers
+    fn name_servers(&self, ns_names: &[Name]) -> Vec<SocketAddr> {
+        let mut addresses = Vec::new();
+        for ns_name in ns_names {
+            for record_type in &[RecordType::A, RecordType::AAAA] {
+                if let Some(lookup) = self.cached(&Query::query(ns_name.clone(), *record_type)) {
+                    addresses.extend(
+                        lookup
+                            .iter()
+                            .filter_map(|rdata| to_socket_addr(rdata, self.port)),
+                    );
+                }
+            }
+        }
+
+        addresses
+    }
+
+    /// Returns the pool of connections to the name servers of the zone
+    fn pool(&self, zone: &Name, name_servers: Vec<SocketAddr>) -> Pool {
+        let mut pools = self.pools.lock().unwrap(); // poison errors should panic
+        if let Some(&mut (ref addresses, ref pool)) = pools.get_mut(zone) {
+            if *addresses == name_servers {
+                return pool.clone();
+            }
+        }
+
+        let mut config = ResolverConfig::from_parts(None, vec![], vec![]);
+        for name_server in &name_servers {
+            for protocol in &[Protocol::Udp, Protocol::Tcp] {
+                config.add_name_server(NameServerConfig {
+                    socket_addr: *name_server,
+                    protocol: protocol.clone(),
+                });
+            }
+        }
+
+        let pool = Pool::from_config(&config, &self.options, &self.reactor);
+        pools.insert(zone.clone(), (name_servers, pool.clone()));
+        pool
+    }
+
+    fn cached(&self, query: &Query) -> Option<Lookup> {
+        self.cached_result(query).and_then(Result::ok)
+    }
+
+    /// The cached records, or the error of a cached negative response
+    fn cached_result(&self, query: &Query) -> Option<ResolveResult<Lookup>> {
+        self.cache
+            .lock()
+            .unwrap() // poison errors should panic
+            .get(query, Instant::now())
+    }
+
+    fn from_cache(&self, query: &Query) -> Option<ResolveResult<Vec<Record>>> {
+        self.cached_result(query).map(|lookup| {
+            lookup.map(|lookup| {
+                let now = Instant::now();
+                let ttl = if lookup.valid_until() > now {
+                    (lookup.valid_until() - now).as_secs().min(u64::from(MAX_TTL)) as u32
+                } else {
+                    0
+                };
+
+                lookup
+                    .iter()
+                    .map(|rdata| {
+                        Record::from_rdata(
+                            query.name().clone(),
+                            ttl,
+                            rdata.to_record_type(),
+                            rdata.clone(),
+                        )
+                    })
+                    .collect()
+            })
+        })
+    }
+
+    fn insert(&self, query: Query, records: &[Record]) {
+        let rdatas = records
+            .iter()
+            .map(|r| (r.rdata().clone(), r.ttl()))
+            .collect();
+
+        self.cache
+            .lock()
+            .unwrap() // poison errors should panic
+            .insert(query, rdatas, false, Instant::now());
+    }
+
+    /// Caches the non-existence of the records, for the minimum of the SOA if present
+    fn negative(&self, query: Query, response: &Message) -> ResolveError {
+        let soa = response
+            .name_servers()
+            .iter()
+            .find(|r| r.rr_type() == RecordType::SOA)
+            .cloned();
+
+        self.cache
+            .lock()
+            .unwrap() // poison errors should panic
+            .negative(query, soa, response.response_code(), Instant::now())
+    }
+}
+
+fn to_socket_addr(rdata: &RData, port: u16) -> Option<SocketAddr> {
+    match *rdata {
+        RData::A(ip) => Some(SocketAddr::new(IpAddr::V4(ip), port)),
+        RData::AAAA(ip) => Some(SocketAddr::new(IpAddr::V6(ip), port)),
+        _ => None,
+    }
+}
@@ -73,7 +73,11 @@ impl Resolver {
     ///
     /// A new Resolver or an error if there was an error with the configuration.
     pub fn new(config: ResolverConfig, options: ResolverOpts) -> io::Result<Self> {
-        let lru = Arc::new(Mutex::new(DnsLru::new(options.cache_size)));
+        // a background refresh would not outlive the Core of a single lookup
+        let lru = Arc::new(Mutex::new(DnsLru::with_opts(&ResolverOpts {
+            prefetch: None,
+            ..options
+        })));
         Ok(Resolver {
             config,
             options,
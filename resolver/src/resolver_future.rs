@@ -12,18 +12,22 @@ use std::sync::{Arc, Mutex};
 
 use futures::Future;
 use tokio_core::reactor::Handle;
-use trust_dns_proto::op::Message;
+use trust_dns_proto::op::{Message, Query};
 use trust_dns_proto::{BasicDnsHandle, DnsHandle, RetryDnsHandle};
 #[cfg(feature = "dnssec")]
 use trust_dns_proto::SecureDnsHandle;
+#[cfg(feature = "dnssec")]
+use trust_dns_proto::rr::dnssec::TrustAnchor;
 use trust_dns_proto::rr::{IntoName, Name, RecordType};
 
 use config::{ResolverConfig, ResolverOpts};
 use error::*;
 use lookup_state::CachingClient;
-use name_server_pool::{NameServerPool, StandardConnection};
+use name_server_pool::{NameServerPool, NameServerRank, StandardConnection};
 use lookup_ip::{InnerLookupIpFuture, LookupIpFuture};
 use dns_lru::DnsLru;
+#[cfg(feature = "dnssec")]
+use dnssec_cache::DnssecCache;
 use lookup::{self, Lookup};
 use lookup::{InnerLookupFuture, LookupEither, LookupFuture};
 use hosts::{parse_literal_ip, Hosts};
@@ -62,6 +66,8 @@ pub struct ResolverFuture {
     config: ResolverConfig,
     options: ResolverOpts,
     client_cache: CachingClient<LookupEither<BasicResolverHandle, StandardConnection>>,
+    // shared with the client_cache, for the stats of the name servers
+    name_servers: NameServerPool<BasicResolverHandle, StandardConnection>,
     hosts: Option<Arc<Hosts>>,
 }
 
@@ -85,17 +91,6 @@ pub fn $p<N: IntoName>(&self, query: N) -> $f {
     self.inner_lookup(name, $r).into()
 }
     };
-    ($p:ident, $f:ty, $r:path, $t:ty) => {
-/// Performs a lookup for the associated type.
-///
-/// # Arguments
-///
-/// * `query` - a type which can be converted to `Name` via `From`.
-pub fn $p(&self, query: $t) -> $f {
-    let name = Name::from(query);
-    self.inner_lookup(name, $r).into()
-}
-    };
 }
 
 impl ResolverFuture {
@@ -107,7 +102,7 @@ impl ResolverFuture {
     /// * `options` - basic lookup options for the resolver
     /// * `reactor` - the [`tokio_core::Core`] to use with this future
     pub fn new(config: ResolverConfig, options: ResolverOpts, reactor: &Handle) -> Self {
-        let lru = Arc::new(Mutex::new(DnsLru::new(options.cache_size)));
+        let lru = Arc::new(Mutex::new(DnsLru::with_opts(&options)));
 
         Self::with_cache(config, options, lru, reactor)
     }
@@ -126,16 +121,13 @@ impl ResolverFuture {
         lru: Arc<Mutex<DnsLru>>,
         reactor: &Handle,
     ) -> Self {
-        let pool = NameServerPool::<BasicResolverHandle, StandardConnection>::from_config(
-            &config,
-            &options,
-            reactor,
-        );
-        let either;
+        let pool = Self::name_server_pool(&config, &options, reactor);
         let client = RetryDnsHandle::new(pool.clone(), options.attempts);
+        let either;
         if options.validate {
             #[cfg(feature = "dnssec")]
             {
+                let client = DnssecCache::new(options.cache_size, client);
                 either = LookupEither::Secure(SecureDnsHandle::new(client));
             }
 
@@ -149,6 +141,58 @@ impl ResolverFuture {
             either = LookupEither::Retry(client);
         }
 
+        Self::with_client(config, options, lru, pool, either, reactor)
+    }
+
+    /// Construct a new validating ResolverFuture, which trusts only the keys of the anchor.
+    ///
+    /// `options.validate` is always enabled, lookups which can not be validated up to the trust
+    ///  anchor fail with `ResolveErrorKind::Bogus`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - configuration, name_servers, etc. for the Resolver
+    /// * `options` - basic lookup options for the resolver
+    /// * `trust_anchor` - the DNSKEYs to trust, for example to pin the keys of a private zone
+    /// * `reactor` - the [`tokio_core::Core`] to use with this future
+    #[cfg(feature = "dnssec")]
+    pub fn with_trust_anchor(
+        config: ResolverConfig,
+        mut options: ResolverOpts,
+        trust_anchor: TrustAnchor,
+        reactor: &Handle,
+    ) -> Self {
+        options.validate = true;
+
+        let lru = Arc::new(Mutex::new(DnsLru::with_opts(&options)));
+        let pool = Self::name_server_pool(&config, &options, reactor);
+        let client = RetryDnsHandle::new(pool.clone(), options.attempts);
+        let client = DnssecCache::new(options.cache_size, client);
+        let either = LookupEither::Secure(SecureDnsHandle::with_trust_anchor(client, trust_anchor));
+
+        Self::with_client(config, options, lru, pool, either, reactor)
+    }
+
+    fn name_server_pool(
+        config: &ResolverConfig,
+        options: &ResolverOpts,
+        reactor: &Handle,
+    ) -> NameServerPool<BasicResolverHandle, StandardConnection> {
+        NameServerPool::<BasicResolverHandle, StandardConnection>::from_config(
+            config,
+            options,
+            reactor,
+        )
+    }
+
+    fn with_client(
+        config: ResolverConfig,
+        options: ResolverOpts,
+        lru: Arc<Mutex<DnsLru>>,
+        name_servers: NameServerPool<BasicResolverHandle, StandardConnection>,
+        either: LookupEither<BasicResolverHandle, StandardConnection>,
+        reactor: &Handle,
+    ) -> Self {
         let hosts = if options.use_hosts_file {
             Some(Arc::new(Hosts::new()))
         } else {
@@ -158,7 +202,8 @@ impl ResolverFuture {
         ResolverFuture {
             config,
             options,
-            client_cache: CachingClient::with_cache(lru, either),
+            client_cache: CachingClient::with_prefetch(lru, either, reactor),
+            name_servers: name_servers,
             hosts: hosts,
         }
     }
@@ -172,6 +217,12 @@ impl ResolverFuture {
         Ok(Self::new(config, options, reactor))
     }
 
+    /// The performance of each of the name servers, from the most to the least preferred, see
+    ///  `NameServerPool::stats()`
+    pub fn name_server_stats(&self) -> Vec<NameServerRank> {
+        self.name_servers.stats()
+    }
+
     fn push_name(name: Name, names: &mut Vec<Name>) {
         if !names.contains(&name) {
             names.push(name);
@@ -292,12 +343,23 @@ impl ResolverFuture {
         self.srv_lookup(name)
     }
 
-    lookup_fn!(
-        reverse_lookup,
-        lookup::ReverseLookupFuture,
-        RecordType::PTR,
-        IpAddr
-    );
+    /// Performs a lookup for the PTR records of the address, the hosts file is consulted first
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - the address, which is converted to its reverse name via `Name::from`
+    pub fn reverse_lookup(&self, query: IpAddr) -> lookup::ReverseLookupFuture {
+        let name = Name::from(query);
+        if let Some(ref hosts) = self.hosts {
+            let ptr = Query::query(name.clone(), RecordType::PTR);
+            if let Some(lookup) = hosts.lookup_static_host(&ptr) {
+                return InnerLookupFuture::ok(self.client_cache.clone(), lookup).into();
+            }
+        }
+
+        self.inner_lookup(name, RecordType::PTR).into()
+    }
+
     lookup_fn!(ipv4_lookup, lookup::Ipv4LookupFuture, RecordType::A);
     lookup_fn!(ipv6_lookup, lookup::Ipv6LookupFuture, RecordType::AAAA);
     lookup_fn!(mx_lookup, lookup::MxLookupFuture, RecordType::MX);
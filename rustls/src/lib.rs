@@ -25,10 +25,12 @@ extern crate trust_dns_proto;
 
 pub mod tls_client_connection;
 pub mod tls_client_stream;
+pub mod tls_server;
 pub mod tls_stream;
 
 pub use self::tls_client_connection::{TlsClientConnection, TlsClientConnectionBuilder};
 pub use self::tls_client_stream::{TlsClientStream, TlsClientStreamBuilder};
+pub use self::tls_server::TlsServerStream;
 pub use self::tls_stream::{TlsStream, TlsStreamBuilder};
 
 #[cfg(test)]
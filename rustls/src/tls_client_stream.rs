@@ -9,14 +9,14 @@ use std::net::SocketAddr;
 use std::io;
 
 use futures::Future;
-use rustls::{Certificate, ClientSession};
+use rustls::{Certificate, ClientConfig, ClientSession};
 use tokio_core::net::TcpStream as TokioTcpStream;
 use tokio_core::reactor::Handle;
 use tokio_rustls::TlsStream as TokioTlsStream;
 
-use trust_dns::error::ClientError;
 use trust_dns::tcp::TcpClientStream;
 use trust_dns_proto::{BufDnsStreamHandle, DnsStreamHandle};
+use trust_dns_proto::error::FromProtoError;
 
 use TlsStreamBuilder;
 
@@ -30,6 +30,11 @@ impl TlsClientStreamBuilder {
         TlsClientStreamBuilder(TlsStreamBuilder::new())
     }
 
+    /// Creates a builder with the TLS configuration, e.g. with the trusted roots of webpki
+    pub fn with_client_config(client_config: ClientConfig) -> TlsClientStreamBuilder {
+        TlsClientStreamBuilder(TlsStreamBuilder::with_client_config(client_config))
+    }
+
     /// Add a custom trusted peer certificate or certificate auhtority.
     ///
     /// If this is the 'client' then the 'server' must have it associated as it's `identity`, or have had the `identity` signed by this certificate.
@@ -50,16 +55,19 @@ impl TlsClientStreamBuilder {
     /// * `name_server` - IP and Port for the remote DNS resolver
     /// * `dns_name` - The DNS name, Subject Public Key Info (SPKI) name, as associated to a certificate
     /// * `loop_handle` - The reactor Core handle
-    pub fn build(
+    pub fn build<E>(
         self,
         name_server: SocketAddr,
         dns_name: String,
         loop_handle: &Handle,
     ) -> (
         Box<Future<Item = TlsClientStream, Error = io::Error>>,
-        Box<DnsStreamHandle<Error = ClientError>>,
-    ) {
-        let (stream_future, sender) = self.0.build(name_server, dns_name, loop_handle);
+        Box<DnsStreamHandle<Error = E>>,
+    )
+    where
+        E: FromProtoError + 'static,
+    {
+        let (stream_future, sender) = self.0.build_with_error(name_server, dns_name, loop_handle);
 
         let new_future: Box<Future<Item = TlsClientStream, Error = io::Error>> =
             Box::new(stream_future.map(move |tls_stream| TcpClientStream::from_stream(tls_stream)));
@@ -0,0 +1,89 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DNS over TLS server side, with certificates and keys in PEM format
+
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+
+use rustls::internal::pemfile;
+use rustls::sign::RSASigningKey;
+use rustls::{ServerConfig, ServerSession};
+use tokio_core::net::TcpStream as TokioTcpStream;
+use tokio_rustls::TlsStream as TokioTlsStream;
+
+use trust_dns::tcp::TcpStream;
+
+pub use rustls::{Certificate, PrivateKey};
+pub use tokio_rustls::ServerConfigExt;
+
+/// A TLS stream accepted by a server
+pub type TlsServerStream = TcpStream<TokioTlsStream<TokioTcpStream, ServerSession>>;
+
+/// Reads the chain of certificates from a PEM file, the certificate of the server first
+pub fn read_cert(path: &Path) -> Result<Vec<Certificate>, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("error opening cert file: {:?}: {}", path, e))?;
+
+    let certs = pemfile::certs(&mut BufReader::new(file))
+        .map_err(|_| format!("badly formatted PEM certs from: {:?}", path))?;
+    if certs.is_empty() {
+        return Err(format!("no certificates in: {:?}", path));
+    }
+
+    Ok(certs)
+}
+
+/// Reads the private key from a PEM file, either PKCS#8 or PKCS#1, only RSA keys are supported
+pub fn read_key(path: &Path) -> Result<PrivateKey, String> {
+    let mut keys = read_keys(path, pemfile::pkcs8_private_keys)?;
+    if keys.is_empty() {
+        keys = read_keys(path, pemfile::rsa_private_keys)?;
+    }
+
+    keys.into_iter()
+        .next()
+        .ok_or_else(|| format!("no private key in: {:?}", path))
+}
+
+fn read_keys(
+    path: &Path,
+    read: fn(&mut io::BufRead) -> Result<Vec<PrivateKey>, ()>,
+) -> Result<Vec<PrivateKey>, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("error opening private key file: {:?}: {}", path, e))?;
+
+    read(&mut BufReader::new(file))
+        .map_err(|_| format!("badly formatted PEM private key from: {:?}", path))
+}
+
+/// Returns the config for accepting TLS connections with the certificate chain and its key
+///
+/// Only TLS 1.2 and later are offered.
+pub fn new_acceptor(cert_chain: Vec<Certificate>, key: PrivateKey) -> io::Result<Arc<ServerConfig>> {
+    new_config(cert_chain, key).map(Arc::new)
+}
+
+/// Returns the config for accepting TLS connections, which may be amended before it is shared,
+///  e.g. with the ALPN protocols of the server
+pub fn new_config(cert_chain: Vec<Certificate>, key: PrivateKey) -> io::Result<ServerConfig> {
+    // rustls panics on a key which it can not use, check it first
+    RSASigningKey::new(&key).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "tls error: the private key is not a supported RSA key",
+        )
+    })?;
+
+    let mut config = ServerConfig::new();
+    config.set_single_cert(cert_chain, key);
+
+    Ok(config)
+}
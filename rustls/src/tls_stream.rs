@@ -11,7 +11,7 @@ use std::sync::Arc;
 
 use futures::{future, Future, IntoFuture};
 use futures::sync::mpsc::unbounded;
-use rustls::{Certificate, ClientConfig, ClientSession};
+use rustls::{Certificate, ClientConfig, ClientSession, Session};
 use tokio_core::net::TcpStream as TokioTcpStream;
 use tokio_core::reactor::Handle;
 use tokio_rustls::{ClientConfigExt, TlsStream as TokioTlsStream};
@@ -19,12 +19,14 @@ use tokio_rustls::{ClientConfigExt, TlsStream as TokioTlsStream};
 use trust_dns::BufStreamHandle;
 use trust_dns::error::ClientError;
 use trust_dns::tcp::TcpStream;
+use trust_dns_proto::error::FromProtoError;
 
 pub type TlsStream = TcpStream<TokioTlsStream<TokioTcpStream, ClientSession>>;
 
-fn tls_new(certs: &[Certificate] /*, pkcs12: Option<Pkcs12>*/) -> io::Result<Arc<ClientConfig>> {
-    let mut builder = ClientConfig::new();
-
+fn tls_new(
+    mut builder: ClientConfig,
+    certs: &[Certificate], /* pkcs12: Option<Pkcs12> */
+) -> io::Result<Arc<ClientConfig>> {
     // mutate the trust_store
     {
         let trust_store = &mut builder.root_store;
@@ -51,13 +53,17 @@ fn tls_new(certs: &[Certificate] /*, pkcs12: Option<Pkcs12>*/) -> io::Result<Arc
     Ok(Arc::new(builder))
 }
 
-/// Initializes a TlsStream with an existing tokio_tls::TlsStream.
+/// Initializes a TlsStream with an existing tokio_rustls::TlsStream.
 ///
-/// This is intended for use with a TlsListener and Incoming connections
-pub fn tls_from_stream(
-    stream: TokioTlsStream<TokioTcpStream, ClientSession>,
+/// This is intended for use with a TlsListener and Incoming connections, where the session is a
+///  `ServerSession`, see `tls_server::TlsServerStream`
+pub fn tls_from_stream<C: Session>(
+    stream: TokioTlsStream<TokioTcpStream, C>,
     peer_addr: SocketAddr,
-) -> (TlsStream, BufStreamHandle<ClientError>) {
+) -> (
+    TcpStream<TokioTlsStream<TokioTcpStream, C>>,
+    BufStreamHandle<ClientError>,
+) {
     let (message_sender, outbound_messages) = unbounded();
     let message_sender = BufStreamHandle::new(message_sender);
 
@@ -68,6 +74,7 @@ pub fn tls_from_stream(
 
 #[derive(Clone)]
 pub struct TlsStreamBuilder {
+    client_config: ClientConfig,
     ca_chain: Vec<Certificate>,
     //identity: Option<Pkcs12>,
 }
@@ -75,7 +82,14 @@ pub struct TlsStreamBuilder {
 impl TlsStreamBuilder {
     /// Constructs a new TlsStreamBuilder
     pub fn new() -> TlsStreamBuilder {
+        Self::with_client_config(ClientConfig::new())
+    }
+
+    /// Constructs a new TlsStreamBuilder with the TLS configuration, e.g. with the trusted roots
+    ///  of webpki, to which the added certificate authorities are appended
+    pub fn with_client_config(client_config: ClientConfig) -> TlsStreamBuilder {
         TlsStreamBuilder {
+            client_config,
             ca_chain: vec![],
             // identity: None,
         }
@@ -128,11 +142,27 @@ impl TlsStreamBuilder {
     ) -> (
         Box<Future<Item = TlsStream, Error = io::Error>>,
         BufStreamHandle<ClientError>,
+    ) {
+        self.build_with_error(name_server, dns_name, loop_handle)
+    }
+
+    /// See `build`, the errors of the returned handle are of the type of its user
+    pub(crate) fn build_with_error<E: FromProtoError>(
+        self,
+        name_server: SocketAddr,
+        dns_name: String,
+        loop_handle: &Handle,
+    ) -> (
+        Box<Future<Item = TlsStream, Error = io::Error>>,
+        BufStreamHandle<E>,
     ) {
         let (message_sender, outbound_messages) = unbounded();
         let message_sender = BufStreamHandle::new(message_sender);
 
-        let tls_connector = match ::tls_stream::tls_new(&self.ca_chain /*, self.identity*/) {
+        let tls_connector = match ::tls_stream::tls_new(
+            self.client_config,
+            &self.ca_chain, /* self.identity */
+        ) {
             Ok(c) => c,
             Err(e) => {
                 return (
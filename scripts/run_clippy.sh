@@ -72,6 +72,13 @@ cargo clippy --all-features -- \
     --allow module_inception
 popd
 
+pushd https
+cargo clippy --all-features -- \
+    --allow type_complexity \
+    --allow doc_markdown \
+    --allow module_inception
+popd
+
 pushd server
 cargo clippy --all-features -- \
     --allow type_complexity \
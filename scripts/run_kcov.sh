@@ -34,7 +34,7 @@ mkdir -p target
 export TDNS_SERVER_SRC_ROOT=./server
 export COVERALLS_PARALLEL=true
 
-SRC_PATHS=client/src,native-tls/src,openssl/src,proto/src,resolver/src,rustls/src,server/src
+SRC_PATHS=client/src,https/src,native-tls/src,openssl/src,proto/src,resolver/src,rustls/src,server/src
 EXCLUDE_PATHS=client/src/error,proto/src/error.rs,server/src/error,compatibility-tests/src/lib.rs
 
 for i in target/debug/deps/trust_dns*-* target/debug/deps/*_tests-* ; do
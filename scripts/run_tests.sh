@@ -1,6 +1,6 @@
 #!/bin/bash -e
 
-MODULES=${MODULES:-"client integration-tests native-tls openssl proto resolver rustls server"}
+MODULES=${MODULES:-"client https integration-tests native-tls openssl proto resolver rustls server"}
 CLIENT_OPTIONS=${CLIENT_OPTIONS} # add in all features
 OPTIONS=${OPTIONS}
 
@@ -0,0 +1,198 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Access control lists, the networks from which requests to a zone are allowed
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use trust_dns_proto::error::{ProtoError, ProtoErrorKind, ProtoResult};
+
+/// A network of addresses in CIDR notation, e.g. `192.0.2.0/24` or `2001:db8::/32`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Network {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl Network {
+    /// Returns a new network
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - the address of the network, bits beyond the prefix are ignored
+    /// * `prefix_len` - the number of leading bits of `addr` which make up the network, at most
+    ///                  32 for IPv4 and 128 for IPv6
+    pub fn new(addr: IpAddr, prefix_len: u8) -> ProtoResult<Self> {
+        let max_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+
+        if prefix_len > max_len {
+            return Err(ProtoErrorKind::Msg(format!("bad prefix length for {}: {}", addr, prefix_len))
+                .into());
+        }
+
+        Ok(Network {
+            addr: addr,
+            prefix_len: prefix_len,
+        })
+    }
+
+    /// The network which is the single address
+    pub fn host(addr: IpAddr) -> Self {
+        let prefix_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+
+        Network {
+            addr: addr,
+            prefix_len: prefix_len,
+        }
+    }
+
+    /// The address of the network
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// The number of leading bits which make up the network
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns true if the address is within the network, IPv4 addresses are never within an IPv6
+    ///  network nor the reverse
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        match (self.addr, *addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                prefix_matches(&network.octets(), &addr.octets(), self.prefix_len)
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                prefix_matches(&network.octets(), &addr.octets(), self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns true if the leading `prefix_len` bits of the addresses are equal
+fn prefix_matches(network: &[u8], addr: &[u8], prefix_len: u8) -> bool {
+    let whole_octets = (prefix_len / 8) as usize;
+    if network[..whole_octets] != addr[..whole_octets] {
+        return false;
+    }
+
+    let remaining_bits = prefix_len % 8;
+    if remaining_bits == 0 {
+        return true;
+    }
+
+    let mask = 0xFF_u8 << (8 - remaining_bits);
+    network[whole_octets] & mask == addr[whole_octets] & mask
+}
+
+impl FromStr for Network {
+    type Err = ProtoError;
+
+    /// Parses the network from CIDR notation, a bare address is a network of just that address
+    fn from_str(s: &str) -> ProtoResult<Self> {
+        let mut parts = s.splitn(2, '/');
+        let addr: IpAddr = parts
+            .next()
+            .unwrap_or("")
+            .parse()
+            .map_err(|_| ProtoErrorKind::Msg(format!("bad network address: {}", s)))?;
+
+        match parts.next() {
+            Some(prefix_len) => {
+                let prefix_len = prefix_len
+                    .parse()
+                    .map_err(|_| ProtoErrorKind::Msg(format!("bad network prefix: {}", s)))?;
+                Network::new(addr, prefix_len)
+            }
+            None => Ok(Network::host(addr)),
+        }
+    }
+}
+
+impl From<IpAddr> for Network {
+    fn from(addr: IpAddr) -> Self {
+        Network::host(addr)
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+/// An access control list, the networks from which a request is allowed
+///
+/// An empty list allows no address, see `Acl::any()` for the list which allows all addresses.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Acl {
+    networks: Vec<Network>,
+}
+
+impl Acl {
+    /// Returns a list which allows the addresses of the networks
+    pub fn new(networks: Vec<Network>) -> Self {
+        Acl { networks: networks }
+    }
+
+    /// Returns a list which allows no address
+    pub fn none() -> Self {
+        Acl::default()
+    }
+
+    /// Returns a list which allows all addresses, IPv4 and IPv6
+    pub fn any() -> Self {
+        Acl::new(vec![
+            Network {
+                addr: IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+                prefix_len: 0,
+            },
+            Network {
+                addr: IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0)),
+                prefix_len: 0,
+            },
+        ])
+    }
+
+    /// The networks which are allowed
+    pub fn networks(&self) -> &[Network] {
+        &self.networks
+    }
+
+    /// Returns true if no address is allowed
+    pub fn is_empty(&self) -> bool {
+        self.networks.is_empty()
+    }
+
+    /// Returns true if the address is within any of the networks
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        self.networks.iter().any(|network| network.contains(addr))
+    }
+}
+
+impl From<Vec<IpAddr>> for Acl {
+    /// The list which allows each of the addresses
+    fn from(addrs: Vec<IpAddr>) -> Self {
+        Acl::new(addrs.into_iter().map(Network::host).collect())
+    }
+}
+
+impl From<Vec<Network>> for Acl {
+    fn from(networks: Vec<Network>) -> Self {
+        Acl::new(networks)
+    }
+}
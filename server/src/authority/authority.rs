@@ -8,21 +8,51 @@
 //! All authority related types
 
 use std::borrow::Borrow;
-use std::collections::BTreeMap;
+#[cfg(feature = "dnssec")]
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
+use std::net::{IpAddr, SocketAddr};
+#[cfg(feature = "dnssec")]
+use std::sync::Arc;
+#[cfg(feature = "dnssec")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::sync::mpsc::UnboundedSender;
 
 #[cfg(feature = "dnssec")]
 use trust_dns::error::*;
 use trust_dns::op::{LowerQuery, ResponseCode};
 use trust_dns::rr::{DNSClass, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey};
-use trust_dns::rr::dnssec::{Signer, SupportedAlgorithms};
+use trust_dns::rr::dnssec::{Signer, SupportedAlgorithms, TSigner};
+use trust_dns::rr::rdata::NSEC3PARAM;
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::rdata::DNSKEY;
+use trust_dns::rr::rdata::tsig::{self, TsigAlgorithm};
 
-use authority::{AuthLookup, Journal, MessageRequest, UpdateResult, ZoneType};
+use authority::{Acl, AuthLookup, Forwarder, Journal, MessageRequest, Notifier, UpdateResult,
+                ZoneDiff, ZoneType};
 #[cfg(feature = "dnssec")]
-use authority::UpdateRequest;
+use authority::{KeyTiming, UpdateRequest};
 
 use error::{PersistenceErrorKind, PersistenceResult};
 
 
+/// Signatures are back-dated by default, for resolvers with a clock behind that of the server
+#[cfg(feature = "dnssec")]
+const DEFAULT_SIG_INCEPTION: u64 = 60 * 60;
+/// Signatures are refreshed by default when they are due to expire within a week
+#[cfg(feature = "dnssec")]
+const DEFAULT_SIG_REFRESH: u64 = 7 * 24 * 60 * 60;
+/// The expirations of the signatures are spread over a day by default
+#[cfg(feature = "dnssec")]
+const DEFAULT_SIG_JITTER: u64 = 24 * 60 * 60;
+
+/// The source of the current time, in seconds since the unix epoch, for the key states and the
+///  signatures of a zone, see `Authority::set_clock()`
+#[cfg(feature = "dnssec")]
+pub type Clock = Arc<Fn() -> u64 + Send + Sync>;
+
 /// Authority is responsible for storing the resource records for a particular zone.
 ///
 /// Authorities default to DNSClass IN. The ZoneType specifies if this should be treated as the
@@ -33,7 +63,16 @@ pub struct Authority {
     journal: Option<Journal>,
     records: BTreeMap<RrKey, RecordSet>,
     zone_type: ZoneType,
+    allow_query: Acl,
     allow_update: bool,
+    allow_update_from: Acl,
+    allow_transfer: Acl,
+    allow_notify: Acl,
+    also_notify: Vec<SocketAddr>,
+    notifier: Option<Notifier>,
+    refresh_trigger: Option<UnboundedSender<()>>,
+    forwarder: Option<Forwarder>,
+    is_expired: bool,
     is_dnssec_enabled: bool,
     // Private key mapped to the Record of the DNSKey
     //  TODO: these private_keys should be stored securely. Ideally, we have keys only stored per
@@ -41,6 +80,18 @@ pub struct Authority {
     //   may not support dynamic updates to register the new key... Trust-DNS will provide support
     //   for this, in some form, perhaps alternate root zones...
     secure_keys: Vec<Signer>,
+    // the timing of each of the secure_keys, in the same order
+    #[cfg(feature = "dnssec")]
+    key_timings: Vec<KeyTiming>,
+    #[cfg(feature = "dnssec")]
+    keys_rolled_at: u64,
+    nsec3param: Option<NSEC3PARAM>,
+    #[cfg(feature = "dnssec")]
+    signature_times: SignatureTimes,
+    #[cfg(feature = "dnssec")]
+    clock: Clock,
+    tsig_keys: Vec<TSigner>,
+    transfer_tsig_keys: Vec<TSigner>,
 }
 
 impl Authority {
@@ -72,38 +123,84 @@ impl Authority {
             journal: None,
             records: records,
             zone_type: zone_type,
+            allow_query: Acl::any(),
             allow_update: allow_update,
+            allow_update_from: Acl::any(),
+            allow_transfer: Acl::none(),
+            allow_notify: Acl::none(),
+            also_notify: Vec::new(),
+            notifier: None,
+            refresh_trigger: None,
+            forwarder: None,
+            is_expired: false,
             is_dnssec_enabled: is_dnssec_enabled,
             secure_keys: Vec::new(),
+            #[cfg(feature = "dnssec")]
+            key_timings: Vec::new(),
+            #[cfg(feature = "dnssec")]
+            keys_rolled_at: 0,
+            nsec3param: None,
+            #[cfg(feature = "dnssec")]
+            signature_times: SignatureTimes::default(),
+            #[cfg(feature = "dnssec")]
+            clock: Arc::new(unix_now),
+            tsig_keys: Vec::new(),
+            transfer_tsig_keys: Vec::new(),
         }
     }
 
     /// By adding a secure key, this will implicitly enable dnssec for the zone.
     ///
+    /// The key is published and active from the start, and never retired, see
+    ///  `add_secure_key_with_timing()`.
+    ///
     /// # Arguments
     ///
     /// * `signer` - Signer with associated private key
     #[cfg(feature = "dnssec")]
     pub fn add_secure_key(&mut self, signer: Signer) -> DnsSecResult<()> {
-        use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType};
-
-        // also add the key to the zone
-        let zone_ttl = self.minimum_ttl();
-        let dnskey = signer.key().to_dnskey(signer.algorithm())?;
-        let dnskey = Record::from_rdata(
-            self.origin.clone().into(),
-            zone_ttl,
-            RecordType::DNSSEC(DNSSECRecordType::DNSKEY),
-            RData::DNSSEC(DNSSECRData::DNSKEY(dnskey)),
-        );
+        self.add_secure_key_with_timing(signer, KeyTiming::default())
+    }
 
-        // TODO: also generate the CDS and CDNSKEY
-        let serial = self.serial();
-        self.upsert(dnskey, serial);
+    /// Adds a secure key, which is published, signs and is removed from the zone at the times of
+    ///  `timing`, see `roll_keys()`
+    ///
+    /// A key with the Secure Entry Point flag on its DNSKEY is a KSK, it signs the DNSKEY, CDS and
+    ///  CDNSKEY records of the zone, the other keys are ZSKs which sign the rest of the zone. When
+    ///  there is no active key of one kind, the keys of the other sign in its place.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - Signer with associated private key
+    /// * `timing` - when the key is published, activated, retired and removed
+    #[cfg(feature = "dnssec")]
+    pub fn add_secure_key_with_timing(
+        &mut self,
+        signer: Signer,
+        timing: KeyTiming,
+    ) -> DnsSecResult<()> {
         self.secure_keys.push(signer);
+        self.key_timings.push(timing);
+
+        let now = self.now();
+        self.publish_keys(now)?;
+        self.keys_rolled_at = now;
         Ok(())
     }
 
+    /// Replaces the source of the current time, the system clock by default, e.g. to test the
+    ///  rollover of keys
+    #[cfg(feature = "dnssec")]
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    /// The current time, in seconds since the unix epoch, from the clock of the zone
+    #[cfg(feature = "dnssec")]
+    fn now(&self) -> u64 {
+        (self.clock)()
+    }
+
     /// Recovers the zone from a Journal, returns an error on failure to recover the zone.
     ///
     /// # Arguments
@@ -144,12 +241,21 @@ impl Authority {
             journal.insert_record(serial, Record::new().set_rr_type(RecordType::AXFR))?;
 
             for rr_set in self.records.values() {
+                // the SOA is written last, it marks the end of this version of the zone
+                if rr_set.record_type() == RecordType::SOA {
+                    continue;
+                }
+
                 // TODO: should we preserve rr_sets or not?
                 for record in rr_set.iter() {
                     journal.insert_record(serial, record)?;
                 }
             }
 
+            for soa in self.soa().iter() {
+                journal.insert_record(serial, soa)?;
+            }
+
             // TODO: COMMIT THE TRANSACTION!!!
         }
 
@@ -166,16 +272,331 @@ impl Authority {
         self.journal.as_ref()
     }
 
+    /// Specifies the networks from which queries of this zone are answered
+    ///
+    /// By default queries from all addresses are answered.
+    pub fn set_allow_query(&mut self, allow_query: Acl) {
+        self.allow_query = allow_query;
+    }
+
+    /// Returns true if queries from the source address should be answered
+    pub fn is_query_allowed(&self, src: &IpAddr) -> bool {
+        self.allow_query.contains(src)
+    }
+
     /// Enables the zone for dynamic DNS updates
     pub fn set_allow_update(&mut self, allow_update: bool) {
         self.allow_update = allow_update;
     }
 
+    /// Specifies the networks from which dynamic updates of this zone are accepted
+    ///
+    /// By default updates are accepted from all addresses. This only restricts where updates may
+    ///  come from, the updates must still be enabled and signed with a key of the zone, see
+    ///  `authorize()`.
+    pub fn set_allow_update_from(&mut self, allow_update_from: Acl) {
+        self.allow_update_from = allow_update_from;
+    }
+
+    /// Returns true if an update from the source address should be considered
+    pub fn is_update_allowed(&self, src: &IpAddr) -> bool {
+        self.allow_update_from.contains(src)
+    }
+
+    /// Specifies the networks which are allowed to perform zone transfers, AXFR and IXFR, of this
+    ///  zone
+    ///
+    /// By default no address is allowed to transfer the zone. Transfers from these networks must
+    ///  also be authorized by their key, see `authorize_transfer()`.
+    pub fn set_allow_transfer(&mut self, allow_transfer: Acl) {
+        self.allow_transfer = allow_transfer;
+    }
+
+    /// Returns true if the source address is allowed to transfer this zone
+    pub fn is_transfer_allowed(&self, src: &IpAddr) -> bool {
+        self.allow_transfer.contains(src)
+    }
+
+    /// Adds a TSIG key, RFC 8945, requests signed with the key may update the zone
+    pub fn add_tsig_key(&mut self, signer: TSigner) {
+        self.tsig_keys.push(signer);
+    }
+
+    /// Adds a TSIG key, RFC 8945, with which transfers of the zone must be signed
+    pub fn add_transfer_tsig_key(&mut self, signer: TSigner) {
+        self.transfer_tsig_keys.push(signer);
+    }
+
+    /// Returns true if transfers of the zone must be signed with one of its transfer keys
+    pub fn is_transfer_signed(&self) -> bool {
+        !self.transfer_tsig_keys.is_empty()
+    }
+
+    /// Returns true if the key is one of the update keys of the zone, see `add_tsig_key()`
+    pub fn is_update_key(&self, signer: &TSigner) -> bool {
+        is_tsig_key_in(&self.tsig_keys, signer)
+    }
+
+    /// Returns true if the key is one of the transfer keys of the zone, see
+    ///  `add_transfer_tsig_key()`
+    pub fn is_transfer_key(&self, signer: &TSigner) -> bool {
+        is_tsig_key_in(&self.transfer_tsig_keys, signer)
+    }
+
+    /// Returns the TSIG key of the zone, update or transfer key, with the name and algorithm
+    pub fn find_tsig_key(&self, key_name: &Name, algorithm: &TsigAlgorithm) -> Option<&TSigner> {
+        self.tsig_keys
+            .iter()
+            .chain(self.transfer_tsig_keys.iter())
+            .find(|key| key.key_name() == key_name && key.algorithm() == algorithm)
+    }
+
+    /// Verifies the TSIG of the request with the TSIG keys of the zone, update and transfer keys
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the request, which must be signed with TSIG
+    /// * `now` - the current time, seconds since the epoch
+    ///
+    /// # Return value
+    ///
+    /// The key the request is signed with, or the TSIG error, `BADKEY`, `BADSIG` or `BADTIME`
+    pub fn verify_tsig(&self, request: &MessageRequest, now: u64) -> Result<&TSigner, ResponseCode> {
+        let (message, record) = match tsig::split_message(request.as_bytes()) {
+            Ok(Some(signed)) => signed,
+            Ok(None) => return Err(ResponseCode::FormErr),
+            Err(e) => {
+                warn!("could not read TSIG of request id {}: {}", request.id(), e);
+                return Err(ResponseCode::FormErr);
+            }
+        };
+
+        let tsig = match *record.rdata() {
+            RData::TSIG(ref tsig) => tsig,
+            _ => return Err(ResponseCode::FormErr),
+        };
+
+        let signer = match self.find_tsig_key(record.name(), tsig.algorithm()) {
+            Some(signer) => signer,
+            None => {
+                warn!("unknown TSIG key {} for zone {}", record.name(), self.origin);
+                return Err(ResponseCode::BADKEY);
+            }
+        };
+
+        if let Err(e) = signer.verify_message(&message, &[], &record, true) {
+            warn!("TSIG of request id {} did not verify: {}", request.id(), e);
+            return Err(ResponseCode::BADSIG);
+        }
+
+        if !tsig.is_current(now) {
+            warn!("TSIG of request id {} is not current", request.id());
+            return Err(ResponseCode::BADTIME);
+        }
+
+        Ok(signer)
+    }
+
+    /// Checks the key of a zone transfer, AXFR or IXFR, request
+    ///
+    /// Like updates, see `authorize()`, transfers are checked by both the ACL of the source address,
+    ///  see `is_transfer_allowed()`, and the key the request is signed with. A signed transfer must
+    ///  be signed with one of the transfer keys of the zone, and if the zone has any, unsigned
+    ///  transfers are refused.
+    pub fn authorize_transfer(&self, transfer_message: &MessageRequest) -> UpdateResult<()> {
+        if transfer_message.tsig().is_none() {
+            if self.is_transfer_signed() {
+                warn!("unsigned transfer of zone: {}", self.origin);
+                return Err(ResponseCode::Refused);
+            }
+
+            return Ok(());
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| ResponseCode::ServFail)?
+            .as_secs();
+
+        let signer = self.verify_tsig(transfer_message, now)?;
+        if !self.is_transfer_key(signer) {
+            warn!(
+                "TSIG key {} may not transfer zone: {}",
+                signer.key_name(),
+                self.origin
+            );
+            return Err(ResponseCode::Refused);
+        }
+
+        Ok(())
+    }
+
+    /// Specifies the networks from which NOTIFY messages are accepted for this zone, i.e. the
+    ///  masters of a Slave zone
+    pub fn set_allow_notify(&mut self, allow_notify: Acl) {
+        self.allow_notify = allow_notify;
+    }
+
+    /// Returns true if a NOTIFY from the source address should be accepted
+    pub fn is_notify_allowed(&self, src: &IpAddr) -> bool {
+        self.allow_notify.contains(src)
+    }
+
+    /// Additional addresses to NOTIFY of changes to the zone, beyond the name servers of the zone
+    pub fn set_also_notify(&mut self, also_notify: Vec<SocketAddr>) {
+        self.also_notify = also_notify;
+    }
+
+    /// Associates the Notifier used to send NOTIFY messages to the slaves of the zone
+    pub fn set_notifier(&mut self, notifier: Notifier) {
+        self.notifier = Some(notifier);
+    }
+
+    /// Sends a NOTIFY, RFC 1996, of the current SOA to the slaves of the zone
+    ///
+    /// The slaves are the name servers of the zone, with the exception of the primary master named
+    ///  in the SOA, and the `also_notify` addresses. Only name servers with addresses in the zone
+    ///  are known, any others must be listed in `also_notify`. Does nothing if there is no
+    ///  associated Notifier.
+    pub fn notify_slaves(&self) {
+        let notifier = match self.notifier {
+            Some(ref notifier) => notifier,
+            None => return,
+        };
+
+        let soa = match self.soa().iter().next() {
+            Some(soa) => soa.clone(),
+            None => return,
+        };
+
+        let targets = self.notify_targets(&soa);
+        if targets.is_empty() {
+            debug!("no slaves to notify for: {}", self.origin);
+            return;
+        }
+
+        notifier.notify(soa, targets);
+    }
+
+    fn notify_targets(&self, soa: &Record) -> Vec<SocketAddr> {
+        let mname = if let RData::SOA(ref soa) = *soa.rdata() {
+            Some(soa.mname())
+        } else {
+            None
+        };
+
+        let mut targets = self.also_notify.clone();
+        for ns in self.lookup(&self.origin, RecordType::NS, false, SupportedAlgorithms::new())
+            .iter()
+        {
+            let ns_name = match *ns.rdata() {
+                RData::NS(ref ns_name) if Some(ns_name) != mname => LowerName::new(ns_name),
+                _ => continue,
+            };
+
+            for record_type in &[RecordType::A, RecordType::AAAA] {
+                let addrs = self.lookup(&ns_name, *record_type, false, SupportedAlgorithms::new());
+                for addr in addrs.iter() {
+                    let ip = match *addr.rdata() {
+                        RData::A(ip) => IpAddr::V4(ip),
+                        RData::AAAA(ip) => IpAddr::V6(ip),
+                        _ => continue,
+                    };
+
+                    let target = SocketAddr::new(ip, 53);
+                    if !targets.contains(&target) {
+                        targets.push(target);
+                    }
+                }
+            }
+        }
+
+        targets
+    }
+
+    /// Registers the sender with which an immediate refresh of a Slave zone is requested, see
+    ///  `SlaveZone`
+    pub fn set_refresh_trigger(&mut self, refresh_trigger: UnboundedSender<()>) {
+        self.refresh_trigger = Some(refresh_trigger);
+    }
+
+    /// Requests an immediate refresh of a Slave zone from its masters, e.g. on NOTIFY
+    ///
+    /// Returns false if nothing is refreshing the zone.
+    pub fn trigger_refresh(&self) -> bool {
+        self.refresh_trigger
+            .as_ref()
+            .map_or(false, |trigger| trigger.unbounded_send(()).is_ok())
+    }
+
+    /// Associates the Forwarder which resolves the requests of a Forward or Hint zone
+    pub fn set_forwarder(&mut self, forwarder: Forwarder) {
+        self.forwarder = Some(forwarder);
+    }
+
+    /// Returns the Forwarder of a Forward or Hint zone, if one has been associated
+    pub fn forwarder(&self) -> Option<&Forwarder> {
+        self.forwarder.as_ref()
+    }
+
+    /// Marks the zone as expired, e.g. a Slave zone which could not be refreshed from its masters
+    ///  before the SOA expire time elapsed. Expired zones are not served.
+    pub fn set_expired(&mut self, is_expired: bool) {
+        self.is_expired = is_expired;
+    }
+
+    /// Returns true if the zone has expired, and should not be answered for
+    pub fn is_expired(&self) -> bool {
+        self.is_expired
+    }
+
     /// Retrieve the Signer, which contains the private keys, for this zone
     pub fn secure_keys(&self) -> &[Signer] {
         &self.secure_keys
     }
 
+    /// True if DNSSEC is enabled for the zone but it has no keys, i.e. the RRSIGs, NSEC or NSEC3
+    ///  and DNSKEY records were loaded from the zone file and are served as they are
+    pub fn is_signed_offline(&self) -> bool {
+        self.is_dnssec_enabled && self.secure_keys.is_empty()
+    }
+
+    /// Denies existence with an NSEC3 chain of these parameters, RFC 5155, rather than NSEC
+    ///
+    /// The chain is generated by `secure_zone()`, with Opt-Out the unsigned delegations are left
+    ///  out of it.
+    pub fn set_nsec3param(&mut self, nsec3param: Option<NSEC3PARAM>) {
+        self.nsec3param = nsec3param;
+    }
+
+    /// The NSEC3 parameters of the zone, if existence is denied with NSEC3
+    pub fn nsec3param(&self) -> Option<&NSEC3PARAM> {
+        self.nsec3param.as_ref()
+    }
+
+    /// Signatures are made with an inception this far in the past, for resolvers with a clock
+    ///  behind that of the server, 1 hour by default
+    #[cfg(feature = "dnssec")]
+    pub fn set_signature_inception(&mut self, inception: Duration) {
+        self.signature_times.inception = inception;
+    }
+
+    /// Record sets with RRSIGs which expire within this are signed again, 7 days by default, see
+    ///  `resign_expiring()`
+    ///
+    /// The window is at most half of the validity of the signatures, `Signer::sig_duration()`.
+    #[cfg(feature = "dnssec")]
+    pub fn set_signature_refresh(&mut self, refresh: Duration) {
+        self.signature_times.refresh = refresh;
+    }
+
+    /// The expiration of each RRSIG is brought forward by a random amount up to this, so that the
+    ///  signatures of the zone do not all expire at once, 1 day by default
+    #[cfg(feature = "dnssec")]
+    pub fn set_signature_jitter(&mut self, jitter: Duration) {
+        self.signature_times.jitter = jitter;
+    }
+
     /// Get the origin of this zone, i.e. example.com is the origin for www.example.com
     pub fn origin(&self) -> &LowerName {
         &self.origin
@@ -263,6 +684,13 @@ impl Authority {
         });
 
         if let Some((soa, serial)) = opt_soa_serial {
+            // the new SOA terminates the update in the journal, see `journal_diffs()`
+            if let Some(ref journal) = self.journal {
+                if let Err(error) = journal.insert_record(serial, &soa) {
+                    error!("could not persist soa: {}", error);
+                }
+            }
+
             self.upsert(soa, serial);
             serial
         } else {
@@ -492,7 +920,34 @@ impl Authority {
             return Err(ResponseCode::Refused);
         }
 
-        // verify sig0, currently the only authorization that is accepted.
+        // without the keys the zone can not be signed again after the update
+        if self.is_signed_offline() {
+            warn!("update attempted on zone signed offline: {}", self.origin);
+            return Err(ResponseCode::Refused);
+        }
+
+        // a TSIG signed update is authorized by the key of the zone it is signed with
+        if update_message.tsig().is_some() {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|_| ResponseCode::ServFail)?
+                .as_secs();
+
+            let signer = self.verify_tsig(update_message, now)?;
+            if !self.is_update_key(signer) {
+                warn!(
+                    "TSIG key {} may not update zone: {}",
+                    signer.key_name(),
+                    self.origin
+                );
+                return Err(ResponseCode::Refused);
+            }
+
+            info!("verified TSIG with key: {}", signer.key_name());
+            return Ok(());
+        }
+
+        // otherwise verify sig0
         let sig0s: &[Record] = update_message.sig0();
         debug!("authorizing with: {:?}", sig0s);
         if !sig0s.is_empty()
@@ -945,7 +1400,7 @@ impl Authority {
         if RecordType::AXFR == record_type {
             match self.zone_type() {
                 ZoneType::Master | ZoneType::Slave => (),
-                // TODO: Forward?
+                // Forward zones have no records of their own, see `Forwarder`
                 _ => return AuthLookup::NoName, // TODO: this sould be an error.
             }
         }
@@ -958,6 +1413,14 @@ impl Authority {
             self.lookup(lookup_name, record_type, is_secure, supported_algorithms)
         };
 
+        // an alias is the answer for all other types, RFC 1034 section 4.3.2, step 3.a
+        if let AuthLookup::NameExists = query_result {
+            match record_type {
+                RecordType::CNAME | RecordType::ANY | RecordType::AXFR | RecordType::SOA => (),
+                _ => return self.alias(lookup_name, record_type, is_secure, supported_algorithms),
+            }
+        }
+
         if RecordType::AXFR == record_type {
             let lookup = self.soa();
             match lookup {
@@ -983,83 +1446,464 @@ impl Authority {
         query_result
     }
 
-    /// Looks up all Resource Records matching the giving `Name` and `RecordType`.
-    ///
-    /// # Arguments
-    ///
-    /// * `name` - The `Name`, label, to lookup.
-    /// * `rtype` - The `RecordType`, to lookup. `RecordType::ANY` will return all records matching
-    ///             `name`. `RecordType::AXFR` will return all record types except `RecordType::SOA`
-    ///             due to the requirements that on zone transfers the `RecordType::SOA` must both
-    ///             preceed and follow all other records.
-    /// * `is_secure` - If the DO bit is set on the EDNS OPT record, then return RRSIGs as well.
-    ///
-    /// # Return value
-    ///
-    /// None if there are no matching records, otherwise a `Vec` containing the found records.
-    pub fn lookup<'s>(
+    /// Returns the chain of CNAMEs from the name, followed by the records of the canonical name if
+    ///  it is in this zone, NameExists if the name is not an alias
+    fn alias<'s>(
         &'s self,
         name: &LowerName,
         rtype: RecordType,
         is_secure: bool,
         supported_algorithms: SupportedAlgorithms,
     ) -> AuthLookup<'s> {
-        let rr_key = RrKey::new(name.clone(), rtype);
+        let mut chain: Vec<&Record> = vec![];
+        let mut name = name.clone();
+
+        // bounded, in case the aliases form a loop
+        for _ in 0..8 {
+            let cnames = match self.lookup(&name, RecordType::CNAME, is_secure, supported_algorithms)
+            {
+                AuthLookup::Records(cnames) => cnames,
+                _ => break,
+            };
 
-        // Collect the records from each rr_set
-        let result: Vec<&Record> = match rtype {
-            RecordType::ANY | RecordType::AXFR => self.records
-                .values()
-                .filter(|rr_set| {
-                    rtype == RecordType::ANY || rr_set.record_type() != RecordType::SOA
+            let target = cnames
+                .iter()
+                .filter_map(|record| match *record.rdata() {
+                    RData::CNAME(ref target) => Some(LowerName::new(target)),
+                    _ => None,
                 })
-                .filter(|rr_set| rtype == RecordType::AXFR || &LowerName::new(rr_set.name()) == name)
-                .fold(Vec::<&Record>::new(), |mut vec, rr_set| {
-                    vec.append(&mut rr_set.records(is_secure, supported_algorithms));
-                    vec
-                }),
-            _ => self.records.get(&rr_key).map_or(vec![], |rr_set| {
-                rr_set
-                    .records(is_secure, supported_algorithms)
-                    .into_iter()
-                    .collect()
-            }),
-        };
+                .next();
+            chain.extend(cnames);
 
-        // This is annoying. The 1035 spec literally specifies that most DNS authorities would want to store
-        //   records in a list except when there are a lot of records. But this makes indexed lookups by name+type
-        //   always return empty sets. This is only important in the negative case, where other DNS authorities
-        //   generally return NoError and no results when other types exist at the same name. bah.
-        if result.is_empty() {
-            if self.records.keys().any(|key| key.name() == name) {
-                return AuthLookup::NameExists;
-            } else {
-                return AuthLookup::NoName;
+            // the resolver continues with targets outside of this zone
+            let target = match target {
+                Some(ref target) if self.origin.zone_of(target) => target.clone(),
+                _ => break,
+            };
+
+            if let AuthLookup::Records(records) =
+                self.lookup(&target, rtype, is_secure, supported_algorithms)
+            {
+                chain.extend(records);
+                break;
             }
+
+            name = target;
         }
 
-        AuthLookup::Records(result)
+        if chain.is_empty() {
+            AuthLookup::NameExists
+        } else {
+            AuthLookup::Records(chain)
+        }
     }
 
-    /// Return the NSEC records based on the given name
+    /// Returns the full set of records for a zone transfer, [RFC 5936](https://tools.ietf.org/html/rfc5936)
     ///
-    /// # Arguments
+    /// ```text
+    /// 2.2.  AXFR Response
+    ///
+    ///    The AXFR response will consist of one or more messages.  The special
+    ///    case of a server closing the TCP connection without sending an AXFR
+    ///    response is covered in section 2.3.
+    ///
+    ///    An AXFR response that is transferring the zone's contents will
+    ///    consist of a series (which could be a series of length 1) of DNS
+    ///    messages.  In such a series, the first message MUST begin with the
+    ///    SOA resource record of the zone, and the last message MUST conclude
+    ///    with the same SOA resource record.  Intermediate messages MUST NOT
+    ///    contain the SOA resource record.
+    /// ```
     ///
-    /// * `name` - given this name (i.e. the lookup name), return the NSEC record that is less than
-    ///            this
-    /// * `is_secure` - if true then it will return RRSIG records as well
-    pub fn get_nsec_records(
-        &self,
-        name: &LowerName,
-        is_secure: bool,
-        supported_algorithms: SupportedAlgorithms,
-    ) -> Vec<&Record> {
-        #[cfg(feature = "dnssec")]
-        fn is_nsec_rrset(rr_set: &RecordSet) -> bool {
-            use trust_dns::rr::rdata::DNSSECRecordType;
-
-            rr_set.record_type() == RecordType::DNSSEC(DNSSECRecordType::NSEC)
-        }
+    /// All RRSIGs, including those covering the SOA, are returned between the two SOA records. If
+    ///  the zone is signed, the NSEC records are part of the zone and are returned as well.
+    ///
+    /// # Return value
+    ///
+    /// None if the zone does not have an SOA, otherwise the ordered set of records to transfer.
+    pub fn transfer_records(&self) -> Option<Vec<&Record>> {
+        let soa = match self.soa() {
+            AuthLookup::Records(soa) => *soa.first()?,
+            AuthLookup::NameExists | AuthLookup::NoName => return None,
+        };
+
+        let mut records: Vec<&Record> = vec![soa];
+        for rr_set in self.records.values() {
+            // the SOA itself is only sent at the beginning and end, though its RRSIGs are not.
+            //  an empty SupportedAlgorithms returns the RRSIGs for all algorithms.
+            records.extend(
+                rr_set
+                    .records(true, SupportedAlgorithms::new())
+                    .into_iter()
+                    .filter(|r| r.rr_type() != RecordType::SOA),
+            );
+        }
+        records.push(soa);
+
+        Some(records)
+    }
+
+    /// Returns the differences between the `serial` version of the zone and the current version,
+    ///  reconstructed from the journal, for an IXFR, [RFC 1995](https://tools.ietf.org/html/rfc1995)
+    ///
+    /// The journal is replayed from the last full dump of the zone. Each version of the zone is
+    ///  terminated in the journal by its SOA, which is what separates one update from the next.
+    ///
+    /// # Arguments
+    ///
+    /// * `serial` - the serial of the version of the zone the requestor currently has
+    ///
+    /// # Return value
+    ///
+    /// None if there is no journal, if the zone is signed (RRSIGs and NSECs are not journaled), or
+    ///  if `serial` is no longer in the history of the zone. In these cases a full AXFR should be
+    ///  sent instead. An empty set is returned if `serial` is the current version.
+    pub fn journal_diffs(&self, serial: u32) -> Option<Vec<ZoneDiff>> {
+        let journal = self.journal.as_ref()?;
+
+        if !self.secure_keys.is_empty() {
+            debug!("signatures are not journaled, no diffs for: {}", self.origin);
+            return None;
+        }
+
+        // only the history since the last full dump of the zone is usable
+        let mut history: Vec<Record> = journal.iter().collect();
+        let start = history
+            .iter()
+            .rposition(|r| r.rr_type() == RecordType::AXFR)? + 1;
+        let history = history.split_off(start);
+
+        let mut versions: Vec<Vec<Record>> = vec![];
+        let mut version: Vec<Record> = vec![];
+        for record in history {
+            let is_soa = record.rr_type() == RecordType::SOA && record.dns_class() == self.class;
+            version.push(record);
+
+            if is_soa {
+                versions.push(mem::replace(&mut version, vec![]));
+            }
+        }
+        // any trailing records without an SOA did not change the zone, and are ignored
+
+        // replay the history against an empty zone
+        let mut versions = versions.into_iter();
+        let mut zone = Authority::new(
+            self.origin.clone().into(),
+            BTreeMap::new(),
+            self.zone_type,
+            false,
+            false,
+        );
+        zone.update_records(&versions.next()?, false).ok()?;
+
+        let mut diffs: Option<Vec<ZoneDiff>> = if zone.serial() == serial {
+            Some(vec![])
+        } else {
+            None
+        };
+
+        for version in versions {
+            let before = if diffs.is_some() {
+                Some((zone.soa().iter().next().cloned()?, zone.snapshot()))
+            } else {
+                None
+            };
+
+            zone.update_records(&version, false).ok()?;
+
+            if let Some((old_soa, before)) = before {
+                let new_soa = zone.soa().iter().next().cloned()?;
+                let after = zone.snapshot();
+
+                let deleted = before.difference(&after).cloned().collect();
+                let added = after.difference(&before).cloned().collect();
+
+                // diffs is always Some when before is
+                if let Some(ref mut diffs) = diffs {
+                    diffs.push(ZoneDiff::new(old_soa, deleted, new_soa, added));
+                }
+            } else if zone.serial() == serial {
+                diffs = Some(vec![]);
+            }
+        }
+
+        // the history must arrive at the current version of the zone
+        if zone.serial() != self.serial() {
+            warn!(
+                "journal for {} ends at serial {}, expected {}",
+                self.origin,
+                zone.serial(),
+                self.serial()
+            );
+            return None;
+        }
+
+        diffs
+    }
+
+    /// Replaces all the records in the zone with those received in a full zone transfer, AXFR
+    ///
+    /// The new version of the zone is persisted to the journal, if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - all the records of the zone, the SOA must be present
+    pub fn replace_records(&mut self, records: Vec<Record>) -> PersistenceResult<()> {
+        let serial = records
+            .iter()
+            .filter_map(|record| {
+                if let RData::SOA(ref soa) = *record.rdata() {
+                    Some(soa.serial())
+                } else {
+                    None
+                }
+            })
+            .next()
+            .ok_or_else(|| PersistenceErrorKind::Msg("transfer is missing the SOA".into()))?;
+
+        self.records.clear();
+        for record in records {
+            if record.dns_class() != self.class {
+                warn!("ignoring record of class {:?}: {:?}", record.dns_class(), record);
+                continue;
+            }
+
+            self.upsert(record, serial);
+        }
+
+        self.persist_to_journal()
+    }
+
+    /// Applies the differences from an incremental zone transfer, IXFR, to the zone
+    ///
+    /// The changes are journaled like any other update, terminated by the new SOA.
+    ///
+    /// # Arguments
+    ///
+    /// * `diff` - the changes between the current version of the zone and the next
+    pub fn apply_diff(&mut self, diff: &ZoneDiff) -> UpdateResult<bool> {
+        let mut records: Vec<Record> =
+            Vec::with_capacity(diff.deleted().len() + diff.added().len() + 1);
+
+        for deleted in diff.deleted() {
+            if deleted.rr_type() == RecordType::SOA {
+                continue;
+            }
+
+            // NONE is the class of an update which deletes a specific record
+            let mut deleted = deleted.clone();
+            deleted.set_dns_class(DNSClass::NONE);
+            records.push(deleted);
+        }
+
+        records.extend(
+            diff.added()
+                .iter()
+                .filter(|r| r.rr_type() != RecordType::SOA)
+                .cloned(),
+        );
+
+        // the new SOA is last, it marks the end of this version in the journal
+        records.push(diff.new_soa().clone());
+
+        self.update_records(&records, false)
+    }
+
+    /// All records in the zone, except the SOA, used for computing differences between versions
+    fn snapshot(&self) -> BTreeSet<Record> {
+        self.records
+            .values()
+            .filter(|rr_set| rr_set.record_type() != RecordType::SOA)
+            .flat_map(|rr_set| rr_set.records_without_rrsigs())
+            .cloned()
+            .collect()
+    }
+
+    /// Looks up all Resource Records matching the giving `Name` and `RecordType`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The `Name`, label, to lookup.
+    /// * `rtype` - The `RecordType`, to lookup. `RecordType::ANY` will return all records matching
+    ///             `name`. `RecordType::AXFR` will return all record types except `RecordType::SOA`
+    ///             due to the requirements that on zone transfers the `RecordType::SOA` must both
+    ///             preceed and follow all other records.
+    /// * `is_secure` - If the DO bit is set on the EDNS OPT record, then return RRSIGs as well.
+    ///
+    /// # Return value
+    ///
+    /// None if there are no matching records, otherwise a `Vec` containing the found records.
+    pub fn lookup<'s>(
+        &'s self,
+        name: &LowerName,
+        rtype: RecordType,
+        is_secure: bool,
+        supported_algorithms: SupportedAlgorithms,
+    ) -> AuthLookup<'s> {
+        let rr_key = RrKey::new(name.clone(), rtype);
+
+        // Collect the records from each rr_set
+        let result: Vec<&Record> = match rtype {
+            RecordType::ANY | RecordType::AXFR => self.records
+                .values()
+                .filter(|rr_set| {
+                    rtype == RecordType::ANY || rr_set.record_type() != RecordType::SOA
+                })
+                .filter(|rr_set| rtype == RecordType::AXFR || &LowerName::new(rr_set.name()) == name)
+                .fold(Vec::<&Record>::new(), |mut vec, rr_set| {
+                    vec.append(&mut rr_set.records(is_secure, supported_algorithms));
+                    vec
+                }),
+            _ => self.records.get(&rr_key).map_or(vec![], |rr_set| {
+                rr_set
+                    .records(is_secure, supported_algorithms)
+                    .into_iter()
+                    .collect()
+            }),
+        };
+
+        // This is annoying. The 1035 spec literally specifies that most DNS authorities would want to store
+        //   records in a list except when there are a lot of records. But this makes indexed lookups by name+type
+        //   always return empty sets. This is only important in the negative case, where other DNS authorities
+        //   generally return NoError and no results when other types exist at the same name. bah.
+        if result.is_empty() {
+            if self.records.keys().any(|key| key.name() == name) {
+                return AuthLookup::NameExists;
+            } else {
+                return AuthLookup::NoName;
+            }
+        }
+
+        AuthLookup::Records(result)
+    }
+
+    /// Returns the delegation of a child zone which contains the name, if any, see
+    ///  [RFC 1034](https://tools.ietf.org/html/rfc1034#section-4.3.2)
+    ///
+    /// ```text
+    ///    3. Start matching down, label by label, in the zone.  The
+    ///       matching process can terminate several ways:
+    ///
+    ///          b. If a match would take us out of the authoritative data,
+    ///             we have a referral.  This happens when we encounter a
+    ///             node with NS RRs marking cuts along the bottom of a
+    ///             zone.
+    ///
+    ///             Copy the NS RRs for the subzone into the authority
+    ///             section of the reply.  Put whatever addresses are
+    ///             available into the additional section, using glue RRs
+    ///             if the addresses are not available from authoritative
+    ///             data or the cache.  Go to step 4.
+    /// ```
+    ///
+    /// # Return value
+    ///
+    /// None if the name is not below a zone cut, otherwise the NS records of the child zone and the
+    ///  A and AAAA records of those name servers which are in this zone. DS records are held by
+    ///  the parent, so a DS query at the zone cut itself is not referred, see
+    ///  [RFC 4035](https://tools.ietf.org/html/rfc4035#section-3.1.4.1).
+    pub fn referral<'s>(
+        &'s self,
+        name: &LowerName,
+        record_type: RecordType,
+    ) -> Option<(Vec<&'s Record>, Vec<&'s Record>)> {
+        if !self.origin.zone_of(name) {
+            return None;
+        }
+
+        #[cfg(feature = "dnssec")]
+        fn is_ds(record_type: RecordType) -> bool {
+            use trust_dns::rr::rdata::DNSSECRecordType;
+
+            record_type == RecordType::DNSSEC(DNSSECRecordType::DS)
+        }
+
+        #[cfg(not(feature = "dnssec"))]
+        fn is_ds(_record_type: RecordType) -> bool {
+            // There's no DS record when DNSSEC is disabled at build time.
+            false
+        }
+
+        // the closest zone cut to the origin takes precedence, the records below it are glue
+        let mut cut = None;
+        let mut candidate = name.clone();
+        if is_ds(record_type) && candidate != self.origin {
+            candidate = candidate.base_name();
+        }
+        while candidate != self.origin {
+            if let AuthLookup::Records(ns) = self.lookup(
+                &candidate,
+                RecordType::NS,
+                false,
+                SupportedAlgorithms::new(),
+            ) {
+                cut = Some(ns);
+            }
+
+            candidate = candidate.base_name();
+        }
+
+        let ns = match cut {
+            Some(ns) => ns,
+            None => return None,
+        };
+
+        let mut glue = vec![];
+        for record in &ns {
+            let ns_name = match *record.rdata() {
+                RData::NS(ref ns_name) => LowerName::new(ns_name),
+                _ => continue,
+            };
+
+            if !self.origin.zone_of(&ns_name) {
+                continue;
+            }
+
+            for rtype in &[RecordType::A, RecordType::AAAA] {
+                if let AuthLookup::Records(mut addresses) =
+                    self.lookup(&ns_name, *rtype, false, SupportedAlgorithms::new())
+                {
+                    glue.append(&mut addresses);
+                }
+            }
+        }
+
+        Some((ns, glue))
+    }
+
+    /// Return the NSEC records based on the given name
+    ///
+    /// If the zone is signed with NSEC3, these are the NSEC3 records which prove the name, or the
+    ///  type at the name, does not exist, see `get_nsec3_records`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - given this name (i.e. the lookup name), return the NSEC record that is less than
+    ///            this
+    /// * `is_secure` - if true then it will return RRSIG records as well
+    pub fn get_nsec_records(
+        &self,
+        name: &LowerName,
+        is_secure: bool,
+        supported_algorithms: SupportedAlgorithms,
+    ) -> Vec<&Record> {
+        #[cfg(feature = "dnssec")]
+        {
+            // a zone signed offline denies existence with the NSEC3 chain published in it
+            let nsec3param = self.nsec3param
+                .as_ref()
+                .or_else(|| self.published_nsec3param());
+            if let Some(nsec3param) = nsec3param {
+                return self.get_nsec3_records(name, nsec3param, is_secure, supported_algorithms);
+            }
+        }
+
+        #[cfg(feature = "dnssec")]
+        fn is_nsec_rrset(rr_set: &RecordSet) -> bool {
+            use trust_dns::rr::rdata::DNSSECRecordType;
+
+            rr_set.record_type() == RecordType::DNSSEC(DNSSECRecordType::NSEC)
+        }
 
         #[cfg(not(feature = "dnssec"))]
         fn is_nsec_rrset(_record: &RecordSet) -> bool {
@@ -1081,12 +1925,135 @@ impl Authority {
             })
     }
 
+    /// The NSEC3PARAM record at the apex of the zone, if there is one
+    #[cfg(feature = "dnssec")]
+    fn published_nsec3param(&self) -> Option<&NSEC3PARAM> {
+        use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType};
+
+        let key = RrKey::new(
+            self.origin.clone(),
+            RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM),
+        );
+        self.records
+            .get(&key)
+            .and_then(|rr_set| rr_set.iter().next())
+            .and_then(|record| match *record.rdata() {
+                RData::DNSSEC(DNSSECRData::NSEC3PARAM(ref nsec3param)) => Some(nsec3param),
+                _ => None,
+            })
+    }
+
+    /// Return the NSEC3 records which prove the name, or the type at the name, does not exist
+    ///
+    /// This is the NSEC3 matching the name when it exists. Otherwise, the closest encloser proof,
+    ///  the NSEC3 matching the closest encloser and the one covering the next closer name, and
+    ///  the NSEC3 matching or covering the wildcard at the closest encloser, RFC 5155 7.2.
+    #[cfg(feature = "dnssec")]
+    fn get_nsec3_records(
+        &self,
+        name: &LowerName,
+        nsec3param: &NSEC3PARAM,
+        is_secure: bool,
+        supported_algorithms: SupportedAlgorithms,
+    ) -> Vec<&Record> {
+        use data_encoding::BASE32_DNSSEC;
+        use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType, NSEC3};
+
+        // the chain, the hash of the owner and the record of each NSEC3
+        let chain = self.records
+            .values()
+            .filter(|rr_set| {
+                rr_set.record_type() == RecordType::DNSSEC(DNSSECRecordType::NSEC3)
+            })
+            .filter_map(|rr_set| {
+                let owner_hash = rr_set
+                    .name()
+                    .iter()
+                    .next()
+                    .and_then(|label| BASE32_DNSSEC.decode(label).ok());
+                let nsec3 = rr_set.iter().next().and_then(|record| match *record.rdata() {
+                    RData::DNSSEC(DNSSECRData::NSEC3(ref nsec3)) => Some(nsec3),
+                    _ => None,
+                });
+
+                match (owner_hash, nsec3) {
+                    (Some(owner_hash), Some(nsec3)) => Some((owner_hash, nsec3, rr_set)),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<(Vec<u8>, &NSEC3, &RecordSet)>>();
+
+        let hash = |name: &Name| {
+            nsec3param
+                .hash_algorithm()
+                .hash(nsec3param.salt(), name, nsec3param.iterations())
+                .map(|hash| hash.as_ref().to_vec())
+                .ok()
+        };
+        let matching = |name: &Name| {
+            hash(name).and_then(|hash| {
+                chain
+                    .iter()
+                    .find(|&&(ref owner, _, _)| *owner == hash)
+                    .map(|&(_, _, rr_set)| rr_set)
+            })
+        };
+        let covering = |name: &Name| {
+            hash(name).and_then(|hash| {
+                chain
+                    .iter()
+                    .find(|&&(ref owner, nsec3, _)| nsec3.covers(owner, &hash))
+                    .map(|&(_, _, rr_set)| rr_set)
+            })
+        };
+
+        let name: Name = name.clone().into();
+        let mut rr_sets: Vec<&RecordSet> = Vec::new();
+        if let Some(rr_set) = matching(&name) {
+            // no data
+            rr_sets.push(rr_set);
+        } else {
+            let origin_labels = Name::from(self.origin.clone()).iter().count();
+            for labels in (origin_labels..name.iter().count()).rev() {
+                let encloser = name.trim_to(labels);
+                if let Some(rr_set) = matching(&encloser) {
+                    rr_sets.push(rr_set);
+                    rr_sets.extend(covering(&name.trim_to(labels + 1)));
+
+                    // the wildcard matches for a wildcard no data response, otherwise it's covered
+                    let wildcard = Name::from_labels(vec!["*"])
+                        .expect("* is a valid label")
+                        .append_name(&encloser);
+                    rr_sets.extend(matching(&wildcard).or_else(|| covering(&wildcard)));
+                    break;
+                }
+            }
+        }
+
+        // the same NSEC3 may be part of more than one proof
+        let mut records: Vec<&Record> = Vec::new();
+        for (i, rr_set) in rr_sets.iter().enumerate() {
+            if rr_sets[..i].iter().any(|other| other.name() == rr_set.name()) {
+                continue;
+            }
+
+            records.extend(rr_set.records(is_secure, supported_algorithms));
+        }
+
+        records
+    }
+
     /// (Re)generates the nsec records, increments the serial number nad signs the zone
     #[cfg(feature = "dnssec")]
     pub fn secure_zone(&mut self) -> DnsSecResult<()> {
+        // the keys in their states as of now, see roll_keys()
+        let now = self.now();
+        self.publish_keys(now)?;
+        self.keys_rolled_at = now;
+
         // TODO: only call nsec_zone after adds/deletes
         // needs to be called before incrementing the soa serial, to make sur IXFR works properly
-        self.nsec_zone();
+        self.nsec_zone()?;
 
         // need to resign any records at the current serial number and bump the number.
         // first bump the serial number on the SOA, so that it is resigned with the new serial.
@@ -1104,20 +2071,23 @@ impl Authority {
 
     /// Dummy implementation for when DNSSEC is disabled.
     #[cfg(feature = "dnssec")]
-    fn nsec_zone(&mut self) {
+    fn nsec_zone(&mut self) -> DnsSecResult<()> {
         use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType, NSEC};
 
         // only create nsec records for secure zones
         if self.secure_keys.is_empty() {
-            return;
+            return Ok(());
         }
         debug!("generating nsec records: {}", self.origin);
 
-        // first remove all existing nsec records
+        // first remove all existing nsec records, the zone may have switched between NSEC and NSEC3
         let delete_keys: Vec<RrKey> = self.records
             .keys()
-            .filter(|k| {
-                k.record_type == RecordType::DNSSEC(DNSSECRecordType::NSEC)
+            .filter(|k| match k.record_type {
+                RecordType::DNSSEC(DNSSECRecordType::NSEC)
+                | RecordType::DNSSEC(DNSSECRecordType::NSEC3)
+                | RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM) => true,
+                _ => false,
             })
             .cloned()
             .collect();
@@ -1126,6 +2096,11 @@ impl Authority {
             self.records.remove(&key);
         }
 
+        if let Some(nsec3param) = self.nsec3param.clone() {
+            debug!("generating nsec3 records: {}", self.origin);
+            return self.nsec3_zone(&nsec3param);
+        }
+
         // now go through and generate the nsec records
         let ttl = self.minimum_ttl();
         let serial = self.serial();
@@ -1172,17 +2147,121 @@ impl Authority {
         for record in records {
             self.upsert(record, serial);
         }
+
+        Ok(())
     }
 
-    /// Signs any records in the zone that have serial numbers greater than or equal to `serial`
+    /// Generates the NSEC3 chain and the NSEC3PARAM of the zone, RFC 5155 7.1
+    ///
+    /// Names below a delegation are not authoritative and are not in the chain, the empty
+    ///  non-terminals are.
     #[cfg(feature = "dnssec")]
-    fn sign_zone(&mut self) -> DnsSecResult<()> {
-        use chrono::Utc;
-        use trust_dns::rr::dnssec::tbs;
-        use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType, SIG};
+    fn nsec3_zone(&mut self, nsec3param: &NSEC3PARAM) -> DnsSecResult<()> {
+        use data_encoding::BASE32_DNSSEC;
+        use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType, NSEC3};
+
+        let ttl = self.minimum_ttl();
+        let serial = self.serial();
+        let origin: Name = self.origin().clone().into();
+
+        // the flags of the NSEC3PARAM are always 0, Opt-Out is only set on the NSEC3 records
+        let mut record = Record::with(
+            origin.clone(),
+            RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM),
+            ttl,
+        );
+        record.set_rdata(RData::DNSSEC(DNSSECRData::NSEC3PARAM(NSEC3PARAM::new(
+            nsec3param.hash_algorithm(),
+            false,
+            nsec3param.iterations(),
+            nsec3param.salt().to_vec(),
+        ))));
+        self.upsert(record, serial);
+
+        let apex = self.origin.clone();
+        let delegations = self.records
+            .keys()
+            .filter(|key| key.record_type == RecordType::NS && key.name != apex)
+            .map(|key| Name::from(key.name.clone()))
+            .collect::<Vec<Name>>();
+
+        let mut types: BTreeMap<Name, Vec<RecordType>> = BTreeMap::new();
+        for key in self.records.keys() {
+            let name = Name::from(key.name.clone());
+            if delegations
+                .iter()
+                .any(|delegation| delegation.zone_of(&name) && *delegation != name)
+            {
+                continue;
+            }
+
+            types
+                .entry(name)
+                .or_insert_with(Vec::new)
+                .push(key.record_type);
+        }
+
+        let mut names: BTreeMap<Name, Vec<RecordType>> = BTreeMap::new();
+        for (name, types) in types {
+            let is_unsigned_delegation = delegations.contains(&name)
+                && !types.contains(&RecordType::DNSSEC(DNSSECRecordType::DS));
+            if nsec3param.opt_out() && is_unsigned_delegation {
+                continue;
+            }
+
+            // the empty non-terminals between the name and the origin
+            let mut ancestor = name.base_name();
+            while ancestor.num_labels() > origin.num_labels() {
+                names.entry(ancestor.clone()).or_insert_with(Vec::new);
+                ancestor = ancestor.base_name();
+            }
+
+            names.insert(name, types);
+        }
+
+        let mut hashed = Vec::with_capacity(names.len());
+        for (name, types) in names {
+            let hash = nsec3param.hash_algorithm().hash(
+                nsec3param.salt(),
+                &name,
+                nsec3param.iterations(),
+            )?;
+            hashed.push((hash.as_ref().to_vec(), types));
+        }
+        hashed.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut records: Vec<Record> = Vec::with_capacity(hashed.len());
+        for (i, &(ref hash, ref types)) in hashed.iter().enumerate() {
+            let owner = Name::from_labels(vec![BASE32_DNSSEC.encode(hash).as_str()])?
+                .append_domain(&origin);
+            let next = hashed[(i + 1) % hashed.len()].0.clone();
+
+            let mut record = Record::with(owner, RecordType::DNSSEC(DNSSECRecordType::NSEC3), ttl);
+            let rdata = NSEC3::new(
+                nsec3param.hash_algorithm(),
+                nsec3param.opt_out(),
+                nsec3param.iterations(),
+                nsec3param.salt().to_vec(),
+                next,
+                types.clone(),
+            );
+            record.set_rdata(RData::DNSSEC(DNSSECRData::NSEC3(rdata)));
+            records.push(record);
+        }
+
+        // insert all the nsec3 records
+        for record in records {
+            self.upsert(record, serial);
+        }
+
+        Ok(())
+    }
 
+    /// Signs all the records of the zone, replacing any existing RRSIGs
+    #[cfg(feature = "dnssec")]
+    fn sign_zone(&mut self) -> DnsSecResult<()> {
         debug!("signing zone: {}", self.origin);
-        let inception = Utc::now();
+        let now = self.now();
         let zone_ttl = self.minimum_ttl();
 
         // TODO: should this be an error?
@@ -1191,88 +2270,524 @@ impl Authority {
         }
 
         // sign all record_sets, as of 0.12.1 this includes DNSKEY
+        let active_keys = ActiveKeys::new(&self.secure_keys, &self.key_timings, now);
         for rr_set in self.records.values_mut() {
-            rr_set.clear_rrsigs();
-            let rrsig_temp = Record::with(
-                rr_set.name().clone(),
-                RecordType::DNSSEC(DNSSECRecordType::RRSIG),
+            let keys = active_keys.for_rr_set(rr_set, &self.origin);
+            sign_rr_set(
+                rr_set,
+                keys,
+                self.class,
                 zone_ttl,
-            );
+                now,
+                &self.signature_times,
+            )?;
+        }
 
-            for signer in &self.secure_keys {
-                debug!(
-                    "signing rr_set: {}, {} with: {}",
-                    rr_set.name(),
-                    rr_set.record_type(),
-                    signer.algorithm(),
-                );
+        Ok(())
+    }
+
+    /// Re-signs the record sets with RRSIGs which expire within the refresh window, see
+    ///  `set_signature_refresh()`
+    ///
+    /// If any are re-signed, the serial of the zone is incremented so that the new signatures are
+    ///  transferred to the slaves of the zone.
+    ///
+    /// # Return value
+    ///
+    /// The number of record sets which were re-signed
+    #[cfg(feature = "dnssec")]
+    pub fn resign_expiring(&mut self) -> DnsSecResult<usize> {
+        if self.secure_keys.is_empty() {
+            return Ok(0);
+        }
+
+        // when the keys change state the whole zone is signed again
+        if self.roll_keys()? {
+            return Ok(self.records.len());
+        }
+
+        let now = self.now();
+        let refresh_before = now + self.refresh_window();
+        let mut expiring: Vec<RrKey> = {
+            let active_keys = ActiveKeys::new(&self.secure_keys, &self.key_timings, now);
+            let origin = &self.origin;
+
+            // without active keys nothing can be signed, until the next transition of the keys
+            self.records
+                .iter()
+                .filter(|&(_, rr_set)| !active_keys.for_rr_set(rr_set, origin).is_empty())
+                .filter(|&(_, rr_set)| {
+                    signature_expirations(rr_set)
+                        .map_or(true, |expiration| expiration <= refresh_before)
+                })
+                .map(|(key, _)| key.clone())
+                .collect()
+        };
+
+        if expiring.is_empty() {
+            return Ok(0);
+        }
 
-                let expiration = inception + signer.sig_duration();
+        info!("re-signing {} record sets of zone: {}", expiring.len(), self.origin);
+        let resigned = expiring.len();
+
+        // the SOA is always signed again, with the new serial
+        self.increment_soa_serial();
+        let soa_key = RrKey::new(self.origin.clone(), RecordType::SOA);
+        if !expiring.contains(&soa_key) {
+            expiring.push(soa_key);
+        }
 
-                let tbs = tbs::rrset_tbs(
-                    rr_set.name(),
+        let zone_ttl = self.minimum_ttl();
+        let active_keys = ActiveKeys::new(&self.secure_keys, &self.key_timings, now);
+        for key in expiring {
+            if let Some(rr_set) = self.records.get_mut(&key) {
+                let keys = active_keys.for_rr_set(rr_set, &self.origin);
+                sign_rr_set(
+                    rr_set,
+                    keys,
                     self.class,
-                    rr_set.name().num_labels(),
-                    rr_set.record_type(),
-                    signer.algorithm(),
-                    rr_set.ttl(),
-                    expiration.timestamp() as u32,
-                    inception.timestamp() as u32,
+                    zone_ttl,
+                    now,
+                    &self.signature_times,
+                )?;
+            }
+        }
+
+        Ok(resigned)
+    }
+
+    /// The time until the first of the RRSIGs of the zone is due to be refreshed, or the next of
+    ///  the keys is due to change state, see `resign_expiring()`, None if the zone is not signed
+    #[cfg(feature = "dnssec")]
+    pub fn next_signature_refresh(&self) -> Option<Duration> {
+        if self.secure_keys.is_empty() {
+            return None;
+        }
+
+        let now = self.now();
+        let active_keys = ActiveKeys::new(&self.secure_keys, &self.key_timings, now);
+
+        // a record set without RRSIGs is due now, if there are active keys to sign it with
+        let first_refresh = self.records
+            .values()
+            .filter(|rr_set| !active_keys.for_rr_set(rr_set, &self.origin).is_empty())
+            .map(|rr_set| {
+                signature_expirations(rr_set)
+                    .unwrap_or(0)
+                    .saturating_sub(self.refresh_window())
+            })
+            .min();
+        let next_transition = self.key_timings
+            .iter()
+            .filter_map(|timing| timing.next_transition(now))
+            .min();
+
+        let due = match (first_refresh, next_transition) {
+            (Some(refresh), Some(transition)) => Some(cmp::min(refresh, transition)),
+            (refresh, transition) => refresh.or(transition),
+        };
+        due.map(|due| Duration::from_secs(due.saturating_sub(now)))
+    }
+
+    /// Moves the keys of the zone to their states at the current time, see `KeyTiming`
+    ///
+    /// When any of the keys changes state, the DNSKEY, CDS and CDNSKEY records are published again,
+    ///  the serial is incremented and the zone is signed again with the active keys. This is called
+    ///  by `resign_expiring()`.
+    ///
+    /// # Return value
+    ///
+    /// True if any of the keys changed state
+    #[cfg(feature = "dnssec")]
+    pub fn roll_keys(&mut self) -> DnsSecResult<bool> {
+        let now = self.now();
+        let rolled_at = self.keys_rolled_at;
+
+        let mut rolled = false;
+        for (signer, timing) in self.secure_keys.iter().zip(&self.key_timings) {
+            let state = timing.state(now);
+            if state != timing.state(rolled_at) {
+                info!(
+                    "key: {} of zone: {} is now: {:?}",
                     signer.calculate_key_tag()?,
-                    signer.signer_name(),
-                    // TODO: this is a nasty clone... the issue is that the vec
-                    //  from records is of Vec<&R>, but we really want &[R]
-                    &rr_set
-                        .records_without_rrsigs()
-                        .into_iter()
-                        .cloned()
-                        .collect::<Vec<Record>>(),
+                    self.origin,
+                    state
                 );
+                rolled = true;
+            }
+        }
 
-                // TODO, maybe chain these with some ETL operations instead?
-                let tbs = match tbs {
-                    Ok(tbs) => tbs,
-                    Err(err) => {
-                        error!("could not serialize rrset to sign: {}", err);
-                        continue;
-                    }
-                };
-
-                let signature = signer.sign(&tbs);
-                let signature = match signature {
-                    Ok(signature) => signature,
-                    Err(err) => {
-                        error!("could not sign rrset: {}", err);
-                        continue;
-                    }
-                };
-
-                let mut rrsig = rrsig_temp.clone();
-                rrsig.set_rdata(RData::DNSSEC(DNSSECRData::SIG(SIG::new(
-                    // type_covered: RecordType,
-                    rr_set.record_type(),
-                    // algorithm: Algorithm,
-                    signer.algorithm(),
-                    // num_labels: u8,
-                    rr_set.name().num_labels(),
-                    // original_ttl: u32,
-                    rr_set.ttl(),
-                    // sig_expiration: u32,
-                    expiration.timestamp() as u32,
-                    // sig_inception: u32,
-                    inception.timestamp() as u32,
-                    // key_tag: u16,
-                    signer.calculate_key_tag()?,
-                    // signer_name: Name,
-                    signer.signer_name().clone(),
-                    // sig: Vec<u8>
-                    signature,
-                ))));
+        if !rolled {
+            return Ok(false);
+        }
 
-                rr_set.insert_rrsig(rrsig);
+        self.secure_zone()?;
+        Ok(true)
+    }
+
+    /// Publishes the DNSKEY of each of the keys in a published state, and the CDS and CDNSKEY,
+    ///  RFC 7344, of the KSKs the parent should delegate to; those of the other keys are removed
+    #[cfg(feature = "dnssec")]
+    fn publish_keys(&mut self, now: u64) -> DnsSecResult<()> {
+        use trust_dns::rr::dnssec::DigestType;
+        use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType, DS};
+
+        let origin: Name = self.origin.clone().into();
+        let zone_ttl = self.minimum_ttl();
+        let serial = self.serial();
+
+        // during a double signature rollover both KSKs are active, the parent should delegate to
+        //  the new one
+        let active_ksks: Vec<usize> = self.secure_keys
+            .iter()
+            .zip(&self.key_timings)
+            .enumerate()
+            .filter(|&(_, (signer, timing))| {
+                is_key_signing_key(signer) && timing.state(now).is_active()
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let delegated: Vec<usize> = if active_ksks
+            .iter()
+            .any(|&i| !self.key_timings[i].is_retiring())
+        {
+            active_ksks
+                .into_iter()
+                .filter(|&i| !self.key_timings[i].is_retiring())
+                .collect()
+        } else {
+            active_ksks
+        };
+
+        let mut published: Vec<Record> = Vec::new();
+        let mut withdrawn: Vec<Record> = Vec::new();
+        for (i, (signer, timing)) in self.secure_keys.iter().zip(&self.key_timings).enumerate() {
+            let dnskey = signer_dnskey(signer)?;
+            let digest = dnskey.to_digest(&origin, DigestType::SHA256)?;
+            let cds = DS::new(
+                signer.calculate_key_tag()?,
+                signer.algorithm(),
+                DigestType::SHA256,
+                digest.as_ref().to_vec(),
+            );
+
+            let is_delegated = delegated.contains(&i);
+            let key_records = vec![
+                (
+                    timing.state(now).is_published(),
+                    DNSSECRecordType::DNSKEY,
+                    DNSSECRData::DNSKEY(dnskey.clone()),
+                ),
+                (
+                    is_delegated,
+                    DNSSECRecordType::CDNSKEY,
+                    DNSSECRData::CDNSKEY(dnskey),
+                ),
+                (is_delegated, DNSSECRecordType::CDS, DNSSECRData::CDS(cds)),
+            ];
+
+            for (is_published, record_type, rdata) in key_records {
+                let record = Record::from_rdata(
+                    origin.clone(),
+                    zone_ttl,
+                    RecordType::DNSSEC(record_type),
+                    RData::DNSSEC(rdata),
+                );
+
+                if is_published {
+                    published.push(record);
+                } else {
+                    withdrawn.push(record);
+                }
             }
         }
 
+        for record in withdrawn {
+            self.withdraw(&record, serial);
+        }
+        for record in published {
+            self.upsert(record, serial);
+        }
+
         Ok(())
     }
+
+    /// Removes the record from the zone, and its record set if that is left empty
+    #[cfg(feature = "dnssec")]
+    fn withdraw(&mut self, record: &Record, serial: u32) -> bool {
+        let rr_key = RrKey::new(record.name().into(), record.rr_type());
+        let (removed, is_empty) = match self.records.get_mut(&rr_key) {
+            Some(rr_set) => (rr_set.remove(record, serial), rr_set.is_empty()),
+            None => return false,
+        };
+
+        if is_empty {
+            self.records.remove(&rr_key);
+        }
+
+        removed
+    }
+
+    /// The refresh window of the signatures, the shortest of the windows of each of the keys
+    #[cfg(feature = "dnssec")]
+    fn refresh_window(&self) -> u64 {
+        self.secure_keys
+            .iter()
+            .map(|signer| self.signature_times.refresh(sig_validity(signer)))
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+/// The inception, refresh and jitter of the signatures of a zone
+#[cfg(feature = "dnssec")]
+#[derive(Clone, Copy, Debug)]
+struct SignatureTimes {
+    inception: Duration,
+    refresh: Duration,
+    jitter: Duration,
+}
+
+#[cfg(feature = "dnssec")]
+impl Default for SignatureTimes {
+    fn default() -> Self {
+        SignatureTimes {
+            inception: Duration::from_secs(DEFAULT_SIG_INCEPTION),
+            refresh: Duration::from_secs(DEFAULT_SIG_REFRESH),
+            jitter: Duration::from_secs(DEFAULT_SIG_JITTER),
+        }
+    }
+}
+
+#[cfg(feature = "dnssec")]
+impl SignatureTimes {
+    /// The refresh window, in seconds, of signatures valid for `validity` seconds
+    ///
+    /// At most half of the validity, otherwise the new signatures would be due for a refresh as
+    ///  soon as they are made.
+    fn refresh(&self, validity: u64) -> u64 {
+        cmp::min(self.refresh.as_secs(), validity / 2)
+    }
+
+    /// The maximum jitter, in seconds, of signatures valid for `validity` seconds
+    ///
+    /// The jittered signatures must still outlast the refresh window.
+    fn jitter(&self, validity: u64) -> u64 {
+        cmp::min(self.jitter.as_secs(), (validity - self.refresh(validity)) / 2)
+    }
+}
+
+/// The keys which sign the record sets of a zone at some time
+#[cfg(feature = "dnssec")]
+struct ActiveKeys<'a> {
+    ksks: Vec<&'a Signer>,
+    zsks: Vec<&'a Signer>,
+}
+
+#[cfg(feature = "dnssec")]
+impl<'a> ActiveKeys<'a> {
+    fn new(secure_keys: &'a [Signer], key_timings: &[KeyTiming], now: u64) -> Self {
+        let (ksks, zsks): (Vec<&'a Signer>, Vec<&'a Signer>) = secure_keys
+            .iter()
+            .zip(key_timings)
+            .filter(|&(_, timing)| timing.state(now).is_active())
+            .map(|(signer, _)| signer)
+            .partition(|signer| is_key_signing_key(signer));
+
+        ActiveKeys {
+            ksks: ksks,
+            zsks: zsks,
+        }
+    }
+
+    /// The KSKs sign the DNSKEY, CDS and CDNSKEY at the apex, the ZSKs all other record sets
+    fn for_rr_set(&self, rr_set: &RecordSet, origin: &LowerName) -> &[&'a Signer] {
+        use trust_dns::rr::rdata::DNSSECRecordType;
+
+        let is_key_set = match rr_set.record_type() {
+            RecordType::DNSSEC(DNSSECRecordType::DNSKEY)
+            | RecordType::DNSSEC(DNSSECRecordType::CDS)
+            | RecordType::DNSSEC(DNSSECRecordType::CDNSKEY) => {
+                LowerName::new(rr_set.name()) == *origin
+            }
+            _ => false,
+        };
+
+        let (keys, others) = if is_key_set {
+            (&self.ksks, &self.zsks)
+        } else {
+            (&self.zsks, &self.ksks)
+        };
+
+        if keys.is_empty() {
+            others
+        } else {
+            keys
+        }
+    }
+}
+
+/// True if one of the keys has the name and algorithm of the signer
+fn is_tsig_key_in(keys: &[TSigner], signer: &TSigner) -> bool {
+    keys.iter()
+        .any(|key| key.key_name() == signer.key_name() && key.algorithm() == signer.algorithm())
+}
+
+/// True if the DNSKEY of the signer has the Secure Entry Point flag, i.e. it is a KSK
+#[cfg(feature = "dnssec")]
+fn is_key_signing_key(signer: &Signer) -> bool {
+    use trust_dns::rr::rdata::DNSSECRData;
+
+    match *signer.key_rdata() {
+        RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey)) => dnskey.secure_entry_point(),
+        _ => false,
+    }
+}
+
+/// The DNSKEY of the signer, as it is published in the zone
+#[cfg(feature = "dnssec")]
+fn signer_dnskey(signer: &Signer) -> DnsSecResult<DNSKEY> {
+    use trust_dns::rr::rdata::DNSSECRData;
+
+    match *signer.key_rdata() {
+        RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey)) => Ok(dnskey.clone()),
+        _ => signer.key().to_dnskey(signer.algorithm()),
+    }
+}
+
+/// Seconds since the unix epoch
+#[cfg(feature = "dnssec")]
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(feature = "dnssec")]
+fn sig_validity(signer: &Signer) -> u64 {
+    cmp::max(signer.sig_duration().num_seconds(), 0) as u64
+}
+
+/// The earliest expiration of the RRSIGs of the record set, None if it is not signed
+#[cfg(feature = "dnssec")]
+fn signature_expirations(rr_set: &RecordSet) -> Option<u64> {
+    use trust_dns::rr::rdata::DNSSECRData;
+
+    rr_set
+        .rrsigs()
+        .iter()
+        .filter_map(|rrsig| match *rrsig.rdata() {
+            RData::DNSSEC(DNSSECRData::SIG(ref sig)) => Some(u64::from(sig.sig_expiration())),
+            _ => None,
+        })
+        .min()
+}
+
+/// Signs the record set with each of the keys, replacing its RRSIGs
+///
+/// The inception is back-dated, and the expiration brought forward by a random jitter, see
+///  `SignatureTimes`.
+#[cfg(feature = "dnssec")]
+fn sign_rr_set(
+    rr_set: &mut RecordSet,
+    secure_keys: &[&Signer],
+    class: DNSClass,
+    zone_ttl: u32,
+    now: u64,
+    signature_times: &SignatureTimes,
+) -> DnsSecResult<()> {
+    use rand::{self, Rng};
+    use trust_dns::rr::dnssec::tbs;
+    use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType, SIG};
+
+    let inception = now.saturating_sub(signature_times.inception.as_secs());
+
+    rr_set.clear_rrsigs();
+    let rrsig_temp = Record::with(
+        rr_set.name().clone(),
+        RecordType::DNSSEC(DNSSECRecordType::RRSIG),
+        zone_ttl,
+    );
+
+    for signer in secure_keys {
+        debug!(
+            "signing rr_set: {}, {} with: {}",
+            rr_set.name(),
+            rr_set.record_type(),
+            signer.algorithm(),
+        );
+
+        let validity = sig_validity(signer);
+        let jitter = match signature_times.jitter(validity) {
+            0 => 0,
+            jitter => rand::thread_rng().gen_range(0, jitter + 1),
+        };
+        let expiration = now + validity - jitter;
+
+        let tbs = tbs::rrset_tbs(
+            rr_set.name(),
+            class,
+            rr_set.name().num_labels(),
+            rr_set.record_type(),
+            signer.algorithm(),
+            rr_set.ttl(),
+            expiration as u32,
+            inception as u32,
+            signer.calculate_key_tag()?,
+            signer.signer_name(),
+            // TODO: this is a nasty clone... the issue is that the vec
+            //  from records is of Vec<&R>, but we really want &[R]
+            &rr_set
+                .records_without_rrsigs()
+                .into_iter()
+                .cloned()
+                .collect::<Vec<Record>>(),
+        );
+
+        // TODO, maybe chain these with some ETL operations instead?
+        let tbs = match tbs {
+            Ok(tbs) => tbs,
+            Err(err) => {
+                error!("could not serialize rrset to sign: {}", err);
+                continue;
+            }
+        };
+
+        let signature = signer.sign(&tbs);
+        let signature = match signature {
+            Ok(signature) => signature,
+            Err(err) => {
+                error!("could not sign rrset: {}", err);
+                continue;
+            }
+        };
+
+        let mut rrsig = rrsig_temp.clone();
+        rrsig.set_rdata(RData::DNSSEC(DNSSECRData::SIG(SIG::new(
+            // type_covered: RecordType,
+            rr_set.record_type(),
+            // algorithm: Algorithm,
+            signer.algorithm(),
+            // num_labels: u8,
+            rr_set.name().num_labels(),
+            // original_ttl: u32,
+            rr_set.ttl(),
+            // sig_expiration: u32,
+            expiration as u32,
+            // sig_inception: u32,
+            inception as u32,
+            // key_tag: u16,
+            signer.calculate_key_tag()?,
+            // signer_name: Name,
+            signer.signer_name().clone(),
+            // sig: Vec<u8>
+            signature,
+        ))));
+
+        rr_set.insert_rrsig(rrsig);
+    }
+
+    Ok(())
 }
@@ -18,19 +18,32 @@
 //  then, if requested, do a recursive lookup... i.e. the catalog would only point to files.
 use std::collections::HashMap;
 use std::io;
-use std::sync::RwLock;
+use std::mem;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use trust_dns::op::{Edns, Header, MessageType, OpCode, LowerQuery, ResponseCode};
-use trust_dns::rr::{LowerName, RecordType};
-use trust_dns::rr::dnssec::{Algorithm, SupportedAlgorithms};
+use trust_dns::rr::{LowerName, RData, Record, RecordType};
+use trust_dns::rr::dnssec::{Algorithm, SupportedAlgorithms, TSigner};
+use trust_dns::rr::rdata::TSIG;
 use trust_dns::rr::rdata::opt::{EdnsCode, EdnsOption};
-use server::{Request, RequestHandler, ResponseHandler};
+use trust_dns::serialize::binary::{BinEncodable, BinEncoder};
+use server::{Protocol, Request, RequestHandler, ResponseHandler, ServerCookies, TsigMessage,
+             TsigResponseHandle};
 
-use authority::{AuthLookup, Authority, MessageRequest, MessageResponse, ZoneType};
+use authority::{serial_ge, AuthLookup, Authority, MessageRequest, MessageResponse, ZoneDiff,
+                ZoneType};
+
+/// Upper bound on the size of the records packed into a single message of a zone transfer. This
+///  leaves room in the 64k TCP message for the header, the question and any EDNS or signature
+///  records.
+const MAX_TRANSFER_PAYLOAD: usize = 60 * 1024;
 
 /// Set of authorities, zones, available to this server.
 pub struct Catalog {
-    authorities: HashMap<LowerName, RwLock<Authority>>,
+    authorities: HashMap<LowerName, Arc<RwLock<Authority>>>,
+    cookies: Option<Arc<ServerCookies>>,
 }
 
 fn send_response<R: ResponseHandler + 'static>(
@@ -59,6 +72,98 @@ fn send_response<R: ResponseHandler + 'static>(
     response_handle.send(response)
 }
 
+/// Returns the serial of the first SOA in the records, e.g. the requestor's SOA carried in the
+///  authority section of an IXFR request, or the hint in the answer section of a NOTIFY
+fn soa_serial(records: &[Record]) -> Option<u32> {
+    records
+        .iter()
+        .filter_map(|record| {
+            if let RData::SOA(ref soa) = *record.rdata() {
+                Some(soa.serial())
+            } else {
+                None
+            }
+        })
+        .next()
+}
+
+fn send_error<R: ResponseHandler + 'static>(
+    response_edns: Option<Edns>,
+    request: &MessageRequest,
+    response_code: ResponseCode,
+    response_handle: R,
+) -> io::Result<()> {
+    let response = MessageResponse::new(Some(request.raw_queries()));
+    send_response(
+        response_edns,
+        response.error_msg(request.id(), request.op_code(), response_code),
+        response_handle,
+    )
+}
+
+/// Responds to a request with a TSIG which could not be verified, RFC 8945 section 5.2
+///
+/// The TSIG of the response carries the error: `BADKEY`, `BADSIG` or `BADTIME`. Only a `BADTIME`
+///  response is signed, with the key of the request, which must be passed as `signer`, the others
+///  are not signed.
+fn send_tsig_error<R: ResponseHandler + 'static>(
+    request: &MessageRequest,
+    error: ResponseCode,
+    signer: Option<&TSigner>,
+    response_handle: R,
+) -> io::Result<()> {
+    let response = MessageResponse::new(Some(request.raw_queries())).error_msg(
+        request.id(),
+        request.op_code(),
+        ResponseCode::NotAuth,
+    );
+
+    let tsig = match request.tsig() {
+        Some(tsig) => tsig,
+        None => return response_handle.send(response),
+    };
+
+    let rdata = match *tsig.rdata() {
+        RData::TSIG(ref rdata) => rdata,
+        _ => return response_handle.send(response),
+    };
+
+    let tsig = match signer {
+        Some(signer) if error == ResponseCode::BADTIME => {
+            let mut buffer = Vec::with_capacity(512);
+            {
+                let mut encoder: BinEncoder = BinEncoder::new(&mut buffer);
+                response.emit(&mut encoder).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("error encoding message: {}", e),
+                    )
+                })?;
+            }
+
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|_| io::Error::new(io::ErrorKind::Other, "time is before the Unix epoch"))?
+                .as_secs();
+
+            signer.sign_badtime(&buffer, rdata, now).map_err(|e| {
+                io::Error::new(io::ErrorKind::Other, format!("error signing message: {}", e))
+            })?
+        }
+        _ => TSIG::new(
+            rdata.algorithm().clone(),
+            rdata.time(),
+            rdata.fudge(),
+            Vec::new(),
+            request.id(),
+            error.into(),
+            Vec::new(),
+        ).into_record(tsig.name().clone()),
+    };
+
+    response_handle.send(TsigMessage::new(response, tsig))
+}
+
 impl RequestHandler for Catalog {
     /// Determine's what needs to happen given the type of request, i.e. Query or Update.
     ///
@@ -74,7 +179,7 @@ impl RequestHandler for Catalog {
         let request_message = &request.message;
         trace!("request: {:?}", request_message);
 
-        let response_edns: Option<Edns>;
+        let mut response_edns: Option<Edns>;
 
         // check if it's edns
         if let Some(req_edns) = request_message.edns() {
@@ -109,40 +214,53 @@ impl RequestHandler for Catalog {
             response_edns = None;
         }
 
-        match request_message.message_type() {
-            // TODO think about threading query lookups for multiple lookups, this could be a huge improvement
-            //  especially for recursive lookups
-            MessageType::Query => match request_message.op_code() {
-                OpCode::Query => {
-                    return self.lookup(request_message, response_edns, response_handle)
-                }
-                OpCode::Update => {
-                    return self.update(request_message, response_edns, response_handle)
-                }
-                c @ _ => {
-                    error!("unimplemented op_code: {:?}", c);
-                    let response = MessageResponse::new(Some(request_message.raw_queries()));
-                    return response_handle.send(response.error_msg(
-                        request_message.id(),
-                        request_message.op_code(),
-                        ResponseCode::NotImp,
-                    ));
+        // a request with a cookie is answered with a fresh server cookie, RFC 7873 section 5.2
+        if let Some(ref mut resp_edns) = response_edns {
+            match self.check_cookie(request, resp_edns) {
+                Ok(()) => (),
+                Err(response_code) => {
+                    let mut resp_edns = resp_edns.clone();
+                    resp_edns.set_rcode_high(response_code.high() as u8);
+                    return send_error(
+                        Some(resp_edns),
+                        request_message,
+                        response_code,
+                        response_handle,
+                    );
                 }
-            },
-            MessageType::Response => {
-                warn!(
-                    "got a response as a request from id: {}",
-                    request_message.id()
-                );
-                let response = MessageResponse::new(Some(request_message.raw_queries()));
+            }
+        }
 
-                return response_handle.send(response.error_msg(
-                    request_message.id(),
-                    request_message.op_code(),
+        // a request signed with TSIG is verified with the keys of its zone, and the response signed
+        if let Some(tsig) = request_message.tsig() {
+            return match self.verify_tsig(request_message) {
+                Ok(signer) => {
+                    let request_mac = match *tsig.rdata() {
+                        RData::TSIG(ref rdata) => rdata.mac().to_vec(),
+                        _ => Vec::new(),
+                    };
+                    let response_handle =
+                        TsigResponseHandle::new(response_handle, signer, &request_mac);
+                    self.dispatch(request, response_edns, response_handle)
+                }
+                Err(ResponseCode::FormErr) => send_error(
+                    response_edns,
+                    request_message,
                     ResponseCode::FormErr,
-                ));
-            }
-        };
+                    response_handle,
+                ),
+                // the signature is valid, only the time is not, the error is signed with the key
+                Err(ResponseCode::BADTIME) => send_tsig_error(
+                    request_message,
+                    ResponseCode::BADTIME,
+                    self.find_tsig_key(request_message).as_ref(),
+                    response_handle,
+                ),
+                Err(error) => send_tsig_error(request_message, error, None, response_handle),
+            };
+        }
+
+        self.dispatch(request, response_edns, response_handle)
     }
 }
 
@@ -151,7 +269,59 @@ impl Catalog {
     pub fn new() -> Self {
         Catalog {
             authorities: HashMap::new(),
+            cookies: None,
+        }
+    }
+
+    /// Enables DNS Cookies, RFC 7873, the server cookies are minted and checked with the secret
+    ///
+    /// Requests over UDP with a server cookie which is not valid, e.g. it expired or was minted by
+    ///  another server, are answered with `BADCOOKIE` and a fresh server cookie. Requests without
+    ///  a server cookie are answered as usual, with a fresh server cookie.
+    pub fn set_server_cookies(&mut self, cookies: Arc<ServerCookies>) {
+        self.cookies = Some(cookies);
+    }
+
+    /// Checks the cookie of the request, and adds the fresh cookie for the client to the response
+    ///
+    /// Returns `FORMERR` for malformed cookies, and `BADCOOKIE` for server cookies over UDP which
+    ///  are not valid.
+    fn check_cookie(&self, request: &Request, response_edns: &mut Edns) -> Result<(), ResponseCode> {
+        let cookies = match self.cookies {
+            Some(ref cookies) => cookies,
+            None => return Ok(()),
+        };
+
+        let cookie = match request
+            .message
+            .edns()
+            .and_then(|edns| edns.option(&EdnsCode::Cookie))
+        {
+            Some(&EdnsOption::Cookie(ref cookie)) => cookie,
+            Some(_) => return Err(ResponseCode::FormErr),
+            None => return Ok(()),
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_secs() as u32)
+            .unwrap_or(0);
+        let client_ip = request.src.ip();
+
+        match cookies.mint(cookie.client(), client_ip, now) {
+            Ok(fresh) => response_edns.set_option(EdnsOption::Cookie(fresh)),
+            Err(e) => warn!("could not mint server cookie: {}", e),
+        }
+
+        // over TCP the address of the client is already verified by the handshake
+        if cookie.has_server() && request.protocol == Protocol::Udp
+            && !cookies.is_valid(cookie, client_ip, now)
+        {
+            debug!("bad server cookie from: {}", client_ip);
+            return Err(ResponseCode::BADCOOKIE);
         }
+
+        Ok(())
     }
 
     /// Insert or update a zone authority
@@ -161,7 +331,17 @@ impl Catalog {
     /// * `name` - zone name, e.g. example.com.
     /// * `authority` - the zone data
     pub fn upsert(&mut self, name: LowerName, authority: Authority) {
-        self.authorities.insert(name, RwLock::new(authority));
+        self.authorities.insert(name, Arc::new(RwLock::new(authority)));
+    }
+
+    /// Returns the authority for the zone, which may be shared with background tasks, e.g. the
+    ///  refresh of a Slave zone, see `SlaveZone`
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - zone name, e.g. example.com.
+    pub fn authority(&self, name: &LowerName) -> Option<Arc<RwLock<Authority>>> {
+        self.authorities.get(name).cloned()
     }
 
     /// Update the zone given the Update request.
@@ -260,7 +440,12 @@ impl Catalog {
                     let update_result = authority.update(update);
                     match update_result {
                         // successful update
-                        Ok(..) => {
+                        Ok(updated) => {
+                            // let the slaves know there is a new version of the zone
+                            if updated {
+                                authority.notify_slaves();
+                            }
+
                             response_header.set_response_code(ResponseCode::NoError);
                         }
                         Err(response_code) => {
@@ -295,6 +480,110 @@ impl Catalog {
         }
     }
 
+    /// Handles a NOTIFY from a master that a Slave zone has changed.
+    ///
+    /// [RFC 1996](https://tools.ietf.org/html/rfc1996), DNS NOTIFY, August 1996
+    ///
+    /// ```text
+    /// 3.7. A NOTIFY request has QDCOUNT>0, ANCOUNT>=0, AUCOUNT>=0,
+    /// ADCOUNT>=0.  If ANCOUNT>0, then the answer section represents an
+    /// unsecure hint at the new RRset for this <QNAME,QCLASS,QTYPE>.  A
+    /// slave receiving such a hint is free to treat equivilence of this
+    /// answer section with its local data as a "no further work needs to be
+    /// done" indication.  If ANCOUNT=0, or ANCOUNT>0 and the answer section
+    /// differs from the slave's local data, then the slave should query its
+    /// known masters to retrieve the new data.
+    ///
+    /// 4.7 Slave Receives a NOTIFY Request from a Master
+    ///
+    ///    When a slave server receives a NOTIFY request from one of its locally
+    ///    designated masters for the zone enclosing the given QNAME, with
+    ///    QTYPE=SOA and QR=0, it should enter the state it would if the zone's
+    ///    refresh timer had expired.  It will also send a NOTIFY response back
+    ///    to the NOTIFY request's source, with the following characteristics:
+    ///
+    ///       query ID:   (same)
+    ///       op:         NOTIFY (4)
+    ///       resp:       NOERROR
+    ///       flags:      QR AA
+    ///       qcount:     1
+    ///       qname:      (zone name)
+    ///       qclass:     (zone class)
+    ///       qtype:      T_SOA
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the NOTIFY message, and the address it was received from
+    /// * `response_handle` - sink for the response message to be sent
+    pub fn notify<'q, R: ResponseHandler + 'static>(
+        &self,
+        request: &'q Request,
+        response_edns: Option<Edns>,
+        response_handle: R,
+    ) -> io::Result<()> {
+        let message = &request.message;
+        let queries = message.queries();
+
+        if queries.len() != 1 {
+            warn!("notify: {} must have exactly one query", message.id());
+            return send_error(response_edns, message, ResponseCode::FormErr, response_handle);
+        }
+
+        // only changes to the SOA, i.e. a new version of the zone, are supported
+        if queries[0].query_type() != RecordType::SOA {
+            return send_error(response_edns, message, ResponseCode::NotImp, response_handle);
+        }
+
+        let authority = match self.authorities.get(queries[0].name()) {
+            Some(authority) => authority.read().unwrap(), // poison errors should panic
+            None => {
+                return send_error(response_edns, message, ResponseCode::NotAuth, response_handle)
+            }
+        };
+
+        if authority.zone_type() != ZoneType::Slave {
+            return send_error(response_edns, message, ResponseCode::NotAuth, response_handle);
+        }
+
+        if !authority.is_notify_allowed(&request.src.ip()) {
+            warn!(
+                "notify: {} of {} not allowed from: {}",
+                message.id(),
+                authority.origin(),
+                request.src
+            );
+            return send_error(response_edns, message, ResponseCode::Refused, response_handle);
+        }
+
+        // the SOA in the answer is a hint, if we already have it there is nothing to do
+        let hint = soa_serial(message.answers());
+        if hint.map_or(false, |serial| serial_ge(authority.serial(), serial)) {
+            info!(
+                "notify: {} zone: {} is up to date at: {}",
+                message.id(),
+                authority.origin(),
+                authority.serial()
+            );
+        } else if !authority.trigger_refresh() {
+            warn!("notify: {} zone: {} is not being refreshed", message.id(), authority.origin());
+        }
+
+        let mut response = MessageResponse::new(Some(message.raw_queries()));
+        let mut response_header = Header::new();
+        response_header.set_id(message.id());
+        response_header.set_op_code(OpCode::Notify);
+        response_header.set_message_type(MessageType::Response);
+        response_header.set_response_code(ResponseCode::NoError);
+        response_header.set_authoritative(true);
+
+        send_response(
+            response_edns,
+            response.build(response_header),
+            response_handle,
+        )
+    }
+
     /// Given the requested query, lookup and return any matching results.
     ///
     /// # Arguments
@@ -318,6 +607,35 @@ impl Catalog {
                     authority.origin()
                 );
 
+                if authority.is_expired() {
+                    warn!("request: {} zone has expired: {}", request.id(), authority.origin());
+                    return send_error(
+                        response_edns,
+                        request,
+                        ResponseCode::ServFail,
+                        response_handle,
+                    );
+                }
+
+                // Forward and Hint zones are resolved elsewhere, the response is sent when that
+                //  completes
+                if let ZoneType::Forward | ZoneType::Hint = authority.zone_type() {
+                    return match authority.forwarder() {
+                        Some(forwarder) => {
+                            forwarder.forward(request, response_edns, response_handle)
+                        }
+                        None => {
+                            warn!("request: {} no forwarder: {}", request.id(), authority.origin());
+                            send_error(
+                                response_edns,
+                                request,
+                                ResponseCode::ServFail,
+                                response_handle,
+                            )
+                        }
+                    };
+                }
+
                 let mut response = MessageResponse::new(Some(request.raw_queries()));
                 let mut response_header = Header::new();
                 response_header.set_id(request.id());
@@ -348,6 +666,20 @@ impl Catalog {
                     );
                 }
 
+                // names below a zone cut are answered with a referral to the child zone
+                if let Some((ns, glue)) = authority.referral(query.name(), query.query_type()) {
+                    info!("request: {} referral for: {}", request.id(), query.name());
+                    response_header.set_response_code(ResponseCode::NoError);
+                    response.name_servers(ns);
+                    response.additionals(glue);
+
+                    return send_response(
+                        response_edns,
+                        response.build(response_header),
+                        response_handle,
+                    );
+                }
+
                 let records = authority.search(query, is_dnssec, supported_algorithms);
                 if !records.is_empty() {
                     response_header.set_response_code(ResponseCode::NoError);
@@ -428,18 +760,404 @@ impl Catalog {
         );
     }
 
+    /// Performs an outbound zone transfer, AXFR or IXFR, of the requested zone.
+    ///
+    /// [RFC 5936](https://tools.ietf.org/html/rfc5936), DNS Zone Transfer Protocol (AXFR), June 2010
+    ///
+    /// ```text
+    /// 2.2.  AXFR Response
+    ///
+    ///    The AXFR response will consist of one or more messages.  The special
+    ///    case of a server closing the TCP connection without sending an AXFR
+    ///    response is covered in section 2.3.
+    ///
+    /// 4.2.  UDP
+    ///
+    ///    With the addition of EDNS0 and applications that require many small
+    ///    zones, such as in web hosting and some ENUM scenarios, AXFR sessions
+    ///    on UDP would now seem desirable.  However, there are still some
+    ///    aspects of AXFR sessions that are not easily translated to UDP.
+    ///
+    ///    Therefore, this document does not update RFC 1035 in this respect:
+    ///    AXFR sessions over UDP transport are not defined.
+    /// ```
+    ///
+    /// [RFC 1995](https://tools.ietf.org/html/rfc1995), Incremental Zone Transfer in DNS, August 1996
+    ///
+    /// ```text
+    /// 2. Brief Description of the Protocol
+    ///
+    ///    If an IXFR query with the same or newer version number than that of
+    ///    the server is received, it is replied to with a single SOA record of
+    ///    the server's current version, just as in AXFR.
+    ///
+    ///    Transport of a query may be by either UDP or TCP.  If an IXFR query
+    ///    is via UDP, the IXFR server may attempt to reply using UDP if the
+    ///    entire response can be contained in a single DNS packet.  If the UDP
+    ///    reply does not fit, the query is responded to with a single SOA
+    ///    record of the server's current version to inform the client that a
+    ///    TCP query should be initiated.
+    ///
+    /// 4. Response Format
+    ///
+    ///    If incremental zone transfer is not available, the entire zone is
+    ///    returned.  The first and the last RR of the response is the SOA
+    ///    record of the zone.  I.e. the behavior is the same as an AXFR
+    ///    response except the query type is IXFR.
+    /// ```
+    ///
+    /// The zone is only transferred over TCP (or TLS), and only to addresses in the zone's allowed
+    ///  transfer list, otherwise REFUSED is returned. IXFR over UDP is always answered with the
+    ///  current SOA. The IXFR difference sequences are built from the zone's journal, if the
+    ///  requested version is no longer available, the entire zone is sent. The records are split
+    ///  across as many messages as necessary.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the AXFR or IXFR request, with the source address and protocol of the requestor
+    /// * `response_handle` - sink for the response messages to be sent
+    pub fn transfer<'q, R: ResponseHandler + 'static>(
+        &self,
+        request: &'q Request,
+        response_edns: Option<Edns>,
+        response_handle: R,
+    ) -> io::Result<()> {
+        let message = &request.message;
+        let queries = message.queries();
+
+        if queries.len() != 1 {
+            warn!("transfer request: {} must have exactly one query", message.id());
+            return send_error(response_edns, message, ResponseCode::FormErr, response_handle);
+        }
+
+        let query_type = queries[0].query_type();
+        if query_type == RecordType::AXFR && !request.protocol.is_stream() {
+            warn!(
+                "request: {} refusing AXFR over {:?} from: {}",
+                message.id(),
+                request.protocol,
+                request.src
+            );
+            return send_error(response_edns, message, ResponseCode::Refused, response_handle);
+        }
+
+        // transfers are only for the zone itself, not any names within the zone
+        let authority = match self.authorities.get(queries[0].name()) {
+            Some(authority) => authority.read().unwrap(), // poison errors should panic
+            None => {
+                return send_error(response_edns, message, ResponseCode::NotAuth, response_handle)
+            }
+        };
+
+        if authority.is_expired() {
+            warn!("request: {} zone has expired: {}", message.id(), authority.origin());
+            return send_error(response_edns, message, ResponseCode::ServFail, response_handle);
+        }
+
+        match authority.zone_type() {
+            ZoneType::Master | ZoneType::Slave => (),
+            _ => {
+                return send_error(response_edns, message, ResponseCode::NotAuth, response_handle)
+            }
+        }
+
+        // as for updates, both the address and the key of the request must be allowed
+        if !authority.is_transfer_allowed(&request.src.ip()) {
+            warn!(
+                "request: {} {} of {} not allowed from: {}",
+                message.id(),
+                query_type,
+                authority.origin(),
+                request.src
+            );
+            return send_error(response_edns, message, ResponseCode::Refused, response_handle);
+        }
+
+        if let Err(response_code) = authority.authorize_transfer(message) {
+            warn!(
+                "request: {} {} of {} not authorized: {}",
+                message.id(),
+                query_type,
+                authority.origin(),
+                response_code
+            );
+            return send_error(response_edns, message, ResponseCode::Refused, response_handle);
+        }
+
+        let soa = match authority.soa() {
+            AuthLookup::Records(soa) => soa.first().map(|soa| *soa),
+            AuthLookup::NameExists | AuthLookup::NoName => None,
+        };
+        let soa = match soa {
+            Some(soa) => soa,
+            None => {
+                warn!("there is no SOA record for: {:?}", authority.origin());
+                return send_error(response_edns, message, ResponseCode::ServFail, response_handle);
+            }
+        };
+
+        let diffs: Option<Vec<ZoneDiff>>;
+        let records: Vec<&Record> = if query_type == RecordType::IXFR {
+            let serial = match soa_serial(message.name_servers()) {
+                Some(serial) => serial,
+                None => {
+                    warn!("IXFR request: {} is missing the SOA", message.id());
+                    return send_error(
+                        response_edns,
+                        message,
+                        ResponseCode::FormErr,
+                        response_handle,
+                    );
+                }
+            };
+
+            diffs = if request.protocol.is_stream() && !serial_ge(serial, authority.serial()) {
+                authority.journal_diffs(serial)
+            } else {
+                // the requestor is up to date, or needs to retry over TCP
+                Some(vec![])
+            };
+
+            match diffs {
+                Some(ref diffs) => {
+                    let mut records: Vec<&Record> = vec![soa];
+                    if !diffs.is_empty() {
+                        records.extend(diffs.iter().flat_map(|diff| diff.records()));
+                        records.push(soa);
+                    }
+                    records
+                }
+                None => {
+                    debug!(
+                        "request: {} no IXFR from serial: {}, sending zone",
+                        message.id(),
+                        serial
+                    );
+                    authority.transfer_records().unwrap_or_default()
+                }
+            }
+        } else {
+            authority.transfer_records().unwrap_or_default()
+        };
+
+        info!(
+            "request: {} {} of zone: {} records: {} to: {}",
+            message.id(),
+            query_type,
+            authority.origin(),
+            records.len(),
+            request.src
+        );
+
+        // pack the records into as few messages as possible
+        let mut messages: Vec<Vec<&Record>> = vec![];
+        let mut answers: Vec<&Record> = vec![];
+        let mut payload_size = 0;
+        for record in records {
+            // this is the uncompressed size, and therefore an upper bound for the message
+            let record_size = record
+                .to_bytes()
+                .map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("error encoding record: {}", e),
+                    )
+                })?
+                .len();
+
+            if !answers.is_empty() && payload_size + record_size > MAX_TRANSFER_PAYLOAD {
+                messages.push(mem::replace(&mut answers, vec![]));
+                payload_size = 0;
+            }
+
+            payload_size += record_size;
+            answers.push(record);
+        }
+        messages.push(answers);
+
+        for (i, answers) in messages.into_iter().enumerate() {
+            // only the first message is required to carry the question
+            let mut response = if i == 0 {
+                MessageResponse::new(Some(message.raw_queries()))
+            } else {
+                MessageResponse::new(None)
+            };
+
+            let mut response_header = Header::new();
+            response_header.set_id(message.id());
+            response_header.set_op_code(OpCode::Query);
+            response_header.set_message_type(MessageType::Response);
+            response_header.set_response_code(ResponseCode::NoError);
+            response_header.set_authoritative(true);
+
+            response.answers(answers);
+
+            send_response(
+                response_edns.clone(),
+                response.build(response_header),
+                response_handle.clone(),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Dispatches the request by its type and op code, i.e. Query or Update
+    fn dispatch<'q, R: ResponseHandler + 'static>(
+        &self,
+        request: &'q Request,
+        response_edns: Option<Edns>,
+        response_handle: R,
+    ) -> io::Result<()> {
+        let request_message = &request.message;
+
+        match request_message.message_type() {
+            // TODO think about threading query lookups for multiple lookups, this could be a huge improvement
+            //  especially for recursive lookups
+            MessageType::Query => match request_message.op_code() {
+                OpCode::Query => {
+                    let is_transfer = request_message
+                        .queries()
+                        .first()
+                        .map_or(false, |q| match q.query_type() {
+                            RecordType::AXFR | RecordType::IXFR => true,
+                            _ => false,
+                        });
+
+                    if is_transfer {
+                        return self.transfer(request, response_edns, response_handle);
+                    }
+
+                    if !self.is_allowed(request, Authority::is_query_allowed) {
+                        return send_error(
+                            response_edns,
+                            request_message,
+                            ResponseCode::Refused,
+                            response_handle,
+                        );
+                    }
+
+                    return self.lookup(request_message, response_edns, response_handle);
+                }
+                OpCode::Update => {
+                    if !self.is_allowed(request, Authority::is_update_allowed) {
+                        return send_error(
+                            response_edns,
+                            request_message,
+                            ResponseCode::Refused,
+                            response_handle,
+                        );
+                    }
+
+                    return self.update(request_message, response_edns, response_handle);
+                }
+                OpCode::Notify => return self.notify(request, response_edns, response_handle),
+                c @ _ => {
+                    error!("unimplemented op_code: {:?}", c);
+                    let response = MessageResponse::new(Some(request_message.raw_queries()));
+                    return response_handle.send(response.error_msg(
+                        request_message.id(),
+                        request_message.op_code(),
+                        ResponseCode::NotImp,
+                    ));
+                }
+            },
+            MessageType::Response => {
+                warn!(
+                    "got a response as a request from id: {}",
+                    request_message.id()
+                );
+                let response = MessageResponse::new(Some(request_message.raw_queries()));
+
+                return response_handle.send(response.error_msg(
+                    request_message.id(),
+                    request_message.op_code(),
+                    ResponseCode::FormErr,
+                ));
+            }
+        };
+    }
+
+    /// Returns true if the source of the request is allowed by the zones of each of its queries,
+    ///  names outside of the catalog are left to the handler of the request
+    fn is_allowed<F>(&self, request: &Request, is_allowed: F) -> bool
+    where
+        F: Fn(&Authority, &IpAddr) -> bool,
+    {
+        let src = request.src.ip();
+        request.message.queries().iter().all(|query| {
+            let authority = match self.find_auth_recurse(query.name()) {
+                Some(authority) => authority.read().unwrap(), // poison errors should panic
+                None => return true,
+            };
+
+            if is_allowed(&authority, &src) {
+                return true;
+            }
+
+            warn!(
+                "request: {} {:?} of {} not allowed from: {}",
+                request.message.id(),
+                request.message.op_code(),
+                authority.origin(),
+                request.src
+            );
+            false
+        })
+    }
+
     /// recursively searches the catalog for a matching auhtority.
     fn find_auth_recurse(&self, name: &LowerName) -> Option<&RwLock<Authority>> {
-        let authority = self.authorities.get(name);
+        let authority = self.authorities.get(name).map(|authority| &**authority);
         if authority.is_some() {
             return authority;
-        } else {
+        } else if !name.is_root() {
+            // down to and including the root zone
             let name = name.base_name();
-            if !name.is_root() {
-                return self.find_auth_recurse(&name);
-            }
+            return self.find_auth_recurse(&name);
         }
 
         None
     }
+
+    /// Verifies the TSIG of the request with the keys of the zone of its query
+    fn verify_tsig(&self, request: &MessageRequest) -> Result<TSigner, ResponseCode> {
+        let authority = match request.queries().first() {
+            Some(query) => self.find_auth_recurse(query.name()),
+            None => return Err(ResponseCode::FormErr),
+        };
+
+        let authority = match authority {
+            Some(authority) => authority.read().unwrap(), // poison errors should panic
+            None => return Err(ResponseCode::BADKEY),
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| ResponseCode::ServFail)?
+            .as_secs();
+
+        let signer = authority.verify_tsig(request, now)?.clone();
+        Ok(signer)
+    }
+
+    /// Returns the key of the zone of its query with which the request is signed, if any, without
+    ///  verifying the TSIG
+    fn find_tsig_key(&self, request: &MessageRequest) -> Option<TSigner> {
+        let authority = request
+            .queries()
+            .first()
+            .and_then(|query| self.find_auth_recurse(query.name()));
+        let authority = match authority {
+            Some(authority) => authority.read().unwrap(), // poison errors should panic
+            None => return None,
+        };
+
+        request.tsig().and_then(|record| match *record.rdata() {
+            RData::TSIG(ref tsig) => authority
+                .find_tsig_key(record.name(), tsig.algorithm())
+                .cloned(),
+            _ => None,
+        })
+    }
 }
@@ -0,0 +1,186 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Forwarding of the requests for a zone to upstream resolvers, or to a recursive resolver
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use futures::Future;
+use tokio_core::reactor::Handle;
+
+use trust_dns::op::{Edns, Message, MessageType, OpCode, ResponseCode};
+use trust_dns::rr::{Name, Record};
+use trust_dns_resolver::{Recursor, ResolverFuture};
+use trust_dns_resolver::config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
+use trust_dns_resolver::error::{ResolveError, ResolveErrorKind};
+use trust_dns_resolver::lookup::Lookup;
+
+use authority::MessageRequest;
+use server::ResponseHandler;
+
+/// Maximum TTL as defined in https://tools.ietf.org/html/rfc2181
+const MAX_TTL: u64 = 2147483647;
+
+/// Resolves the requests for a Forward zone through upstream name servers, or for a Hint zone
+///  recursively from the root name servers
+///
+/// Lookups are made with a `ResolverFuture` or a `Recursor`, and so are answered from their cache
+///  while the records are valid. The responses are not authoritative, and have recursion
+///  available, RA, set.
+pub struct Forwarder {
+    resolver: Resolver,
+    handle: Handle,
+}
+
+enum Resolver {
+    Forward(ResolverFuture),
+    Recursive(Recursor),
+}
+
+impl Forwarder {
+    /// Creates a new Forwarder, all lookups are sent to the name servers, over UDP with a fallback
+    ///  to TCP.
+    ///
+    /// # Arguments
+    ///
+    /// * `name_servers` - addresses of the upstream resolvers
+    /// * `handle` - handle to the reactor on which the lookups are run
+    pub fn new(name_servers: &[SocketAddr], handle: &Handle) -> Self {
+        let mut config = ResolverConfig::from_parts(None, vec![], vec![]);
+        for name_server in name_servers {
+            config.add_name_server(NameServerConfig {
+                socket_addr: *name_server,
+                protocol: Protocol::Udp,
+            });
+            config.add_name_server(NameServerConfig {
+                socket_addr: *name_server,
+                protocol: Protocol::Tcp,
+            });
+        }
+
+        // names of the zone are served as is, the local hosts file does not apply
+        let mut options = ResolverOpts::default();
+        options.use_hosts_file = false;
+
+        Forwarder {
+            resolver: Resolver::Forward(ResolverFuture::new(config, options, handle)),
+            handle: handle.clone(),
+        }
+    }
+
+    /// Creates a new Forwarder which resolves all lookups itself, following the referrals from
+    ///  the root name servers.
+    ///
+    /// # Arguments
+    ///
+    /// * `recursor` - the recursive resolver, see `Recursor::new`
+    /// * `handle` - handle to the reactor on which the lookups are run
+    pub fn recursive(recursor: Recursor, handle: &Handle) -> Self {
+        Forwarder {
+            resolver: Resolver::Recursive(recursor),
+            handle: handle.clone(),
+        }
+    }
+
+    /// Resolves the query of the request, the response is sent once the lookup completes.
+    ///
+    /// The resolver does not yet distinguish a name which does not exist from one which has no
+    ///  records of the requested type, both are answered with NoError and no records.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the query to forward, only the first query of the request is resolved
+    /// * `response_edns` - EDNS to attach to the response
+    /// * `response_handle` - sink for the response message to be sent
+    pub fn forward<R: ResponseHandler + 'static>(
+        &self,
+        request: &MessageRequest,
+        response_edns: Option<Edns>,
+        response_handle: R,
+    ) -> io::Result<()> {
+        let mut response = Message::new();
+        response
+            .set_id(request.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(request.recursion_desired())
+            .set_recursion_available(true);
+        if let Some(edns) = response_edns {
+            response.set_edns(edns);
+        }
+
+        let query = match request.queries().first() {
+            Some(query) => query.original().clone(),
+            None => {
+                response.set_response_code(ResponseCode::FormErr);
+                return response_handle.send(response);
+            }
+        };
+        response.add_query(query.clone());
+
+        let id = request.id();
+        let name = query.name().clone();
+        let record_type = query.query_type();
+        debug!("request: {} forwarding: {} {:?}", id, name, record_type);
+
+        let records: Box<Future<Item = Vec<Record>, Error = ResolveError>> = match self.resolver {
+            Resolver::Forward(ref resolver) => Box::new(
+                resolver
+                    .lookup(name.clone(), record_type)
+                    .map(move |lookup| records(&name, &lookup)),
+            ),
+            Resolver::Recursive(ref recursor) => Box::new(recursor.resolve(query)),
+        };
+
+        self.handle.spawn(
+            records
+                .then(move |records| {
+                    match records {
+                        Ok(records) => {
+                            response.set_response_code(ResponseCode::NoError);
+                            response.add_answers(records);
+                        }
+                        Err(e) => match *e.kind() {
+                            // NXDomain or NoData, with the SOA for negative caching downstream
+                            ResolveErrorKind::NoRecordsFound(_, ref soa, response_code) => {
+                                response.set_response_code(response_code);
+                                if let Some(ref soa) = *soa {
+                                    response.add_name_server(soa.clone());
+                                }
+                            }
+                            _ => {
+                                warn!("request: {} resolution failed: {}", id, e);
+                                response.set_response_code(ResponseCode::ServFail);
+                            }
+                        },
+                    }
+
+                    response_handle.send(response)
+                })
+                .map_err(move |e| warn!("request: {} error sending response: {}", id, e)),
+        );
+
+        Ok(())
+    }
+}
+
+/// Converts the lookup into records of the name, with the TTL remaining in the resolver's cache
+fn records(name: &Name, lookup: &Lookup) -> Vec<Record> {
+    let now = Instant::now();
+    let ttl = if lookup.valid_until() > now {
+        (lookup.valid_until() - now).as_secs().min(MAX_TTL) as u32
+    } else {
+        0
+    };
+
+    lookup
+        .iter()
+        .map(|rdata| Record::from_rdata(name.clone(), ttl, rdata.to_record_type(), rdata.clone()))
+        .collect()
+}
@@ -0,0 +1,162 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The states of the DNSSEC keys of a zone over time, for key rollovers
+
+use trust_dns::error::*;
+
+/// The state of a key of a zone at some time, see `KeyTiming`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyState {
+    /// The key is not yet in the zone
+    Created,
+    /// The DNSKEY is in the zone, but the key does not sign, e.g. a ZSK published ahead of its use
+    ///  so that resolvers have it cached by the time its signatures are served
+    Published,
+    /// The DNSKEY is in the zone and the key signs
+    Active,
+    /// The DNSKEY is still in the zone, for the signatures resolvers have cached, but the key no
+    ///  longer signs
+    Retired,
+    /// The key is no longer in the zone
+    Removed,
+}
+
+impl KeyState {
+    /// True if the DNSKEY of the key is in the zone in this state
+    pub fn is_published(&self) -> bool {
+        match *self {
+            KeyState::Published | KeyState::Active | KeyState::Retired => true,
+            KeyState::Created | KeyState::Removed => false,
+        }
+    }
+
+    /// True if the key signs the zone in this state
+    pub fn is_active(&self) -> bool {
+        *self == KeyState::Active
+    }
+}
+
+/// When a key of a zone is published, activated, retired and removed, in seconds since the unix
+///  epoch
+///
+/// [RFC 6781](https://tools.ietf.org/html/rfc6781#section-4.1), DNSSEC Operational Practices,
+///  describes the rollovers which are driven by these times. A ZSK is rolled by pre-publishing the
+///  new key ahead of its activation, and retiring the old key when the new one is activated; the
+///  old key is removed once its signatures have expired from caches. A KSK is rolled by double
+///  signature, both keys sign the DNSKEY RRset until the DS of the new key has replaced that of the
+///  old key at the parent, see the CDS and CDNSKEY published by `Authority`.
+///
+/// The default is a key which is published and active from the start, and never retired.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyTiming {
+    publish: Option<u64>,
+    activate: Option<u64>,
+    retire: Option<u64>,
+    remove: Option<u64>,
+}
+
+impl KeyTiming {
+    /// Creates a new KeyTiming
+    ///
+    /// # Arguments
+    ///
+    /// * `publish` - the DNSKEY is added to the zone at this time, from the start if None
+    /// * `activate` - the key signs the zone from this time, as soon as it is published if None
+    /// * `retire` - the key no longer signs the zone from this time, when it is removed if None
+    /// * `remove` - the DNSKEY is removed from the zone at this time, never if None
+    ///
+    /// # Return value
+    ///
+    /// An error if the times are not in this order
+    pub fn new(
+        publish: Option<u64>,
+        activate: Option<u64>,
+        retire: Option<u64>,
+        remove: Option<u64>,
+    ) -> DnsSecResult<Self> {
+        let timing = KeyTiming {
+            publish: publish,
+            activate: activate,
+            retire: retire,
+            remove: remove,
+        };
+
+        let publish = timing.publish.unwrap_or(0);
+        let activate = timing.activate.unwrap_or(publish);
+        if activate < publish {
+            return Err("key is activated before it is published".into());
+        }
+
+        if let Some(retire) = timing.retire {
+            if retire < activate {
+                return Err("key is retired before it is activated".into());
+            }
+
+            if timing.remove.map_or(false, |remove| remove < retire) {
+                return Err("key is removed before it is retired".into());
+            }
+        } else if timing.remove.map_or(false, |remove| remove < activate) {
+            return Err("key is removed before it is activated".into());
+        }
+
+        Ok(timing)
+    }
+
+    /// The time the DNSKEY is added to the zone
+    pub fn publish(&self) -> Option<u64> {
+        self.publish
+    }
+
+    /// The time the key starts signing the zone
+    pub fn activate(&self) -> Option<u64> {
+        self.activate
+    }
+
+    /// The time the key stops signing the zone
+    pub fn retire(&self) -> Option<u64> {
+        self.retire
+    }
+
+    /// The time the DNSKEY is removed from the zone
+    pub fn remove(&self) -> Option<u64> {
+        self.remove
+    }
+
+    /// The state of the key at `now`
+    pub fn state(&self, now: u64) -> KeyState {
+        let publish = self.publish.unwrap_or(0);
+        let activate = self.activate.unwrap_or(publish);
+        let is_due = |time: Option<u64>| time.map_or(false, |time| time <= now);
+
+        if is_due(self.remove) {
+            KeyState::Removed
+        } else if is_due(self.retire) {
+            KeyState::Retired
+        } else if activate <= now {
+            KeyState::Active
+        } else if publish <= now {
+            KeyState::Published
+        } else {
+            KeyState::Created
+        }
+    }
+
+    /// True if the key is due to be retired, it is not the key the parent should delegate to
+    pub fn is_retiring(&self) -> bool {
+        self.retire.is_some() || self.remove.is_some()
+    }
+
+    /// The time of the next change of state after `now`, None if there is none
+    pub fn next_transition(&self, now: u64) -> Option<u64> {
+        [self.publish, self.activate, self.retire, self.remove]
+            .iter()
+            .filter_map(|time| *time)
+            .filter(|time| *time > now)
+            .min()
+    }
+}
@@ -6,7 +6,7 @@
 // copied, modified, or distributed except according to those terms.
 
 use trust_dns_proto::error::*;
-use trust_dns_proto::rr::Record;
+use trust_dns_proto::rr::{Record, RecordType};
 use trust_dns_proto::serialize::binary::{BinDecodable, BinDecoder, BinEncoder};
 use trust_dns_proto::op::{Edns, EncodableMessage, Header, Message, MessageType, OpCode, ResponseCode};
 use trust_dns::op::LowerQuery;
@@ -21,6 +21,7 @@ pub struct MessageRequest<'r> {
     additionals: Vec<Record>,
     sig0: Vec<Record>,
     edns: Option<Edns>,
+    raw: &'r [u8],
 }
 
 impl<'r> MessageRequest<'r> {
@@ -149,6 +150,19 @@ impl<'r> MessageRequest<'r> {
         &self.sig0
     }
 
+    /// The TSIG record, if the message is signed with TSIG, RFC 8945
+    pub fn tsig(&self) -> Option<&Record> {
+        match self.sig0.last() {
+            Some(record) if record.rr_type() == RecordType::TSIG => Some(record),
+            _ => None,
+        }
+    }
+
+    /// The message as it was received, e.g. for verifying its TSIG
+    pub fn as_bytes(&self) -> &'r [u8] {
+        self.raw
+    }
+
     /// # Return value
     ///
     /// the max payload value as it's defined in the EDNS section.
@@ -178,6 +192,7 @@ impl<'r> BinDecodable<'r> for MessageRequest<'r> {
     // TODO: generify this with Message?
     /// Reads a MessageRequest from the decoder
     fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        let start = decoder.index();
         let header = Header::read(decoder)?;
 
         // TODO/FIXME: return just header, and in the case of the rest of message getting an error.
@@ -204,6 +219,7 @@ impl<'r> BinDecodable<'r> for MessageRequest<'r> {
             additionals: additionals,
             sig0: sig0,
             edns: edns,
+            raw: decoder.slice_from(start)?,
         })
     }
 }
@@ -114,6 +114,12 @@ impl<'q, 'a> MessageResponseBuilder<'q, 'a> {
         self
     }
 
+    /// Associate a set of additional records with the response, generally owned by either a cache or [`trust_dns_server::authorith::Authority`]
+    pub fn additionals(&mut self, records: Vec<&'a Record>) -> &mut Self {
+        self.additionals = Some(records);
+        self
+    }
+
     /// Associate EDNS with the Response
     pub fn edns(&mut self, edns: Edns) -> &mut Self {
         self.edns = Some(edns);
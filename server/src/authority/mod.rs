@@ -34,16 +34,42 @@ pub enum ZoneType {
     Forward,
 }
 
+/// [RFC 1982](https://tools.ietf.org/html/rfc1982) serial number arithmetic, returns true if `s1`
+///  is the same or a newer version than `s2`
+fn serial_ge(s1: u32, s2: u32) -> bool {
+    s1 == s2 || (s1.wrapping_sub(s2) as i32) > 0
+}
+
+mod acl;
 mod auth_lookup;
 pub mod authority;
 mod catalog;
+mod forwarder;
+#[cfg(feature = "dnssec")]
+mod key_timing;
 mod message_request;
 mod message_response;
+mod notifier;
 pub mod persistence;
+#[cfg(feature = "dnssec")]
+mod signed_zone;
+mod slave;
+mod zone_diff;
 
+pub use self::acl::{Acl, Network};
 pub use self::auth_lookup::AuthLookup;
 pub use self::authority::Authority;
+#[cfg(feature = "dnssec")]
+pub use self::authority::Clock;
 pub use self::catalog::Catalog;
+pub use self::forwarder::Forwarder;
+#[cfg(feature = "dnssec")]
+pub use self::key_timing::{KeyState, KeyTiming};
 pub use self::message_request::{MessageRequest, Queries, UpdateRequest};
 pub use self::message_response::{MessageResponse, MessageResponseBuilder};
+pub use self::notifier::Notifier;
 pub use self::persistence::Journal;
+#[cfg(feature = "dnssec")]
+pub use self::signed_zone::SignedZone;
+pub use self::slave::{Refresh, SlaveZone};
+pub use self::zone_diff::ZoneDiff;
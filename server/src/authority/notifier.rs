@@ -0,0 +1,101 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Outbound NOTIFY of zone changes
+
+use std::net::SocketAddr;
+
+use futures::{Future, Stream};
+use futures::sync::mpsc::{unbounded, UnboundedSender};
+use tokio_core::reactor::Handle;
+
+use trust_dns::client::{ClientFuture, ClientHandle, RetryClientHandle};
+use trust_dns::op::ResponseCode;
+use trust_dns::rr::{DNSClass, Record, RecordType};
+use trust_dns::udp::UdpClientStream;
+
+/// Number of times a NOTIFY is sent to a slave which does not respond
+const NOTIFY_ATTEMPTS: usize = 3;
+
+/// Sends NOTIFY messages to the slaves of zones when the zone changes
+///
+/// [RFC 1996](https://tools.ietf.org/html/rfc1996), DNS NOTIFY, August 1996
+///
+/// ```text
+/// 3.5. If TCP is used, both master and slave must continue to offer
+/// name service during the transaction, even when the TCP transaction is
+/// not making progress.  The NOTIFY request is sent once, and a
+/// "timeout" is said to have occurred if no NOTIFY response is received
+/// within a reasonable interval.
+///
+/// 3.6. If UDP is used, a master periodically sends a NOTIFY request to
+/// a slave until either too many copies have been sent (a "timeout"), an
+/// ICMP message indicating that the port is unreachable, or until a
+/// NOTIFY response is received from the slave with a matching query ID,
+/// QNAME, IP source address, and UDP source port number.
+/// ```
+///
+/// The Notifier is a handle to a task on the reactor, it can be cloned and shared between all the
+///  zones of the server, see `Authority::set_notifier()`.
+#[derive(Clone)]
+pub struct Notifier {
+    sender: UnboundedSender<(Record, Vec<SocketAddr>)>,
+}
+
+impl Notifier {
+    /// Spawns the task which sends the NOTIFY messages onto the reactor
+    pub fn spawn(handle: &Handle) -> Self {
+        let (sender, receiver) = unbounded::<(Record, Vec<SocketAddr>)>();
+
+        let loop_handle = handle.clone();
+        handle.spawn(receiver.for_each(move |(soa, targets)| {
+            for target in targets {
+                notify(&soa, target, &loop_handle);
+            }
+
+            Ok(())
+        }));
+
+        Notifier { sender: sender }
+    }
+
+    /// Sends a NOTIFY of the new SOA of a zone to each of the targets
+    ///
+    /// # Arguments
+    ///
+    /// * `soa` - the SOA of the current version of the zone
+    /// * `targets` - addresses of the slaves to notify
+    pub fn notify(&self, soa: Record, targets: Vec<SocketAddr>) {
+        if let Err(e) = self.sender.unbounded_send((soa, targets)) {
+            error!("notifier has stopped: {}", e);
+        }
+    }
+}
+
+fn notify(soa: &Record, target: SocketAddr, handle: &Handle) {
+    info!("sending NOTIFY for: {} to: {}", soa.name(), target);
+
+    let (stream, sender) = UdpClientStream::new(target, handle);
+    let client = ClientFuture::new(stream, sender, handle, None);
+    let mut client = RetryClientHandle::new(client, NOTIFY_ATTEMPTS);
+
+    let name = soa.name().clone();
+    handle.spawn(
+        client
+            .notify(
+                soa.name().clone(),
+                DNSClass::IN,
+                RecordType::SOA,
+                Some(soa.clone()),
+            )
+            .map(move |response| match response.response_code() {
+                ResponseCode::NoError => debug!("NOTIFY for: {} accepted by: {}", name, target),
+                code => warn!("NOTIFY for: {} rejected by: {}: {:?}", name, target, code),
+            })
+            .map_err(move |e| warn!("NOTIFY to: {} failed: {}", target, e)),
+    );
+}
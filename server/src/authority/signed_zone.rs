@@ -0,0 +1,115 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Refresh of the signatures of DNSSEC signed zones
+
+use std::cmp;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use futures::{future, Future, IntoFuture};
+use futures::future::{Either, Loop};
+use tokio_core::reactor::{Handle, Timeout};
+
+use trust_dns::rr::Name;
+
+use authority::Authority;
+
+/// Minimum time between attempts to re-sign a zone, e.g. when signing fails
+const MIN_RESIGN_INTERVAL: u64 = 60;
+/// Maximum time between checks of the signatures of a zone, to recover from clock changes
+const MAX_RESIGN_INTERVAL: u64 = 24 * 60 * 60;
+
+/// A signed zone, whose RRSIGs are refreshed before they expire
+///
+/// The signatures are made valid for the `Signer::sig_duration()` of each key, and signed again
+///  when they are due to expire within the refresh window of the zone, see
+///  `Authority::set_signature_refresh()` and `Authority::resign_expiring()`. When any signatures
+///  are refreshed the serial of the zone is incremented and the slaves of the zone are notified.
+///  The keys are rolled over in the same way, at the times of their `KeyTiming`, see
+///  `Authority::roll_keys()`.
+pub struct SignedZone {
+    authority: Arc<RwLock<Authority>>,
+}
+
+impl SignedZone {
+    /// Creates a new SignedZone
+    ///
+    /// # Arguments
+    ///
+    /// * `authority` - the zone to keep signed, this is shared with the `Catalog`, see
+    ///                 `Catalog::authority()`
+    pub fn new(authority: Arc<RwLock<Authority>>) -> Self {
+        SignedZone {
+            authority: authority,
+        }
+    }
+
+    /// Spawns the refresh of the signatures onto the reactor
+    ///
+    /// The signatures are checked immediately, and thereafter when the next of them is due to be
+    ///  refreshed, or the next of the keys is due to change state. The refresh stops if the zone has no keys.
+    pub fn spawn(self, handle: &Handle) {
+        let loop_handle = handle.clone();
+        handle.spawn(future::loop_fn(self, move |zone| {
+            let wait = match zone.resign() {
+                Some(wait) => wait,
+                None => {
+                    info!("stopping signing of zone: {}", zone.origin());
+                    return Either::A(future::ok(Loop::Break(())));
+                }
+            };
+
+            let wait = cmp::min(
+                cmp::max(wait, Duration::from_secs(MIN_RESIGN_INTERVAL)),
+                Duration::from_secs(MAX_RESIGN_INTERVAL),
+            );
+            debug!("next signing of zone: {} in: {:?}", zone.origin(), wait);
+
+            let timer = Timeout::new(wait, &loop_handle).into_future().flatten();
+            Either::B(timer.then(move |waited| {
+                if let Err(e) = waited {
+                    error!("error in signing timer: {}", e);
+                }
+
+                Ok::<_, ()>(Loop::Continue(zone))
+            }))
+        }));
+    }
+
+    /// Signs again the record sets of the zone whose RRSIGs are due to expire
+    ///
+    /// # Return value
+    ///
+    /// The time until the next of the signatures is due to be refreshed, None if the zone is not
+    ///  signed
+    pub fn resign(&self) -> Option<Duration> {
+        let mut authority = self.authority.write().unwrap(); // poison errors should panic
+
+        match authority.resign_expiring() {
+            Ok(0) => (),
+            Ok(resigned) => {
+                info!(
+                    "re-signed {} record sets of zone: {}, serial: {}",
+                    resigned,
+                    authority.origin(),
+                    authority.serial()
+                );
+
+                // pass the new signatures along to any slaves of this zone
+                authority.notify_slaves();
+            }
+            Err(error) => error!("failed to sign zone: {}: {}", authority.origin(), error),
+        }
+
+        authority.next_signature_refresh()
+    }
+
+    fn origin(&self) -> Name {
+        self.authority.read().unwrap().origin().clone().into()
+    }
+}
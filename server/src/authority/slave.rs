@@ -0,0 +1,450 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Refresh of Slave zones from their masters
+
+use std::cmp;
+use std::iter::Peekable;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use futures::{future, Future, IntoFuture, Stream};
+use futures::future::{Either, Loop};
+use futures::sync::mpsc::unbounded;
+use tokio_core::reactor::{Handle, Timeout};
+
+use trust_dns::client::{ClientFuture, ClientHandle};
+use trust_dns::error::{ClientError, ClientErrorKind};
+use trust_dns::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use trust_dns::rr::{DNSClass, Name, RData, Record, RecordType};
+use trust_dns::tcp::TcpClientStream;
+use trust_dns_proto::DnsHandle;
+
+use authority::{serial_ge, Authority, ZoneDiff};
+
+/// Time allowed for the connection to a master to be established
+const CONNECT_TIMEOUT: u64 = 5;
+/// Time allowed for an entire zone transfer, all the messages, to be received
+const TRANSFER_TIMEOUT: u64 = 60;
+/// Interval between attempts to transfer a zone for which there is no SOA yet
+const DEFAULT_RETRY: u64 = 60;
+
+/// The outcome of a successful refresh of a Slave zone
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Refresh {
+    /// The zone is already at the same version as the master
+    UpToDate,
+    /// The zone was replaced with a full transfer, AXFR, at the specified serial
+    Full(u32),
+    /// The zone was updated with an incremental transfer, IXFR, to the specified serial
+    Incremental(u32),
+}
+
+/// A Slave zone, which is kept up to date with its masters
+///
+/// [RFC 1034](https://tools.ietf.org/html/rfc1034), Domain Concepts and Facilities, November 1987
+///
+/// ```text
+/// 4.3.5. Zone maintenance and transfers
+///
+/// The periodic polling of the secondary servers is controlled by
+/// parameters in the SOA RR for the zone, which set the minimum acceptable
+/// polling intervals.  The parameters are called REFRESH, RETRY, and
+/// EXPIRE.  Whenever a new zone is loaded in a secondary, the secondary
+/// waits REFRESH seconds before checking with the primary for a new serial.
+/// If this check cannot be completed, new checks are started every RETRY
+/// seconds.  The check is a simple query to the primary for the SOA RR of
+/// the zone.  If the serial field in the secondary's zone copy is equal to
+/// the serial returned by the primary, then no changes have occurred, and
+/// the REFRESH interval wait is restarted.  If the secondary finds it
+/// impossible to perform a serial check for the EXPIRE interval, it must
+/// assume that its copy of the zone is obsolete an discard it.
+/// ```
+///
+/// The serial check is performed with an IXFR, [RFC 1995](https://tools.ietf.org/html/rfc1995),
+///  which answers with only the SOA if the zone is up to date. A full AXFR is requested if the
+///  zone has no SOA yet, or if the master is unable to send an incremental transfer.
+pub struct SlaveZone {
+    authority: Arc<RwLock<Authority>>,
+    masters: Vec<SocketAddr>,
+}
+
+impl SlaveZone {
+    /// Creates a new SlaveZone
+    ///
+    /// # Arguments
+    ///
+    /// * `authority` - the zone to keep up to date, this is shared with the `Catalog`, see
+    ///                 `Catalog::authority()`
+    /// * `masters` - addresses of the masters, these are tried in order
+    pub fn new(authority: Arc<RwLock<Authority>>, masters: Vec<SocketAddr>) -> Self {
+        SlaveZone {
+            authority: authority,
+            masters: masters,
+        }
+    }
+
+    /// Spawns the refresh of the zone onto the reactor
+    ///
+    /// The zone is refreshed immediately, and thereafter according to the refresh and retry
+    ///  intervals in the SOA, or when a refresh is triggered by a NOTIFY from a master, see
+    ///  `Authority::trigger_refresh()`. If the zone can not be refreshed before the expire interval
+    ///  has elapsed, the zone is marked as expired and is no longer served until the next
+    ///  successful refresh. A zone without an SOA, i.e. with no local copy, is expired until it is
+    ///  transferred.
+    pub fn spawn(self, handle: &Handle) {
+        let (refresh_trigger, notifications) = unbounded::<()>();
+
+        let expires_at = {
+            let mut authority = self.authority.write().unwrap(); // poison errors should panic
+            let expire = soa_timers(&authority).map(|(_, _, expire)| expire);
+
+            authority.set_refresh_trigger(refresh_trigger);
+            authority.set_expired(expire.is_none());
+            expire.map(|expire| Instant::now() + expire)
+        };
+
+        let loop_handle = handle.clone();
+        handle.spawn(future::loop_fn(
+            (self, expires_at, notifications),
+            move |(zone, expires_at, notifications)| {
+                let handle = loop_handle.clone();
+
+                zone.refresh(&handle).then(move |result| {
+                    let (refresh, retry, expire) = zone.timers();
+                    let now = Instant::now();
+
+                    let (wait, expires_at) = match result {
+                        Ok(refreshed) => {
+                            info!("refreshed zone: {}: {:?}", zone.origin(), refreshed);
+                            let mut authority = zone.authority.write().unwrap();
+                            authority.set_expired(false);
+
+                            // pass the change along to any slaves of this zone
+                            if refreshed != Refresh::UpToDate {
+                                authority.notify_slaves();
+                            }
+
+                            (refresh, Some(now + expire))
+                        }
+                        Err(error) => {
+                            warn!("failed to refresh zone: {}: {}", zone.origin(), error);
+
+                            match expires_at {
+                                Some(expires_at) if expires_at <= now => {
+                                    warn!("zone has expired: {}", zone.origin());
+                                    zone.authority.write().unwrap().set_expired(true);
+                                    (retry, None)
+                                }
+                                // retry, but don't sleep past the expiration of the zone
+                                Some(expires_at) => {
+                                    (cmp::min(retry, expires_at - now), Some(expires_at))
+                                }
+                                None => (retry, None),
+                            }
+                        }
+                    };
+
+                    debug!("next refresh of zone: {} in: {:?}", zone.origin(), wait);
+                    let timer = Timeout::new(wait, &handle).into_future().flatten();
+
+                    // wait for the timer, or a NOTIFY, whichever is first
+                    timer
+                        .select2(notifications.into_future())
+                        .then(move |waited| {
+                            let notifications = match waited {
+                                Ok(Either::A((_, notified))) => notified.into_inner(),
+                                Err(Either::A((e, notified))) => {
+                                    error!("error in refresh timer: {}", e);
+                                    notified.into_inner()
+                                }
+                                Ok(Either::B(((Some(()), notifications), _))) => {
+                                    info!("refresh of zone: {} triggered", zone.origin());
+                                    Some(notifications)
+                                }
+                                Ok(Either::B(((None, _), _))) | Err(Either::B(_)) => None,
+                            };
+
+                            match notifications {
+                                Some(notifications) => {
+                                    Ok::<_, ()>(Loop::Continue((zone, expires_at, notifications)))
+                                }
+                                // the authority, and its trigger, has been dropped
+                                None => {
+                                    info!("stopping refresh of zone: {}", zone.origin());
+                                    Ok(Loop::Break(()))
+                                }
+                            }
+                        })
+                })
+            },
+        ));
+    }
+
+    /// Refreshes the zone from the first of the masters which successfully responds
+    pub fn refresh(&self, handle: &Handle) -> Box<Future<Item = Refresh, Error = ClientError>> {
+        let error = ClientErrorKind::Msg(format!("no masters for zone: {}", self.origin()));
+        let mut refresh: Box<Future<Item = Refresh, Error = ClientError>> =
+            Box::new(future::err(ClientError::from(error)));
+
+        for master in self.masters.iter().cloned() {
+            let authority = Arc::clone(&self.authority);
+            let handle = handle.clone();
+
+            refresh = Box::new(refresh.or_else(move |error| {
+                debug!("trying master: {}, previous error: {}", master, error);
+                refresh_from(authority, master, &handle)
+            }));
+        }
+
+        refresh
+    }
+
+    fn origin(&self) -> Name {
+        self.authority.read().unwrap().origin().clone().into()
+    }
+
+    /// The refresh, retry and expire intervals from the SOA
+    fn timers(&self) -> (Duration, Duration, Duration) {
+        let default = Duration::from_secs(DEFAULT_RETRY);
+
+        soa_timers(&self.authority.read().unwrap()).unwrap_or((default, default, default))
+    }
+}
+
+fn soa_timers(authority: &Authority) -> Option<(Duration, Duration, Duration)> {
+    // negative intervals are nonsensical, and at least a second between attempts
+    let seconds = |interval: i32| Duration::from_secs(cmp::max(interval, 1) as u64);
+
+    authority.soa().iter().next().and_then(|soa| {
+        if let RData::SOA(ref soa) = *soa.rdata() {
+            Some((seconds(soa.refresh()), seconds(soa.retry()), seconds(soa.expire())))
+        } else {
+            None
+        }
+    })
+}
+
+fn soa_serial(record: &Record) -> Option<u32> {
+    if let RData::SOA(ref soa) = *record.rdata() {
+        Some(soa.serial())
+    } else {
+        None
+    }
+}
+
+/// Transfers the zone from the master, IXFR if there is a current version of the zone, otherwise
+///  AXFR
+fn refresh_from(
+    authority: Arc<RwLock<Authority>>,
+    master: SocketAddr,
+    handle: &Handle,
+) -> Box<Future<Item = Refresh, Error = ClientError>> {
+    let (stream, sender) =
+        TcpClientStream::with_timeout(master, handle, Duration::from_secs(CONNECT_TIMEOUT));
+    let mut client = ClientFuture::with_timeout(
+        stream,
+        sender,
+        handle,
+        Duration::from_secs(TRANSFER_TIMEOUT),
+        None,
+    );
+
+    let (origin, soa) = {
+        let authority = authority.read().unwrap(); // poison errors should panic
+        let origin: Name = authority.origin().clone().into();
+        (origin, authority.soa().iter().next().cloned())
+    };
+
+    let axfr = {
+        let authority = Arc::clone(&authority);
+        let origin = origin.clone();
+        let mut client = client.clone();
+
+        move || -> Box<Future<Item = Refresh, Error = ClientError>> {
+            info!("requesting AXFR of zone: {} from: {}", origin, master);
+
+            Box::new(
+                client
+                    .query(origin, DNSClass::IN, RecordType::AXFR)
+                    .and_then(move |response| apply_transfer(&authority, response, None))
+                    .and_then(|refresh| {
+                        refresh.ok_or_else(|| {
+                            ClientError::from(ClientErrorKind::Msg(
+                                "AXFR response was a lone SOA".to_string(),
+                            ))
+                        })
+                    }),
+            )
+        }
+    };
+
+    let soa = match soa {
+        Some(soa) => soa,
+        None => return axfr(),
+    };
+
+    info!(
+        "requesting IXFR of zone: {} from: {} at serial: {:?}",
+        origin,
+        master,
+        soa_serial(&soa)
+    );
+
+    let mut message = Message::new();
+    message
+        .add_query(Query::query(origin, RecordType::IXFR))
+        .add_name_server(soa.clone())
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query);
+
+    Box::new(
+        client
+            .send(message)
+            .and_then(move |response| {
+                apply_transfer(&authority, response, soa_serial(&soa))
+            })
+            .then(move |result| -> Box<Future<Item = Refresh, Error = ClientError>> {
+                match result {
+                    Ok(Some(refresh)) => Box::new(future::ok::<Refresh, ClientError>(refresh)),
+                    // the master does not know our version, or does not support IXFR
+                    Ok(None) => axfr(),
+                    Err(error) => {
+                        debug!("IXFR failed, falling back to AXFR: {}", error);
+                        axfr()
+                    }
+                }
+            }),
+    )
+}
+
+/// Applies the records of an AXFR or IXFR response to the zone
+///
+/// # Return value
+///
+/// None if the response can not be applied to the current version of the zone, and a full AXFR
+///  is required.
+fn apply_transfer(
+    authority: &RwLock<Authority>,
+    mut response: Message,
+    current_serial: Option<u32>,
+) -> Result<Option<Refresh>, ClientError> {
+    if response.response_code() != ResponseCode::NoError {
+        return Err(
+            ClientErrorKind::Msg(format!(
+                "transfer failed: {:?}",
+                response.response_code()
+            )).into(),
+        );
+    }
+
+    let mut records = response.take_answers();
+    let serial = records.first().and_then(soa_serial).ok_or_else(|| {
+        ClientError::from(ClientErrorKind::Msg(
+            "transfer does not begin with the SOA".to_string(),
+        ))
+    })?;
+
+    if let Some(current_serial) = current_serial {
+        if serial_ge(current_serial, serial) {
+            return Ok(Some(Refresh::UpToDate));
+        }
+    }
+
+    // a lone SOA, which is newer, is the master asking for a retry with AXFR
+    if records.len() == 1 {
+        return Ok(None);
+    }
+
+    if records.last().and_then(soa_serial) != Some(serial) {
+        return Err(ClientErrorKind::Msg("transfer does not end with the SOA".to_string()).into());
+    }
+
+    let mut authority = authority.write().unwrap(); // poison errors should panic
+
+    // an incremental transfer has the old SOA of the first difference as the second record
+    if records[1].rr_type() == RecordType::SOA {
+        let diffs = match ixfr_diffs(records) {
+            Some(diffs) => diffs,
+            None => return Err(ClientErrorKind::Msg("malformed IXFR response".to_string()).into()),
+        };
+
+        if diffs.first().and_then(|d| soa_serial(d.old_soa())) != current_serial {
+            warn!("IXFR does not start at serial: {:?}", current_serial);
+            return Ok(None);
+        }
+
+        for diff in &diffs {
+            authority.apply_diff(diff).map_err(|code| {
+                ClientError::from(ClientErrorKind::Msg(format!(
+                    "failed to apply IXFR: {:?}",
+                    code
+                )))
+            })?;
+        }
+
+        Ok(Some(Refresh::Incremental(authority.serial())))
+    } else {
+        // the SOA is at the beginning and the end
+        records.pop();
+
+        authority.replace_records(records).map_err(|e| {
+            ClientError::from(ClientErrorKind::Msg(format!("failed to store AXFR: {}", e)))
+        })?;
+
+        Ok(Some(Refresh::Full(authority.serial())))
+    }
+}
+
+/// Splits the records of an incremental IXFR response into the differences between versions
+///
+/// [RFC 1995](https://tools.ietf.org/html/rfc1995), Incremental Zone Transfer in DNS, August 1996
+///
+/// ```text
+/// 4. Response Format
+///
+///    If incremental zone transfer is available, one or more difference
+///    sequences is returned.  The list of difference sequences is preceded
+///    and followed by a copy of the server's current version of the SOA.
+///
+///    Each difference sequence represents one update to the zone (one SOA
+///    serial change) consisting of deleted RRs and added RRs.  The first RR
+///    of the deleted RRs is the older SOA RR and the first RR of the added
+///    RRs is the newer SOA RR.
+/// ```
+fn ixfr_diffs(records: Vec<Record>) -> Option<Vec<ZoneDiff>> {
+    let count = records.len();
+    let mut records = records.into_iter().skip(1).take(count - 2).peekable();
+    let mut diffs = vec![];
+
+    while let Some(old_soa) = records.next() {
+        if old_soa.rr_type() != RecordType::SOA {
+            return None;
+        }
+
+        let deleted = take_until_soa(&mut records);
+        let new_soa = records.next()?;
+        let added = take_until_soa(&mut records);
+
+        diffs.push(ZoneDiff::new(old_soa, deleted, new_soa, added));
+    }
+
+    Some(diffs)
+}
+
+fn take_until_soa<I: Iterator<Item = Record>>(records: &mut Peekable<I>) -> Vec<Record> {
+    let mut taken = vec![];
+    while records
+        .peek()
+        .map_or(false, |r| r.rr_type() != RecordType::SOA)
+    {
+        taken.extend(records.next());
+    }
+
+    taken
+}
@@ -0,0 +1,81 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use trust_dns::rr::Record;
+
+/// The difference between two versions of a zone, as sent in an IXFR
+///
+/// [RFC 1995](https://tools.ietf.org/html/rfc1995), Incremental Zone Transfer in DNS, August 1996
+///
+/// ```text
+/// 4. Response Format
+///
+///    If incremental zone transfer is available, one or more difference
+///    sequences is returned.  The list of difference sequences is preceded
+///    and followed by a copy of the server's current version of the SOA.
+///
+///    Each difference sequence represents one update to the zone (one SOA
+///    serial change) consisting of deleted RRs and added RRs.  The first RR
+///    of the deleted RRs is the older SOA RR and the first RR of the added
+///    RRs is the newer SOA RR.
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZoneDiff {
+    old_soa: Record,
+    deleted: Vec<Record>,
+    new_soa: Record,
+    added: Vec<Record>,
+}
+
+impl ZoneDiff {
+    /// Creates a new difference sequence
+    ///
+    /// # Arguments
+    ///
+    /// * `old_soa` - the SOA of the version the differences apply to
+    /// * `deleted` - records removed from the older version, excluding the SOA
+    /// * `new_soa` - the SOA of the version after the differences are applied
+    /// * `added` - records added in the newer version, excluding the SOA
+    pub fn new(old_soa: Record, deleted: Vec<Record>, new_soa: Record, added: Vec<Record>) -> Self {
+        ZoneDiff {
+            old_soa,
+            deleted,
+            new_soa,
+            added,
+        }
+    }
+
+    /// The SOA of the version to which these differences apply
+    pub fn old_soa(&self) -> &Record {
+        &self.old_soa
+    }
+
+    /// Records deleted from the older version
+    pub fn deleted(&self) -> &[Record] {
+        &self.deleted
+    }
+
+    /// The SOA of the version after these differences are applied
+    pub fn new_soa(&self) -> &Record {
+        &self.new_soa
+    }
+
+    /// Records added to the newer version
+    pub fn added(&self) -> &[Record] {
+        &self.added
+    }
+
+    /// Returns the records of this sequence in the order they are sent in an IXFR response
+    pub fn records(&self) -> Vec<&Record> {
+        let mut records: Vec<&Record> = Vec::with_capacity(self.deleted.len() + self.added.len() + 2);
+        records.push(&self.old_soa);
+        records.extend(self.deleted.iter());
+        records.push(&self.new_soa);
+        records.extend(self.added.iter());
+        records
+    }
+}
@@ -18,29 +18,41 @@
 
 use std::fs::File;
 use std::io::Read;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
 
+#[cfg(feature = "dnssec")]
+use chrono::DateTime;
 use log;
 use rustc_serialize::Decodable;
+use rustc_serialize::base64::FromBase64;
+use rustc_serialize::hex::FromHex;
 use toml::{Decoder, Value};
 
 #[cfg(feature = "dnssec")]
 use trust_dns::error::*;
 use trust_dns::rr::Name;
+use trust_dns::rr::dnssec::{Nsec3HashAlgorithm, TSigner, DEFAULT_TSIG_FUDGE};
+use trust_dns::rr::rdata::NSEC3PARAM;
+use trust_dns::rr::rdata::tsig::TsigAlgorithm;
 #[cfg(feature = "dnssec")]
 use trust_dns::rr::dnssec::{Algorithm, KeyFormat};
 use trust_dns_proto::error::ProtoResult;
 
-use authority::ZoneType;
+use authority::{Acl, Network, ZoneType};
+#[cfg(feature = "dnssec")]
+use authority::KeyTiming;
+use server::{server_cookies, RateLimitOpts, ServerCookies};
 use error::{ConfigError, ConfigErrorKind, ConfigResult};
 
 static DEFAULT_PATH: &'static str = "/var/named"; // TODO what about windows (do I care? ;)
 static DEFAULT_PORT: u16 = 53;
 static DEFAULT_TLS_PORT: u16 = 853;
 static DEFAULT_TCP_REQUEST_TIMEOUT: u64 = 5;
+static DEFAULT_HTTPS_ENDPOINT: &'static str = "/dns-query";
+static DEFAULT_SIGNATURE_VALIDITY: u64 = 30 * 24 * 60 * 60;
 
 /// Server configuration
 #[derive(RustcDecodable, Debug)]
@@ -53,6 +65,10 @@ pub struct Config {
     listen_port: Option<u16>,
     /// Secure port to listen on
     tls_listen_port: Option<u16>,
+    /// Port on which to listen for DNS over HTTPS, disabled by default
+    https_listen_port: Option<u16>,
+    /// Path of the DNS query endpoint of DNS over HTTPS
+    https_endpoint: Option<String>,
     /// Timeout associated to a request before it is closed.
     tcp_request_timeout: Option<u64>,
     /// Level at which to log, default is INFO
@@ -63,6 +79,14 @@ pub struct Config {
     zones: Vec<ZoneConfig>,
     /// Certificate to associate to TLS connections
     tls_cert: Option<TlsCertConfig>,
+    /// TSIG keys, which zones name in their `tsig_keys`
+    tsig_keys: Vec<TsigKeyConfig>,
+    /// Response Rate Limiting of UDP responses, none by default
+    rate_limit: Option<RateLimitConfig>,
+    /// DNS Cookies, RFC 7873, enabled by default
+    enable_cookies: Option<bool>,
+    /// Secret for minting server cookies, base64 encoded, random by default
+    cookie_secret: Option<String>,
 }
 
 impl Config {
@@ -96,6 +120,16 @@ impl Config {
     pub fn get_tls_listen_port(&self) -> u16 {
         self.tls_listen_port.unwrap_or(DEFAULT_TLS_PORT)
     }
+    /// port on which to listen for DNS over HTTPS, if it is enabled, it uses the `tls_cert`
+    pub fn get_https_listen_port(&self) -> Option<u16> {
+        self.https_listen_port
+    }
+    /// path of the DNS query endpoint of DNS over HTTPS, defaults to `/dns-query`
+    pub fn get_https_endpoint(&self) -> &str {
+        self.https_endpoint
+            .as_ref()
+            .map_or(DEFAULT_HTTPS_ENDPOINT, |s| s.as_str())
+    }
     /// default timeout for all TCP connections before forceably shutdown
     pub fn get_tcp_request_timeout(&self) -> Duration {
         Duration::from_secs(
@@ -134,6 +168,37 @@ impl Config {
     pub fn get_tls_cert(&self) -> Option<&TlsCertConfig> {
         self.tls_cert.as_ref()
     }
+    /// the TSIG keys shared with clients, zones accept requests signed with the keys they name
+    pub fn get_tsig_keys(&self) -> &[TsigKeyConfig] {
+        &self.tsig_keys
+    }
+    /// the limits on UDP responses, if any, see `RateLimiter`
+    pub fn get_rate_limit(&self) -> Option<&RateLimitConfig> {
+        self.rate_limit.as_ref()
+    }
+    /// the server cookies, if DNS Cookies are enabled, with a random secret unless one is
+    ///  configured, see `ServerCookies`
+    pub fn get_server_cookies(&self) -> ConfigResult<Option<ServerCookies>> {
+        if !self.enable_cookies.unwrap_or(true) {
+            return Ok(None);
+        }
+
+        let secret = match self.cookie_secret {
+            Some(ref secret) => secret
+                .from_base64()
+                .map_err(|e| format!("bad cookie_secret: {}", e))?,
+            None => return Ok(Some(ServerCookies::random())),
+        };
+
+        if secret.len() != server_cookies::SECRET_LEN {
+            return Err(format!(
+                "cookie_secret must be {} bytes",
+                server_cookies::SECRET_LEN
+            ).into());
+        }
+
+        Ok(Some(ServerCookies::new(secret)?))
+    }
 }
 
 impl FromStr for Config {
@@ -151,10 +216,23 @@ impl FromStr for Config {
 pub struct ZoneConfig {
     zone: String, // TODO: make Domain::Name decodable
     zone_type: ZoneType,
-    file: String,
-    allow_update: Option<bool>,
+    file: Option<String>,
+    allow_query: Vec<String>,
+    allow_update: Option<AllowUpdate>,
+    allow_transfer: Vec<String>,
+    allow_notify: Vec<String>,
+    masters: Vec<String>,
+    also_notify: Vec<String>,
+    forwarders: Vec<String>,
     enable_dnssec: Option<bool>,
     keys: Vec<KeyConfig>,
+    nsec3: Option<Nsec3Config>,
+    signature_validity: Option<u64>,
+    signature_inception: Option<u64>,
+    signature_refresh: Option<u64>,
+    signature_jitter: Option<u64>,
+    tsig_keys: Vec<String>,
+    transfer_tsig_keys: Vec<String>,
 }
 
 impl ZoneConfig {
@@ -179,10 +257,23 @@ impl ZoneConfig {
         ZoneConfig {
             zone: zone,
             zone_type: zone_type,
-            file: file,
-            allow_update: allow_update,
+            file: Some(file),
+            allow_query: Vec::new(),
+            allow_update: allow_update.map(AllowUpdate::Enabled),
+            allow_transfer: Vec::new(),
+            allow_notify: Vec::new(),
+            masters: Vec::new(),
+            also_notify: Vec::new(),
+            forwarders: Vec::new(),
             enable_dnssec: enable_dnssec,
             keys: keys,
+            nsec3: None,
+            signature_validity: None,
+            signature_inception: None,
+            signature_refresh: None,
+            signature_jitter: None,
+            tsig_keys: Vec::new(),
+            transfer_tsig_keys: Vec::new(),
         }
     }
 
@@ -201,13 +292,85 @@ impl ZoneConfig {
     ///
     /// this is ony used on first load, if dynamic update is enabled for the zone, then the journal
     /// file is the actual source of truth for the zone.
-    pub fn get_file(&self) -> PathBuf {
-        PathBuf::from(&self.file)
+    ///
+    /// required for all but Forward zones, which have no records of their own
+    pub fn get_file(&self) -> Option<PathBuf> {
+        self.file.as_ref().map(PathBuf::from)
     }
 
-    /// enable dynamic updates for the zone (see SIG0 and the registered keys)
+    /// networks from which queries of the zone are answered, e.g. "192.0.2.0/24" or "2001:db8::1"
+    ///
+    /// by default, queries from all addresses are answered
+    pub fn get_allow_query(&self) -> ConfigResult<Acl> {
+        if self.allow_query.is_empty() {
+            return Ok(Acl::any());
+        }
+
+        parse_acl(&self.allow_query)
+    }
+
+    /// enable dynamic updates for the zone (see SIG0, TSIG and the registered keys), either
+    ///  `allow_update = true` or a list of the networks from which updates are accepted
     pub fn is_update_allowed(&self) -> bool {
-        self.allow_update.unwrap_or(false)
+        match self.allow_update {
+            Some(AllowUpdate::Enabled(allow_update)) => allow_update,
+            Some(AllowUpdate::Networks(ref networks)) => !networks.is_empty(),
+            None => false,
+        }
+    }
+
+    /// networks from which updates of the zone are accepted, updates must still be signed with
+    ///  SIG(0) or one of the `tsig_keys`
+    ///
+    /// by default, or with `allow_update = true`, updates from all addresses are considered
+    pub fn get_allow_update(&self) -> ConfigResult<Acl> {
+        match self.allow_update {
+            Some(AllowUpdate::Networks(ref networks)) => parse_acl(networks),
+            _ => Ok(Acl::any()),
+        }
+    }
+
+    /// networks which are allowed to perform zone transfers, AXFR and IXFR, of the zone
+    ///
+    /// by default, zone transfers are refused from all addresses. As with `allow_update`, both the
+    ///  address and the key of a transfer are checked: transfers from these networks must still be
+    ///  signed with one of the `transfer_tsig_keys`, unless the zone has none.
+    pub fn get_allow_transfer(&self) -> ConfigResult<Acl> {
+        parse_acl(&self.allow_transfer)
+    }
+
+    /// networks from which NOTIFY messages are accepted for a Slave zone
+    ///
+    /// by default, NOTIFY is accepted from the addresses of the `masters`
+    pub fn get_allow_notify(&self) -> ConfigResult<Acl> {
+        if self.allow_notify.is_empty() {
+            let masters = self.get_masters()?.iter().map(|s| Network::host(s.ip())).collect();
+            return Ok(Acl::new(masters));
+        }
+
+        parse_acl(&self.allow_notify)
+    }
+
+    /// addresses of the masters from which a Slave zone is transferred, AXFR or IXFR
+    ///
+    /// the port defaults to 53 if not specified, e.g. "192.0.2.1" or "[2001:db8::1]:5353"
+    pub fn get_masters(&self) -> ConfigResult<Vec<SocketAddr>> {
+        parse_socket_addrs(&self.masters)
+    }
+
+    /// addresses of additional slaves to NOTIFY of changes to the zone, beyond the name servers
+    ///  listed in the zone
+    ///
+    /// the port defaults to 53 if not specified
+    pub fn get_also_notify(&self) -> ConfigResult<Vec<SocketAddr>> {
+        parse_socket_addrs(&self.also_notify)
+    }
+
+    /// addresses of the upstream resolvers to which all requests for a Forward zone are sent
+    ///
+    /// the port defaults to 53 if not specified
+    pub fn get_forwarders(&self) -> ConfigResult<Vec<SocketAddr>> {
+        parse_socket_addrs(&self.forwarders)
     }
 
     /// declare that this zone should be signed, see keys for configuration of the keys for signing
@@ -219,6 +382,86 @@ impl ZoneConfig {
     pub fn get_keys(&self) -> &[KeyConfig] {
         &self.keys
     }
+
+    /// deny existence with NSEC3 rather than NSEC when the zone is signed, see `Nsec3Config`
+    pub fn get_nsec3(&self) -> Option<&Nsec3Config> {
+        self.nsec3.as_ref()
+    }
+
+    /// seconds for which the signatures of the zone are valid, 30 days by default
+    pub fn get_signature_validity(&self) -> Duration {
+        Duration::from_secs(self.signature_validity.unwrap_or(DEFAULT_SIGNATURE_VALIDITY))
+    }
+
+    /// seconds by which the inception of the signatures is back-dated, for resolvers with a clock
+    ///  behind that of the server, see `Authority::set_signature_inception`
+    pub fn get_signature_inception(&self) -> Option<Duration> {
+        self.signature_inception.map(Duration::from_secs)
+    }
+
+    /// signatures which expire within these seconds are refreshed, see
+    ///  `Authority::set_signature_refresh`
+    pub fn get_signature_refresh(&self) -> Option<Duration> {
+        self.signature_refresh.map(Duration::from_secs)
+    }
+
+    /// the expirations of the signatures are spread randomly over these seconds, see
+    ///  `Authority::set_signature_jitter`
+    pub fn get_signature_jitter(&self) -> Option<Duration> {
+        self.signature_jitter.map(Duration::from_secs)
+    }
+
+    /// the names of the TSIG keys, see `Config::get_tsig_keys`, with which requests may update the
+    ///  zone
+    pub fn get_tsig_keys(&self) -> &[String] {
+        &self.tsig_keys
+    }
+
+    /// the names of the TSIG keys, see `Config::get_tsig_keys`, with which transfers of the zone
+    ///  must be signed
+    pub fn get_transfer_tsig_keys(&self) -> &[String] {
+        &self.transfer_tsig_keys
+    }
+}
+
+/// Configuration of the NSEC3 chain of a signed zone, RFC 5155
+#[derive(RustcDecodable, PartialEq, Debug)]
+pub struct Nsec3Config {
+    salt: Option<String>,
+    iterations: Option<u16>,
+    opt_out: Option<bool>,
+}
+
+impl Nsec3Config {
+    /// the salt appended to the names before hashing, hex encoded, by default or with "-" there is
+    ///  no salt
+    pub fn get_salt(&self) -> ConfigResult<Vec<u8>> {
+        match self.salt.as_ref().map(|s| s.as_str()) {
+            None | Some("-") => Ok(vec![]),
+            Some(salt) => salt.from_hex()
+                .map_err(|e| format!("bad NSEC3 salt {}: {}", salt, e).into()),
+        }
+    }
+
+    /// the additional times the names are hashed, 0 by default as recommended by RFC 9276
+    pub fn get_iterations(&self) -> u16 {
+        self.iterations.unwrap_or(0)
+    }
+
+    /// leave the delegations to unsigned zones out of the chain, false by default
+    pub fn is_opt_out(&self) -> bool {
+        self.opt_out.unwrap_or(false)
+    }
+
+    /// the parameters of the chain, which is always hashed with SHA1
+    pub fn to_nsec3param(&self) -> ConfigResult<NSEC3PARAM> {
+        Ok(NSEC3PARAM::new(
+            Nsec3HashAlgorithm::SHA1,
+            self.is_opt_out(),
+            self.get_iterations(),
+            self.get_salt()?,
+        ))
+    }
 }
 
 /// Key pair configuration for DNSSec keys for signing a zone
@@ -231,6 +474,11 @@ pub struct KeyConfig {
     signer_name: Option<String>,
     is_zone_signing_key: Option<bool>,
     is_zone_update_auth: Option<bool>,
+    is_key_signing_key: Option<bool>,
+    publish: Option<String>,
+    activate: Option<String>,
+    retire: Option<String>,
+    remove: Option<String>,
 }
 
 #[cfg(feature = "dnssec")]
@@ -260,6 +508,11 @@ impl KeyConfig {
             signer_name: Some(signer_name),
             is_zone_signing_key: Some(is_zone_signing_key),
             is_zone_update_auth: Some(is_zone_update_auth),
+            is_key_signing_key: None,
+            publish: None,
+            activate: None,
+            retire: None,
+            remove: None,
         }
     }
 
@@ -335,6 +588,39 @@ impl KeyConfig {
     pub fn is_zone_update_auth(&self) -> bool {
         self.is_zone_update_auth.unwrap_or(false)
     }
+
+    /// this key is a KSK, the Secure Entry Point flag is set on its DNSKEY
+    ///
+    /// A KSK signs the DNSKEY, CDS and CDNSKEY records of the zone, and the other keys, the ZSKs,
+    /// sign the rest of it. Set this to false for a ZSK. This defaults to true, as keys always had
+    /// the flag set, and changing it would change the DS of the key at the parent.
+    pub fn is_key_signing_key(&self) -> bool {
+        self.is_key_signing_key.unwrap_or(true)
+    }
+
+    /// when the key is published, activated, retired and removed, for rollovers of the keys
+    ///
+    /// The times are in RFC 3339 format, e.g. `2018-03-01T00:00:00Z`. A key without times is
+    /// published and active from the start, see `KeyTiming`.
+    pub fn timing(&self) -> ParseResult<KeyTiming> {
+        fn parse_time(time: &Option<String>) -> ParseResult<Option<u64>> {
+            match *time {
+                Some(ref time) => DateTime::parse_from_rfc3339(time)
+                    .map(|time| Some(time.timestamp() as u64))
+                    .map_err(|e| {
+                        ParseErrorKind::Msg(format!("bad key time: {}: {}", time, e)).into()
+                    }),
+                None => Ok(None),
+            }
+        }
+
+        KeyTiming::new(
+            parse_time(&self.publish)?,
+            parse_time(&self.activate)?,
+            parse_time(&self.retire)?,
+            parse_time(&self.remove)?,
+        ).map_err(|e| ParseErrorKind::Msg(format!("bad key times: {}", e)).into())
+    }
 }
 
 #[cfg(not(feature = "dnssec"))]
@@ -342,20 +628,157 @@ impl KeyConfig {
 #[derive(RustcDecodable, PartialEq, Debug)]
 pub struct KeyConfig {}
 
+/// Configuration for a TSIG key, RFC 8945, a secret shared with the clients that sign with it
+#[derive(RustcDecodable, PartialEq, Debug)]
+pub struct TsigKeyConfig {
+    name: String,
+    algorithm: String,
+    secret: String,
+}
+
+impl TsigKeyConfig {
+    /// the name of the key, e.g. `key.example.com.`
+    pub fn get_name(&self) -> ConfigResult<Name> {
+        Ok(Name::parse(&self.name, Some(&Name::new()))?)
+    }
+
+    /// the HMAC algorithm of the key, `hmac-sha256` or `hmac-sha512`
+    pub fn get_algorithm(&self) -> ConfigResult<TsigAlgorithm> {
+        match self.algorithm.to_lowercase().as_str() {
+            "hmac-sha256" => Ok(TsigAlgorithm::HmacSha256),
+            "hmac-sha512" => Ok(TsigAlgorithm::HmacSha512),
+            s => Err(format!("unsupported TSIG algorithm: {}", s).into()),
+        }
+    }
+
+    /// the shared secret, which is base64 encoded in the configuration
+    pub fn get_secret(&self) -> ConfigResult<Vec<u8>> {
+        self.secret
+            .from_base64()
+            .map_err(|e| format!("bad TSIG secret for {}: {}", self.name, e).into())
+    }
+
+    /// the signer for the key
+    pub fn to_signer(&self) -> ConfigResult<TSigner> {
+        Ok(TSigner::new(
+            self.get_name()?,
+            self.get_algorithm()?,
+            self.get_secret()?,
+            DEFAULT_TSIG_FUDGE,
+        )?)
+    }
+}
+
+/// Configuration for Response Rate Limiting, see `RateLimitOpts` for the defaults
+#[derive(RustcDecodable, PartialEq, Debug)]
+pub struct RateLimitConfig {
+    responses_per_second: Option<u32>,
+    slip: Option<u32>,
+    ipv4_prefix_len: Option<u8>,
+    ipv6_prefix_len: Option<u8>,
+    window: Option<u64>,
+    log_only: Option<bool>,
+    max_table_size: Option<usize>,
+}
+
+impl RateLimitConfig {
+    /// the limits, where the unspecified options are the defaults
+    pub fn to_opts(&self) -> ConfigResult<RateLimitOpts> {
+        let defaults = RateLimitOpts::default();
+        let opts = RateLimitOpts {
+            responses_per_second: self.responses_per_second
+                .unwrap_or(defaults.responses_per_second),
+            slip: self.slip.unwrap_or(defaults.slip),
+            ipv4_prefix_len: self.ipv4_prefix_len.unwrap_or(defaults.ipv4_prefix_len),
+            ipv6_prefix_len: self.ipv6_prefix_len.unwrap_or(defaults.ipv6_prefix_len),
+            window: self.window.map_or(defaults.window, Duration::from_secs),
+            log_only: self.log_only.unwrap_or(defaults.log_only),
+            max_table_size: self.max_table_size.unwrap_or(defaults.max_table_size),
+        };
+
+        if opts.responses_per_second == 0 {
+            return Err("responses_per_second must be greater than 0".into());
+        }
+        if opts.ipv4_prefix_len > 32 || opts.ipv6_prefix_len > 128 {
+            return Err("bad rate limit prefix length".into());
+        }
+        if opts.max_table_size == 0 {
+            return Err("max_table_size must be greater than 0".into());
+        }
+
+        Ok(opts)
+    }
+}
+
+/// The format of the TLS certificate
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CertType {
+    /// A PKCS#12 archive, DER encoded, of the key and certificates, requires the `tls-openssl`
+    ///  feature
+    Pkcs12,
+    /// A chain of PEM certificates with a separate PEM private key, requires the `tls-ring`
+    ///  feature
+    Pem,
+}
+
 /// Configuration for a TLS certificate
 #[derive(RustcDecodable, PartialEq, Debug)]
 pub struct TlsCertConfig {
     path: String,
+    cert_type: Option<String>,
     password: Option<String>,
+    private_key: Option<String>,
 }
 
 impl TlsCertConfig {
-    /// path to the pkcs12 der formated certificate file
+    /// path to the certificate file, a pkcs12 der formated file or PEM certificate chain
     pub fn get_path(&self) -> &Path {
         Path::new(&self.path)
     }
+    /// the format of the certificate file, `pkcs12` by default or `pem`
+    pub fn get_cert_type(&self) -> ConfigResult<CertType> {
+        match self.cert_type.as_ref().map(|s| s.to_lowercase()) {
+            None => Ok(CertType::Pkcs12),
+            Some(ref s) if s == "pkcs12" => Ok(CertType::Pkcs12),
+            Some(ref s) if s == "pem" => Ok(CertType::Pem),
+            Some(s) => Err(format!("unsupported cert_type: {}", s).into()),
+        }
+    }
     /// optional password for open the pkcs12, none assumes no password
     pub fn get_password(&self) -> Option<&str> {
         self.password.as_ref().map(|s| s.as_str())
     }
+    /// path to the PEM private key of a PEM certificate chain
+    pub fn get_private_key(&self) -> Option<&Path> {
+        self.private_key.as_ref().map(Path::new)
+    }
+}
+
+/// `allow_update` of a zone, a flag for all addresses or the networks from which to accept updates
+#[derive(RustcDecodable, PartialEq, Debug)]
+enum AllowUpdate {
+    Enabled(bool),
+    Networks(Vec<String>),
+}
+
+/// parses networks in CIDR notation, where a bare ip is just that address
+fn parse_acl(networks: &[String]) -> ConfigResult<Acl> {
+    let networks = networks
+        .iter()
+        .map(|s| s.parse::<Network>())
+        .collect::<ProtoResult<Vec<Network>>>()?;
+
+    Ok(Acl::new(networks))
+}
+
+/// parses "ip:port" addresses, where the port defaults to 53 for a bare ip
+fn parse_socket_addrs(addrs: &[String]) -> ConfigResult<Vec<SocketAddr>> {
+    addrs
+        .iter()
+        .map(|s| {
+            s.parse()
+                .or_else(|_| s.parse().map(|ip| SocketAddr::new(ip, 53)))
+                .map_err(|e| format!("bad address {}: {}", s, e).into())
+        })
+        .collect()
 }
@@ -27,24 +27,40 @@
 //! * Secure dynamic update
 //! * New features for securing public information
 
+#[cfg(feature = "https")]
+extern crate bytes;
 extern crate chrono;
+#[cfg(feature = "dnssec")]
+extern crate data_encoding;
 extern crate env_logger;
 #[macro_use]
 extern crate error_chain;
 #[macro_use]
 extern crate futures;
+#[cfg(feature = "https")]
+extern crate h2;
+#[cfg(feature = "https")]
+extern crate http;
 #[macro_use]
 extern crate log;
+extern crate rand;
 extern crate rusqlite;
 extern crate rustc_serialize;
 extern crate time;
 extern crate tokio_core;
+#[cfg(feature = "https")]
+extern crate tokio_io;
 extern crate toml;
 extern crate trust_dns;
 extern crate trust_dns_proto;
+extern crate trust_dns_resolver;
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "tls-openssl")]
 extern crate trust_dns_openssl;
+#[cfg(feature = "tls-ring")]
+extern crate trust_dns_rustls;
+#[cfg(feature = "https")]
+extern crate trust_dns_https;
 
 pub mod authority;
 pub mod config;
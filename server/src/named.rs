@@ -29,6 +29,7 @@
 //!    -z DIR, --zonedir=DIR   Path to the root directory for all zone files, see also config toml
 //!    -p PORT, --port=PORT    Override the listening port
 //!    --tls-port=PORT         Override the listening port for TLS connections
+//!    --https-port=PORT       Listening port for DNS over HTTPS, enables DNS over HTTPS
 //! ```
 
 extern crate chrono;
@@ -37,41 +38,61 @@ extern crate clap;
 #[macro_use]
 extern crate log;
 extern crate rustc_serialize;
+extern crate tokio_core;
 extern crate trust_dns;
+extern crate trust_dns_resolver;
 extern crate trust_dns_server;
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "tls-openssl")]
 extern crate trust_dns_openssl;
+#[cfg(feature = "tls-ring")]
+extern crate trust_dns_rustls;
 
 use std::fs::File;
 use std::collections::BTreeMap;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, ToSocketAddrs, UdpSocket};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 use std::io::Read;
+#[cfg(feature = "dnssec")]
+use std::time::Duration as StdDuration;
 
 #[cfg(feature = "dnssec")]
 use chrono::Duration;
 
 use clap::{Arg, ArgMatches};
+use tokio_core::reactor::Handle;
 
 use trust_dns::error::ParseResult;
 use trust_dns::serialize::txt::{Lexer, Parser};
-use trust_dns::rr::Name;
+use trust_dns::rr::{LowerName, Name, RData, RecordType};
+use trust_dns::rr::dnssec::{SupportedAlgorithms, TSigner};
 
 #[cfg(feature = "dnssec")]
 use trust_dns::rr::dnssec::{KeyPair, Signer};
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::rdata::DNSKEY;
 
-use trust_dns_server::authority::{Authority, Catalog, Journal, ZoneType};
+use trust_dns_resolver::Recursor;
+use trust_dns_resolver::config::ResolverOpts;
+use trust_dns_server::authority::{Authority, Catalog, Forwarder, Journal, Notifier, SlaveZone,
+                                  ZoneType};
+#[cfg(feature = "dnssec")]
+use trust_dns_server::authority::SignedZone;
 use trust_dns_server::config::{Config, TlsCertConfig, ZoneConfig};
+#[cfg(feature = "tls")]
+use trust_dns_server::config::CertType;
 use trust_dns_server::logger;
 
 #[cfg(feature = "dnssec")]
 use trust_dns_server::config::KeyConfig;
 
-use trust_dns_server::server::ServerFuture;
+use trust_dns_server::server::{RateLimiter, ServerFuture};
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "tls-openssl")]
 use trust_dns_openssl::tls_server::*;
+#[cfg(feature = "tls-ring")]
+use trust_dns_rustls::tls_server::{self as rustls_server, Certificate, PrivateKey};
 
 fn parse_zone_file(
     file: File,
@@ -99,15 +120,54 @@ fn parse_zone_file(
 }
 
 #[cfg_attr(not(feature = "dnssec"), allow(unused_mut))]
-fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, String> {
+fn load_zone(
+    zone_dir: &Path,
+    zone_config: &ZoneConfig,
+    tsig_keys: &[TSigner],
+) -> Result<Authority, String> {
     debug!("loading zone with config: {:#?}", zone_config);
 
     let zone_name: Name = zone_config.get_zone().expect("bad zone name");
-    let zone_path: PathBuf = zone_dir.to_owned().join(zone_config.get_file());
+
+    // forward zones have no records, all requests are resolved by the forwarders
+    if zone_config.get_zone_type() == ZoneType::Forward {
+        let forwarders = zone_config
+            .get_forwarders()
+            .map_err(|e| format!("bad forwarders: {}", e))?;
+        if forwarders.is_empty() {
+            return Err(format!("no forwarders for forward zone: {}", zone_name));
+        }
+
+        info!("forwarding zone: {}", zone_name);
+        let mut authority = Authority::new(
+            zone_name,
+            BTreeMap::new(),
+            ZoneType::Forward,
+            false,
+            false,
+        );
+        authority.set_allow_query(zone_config
+            .get_allow_query()
+            .map_err(|e| format!("bad allow_query: {}", e))?);
+
+        return Ok(authority);
+    }
+
+    let zone_file = zone_config
+        .get_file()
+        .ok_or_else(|| format!("no zone file defined for: {}", zone_name))?;
+    let zone_path: PathBuf = zone_dir.to_owned().join(zone_file);
     let journal_path: PathBuf = zone_path.with_extension("jrnl");
 
+    // dynamic zones are journaled, as are slave zones so that they can be served on restart. Zones
+    //  signed offline refuse updates, and their RRSIGs are not journaled, so they are always loaded
+    //  from the zone file.
+    let is_signed_offline = zone_config.is_dnssec_enabled() && zone_config.get_keys().is_empty();
+    let is_journaled = (zone_config.is_update_allowed() && !is_signed_offline)
+        || zone_config.get_zone_type() == ZoneType::Slave;
+
     // load the zone
-    let mut authority = if zone_config.is_update_allowed() && journal_path.exists() {
+    let mut authority = if is_journaled && journal_path.exists() {
         info!("recovering zone from journal: {:?}", journal_path);
         let journal = Journal::from_file(&journal_path).map_err(|e| {
             format!("error opening journal: {:?}: {}", journal_path, e)
@@ -144,7 +204,7 @@ fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, Str
         ).map_err(|e| format!("error reading zone: {:?}: {}", zone_path, e))?;
 
         // if dynamic update is enabled, enable the journal
-        if zone_config.is_update_allowed() {
+        if is_journaled {
             info!("enabling journal: {:?}", journal_path);
             let journal = Journal::from_file(&journal_path).map_err(|e| {
                 format!("error creating journal {:?}: {}", journal_path, e)
@@ -159,6 +219,23 @@ fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, Str
         }
 
         info!("zone file loaded: {}", zone_name);
+        authority
+    } else if zone_config.get_zone_type() == ZoneType::Slave {
+        info!("no local copy of slave zone: {}, awaiting transfer", zone_name);
+
+        let journal = Journal::from_file(&journal_path).map_err(|e| {
+            format!("error creating journal {:?}: {}", journal_path, e)
+        })?;
+
+        let mut authority = Authority::new(
+            zone_name.clone(),
+            BTreeMap::new(),
+            zone_config.get_zone_type(),
+            zone_config.is_update_allowed(),
+            zone_config.is_dnssec_enabled(),
+        );
+        authority.set_journal(journal);
+
         authority
     } else {
         return Err(format!("no zone file defined at: {:?}", zone_path));
@@ -171,21 +248,66 @@ fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, Str
         zone_config: &ZoneConfig,
     ) -> Result<(), String> {
         if zone_config.is_dnssec_enabled() {
+            // without keys the signatures and denial of existence in the zone file are served as
+            //  they are
+            if zone_config.get_keys().is_empty() {
+                if zone_config.get_nsec3().is_some() {
+                    warn!(
+                        "nsec3 is ignored without keys, the NSEC3PARAM of the zone is used: {}",
+                        zone_name
+                    );
+                }
+
+                info!("serving zone signed offline: {}", zone_name);
+                return Ok(());
+            }
+
             for key_config in zone_config.get_keys() {
-                let signer = load_key(zone_name.clone(), key_config).map_err(|e| {
+                let signer = load_key(
+                    zone_name.clone(),
+                    key_config,
+                    zone_config.get_signature_validity(),
+                ).map_err(|e| {
                     format!("failed to load key: {:?} msg: {}", key_config.key_path(), e)
                 })?;
+                let timing = key_config.timing().map_err(|e| {
+                    format!("bad key times: {:?} msg: {}", key_config.key_path(), e)
+                })?;
                 info!(
-                    "adding key to zone: {:?}, is_zsk: {}, is_auth: {}",
+                    "adding key to zone: {:?}, is_zsk: {}, is_ksk: {}, is_auth: {}, timing: {:?}",
                     key_config.key_path(),
                     key_config.is_zone_signing_key(),
-                    key_config.is_zone_update_auth()
+                    key_config.is_key_signing_key(),
+                    key_config.is_zone_update_auth(),
+                    timing
                 );
                 authority
-                    .add_secure_key(signer)
+                    .add_secure_key_with_timing(signer, timing)
                     .expect("failed to add key to authority");
             }
 
+            if let Some(nsec3) = zone_config.get_nsec3() {
+                let nsec3param = nsec3
+                    .to_nsec3param()
+                    .map_err(|e| format!("bad nsec3 configuration: {}", e))?;
+                info!(
+                    "denying existence with nsec3, iterations: {}, opt_out: {}",
+                    nsec3param.iterations(),
+                    nsec3param.opt_out()
+                );
+                authority.set_nsec3param(Some(nsec3param));
+            }
+
+            if let Some(inception) = zone_config.get_signature_inception() {
+                authority.set_signature_inception(inception);
+            }
+            if let Some(refresh) = zone_config.get_signature_refresh() {
+                authority.set_signature_refresh(refresh);
+            }
+            if let Some(jitter) = zone_config.get_signature_jitter() {
+                authority.set_signature_jitter(jitter);
+            }
+
             info!("signing zone: {}", zone_config.get_zone().unwrap());
             authority.secure_zone().expect("failed to sign zone");
         }
@@ -201,6 +323,40 @@ fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, Str
         Ok(())
     }
 
+    // requests from outside of the networks of the access control lists are refused
+    authority.set_allow_query(zone_config
+        .get_allow_query()
+        .map_err(|e| format!("bad allow_query: {}", e))?);
+    authority.set_allow_update_from(zone_config
+        .get_allow_update()
+        .map_err(|e| format!("bad allow_update: {}", e))?);
+    authority.set_allow_transfer(zone_config
+        .get_allow_transfer()
+        .map_err(|e| format!("bad allow_transfer: {}", e))?);
+    authority.set_also_notify(zone_config
+        .get_also_notify()
+        .map_err(|e| format!("bad also_notify: {}", e))?);
+
+    // requests signed with the TSIG keys of the zone may update it
+    for key_name in zone_config.get_tsig_keys() {
+        authority.add_tsig_key(find_tsig_key(tsig_keys, key_name)?);
+    }
+
+    // transfers must be signed with one of the transfer keys, if the zone has any
+    for key_name in zone_config.get_transfer_tsig_keys() {
+        authority.add_transfer_tsig_key(find_tsig_key(tsig_keys, key_name)?);
+    }
+
+    // slave zones accept NOTIFY from their masters, unless allow_notify says otherwise
+    if zone_config.get_zone_type() == ZoneType::Slave {
+        zone_config
+            .get_masters()
+            .map_err(|e| format!("bad masters: {}", e))?;
+        authority.set_allow_notify(zone_config
+            .get_allow_notify()
+            .map_err(|e| format!("bad allow_notify: {}", e))?);
+    }
+
     // load any keys for the Zone, if it is a dynamic update zone, then keys are required
     load_keys(&mut authority, zone_name, zone_config)?;
 
@@ -211,6 +367,45 @@ fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, Str
     Ok(authority)
 }
 
+/// Returns the TSIG key with the name, from the `tsig_keys` of the configuration
+fn find_tsig_key(tsig_keys: &[TSigner], key_name: &str) -> Result<TSigner, String> {
+    let key_name = Name::parse(key_name, Some(&Name::new()))
+        .map_err(|e| format!("bad TSIG key name {}: {}", key_name, e))?;
+
+    tsig_keys
+        .iter()
+        .find(|signer| *signer.key_name() == key_name)
+        .cloned()
+        .ok_or_else(|| format!("unknown TSIG key: {}", key_name))
+}
+
+/// Returns the addresses of the root name servers from the records of a Hint zone, i.e. the A and
+///  AAAA records of the name servers of its origin
+fn root_hints(authority: &Authority) -> Vec<SocketAddr> {
+    let no_algorithms = SupportedAlgorithms::new();
+    let ns = authority.lookup(authority.origin(), RecordType::NS, false, no_algorithms);
+
+    let mut hints = Vec::new();
+    for record in ns.iter() {
+        let ns_name = match *record.rdata() {
+            RData::NS(ref ns_name) => LowerName::from(ns_name),
+            _ => continue,
+        };
+
+        for record_type in &[RecordType::A, RecordType::AAAA] {
+            for address in authority.lookup(&ns_name, *record_type, false, no_algorithms).iter() {
+                match *address.rdata() {
+                    RData::A(ip) => hints.push(SocketAddr::new(IpAddr::V4(ip), 53)),
+                    RData::AAAA(ip) => hints.push(SocketAddr::new(IpAddr::V6(ip), 53)),
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    hints
+}
+
 /// set of DNSSEC algorithms to use to sign the zone. enable_dnssec must be true.
 /// these will be lookedup by $file.{key_name}.pem, for backward compatability
 /// with previous versions of TRust-DNS, if enable_dnssec is enabled but
@@ -226,7 +421,11 @@ fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, Str
 /// same directory has the zone $file:
 ///  keys = [ "my_rsa_2048|RSASHA256", "/path/to/my_ed25519|ED25519" ]
 #[cfg(feature = "dnssec")]
-fn load_key(zone_name: Name, key_config: &KeyConfig) -> Result<Signer, String> {
+fn load_key(
+    zone_name: Name,
+    key_config: &KeyConfig,
+    sig_duration: StdDuration,
+) -> Result<Signer, String> {
     let key_path = key_config.key_path();
     let algorithm = key_config
         .algorithm()
@@ -258,19 +457,22 @@ fn load_key(zone_name: Name, key_config: &KeyConfig) -> Result<Signer, String> {
         .map_err(|e| format!("error reading name: {}", e))?
         .unwrap_or(zone_name);
 
-    // add the key to the zone
-    // TODO: allow the duration of signatutes to be customized
-    let dnskey = key.to_dnskey(algorithm)
+    // add the key to the zone, only KSKs have the Secure Entry Point flag
+    let public_key = key.to_public_bytes()
         .map_err(|e| format!("error converting to dnskey: {}", e))?;
-    Ok(Signer::dnssec(
-        dnskey.clone(),
-        key,
-        name,
-        Duration::weeks(52),
-    ))
+    let dnskey = DNSKEY::new(
+        true,
+        key_config.is_key_signing_key(),
+        false,
+        algorithm,
+        public_key,
+    );
+    let sig_duration = Duration::from_std(sig_duration)
+        .map_err(|e| format!("bad signature_validity: {}", e))?;
+    Ok(Signer::dnssec(dnskey, key, name, sig_duration))
 }
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "tls-openssl")]
 fn load_cert(zone_dir: &Path, tls_cert_config: &TlsCertConfig) -> Result<ParsedPkcs12, String> {
     let path = zone_dir.to_owned().join(tls_cert_config.get_path());
     let password = tls_cert_config.get_password();
@@ -286,6 +488,7 @@ const CONFIG_ARG: &str = "config";
 const ZONEDIR_ARG: &str = "zonedir";
 const PORT_ARG: &str = "port";
 const TLS_PORT_ARG: &str = "tls-port";
+const HTTPS_PORT_ARG: &str = "https-port";
 
 /// Args struct for all options
 struct Args {
@@ -295,6 +498,7 @@ struct Args {
     pub flag_zonedir: Option<String>,
     pub flag_port: Option<u16>,
     pub flag_tls_port: Option<u16>,
+    pub flag_https_port: Option<u16>,
 }
 
 impl<'a> From<ArgMatches<'a>> for Args {
@@ -313,6 +517,9 @@ impl<'a> From<ArgMatches<'a>> for Args {
             flag_tls_port: matches.value_of(TLS_PORT_ARG).map(|s| {
                 u16::from_str_radix(s, 10).expect("bad tls-port argument")
             }),
+            flag_https_port: matches.value_of(HTTPS_PORT_ARG).map(|s| {
+                u16::from_str_radix(s, 10).expect("bad https-port argument")
+            }),
         }
     }
 }
@@ -364,6 +571,12 @@ pub fn main() {
                 .help("Listening port for DNS over TLS queries, overrides any value in config file")
                 .value_name(TLS_PORT_ARG),
         )
+        .arg(
+            Arg::with_name(HTTPS_PORT_ARG)
+                .long(HTTPS_PORT_ARG)
+                .help("Listening port for DNS over HTTPS queries, enables DNS over HTTPS")
+                .value_name(HTTPS_PORT_ARG),
+        )
         .get_matches();
 
     let args: Args = args.into();
@@ -390,14 +603,68 @@ pub fn main() {
         .map(Path::new)
         .unwrap_or_else(|| config.get_directory());
 
+    let tsig_keys: Vec<TSigner> = config
+        .get_tsig_keys()
+        .iter()
+        .map(|key| {
+            key.to_signer()
+                .expect(&format!("bad TSIG key in {:?}", config_path))
+        })
+        .collect();
+
+    let server_cookies = config
+        .get_server_cookies()
+        .expect(&format!("bad cookie_secret in {:?}", config_path))
+        .map(Arc::new);
+
     let mut catalog: Catalog = Catalog::new();
+    if let Some(ref server_cookies) = server_cookies {
+        catalog.set_server_cookies(server_cookies.clone());
+    }
+    let mut slave_zones: Vec<SlaveZone> = Vec::new();
+    let mut notifying_zones: Vec<Arc<RwLock<Authority>>> = Vec::new();
+    let mut forward_zones: Vec<(Arc<RwLock<Authority>>, Vec<SocketAddr>)> = Vec::new();
+    let mut hint_zones: Vec<Arc<RwLock<Authority>>> = Vec::new();
+    let mut signed_zones: Vec<Arc<RwLock<Authority>>> = Vec::new();
     // configure our server based on the config_path
     for zone in config.get_zones() {
         let zone_name = zone.get_zone()
             .expect(&format!("bad zone name in {:?}", config_path));
 
-        match load_zone(zone_dir, zone) {
-            Ok(authority) => catalog.upsert(zone_name.into(), authority),
+        match load_zone(zone_dir, zone, &tsig_keys) {
+            Ok(authority) => {
+                let zone_name = LowerName::from(zone_name);
+                catalog.upsert(zone_name.clone(), authority);
+                let authority = catalog
+                    .authority(&zone_name)
+                    .expect("zone was just inserted");
+
+                match zone.get_zone_type() {
+                    ZoneType::Master => {
+                        // zones signed offline are not signed again
+                        if zone.is_dnssec_enabled() && !zone.get_keys().is_empty() {
+                            signed_zones.push(authority.clone());
+                        }
+
+                        notifying_zones.push(authority)
+                    }
+                    ZoneType::Slave => {
+                        let masters = zone.get_masters().expect("masters checked by load_zone");
+                        if masters.is_empty() {
+                            warn!("no masters for slave zone: {}", zone_name);
+                        }
+
+                        notifying_zones.push(authority.clone());
+                        slave_zones.push(SlaveZone::new(authority, masters));
+                    }
+                    ZoneType::Forward => {
+                        let forwarders =
+                            zone.get_forwarders().expect("forwarders checked by load_zone");
+                        forward_zones.push((authority, forwarders))
+                    }
+                    ZoneType::Hint => hint_zones.push(authority),
+                }
+            }
             Err(error) => error!("could not load zone {}: {}", zone_name, error),
         }
     }
@@ -438,6 +705,19 @@ pub fn main() {
     // now, run the server, based on the config
     let mut server = ServerFuture::new(catalog).expect("error creating ServerFuture");
 
+    // limit the UDP responses, before the sockets are registered
+    if let Some(rate_limit) = config.get_rate_limit() {
+        let opts = rate_limit.to_opts().expect("bad rate_limit");
+        info!("limiting UDP responses: {:?}", opts);
+        let mut rate_limiter = RateLimiter::new(opts);
+
+        // clients with valid cookies are not limited
+        if let Some(ref server_cookies) = server_cookies {
+            rate_limiter.set_server_cookies(server_cookies.clone());
+        }
+        server.set_rate_limiter(rate_limiter);
+    }
+
     // load all the listeners
     for udp_socket in udp_sockets {
         info!("listening for UDP on {:?}", udp_socket);
@@ -464,6 +744,44 @@ pub fn main() {
         );
     }
 
+    // and HTTPS, with the certificate of TLS, as necessary
+    if args.flag_https_port.is_some() || config.get_https_listen_port().is_some() {
+        config_https(&args, &mut server, &config, zone_dir, &listen_addrs);
+    }
+
+    // NOTIFY the slaves of zones as the zones change
+    let handle = server.tokio_core().handle();
+    let notifier = Notifier::spawn(&handle);
+    for authority in notifying_zones {
+        authority.write().unwrap().set_notifier(notifier.clone());
+    }
+
+    // resolve the forward zones through their forwarders
+    for (authority, forwarders) in forward_zones {
+        let forwarder = Forwarder::new(&forwarders, &handle);
+        authority.write().unwrap().set_forwarder(forwarder);
+    }
+
+    // resolve the hint zones recursively, starting from the root name servers of the hints
+    for authority in hint_zones {
+        let mut authority = authority.write().unwrap();
+        let hints = root_hints(&authority);
+        if hints.is_empty() {
+            warn!("no root name server addresses in hint zone: {}", authority.origin());
+        }
+
+        let recursor = Recursor::new(hints, ResolverOpts::default(), &handle);
+        authority.set_forwarder(Forwarder::recursive(recursor, &handle));
+    }
+
+    // keep the slave zones up to date with their masters
+    for slave_zone in slave_zones {
+        slave_zone.spawn(&handle);
+    }
+
+    // refresh the signatures of the signed zones before they expire
+    spawn_signed_zones(signed_zones, &handle);
+
     // config complete, starting!
     banner();
     info!("awaiting connections...");
@@ -475,6 +793,20 @@ pub fn main() {
     info!("Trust-DNS {} stopping", trust_dns::version());
 }
 
+#[cfg(feature = "dnssec")]
+fn spawn_signed_zones(signed_zones: Vec<Arc<RwLock<Authority>>>, handle: &Handle) {
+    for authority in signed_zones {
+        SignedZone::new(authority).spawn(handle);
+    }
+}
+
+#[cfg(not(feature = "dnssec"))]
+fn spawn_signed_zones(signed_zones: Vec<Arc<RwLock<Authority>>>, _handle: &Handle) {
+    if !signed_zones.is_empty() {
+        warn!("DNSSEC is not enabled, the zones will not be signed");
+    }
+}
+
 #[cfg(not(feature = "tls"))]
 fn config_tls(
     _args: &Args,
@@ -512,22 +844,171 @@ fn config_tls(
         warn!("a tls certificate was specified, but no TCP addresses configured to listen on");
     }
 
+    let cert_type = tls_cert_config
+        .get_cert_type()
+        .expect("bad tls_cert cert_type");
     for tls_listener in tls_listeners {
         info!(
             "loading cert for DNS over TLS: {:?}",
             tls_cert_config.get_path()
         );
-        // TODO: see about modifying native_tls to impl Clone for Pkcs12
-        let tls_cert =
-            load_cert(zone_dir, tls_cert_config).expect("error loading tls certificate file");
 
         info!("listening for TLS on {:?}", tls_listener);
+        match cert_type {
+            CertType::Pkcs12 => register_pkcs12_listener(
+                server,
+                tls_listener,
+                config.get_tcp_request_timeout(),
+                tls_cert_config,
+                zone_dir,
+            ),
+            CertType::Pem => register_pem_listener(
+                server,
+                tls_listener,
+                config.get_tcp_request_timeout(),
+                tls_cert_config,
+                zone_dir,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "tls-openssl")]
+fn register_pkcs12_listener(
+    server: &mut ServerFuture<Catalog>,
+    tls_listener: TcpListener,
+    timeout: std::time::Duration,
+    tls_cert_config: &TlsCertConfig,
+    zone_dir: &Path,
+) {
+    // TODO: see about modifying native_tls to impl Clone for Pkcs12
+    let tls_cert =
+        load_cert(zone_dir, tls_cert_config).expect("error loading tls certificate file");
+
+    server
+        .register_tls_listener(tls_listener, timeout, tls_cert)
+        .expect("could not register TLS listener");
+}
+
+#[cfg(all(feature = "tls", not(feature = "tls-openssl")))]
+fn register_pkcs12_listener(
+    _server: &mut ServerFuture<Catalog>,
+    _tls_listener: TcpListener,
+    _timeout: std::time::Duration,
+    _tls_cert_config: &TlsCertConfig,
+    _zone_dir: &Path,
+) {
+    panic!("pkcs12 certificates require the tls-openssl feature, use cert_type = \"pem\"");
+}
+
+#[cfg(feature = "tls-ring")]
+fn register_pem_listener(
+    server: &mut ServerFuture<Catalog>,
+    tls_listener: TcpListener,
+    timeout: std::time::Duration,
+    tls_cert_config: &TlsCertConfig,
+    zone_dir: &Path,
+) {
+    let (cert_chain, key) = load_pem(zone_dir, tls_cert_config);
+
+    server
+        .register_rustls_listener(tls_listener, timeout, cert_chain, key)
+        .expect("could not register TLS listener");
+}
+
+#[cfg(feature = "tls-ring")]
+fn load_pem(zone_dir: &Path, tls_cert_config: &TlsCertConfig) -> (Vec<Certificate>, PrivateKey) {
+    let cert_path = zone_dir.to_owned().join(tls_cert_config.get_path());
+    let key_path = zone_dir.to_owned().join(
+        tls_cert_config
+            .get_private_key()
+            .expect("a private_key is required for a pem tls_cert"),
+    );
+
+    info!("reading TLS certificate from: {:?}", cert_path);
+    let cert_chain =
+        rustls_server::read_cert(&cert_path).expect("error loading tls certificate file");
+    info!("reading TLS private key from: {:?}", key_path);
+    let key = rustls_server::read_key(&key_path).expect("error loading tls private key file");
+
+    (cert_chain, key)
+}
+
+#[cfg(not(feature = "https"))]
+fn config_https(
+    _args: &Args,
+    _server: &mut ServerFuture<Catalog>,
+    _config: &Config,
+    _zone_dir: &Path,
+    _listen_addrs: &[IpAddr],
+) {
+    panic!("HTTPS not enabled");
+}
+
+#[cfg(feature = "https")]
+fn config_https(
+    args: &Args,
+    server: &mut ServerFuture<Catalog>,
+    config: &Config,
+    zone_dir: &Path,
+    listen_addrs: &[IpAddr],
+) {
+    let https_listen_port: u16 = args.flag_https_port
+        .or_else(|| config.get_https_listen_port())
+        .expect("no https listen port");
+    let https_sockaddrs: Vec<SocketAddr> = listen_addrs
+        .iter()
+        .flat_map(|x| (*x, https_listen_port).to_socket_addrs().unwrap())
+        .collect();
+    let https_listeners: Vec<TcpListener> = https_sockaddrs
+        .iter()
+        .map(|x| {
+            TcpListener::bind(x).expect(&format!("could not bind to https: {}", x))
+        })
+        .collect();
+    if https_listeners.is_empty() {
+        warn!("a tls certificate was specified, but no HTTPS addresses configured to listen on");
+    }
+
+    // DNS over HTTPS shares the certificate of DNS over TLS
+    let tls_cert_config = config
+        .get_tls_cert()
+        .expect("DNS over HTTPS requires a tls_cert");
+    if tls_cert_config.get_cert_type().expect("bad tls_cert cert_type") != CertType::Pem {
+        panic!("DNS over HTTPS requires a tls_cert with cert_type = \"pem\"");
+    }
+
+    for https_listener in https_listeners {
+        let (cert_chain, key) = load_pem(zone_dir, tls_cert_config);
+
+        info!(
+            "listening for HTTPS on {:?} at {}",
+            https_listener,
+            config.get_https_endpoint()
+        );
         server
-            .register_tls_listener(tls_listener, config.get_tcp_request_timeout(), tls_cert)
-            .expect("could not register TLS listener");
+            .register_https_listener(
+                https_listener,
+                config.get_tcp_request_timeout(),
+                cert_chain,
+                key,
+                config.get_https_endpoint().to_string(),
+            )
+            .expect("could not register HTTPS listener");
     }
 }
 
+#[cfg(all(feature = "tls", not(feature = "tls-ring")))]
+fn register_pem_listener(
+    _server: &mut ServerFuture<Catalog>,
+    _tls_listener: TcpListener,
+    _timeout: std::time::Duration,
+    _tls_cert_config: &TlsCertConfig,
+    _zone_dir: &Path,
+) {
+    panic!("pem certificates require the tls-ring feature, use cert_type = \"pkcs12\"");
+}
+
 fn banner() {
     info!("");
     info!("    o                      o            o             ");
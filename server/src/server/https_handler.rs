@@ -0,0 +1,103 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::{Future, Stream};
+use futures::sync::mpsc::unbounded;
+use h2;
+use h2::RecvStream;
+use h2::server::SendResponse;
+use http::{Request, StatusCode};
+use tokio_core::reactor::Handle;
+use tokio_io::{AsyncRead, AsyncWrite};
+
+use trust_dns::BufStreamHandle;
+use trust_dns_https::{self, HttpsError};
+use trust_dns_https::https_server;
+
+use server::{Protocol, RequestHandler, ResponseHandle, ServerFuture, TimeoutStream};
+
+/// Serves the DNS query endpoint at `path` on an HTTP/2 connection, over an established TLS
+///  stream
+///
+/// Each request is handled by the `RequestHandler`, the first message sent in response is
+///  returned to the client. The connection is closed when no request arrives within `timeout`.
+pub(crate) fn h2_handler<T, I>(
+    handler: Arc<T>,
+    io: I,
+    src_addr: SocketAddr,
+    path: Arc<String>,
+    timeout: Duration,
+    reactor: Handle,
+) -> Box<Future<Item = (), Error = io::Error>>
+where
+    T: RequestHandler + 'static,
+    I: AsyncRead + AsyncWrite + 'static,
+{
+    Box::new(
+        h2::server::handshake(io)
+            .map_err(h2_to_io)
+            .and_then(move |connection| {
+                let requests = TimeoutStream::new(connection.map_err(h2_to_io), timeout, &reactor)?;
+
+                Ok(requests.for_each(move |(request, respond)| {
+                    debug!("received DNS over HTTPS request from: {}", src_addr);
+                    reactor.spawn(serve(handler.clone(), src_addr, &path, request, respond));
+                    Ok(())
+                }))
+            })
+            .flatten(),
+    )
+}
+
+/// Answers a single request, the stream of which is independent of the others on the connection
+fn serve<T: RequestHandler + 'static>(
+    handler: Arc<T>,
+    src_addr: SocketAddr,
+    path: &str,
+    request: Request<RecvStream>,
+    mut respond: SendResponse<Bytes>,
+) -> Box<Future<Item = (), Error = ()>> {
+    Box::new(
+        https_server::message_from(path, request)
+            .and_then(move |message| {
+                // the response of the handler is received here, rather than sent to a connection
+                let (sender, receiver) = unbounded();
+                let response_handle = ResponseHandle::new(src_addr, BufStreamHandle::new(sender));
+
+                ServerFuture::<T>::handle_request(
+                    message,
+                    src_addr,
+                    Protocol::Https,
+                    response_handle,
+                    handler,
+                ).map_err(|e| {
+                    trust_dns_https::error::status(
+                        StatusCode::BAD_REQUEST,
+                        format!("bad DNS message: {}", e),
+                    )
+                })?;
+
+                Ok(receiver.into_future().then(|next| match next {
+                    Ok((Some((response, _)), _)) => Ok(response),
+                    _ => Err(HttpsError::from("no response to the request")),
+                }))
+            })
+            .flatten()
+            .then(move |message| https_server::send_response(&mut respond, message))
+            .map_err(move |e| debug!("error in DNS over HTTPS response to: {}: {}", src_addr, e)),
+    )
+}
+
+fn h2_to_io(e: h2::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("http/2 error: {}", e))
+}
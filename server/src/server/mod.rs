@@ -16,12 +16,19 @@
 
 //! `Server` component for hosting a domain name servers operations.
 
+#[cfg(feature = "https")]
+mod https_handler;
 mod server_future;
 mod timeout_stream;
+pub mod rate_limiter;
 mod request_handler;
 mod response_handler;
+pub mod server_cookies;
 
-pub use self::response_handler::{ResponseHandle, ResponseHandler};
+pub use self::response_handler::{ResponseHandle, ResponseHandler, TsigResponseHandle};
+pub(crate) use self::response_handler::TsigMessage;
+pub use self::rate_limiter::{RateLimitAction, RateLimitOpts, RateLimiter};
+pub use self::server_cookies::ServerCookies;
 pub use self::server_future::ServerFuture;
 pub use self::timeout_stream::TimeoutStream;
-pub use self::request_handler::{Request, RequestHandler};
+pub use self::request_handler::{Protocol, Request, RequestHandler};
@@ -0,0 +1,370 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Response Rate Limiting, RRL, of UDP responses
+//!
+//! Spoofed UDP requests make a name server into an amplifier of traffic toward the spoofed
+//!  address. Responses are accounted in token buckets, one per client network and response, and
+//!  once a bucket is empty further responses are dropped, or "slipped", sent truncated so that a
+//!  legitimate client retries over TCP. This follows the design of RRL in BIND.
+//!
+//! Clients which return a valid server cookie, RFC 7873, have proven that they receive responses
+//!  at their address, and are not limited.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use trust_dns::op::{Header, Query, ResponseCode};
+use trust_dns::rr::{LowerName, Record, RecordType};
+use trust_dns::rr::rdata::opt::{EdnsCode, EdnsOption};
+use trust_dns::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder};
+use trust_dns_proto::error::ProtoResult;
+
+use authority::MessageRequest;
+use server::ServerCookies;
+
+/// Configuration for the `RateLimiter`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RateLimitOpts {
+    /// Identical responses per second to a client network before responses are limited, this is
+    ///  also the size of the burst allowed. Defaults to 5
+    pub responses_per_second: u32,
+    /// Of the limited responses, every `slip`th is sent truncated rather than dropped, 0 drops all
+    ///  limited responses. Defaults to 2
+    pub slip: u32,
+    /// Clients are grouped into IPv4 networks of this prefix length. Defaults to 24
+    pub ipv4_prefix_len: u8,
+    /// Clients are grouped into IPv6 networks of this prefix length. Defaults to 56
+    pub ipv6_prefix_len: u8,
+    /// Accounts of responses which have not been seen in this long are forgotten. Defaults to 15
+    ///  seconds
+    pub window: Duration,
+    /// Only log the responses which would be limited, all responses are sent. Defaults to false
+    pub log_only: bool,
+    /// The most responses accounted at once, once reached the responses seen longest ago are
+    ///  forgotten to make room for new ones. Defaults to 20000
+    pub max_table_size: usize,
+}
+
+impl Default for RateLimitOpts {
+    fn default() -> Self {
+        RateLimitOpts {
+            responses_per_second: 5,
+            slip: 2,
+            ipv4_prefix_len: 24,
+            ipv6_prefix_len: 56,
+            window: Duration::from_secs(15),
+            log_only: false,
+            max_table_size: 20_000,
+        }
+    }
+}
+
+/// What to do with a response, see `RateLimiter::check`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RateLimitAction {
+    /// The response is within the limits, send it
+    Send,
+    /// The response is limited, drop it
+    Drop,
+    /// The response is limited, send it truncated, see `truncate`
+    Slip,
+}
+
+/// Responses are accounted by the network of the client and the name, type and response code
+///
+/// The name of NXDOMAIN and NODATA responses is the owner of the SOA of the response, the zone,
+///  rather than the name of the question, as random names would otherwise each have an account.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ResponseKey {
+    network: IpAddr,
+    name: LowerName,
+    query_type: RecordType,
+    response_code: u8,
+}
+
+/// The token bucket of a `ResponseKey`
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    limited: u32,
+}
+
+/// Response Rate Limiting with token buckets, one per client network and response
+///
+/// The current time is passed to `check`, rather than read from the system clock, so that the
+///  limits can be tested with a synthetic clock.
+#[derive(Debug)]
+pub struct RateLimiter {
+    opts: RateLimitOpts,
+    buckets: HashMap<ResponseKey, Bucket>,
+    purged: Option<Instant>,
+    cookies: Option<Arc<ServerCookies>>,
+}
+
+impl RateLimiter {
+    /// Returns a new `RateLimiter` with no responses accounted
+    pub fn new(opts: RateLimitOpts) -> Self {
+        RateLimiter {
+            opts: opts,
+            buckets: HashMap::new(),
+            purged: None,
+            cookies: None,
+        }
+    }
+
+    /// Exempts the clients with a valid server cookie from the limits, see `is_exempt`
+    ///
+    /// These should be the same cookies as those of the `Catalog`, which mints them.
+    pub fn set_server_cookies(&mut self, cookies: Arc<ServerCookies>) {
+        self.cookies = Some(cookies);
+    }
+
+    /// Returns true if the responses to the request should not be limited, as it carries a valid
+    ///  server cookie for the client
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the request
+    /// * `src` - the address of the client the request is from
+    /// * `now` - the current time, in seconds since the Unix epoch
+    pub fn is_exempt(&self, request: &MessageRequest, src: IpAddr, now: u32) -> bool {
+        let cookies = match self.cookies {
+            Some(ref cookies) => cookies,
+            None => return false,
+        };
+
+        match request.edns().and_then(|edns| edns.option(&EdnsCode::Cookie)) {
+            Some(&EdnsOption::Cookie(ref cookie)) => {
+                cookie.has_server() && cookies.is_valid(cookie, src, now)
+            }
+            _ => false,
+        }
+    }
+
+    /// The configuration of the limits
+    pub fn opts(&self) -> &RateLimitOpts {
+        &self.opts
+    }
+
+    /// Accounts for a response, and returns what should be done with it
+    ///
+    /// # Arguments
+    ///
+    /// * `dst` - the address of the client the response is for
+    /// * `response` - the response message, in wire format
+    /// * `now` - the current time
+    pub fn check(&mut self, dst: IpAddr, response: &[u8], now: Instant) -> RateLimitAction {
+        let key = match self.response_key(dst, response) {
+            Ok(key) => key,
+            Err(e) => {
+                // all responses are encoded by this server, so this should not happen
+                warn!("could not account response to {}: {}", dst, e);
+                return RateLimitAction::Send;
+            }
+        };
+
+        self.purge(now);
+        if self.buckets.len() >= self.opts.max_table_size && !self.buckets.contains_key(&key) {
+            self.evict(now);
+        }
+
+        let rate = f64::from(self.opts.responses_per_second);
+        let bucket = self.buckets.entry(key).or_insert(Bucket {
+            tokens: rate,
+            updated: now,
+            limited: 0,
+        });
+
+        // refill for the time since the last response, up to the burst of one second
+        let elapsed = duration_as_secs(now.duration_since(bucket.updated));
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(rate);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            bucket.limited = 0;
+            return RateLimitAction::Send;
+        }
+
+        bucket.limited = bucket.limited.wrapping_add(1);
+        let action = if self.opts.slip > 0 && bucket.limited % self.opts.slip == 0 {
+            RateLimitAction::Slip
+        } else {
+            RateLimitAction::Drop
+        };
+
+        if self.opts.log_only {
+            info!("would limit response to {}: {:?}", dst, action);
+            return RateLimitAction::Send;
+        }
+
+        debug!("limiting response to {}: {:?}", dst, action);
+        action
+    }
+
+    /// The number of responses currently accounted
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Returns true if no responses are accounted
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    fn response_key(&self, dst: IpAddr, response: &[u8]) -> ProtoResult<ResponseKey> {
+        let mut decoder = BinDecoder::new(response);
+        let header = Header::read(&mut decoder)?;
+
+        // responses without a question, e.g. FORMERR, are all accounted together
+        let (mut name, query_type) = if header.query_count() > 0 {
+            let query = Query::read(&mut decoder)?;
+            (LowerName::new(query.name()), query.query_type())
+        } else {
+            (LowerName::default(), RecordType::NULL)
+        };
+
+        // NXDOMAIN and NODATA are accounted by the zone, the owner of the SOA in the authorities
+        let is_nx_domain = header.response_code() == ResponseCode::NXDomain.low();
+        let is_no_data =
+            header.response_code() == ResponseCode::NoError.low() && header.answer_count() == 0;
+        if header.query_count() == 1 && (is_nx_domain || is_no_data) {
+            for _ in 0..header.answer_count() {
+                Record::read(&mut decoder)?;
+            }
+
+            for _ in 0..header.name_server_count() {
+                let record = Record::read(&mut decoder)?;
+                if record.rr_type() == RecordType::SOA {
+                    name = LowerName::new(record.name());
+                    break;
+                }
+            }
+        }
+
+        Ok(ResponseKey {
+            network: self.network(dst),
+            name: name,
+            query_type: query_type,
+            response_code: header.response_code(),
+        })
+    }
+
+    /// The address of the network of the client
+    fn network(&self, addr: IpAddr) -> IpAddr {
+        match addr {
+            IpAddr::V4(addr) => {
+                let mut octets = addr.octets();
+                mask(&mut octets, self.opts.ipv4_prefix_len);
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            IpAddr::V6(addr) => {
+                let mut octets = addr.octets();
+                mask(&mut octets, self.opts.ipv6_prefix_len);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+        }
+    }
+
+    /// Forgets the responses which have not been seen within the window, at most once a second
+    fn purge(&mut self, now: Instant) {
+        if let Some(purged) = self.purged {
+            if now.duration_since(purged) < Duration::from_secs(1) {
+                return;
+            }
+        }
+
+        let window = self.opts.window;
+        self.buckets
+            .retain(|_, bucket| now.duration_since(bucket.updated) < window);
+        self.purged = Some(now);
+    }
+
+    /// Makes room for a new response in a full table, by forgetting the responses outside of the
+    ///  window, and if that is not enough, the eighth of the responses which were seen longest ago
+    fn evict(&mut self, now: Instant) {
+        self.purged = None;
+        self.purge(now);
+        if self.buckets.len() < self.opts.max_table_size {
+            return;
+        }
+
+        let mut updated: Vec<Instant> = self.buckets.values().map(|bucket| bucket.updated).collect();
+        if updated.is_empty() {
+            return;
+        }
+
+        updated.sort();
+        let oldest = updated[updated.len() / 8];
+        debug!("rate limiter table is full, forgetting responses seen before: {:?}", oldest);
+        self.buckets.retain(|_, bucket| bucket.updated > oldest);
+    }
+}
+
+/// Clears the bits of the address beyond the prefix
+fn mask(octets: &mut [u8], prefix_len: u8) {
+    for (i, octet) in octets.iter_mut().enumerate() {
+        let bits = (i * 8) as u32;
+        let prefix_len = u32::from(prefix_len);
+        if prefix_len <= bits {
+            *octet = 0;
+        } else if prefix_len < bits + 8 {
+            *octet &= 0xFF_u8 << (bits + 8 - prefix_len);
+        }
+    }
+}
+
+fn duration_as_secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1_000_000_000.0
+}
+
+/// Returns the "slip" of a response, the header with the truncated bit set and the question, with
+///  no records but the OPT of EDNS, which prompts the client to retry over TCP
+pub fn truncate(response: &[u8]) -> ProtoResult<Vec<u8>> {
+    let mut decoder = BinDecoder::new(response);
+    let mut header = Header::read(&mut decoder)?;
+
+    let mut queries = Vec::with_capacity(header.query_count() as usize);
+    for _ in 0..header.query_count() {
+        queries.push(Query::read(&mut decoder)?);
+    }
+
+    // the OPT carries the high bits of the response code, and the payload size of the server
+    let mut opt = None;
+    let records = header.answer_count() as usize + header.name_server_count() as usize
+        + header.additional_count() as usize;
+    for _ in 0..records {
+        let record = Record::read(&mut decoder)?;
+        if record.rr_type() == RecordType::OPT {
+            opt = Some(record);
+        }
+    }
+
+    header
+        .set_truncated(true)
+        .set_answer_count(0)
+        .set_name_server_count(0)
+        .set_additional_count(if opt.is_some() { 1 } else { 0 });
+
+    let mut buffer = Vec::with_capacity(512);
+    {
+        let mut encoder = BinEncoder::new(&mut buffer);
+        header.emit(&mut encoder)?;
+        for query in &queries {
+            query.emit(&mut encoder)?;
+        }
+        if let Some(ref opt) = opt {
+            opt.emit(&mut encoder)?;
+        }
+    }
+
+    Ok(buffer)
+}
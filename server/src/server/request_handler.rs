@@ -13,12 +13,37 @@ use std::io;
 use authority::MessageRequest;
 use server::ResponseHandler;
 
+/// The transport over which a request was received
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    /// User Datagram Protocol
+    Udp,
+    /// Transmission Control Protocol
+    Tcp,
+    /// DNS over TLS
+    Tls,
+    /// DNS over HTTPS, each HTTP response carries a single message
+    Https,
+}
+
+impl Protocol {
+    /// Returns true if the protocol is stream based, i.e. multiple messages may be sent in response
+    pub fn is_stream(&self) -> bool {
+        match *self {
+            Protocol::Udp | Protocol::Https => false,
+            Protocol::Tcp | Protocol::Tls => true,
+        }
+    }
+}
+
 /// An incoming request to the DNS catalog
 pub struct Request<'r> {
     /// Message with the associated query or update data
     pub message: MessageRequest<'r>,
     /// Source address of the Client
     pub src: SocketAddr,
+    /// Protocol over which the request was received
+    pub protocol: Protocol,
 }
 
 /// Trait for handling incoming requests, and providing a message response.
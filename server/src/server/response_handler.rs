@@ -7,38 +7,97 @@
 
 use std::io;
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use trust_dns::BufStreamHandle;
 use trust_dns::error::ClientError;
+use trust_dns::op::{Edns, Header};
+use trust_dns::rr::{RData, Record};
+use trust_dns::rr::dnssec::TSigner;
+use trust_dns::rr::rdata::tsig;
+use trust_dns_proto::error::ProtoResult;
 use trust_dns_proto::op::EncodableMessage;
 use trust_dns::serialize::binary::{BinEncodable, BinEncoder};
 
+use authority::MessageRequest;
+use server::rate_limiter::{self, RateLimitAction, RateLimiter};
+
 /// A handler for send a response to a client
-pub trait ResponseHandler {
+///
+/// Handlers are `Clone` so that multi-message responses, e.g. AXFR over TCP, can send each message
+///  of the response stream through a clone of the original handle.
+pub trait ResponseHandler: Clone {
     /// Serializes and sends a message to to the wrapped handle
     ///
-    /// self is consumed as only one message should ever be sent in response to a Request
+    /// self is consumed by each message. Most Requests are answered with a single message, a
+    ///  multi-message response, e.g. AXFR or IXFR over TCP, sends each of its messages through a
+    ///  clone of the handle, in order.
     fn send<M: EncodableMessage>(self, response: M) -> io::Result<()>;
 }
 
 /// A handler for wraping a BufStreamHandle, which will properly serialize the message and add the
 ///  associated destination.
+#[derive(Clone)]
 pub struct ResponseHandle {
     dst: SocketAddr,
     stream_handle: BufStreamHandle<ClientError>,
+    rate_limiter: Option<Arc<Mutex<RateLimiter>>>,
 }
 
 impl ResponseHandle {
     /// Returns a new `ResponseHandle` for sending a response message
     pub fn new(dst: SocketAddr, stream_handle: BufStreamHandle<ClientError>) -> Self {
-        ResponseHandle { dst, stream_handle }
+        ResponseHandle {
+            dst,
+            stream_handle,
+            rate_limiter: None,
+        }
+    }
+
+    /// Returns a new `ResponseHandle` for which responses are limited, see `RateLimiter`
+    pub fn with_rate_limiter(
+        dst: SocketAddr,
+        stream_handle: BufStreamHandle<ClientError>,
+        rate_limiter: Arc<Mutex<RateLimiter>>,
+    ) -> Self {
+        ResponseHandle {
+            dst,
+            stream_handle,
+            rate_limiter: Some(rate_limiter),
+        }
+    }
+
+    /// Removes the limit on the responses to the request, if it is exempt, see
+    ///  `RateLimiter::is_exempt`
+    pub fn exempt(mut self, request: &MessageRequest) -> Self {
+        let exempt = match self.rate_limiter {
+            Some(ref rate_limiter) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|now| now.as_secs() as u32)
+                    .unwrap_or(0);
+
+                rate_limiter
+                    .lock()
+                    .unwrap() // poison errors should panic
+                    .is_exempt(request, self.dst.ip(), now)
+            }
+            None => false,
+        };
+
+        if exempt {
+            self.rate_limiter = None;
+        }
+        self
     }
 }
 
 impl ResponseHandler for ResponseHandle {
     /// Serializes and sends a message to to the wrapped handle
     ///
-    /// self is consumed as only one message should ever be sent in response to a Request
+    /// self is consumed by each message, the messages of a multi-message response, e.g. AXFR or
+    ///  IXFR over TCP, are each sent through a clone of the handle to the same destination.
     fn send<M: EncodableMessage>(self, response: M) -> io::Result<()> {
         info!(
             "response: {} response_code: {} answers: {} name_servers: {} additionals: {}",
@@ -61,8 +120,159 @@ impl ResponseHandler for ResponseHandle {
             )
         })?;
 
+        if let Some(ref rate_limiter) = self.rate_limiter {
+            let action = rate_limiter
+                .lock()
+                .unwrap() // poison errors should panic
+                .check(self.dst.ip(), &buffer, Instant::now());
+
+            match action {
+                RateLimitAction::Send => (),
+                RateLimitAction::Drop => return Ok(()),
+                RateLimitAction::Slip => {
+                    buffer = rate_limiter::truncate(&buffer).map_err(|e| {
+                        io::Error::new(
+                            io::ErrorKind::Other,
+                            format!("error truncating message: {}", e),
+                        )
+                    })?
+                }
+            }
+        }
+
         self.stream_handle
             .unbounded_send((buffer, self.dst))
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "unknown"))
     }
 }
+
+/// A handler which signs each message of a response with TSIG, RFC 8945
+///
+/// The MAC of the first message covers the MAC of the request, and the MAC of each subsequent
+///  message of a multi-message response, e.g. AXFR, covers the MAC of the message before it.
+#[derive(Clone)]
+pub struct TsigResponseHandle<R: ResponseHandler> {
+    inner: R,
+    signer: Arc<TSigner>,
+    // the prefixed MAC covered by the next message, and true until the first message is sent
+    state: Arc<Mutex<(Vec<u8>, bool)>>,
+}
+
+impl<R: ResponseHandler> TsigResponseHandle<R> {
+    /// Returns a new `TsigResponseHandle` for the response to a request signed with the key
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - the handle to which the signed messages are sent
+    /// * `signer` - the key of the TSIG of the request
+    /// * `request_mac` - the MAC of the TSIG of the request
+    pub fn new(inner: R, signer: TSigner, request_mac: &[u8]) -> Self {
+        TsigResponseHandle {
+            inner,
+            signer: Arc::new(signer),
+            state: Arc::new(Mutex::new((tsig::prefixed_mac(request_mac), true))),
+        }
+    }
+}
+
+impl<R: ResponseHandler> ResponseHandler for TsigResponseHandle<R> {
+    /// Signs the message and sends it to the wrapped handle
+    fn send<M: EncodableMessage>(self, response: M) -> io::Result<()> {
+        let mut buffer = Vec::with_capacity(512);
+        {
+            let mut encoder: BinEncoder = BinEncoder::new(&mut buffer);
+            response.emit(&mut encoder).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("error encoding message: {}", e),
+                )
+            })?;
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "time is before the Unix epoch"))?
+            .as_secs();
+
+        let tsig = {
+            let mut state = self.state.lock().expect("TSIG state was poisoned");
+            let (ref mut previous, ref mut first) = *state;
+
+            let tsig = self.signer
+                .sign_message(&buffer, previous, *first, 0, now)
+                .map_err(|e| {
+                    io::Error::new(io::ErrorKind::Other, format!("error signing message: {}", e))
+                })?;
+
+            if let RData::TSIG(ref rdata) = *tsig.rdata() {
+                *previous = tsig::prefixed_mac(rdata.mac());
+            }
+            *first = false;
+            tsig
+        };
+
+        self.inner.send(TsigMessage::new(response, tsig))
+    }
+}
+
+/// A message with a TSIG, which is the final record of the message
+pub(crate) struct TsigMessage<M: EncodableMessage> {
+    message: M,
+    tsig: [Record; 1],
+}
+
+impl<M: EncodableMessage> TsigMessage<M> {
+    /// Appends the TSIG record to the message
+    pub(crate) fn new(message: M, tsig: Record) -> Self {
+        TsigMessage {
+            message,
+            tsig: [tsig],
+        }
+    }
+}
+
+impl<M: EncodableMessage> EncodableMessage for TsigMessage<M> {
+    fn header(&self) -> &Header {
+        self.message.header()
+    }
+
+    fn queries_len(&self) -> usize {
+        self.message.queries_len()
+    }
+
+    fn emit_queries(&self, encoder: &mut BinEncoder) -> ProtoResult<()> {
+        self.message.emit_queries(encoder)
+    }
+
+    fn answers_len(&self) -> usize {
+        self.message.answers_len()
+    }
+
+    fn emit_answers(&self, encoder: &mut BinEncoder) -> ProtoResult<()> {
+        self.message.emit_answers(encoder)
+    }
+
+    fn name_servers_len(&self) -> usize {
+        self.message.name_servers_len()
+    }
+
+    fn emit_name_servers(&self, encoder: &mut BinEncoder) -> ProtoResult<()> {
+        self.message.emit_name_servers(encoder)
+    }
+
+    fn additionals_len(&self) -> usize {
+        self.message.additionals_len()
+    }
+
+    fn emit_additionals(&self, encoder: &mut BinEncoder) -> ProtoResult<()> {
+        self.message.emit_additionals(encoder)
+    }
+
+    fn edns(&self) -> Option<&Edns> {
+        self.message.edns()
+    }
+
+    fn sig0(&self) -> &[Record] {
+        &self.tsig
+    }
+}
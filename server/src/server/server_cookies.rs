@@ -0,0 +1,225 @@
+// Copyright 2015-2017 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Server cookies, which authenticate the clients of a server, RFC 7873
+
+use std::fmt;
+use std::net::IpAddr;
+
+use rand;
+use trust_dns::rr::rdata::opt::Cookie;
+use trust_dns_proto::error::{ProtoErrorKind, ProtoResult};
+
+/// The version of the layout of the server cookie, RFC 9018
+const VERSION: u8 = 1;
+/// The length of the server cookie, the version, reserved bytes, timestamp and hash
+const SERVER_COOKIE_LEN: usize = 16;
+/// The length of the secret, the key of SipHash-2-4
+pub const SECRET_LEN: usize = 16;
+/// Cookies older than this are no longer valid, in seconds
+const MAX_AGE: u32 = 3600;
+/// Cookies from clocks ahead of this server by up to this many seconds are valid
+const MAX_SKEW: u32 = 300;
+
+/// Mints and checks server cookies with a secret of the server
+///
+/// [RFC 9018, Interoperable Domain Name System (DNS) Server Cookies, April 2021](https://tools.ietf.org/html/rfc9018)
+///
+/// ```text
+///     1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+///     0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |    Version    |                   Reserved                    |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |                           Timestamp                           |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |                             Hash                              |
+///    |                                                               |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// The hash is SipHash-2-4, keyed with the secret, over the client cookie, the version, reserved
+///  and timestamp, and the address of the client, as in RFC 9018 section 4.4. Servers which share
+///  the secret accept the cookies minted by each other, including those of other implementations.
+#[derive(Clone)]
+pub struct ServerCookies {
+    secret: [u8; SECRET_LEN],
+}
+
+impl ServerCookies {
+    /// Returns a new `ServerCookies` with the secret, which must be 16 random bytes
+    pub fn new(secret: Vec<u8>) -> ProtoResult<Self> {
+        if secret.len() != SECRET_LEN {
+            return Err(ProtoErrorKind::Msg(format!(
+                "the cookie secret must be {} bytes",
+                SECRET_LEN
+            )).into());
+        }
+
+        let mut key = [0; SECRET_LEN];
+        key.copy_from_slice(&secret);
+        Ok(ServerCookies { secret: key })
+    }
+
+    /// Returns a new `ServerCookies` with a random secret, the cookies are only valid for this
+    ///  instance
+    pub fn random() -> Self {
+        ServerCookies {
+            secret: rand::random(),
+        }
+    }
+
+    /// Returns the cookie for the client, with a fresh server cookie
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - the client cookie of the request
+    /// * `client_ip` - the address of the client
+    /// * `now` - the current time, in seconds since the Unix epoch
+    pub fn mint(&self, client: &[u8; 8], client_ip: IpAddr, now: u32) -> ProtoResult<Cookie> {
+        let mut server = Vec::with_capacity(SERVER_COOKIE_LEN);
+        server.extend_from_slice(&[VERSION, 0, 0, 0]);
+        server.extend_from_slice(&to_be_bytes(now));
+
+        let hash = self.hash(client, &server, client_ip);
+        server.extend_from_slice(&hash);
+
+        Ok(Cookie::new(*client, server))
+    }
+
+    /// Returns true if the server cookie was minted by this server for the client, and has not
+    ///  expired
+    ///
+    /// # Arguments
+    ///
+    /// * `cookie` - the cookie of the request
+    /// * `client_ip` - the address of the client
+    /// * `now` - the current time, in seconds since the Unix epoch
+    pub fn is_valid(&self, cookie: &Cookie, client_ip: IpAddr, now: u32) -> bool {
+        let server = cookie.server();
+        if server.len() != SERVER_COOKIE_LEN || server[0] != VERSION {
+            return false;
+        }
+
+        let timestamp = from_be_bytes(&server[4..8]);
+        if timestamp.wrapping_sub(now) as i32 > MAX_SKEW as i32
+            || now.wrapping_sub(timestamp) as i32 > MAX_AGE as i32
+        {
+            debug!("expired server cookie from {}", client_ip);
+            return false;
+        }
+
+        let hash = self.hash(cookie.client(), &server[..8], client_ip);
+        constant_time_eq(&hash, &server[8..])
+    }
+
+    fn hash(&self, client: &[u8; 8], header: &[u8], client_ip: IpAddr) -> [u8; 8] {
+        let mut tbs = Vec::with_capacity(8 + 8 + 16);
+        tbs.extend_from_slice(client);
+        tbs.extend_from_slice(header);
+        match client_ip {
+            IpAddr::V4(ip) => tbs.extend_from_slice(&ip.octets()),
+            IpAddr::V6(ip) => tbs.extend_from_slice(&ip.octets()),
+        }
+
+        siphash24(&self.secret, &tbs)
+    }
+}
+
+impl fmt::Debug for ServerCookies {
+    /// The secret is not shown
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ServerCookies")
+    }
+}
+
+fn to_be_bytes(value: u32) -> [u8; 4] {
+    [
+        (value >> 24) as u8,
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+    ]
+}
+
+fn from_be_bytes(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0, |value, byte| (value << 8) | u32::from(*byte))
+}
+
+/// SipHash-2-4 of the data, the 8 bytes of the hash are little endian
+///
+/// [SipHash: a fast short-input PRF](https://131002.net/siphash/siphash.pdf)
+fn siphash24(key: &[u8; SECRET_LEN], data: &[u8]) -> [u8; 8] {
+    let k0 = from_le_bytes(&key[..8]);
+    let k1 = from_le_bytes(&key[8..]);
+    let mut v = [
+        k0 ^ 0x736f_6d65_7073_6575,
+        k1 ^ 0x646f_7261_6e64_6f6d,
+        k0 ^ 0x6c79_6765_6e65_7261,
+        k1 ^ 0x7465_6462_7974_6573,
+    ];
+
+    let (blocks, tail) = data.split_at(data.len() - data.len() % 8);
+    for block in blocks.chunks(8) {
+        let m = from_le_bytes(block);
+        v[3] ^= m;
+        sip_round(&mut v);
+        sip_round(&mut v);
+        v[0] ^= m;
+    }
+
+    // the final block is the remaining bytes, and the length of the data in the top byte
+    let b = ((data.len() as u64) << 56) | from_le_bytes(tail);
+    v[3] ^= b;
+    sip_round(&mut v);
+    sip_round(&mut v);
+    v[0] ^= b;
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        sip_round(&mut v);
+    }
+
+    let hash = v[0] ^ v[1] ^ v[2] ^ v[3];
+    let mut bytes = [0; 8];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (hash >> (8 * i)) as u8;
+    }
+    bytes
+}
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13);
+    v[1] ^= v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16);
+    v[3] ^= v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21);
+    v[3] ^= v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17);
+    v[1] ^= v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+/// The little endian value of up to 8 bytes
+fn from_le_bytes(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, byte| (value << 8) | u64::from(*byte))
+}
+
+/// Compares without returning early, so that the time taken does not reveal the matching prefix
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
@@ -7,7 +7,7 @@
 use std;
 use std::io;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use futures::{Async, Future, Poll, Stream};
@@ -15,20 +15,28 @@ use futures::{Async, Future, Poll, Stream};
 use tokio_core;
 use tokio_core::reactor::Core;
 
-use trust_dns::error::*;
-use trust_dns::BufStreamHandle;
 use trust_dns::udp::UdpStream;
 use trust_dns::tcp::TcpStream;
 use trust_dns::serialize::binary::{BinDecodable, BinDecoder};
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "tls-openssl")]
 use trust_dns_openssl::{tls_server, TlsStream};
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "tls-openssl")]
 use trust_dns_openssl::tls_server::*;
 
+#[cfg(feature = "tls-ring")]
+use trust_dns_rustls::tls_server::{self as rustls_server, Certificate, PrivateKey, ServerConfigExt};
+#[cfg(feature = "tls-ring")]
+use trust_dns_rustls::tls_stream::tls_from_stream;
+
+#[cfg(feature = "https")]
+use trust_dns_https::https_server;
+#[cfg(feature = "https")]
+use server::https_handler::h2_handler;
+
 use authority::MessageRequest;
-use server::{Request, RequestHandler, ResponseHandle, TimeoutStream};
+use server::{Protocol, RateLimiter, Request, RequestHandler, ResponseHandle, TimeoutStream};
 
 // TODO, would be nice to have a Slab for buffers here...
 
@@ -36,6 +44,7 @@ use server::{Request, RequestHandler, ResponseHandle, TimeoutStream};
 pub struct ServerFuture<T: RequestHandler + 'static> {
     io_loop: Core,
     handler: Arc<T>,
+    rate_limiter: Option<Arc<Mutex<RateLimiter>>>,
 }
 
 impl<T: RequestHandler> ServerFuture<T> {
@@ -44,9 +53,18 @@ impl<T: RequestHandler> ServerFuture<T> {
         Ok(ServerFuture {
             io_loop: Core::new()?,
             handler: Arc::new(handler),
+            rate_limiter: None,
         })
     }
 
+    /// Limits the rate of responses to UDP requests, see `RateLimiter`
+    ///
+    /// This applies to the UDP sockets registered after it is set, responses over TCP are never
+    ///  limited as the source address of a TCP connection can not be spoofed.
+    pub fn set_rate_limiter(&mut self, rate_limiter: RateLimiter) {
+        self.rate_limiter = Some(Arc::new(Mutex::new(rate_limiter)));
+    }
+
     /// Register a UDP socket. Should be bound before calling this function.
     pub fn register_socket(&self, socket: std::net::UdpSocket) {
         debug!("registered udp: {:?}", socket);
@@ -55,12 +73,28 @@ impl<T: RequestHandler> ServerFuture<T> {
         let (buf_stream, stream_handle) = UdpStream::with_bound(socket, &self.io_loop.handle());
         //let request_stream = RequestStream::new(buf_stream, stream_handle);
         let handler = self.handler.clone();
+        let rate_limiter = self.rate_limiter.clone();
 
         // this spawns a ForEach future which handles all the requests into a Handler.
         self.io_loop.handle().spawn(
             buf_stream
                 .for_each(move |(buffer, src_addr)| {
-                    Self::handle_request(buffer, src_addr, stream_handle.clone(), handler.clone())
+                    let response_handle = match rate_limiter {
+                        Some(ref rate_limiter) => ResponseHandle::with_rate_limiter(
+                            src_addr,
+                            stream_handle.clone(),
+                            rate_limiter.clone(),
+                        ),
+                        None => ResponseHandle::new(src_addr, stream_handle.clone()),
+                    };
+
+                    Self::handle_request(
+                        buffer,
+                        src_addr,
+                        Protocol::Udp,
+                        response_handle,
+                        handler.clone(),
+                    )
                 })
                 .map_err(|e| debug!("error in UDP request_stream handler: {}", e)),
         );
@@ -110,7 +144,8 @@ impl<T: RequestHandler> ServerFuture<T> {
                                 Self::handle_request(
                                     buffer,
                                     src_addr,
-                                    stream_handle.clone(),
+                                    Protocol::Tcp,
+                                    ResponseHandle::new(src_addr, stream_handle.clone()),
                                     handler.clone(),
                                 )
                             })
@@ -144,7 +179,7 @@ impl<T: RequestHandler> ServerFuture<T> {
     ///               possible to create long-lived queries, but these should be from trusted sources
     ///               only, this would require some type of whitelisting.
     /// * `pkcs12` - certificate used to announce to clients
-    #[cfg(feature = "tls")]
+    #[cfg(feature = "tls-openssl")]
     pub fn register_tls_listener(
         &self,
         listener: std::net::TcpListener,
@@ -193,7 +228,8 @@ impl<T: RequestHandler> ServerFuture<T> {
                                         Self::handle_request(
                                             buffer,
                                             addr,
-                                            stream_handle.clone(),
+                                            Protocol::Tls,
+                                            ResponseHandle::new(addr, stream_handle.clone()),
                                             handler.clone(),
                                         )
                                     })
@@ -216,6 +252,168 @@ impl<T: RequestHandler> ServerFuture<T> {
         Ok(())
     }
 
+    /// Register a TlsListener to the Server, where TLS is provided by rustls rather than OpenSSL.
+    ///  The TlsListener should already be bound to either an IPv6 or an IPv4 address.
+    ///
+    /// To make the server more resilient to DOS issues, there is a timeout. Care should be taken
+    ///  to not make this too low depending on use cases.
+    ///
+    /// # Arguments
+    /// * `listener` - a bound TCP (needs to be on a different port from standard TCP connections) socket
+    /// * `timeout` - timeout duration of incoming requests, any connection that does not send
+    ///               requests within this time period will be closed.
+    /// * `cert_chain` - certificate chain announced to clients, the certificate of the server first
+    /// * `key` - private key of the certificate, see `trust_dns_rustls::tls_server::read_key`
+    #[cfg(feature = "tls-ring")]
+    pub fn register_rustls_listener(
+        &self,
+        listener: std::net::TcpListener,
+        timeout: Duration,
+        cert_chain: Vec<Certificate>,
+        key: PrivateKey,
+    ) -> io::Result<()> {
+        let handle = self.io_loop.handle();
+        let handler = self.handler.clone();
+        // TODO: this is an awkward interface with socketaddr...
+        let addr = listener.local_addr().expect("listener is not bound?");
+        let listener = tokio_core::net::TcpListener::from_listener(listener, &addr, &handle)
+            .expect("could not register listener");
+        debug!("registered tcp: {:?}", listener);
+
+        let tls_acceptor = rustls_server::new_acceptor(cert_chain, key)?;
+
+        // for each incoming request...
+        self.io_loop.handle().spawn(
+            listener
+                .incoming()
+                .for_each(move |(tcp_stream, src_addr)| {
+                    debug!("accepted request from: {}", src_addr);
+                    let handle = handle.clone();
+                    let stream_spawner = handle.clone();
+                    let handler = handler.clone();
+
+                    // the handshake is spawned, a failed handshake does not stop the listener
+                    stream_spawner.spawn(
+                        tls_acceptor
+                            .accept_async(tcp_stream)
+                            .map_err(|e| {
+                                io::Error::new(
+                                    io::ErrorKind::ConnectionRefused,
+                                    format!("tls error: {}", e),
+                                )
+                            })
+                            .and_then(move |tls_stream| {
+                                let (buf_stream, stream_handle) =
+                                    tls_from_stream(tls_stream, src_addr);
+                                let timeout_stream =
+                                    TimeoutStream::new(buf_stream, timeout, &handle)?;
+
+                                // and spawn to the io_loop
+                                handle.spawn(
+                                    timeout_stream
+                                        .for_each(move |(buffer, addr)| {
+                                            Self::handle_request(
+                                                buffer,
+                                                addr,
+                                                Protocol::Tls,
+                                                ResponseHandle::new(addr, stream_handle.clone()),
+                                                handler.clone(),
+                                            )
+                                        })
+                                        .map_err(move |e| {
+                                            debug!(
+                                                "error in TLS request_stream src: {:?} error: {}",
+                                                src_addr,
+                                                e
+                                            )
+                                        }),
+                                );
+
+                                Ok(())
+                            })
+                            .map_err(move |e| {
+                                debug!("error in TLS handshake src: {:?} error: {}", src_addr, e)
+                            }),
+                    );
+
+                    Ok(())
+                })
+                .map_err(|e| debug!("error in inbound tls_stream: {}", e)),
+        );
+
+        Ok(())
+    }
+
+    /// Register a listener for DNS over HTTPS, RFC 8484, to the Server. The listener should
+    ///  already be bound to either an IPv6 or an IPv4 address.
+    ///
+    /// Requests are served over HTTP/2, with TLS provided by rustls. The DNS message of a request
+    ///  is the body of a POST or the base64url `dns` parameter of a GET, of the media type
+    ///  `application/dns-message`. Responses may be cached for the minimum TTL of the answers.
+    ///
+    /// # Arguments
+    /// * `listener` - a bound TCP socket, usually on port 443
+    /// * `timeout` - timeout duration of incoming requests, any connection that does not send
+    ///               requests within this time period will be closed.
+    /// * `cert_chain` - certificate chain announced to clients, the certificate of the server first
+    /// * `key` - private key of the certificate, see `trust_dns_rustls::tls_server::read_key`
+    /// * `endpoint` - the path of the DNS query endpoint, e.g. `/dns-query`
+    #[cfg(feature = "https")]
+    pub fn register_https_listener(
+        &self,
+        listener: std::net::TcpListener,
+        timeout: Duration,
+        cert_chain: Vec<Certificate>,
+        key: PrivateKey,
+        endpoint: String,
+    ) -> io::Result<()> {
+        let handle = self.io_loop.handle();
+        let handler = self.handler.clone();
+        let endpoint = Arc::new(endpoint);
+        // TODO: this is an awkward interface with socketaddr...
+        let addr = listener.local_addr().expect("listener is not bound?");
+        let listener = tokio_core::net::TcpListener::from_listener(listener, &addr, &handle)
+            .expect("could not register listener");
+        debug!("registered https: {:?}", listener);
+
+        let tls_acceptor = https_server::new_acceptor(cert_chain, key)?;
+
+        // for each incoming request...
+        self.io_loop.handle().spawn(
+            listener
+                .incoming()
+                .for_each(move |(tcp_stream, src_addr)| {
+                    debug!("accepted https request from: {}", src_addr);
+                    let handle = handle.clone();
+                    let handler = handler.clone();
+                    let endpoint = endpoint.clone();
+
+                    // the handshake is spawned, a failed handshake does not stop the listener
+                    handle.clone().spawn(
+                        tls_acceptor
+                            .accept_async(tcp_stream)
+                            .map_err(|e| {
+                                io::Error::new(
+                                    io::ErrorKind::ConnectionRefused,
+                                    format!("tls error: {}", e),
+                                )
+                            })
+                            .and_then(move |tls_stream| {
+                                h2_handler(handler, tls_stream, src_addr, endpoint, timeout, handle)
+                            })
+                            .map_err(move |e| {
+                                debug!("error in HTTPS request_stream src: {:?} error: {}", src_addr, e)
+                            }),
+                    );
+
+                    Ok(())
+                })
+                .map_err(|e| debug!("error in inbound https_stream: {}", e)),
+        );
+
+        Ok(())
+    }
+
     /// TODO: how to do threads? should we do a bunch of listener threads and then query threads?
     /// Ideally the processing would be n-threads for recieving, which hand off to m-threads for
     ///  request handling. It would generally be the case that n <= m.
@@ -234,13 +432,13 @@ impl<T: RequestHandler> ServerFuture<T> {
         &mut self.io_loop
     }
 
-    fn handle_request(
+    pub(crate) fn handle_request(
         buffer: Vec<u8>,
         src_addr: SocketAddr,
-        stream_handle: BufStreamHandle<ClientError>,
+        protocol: Protocol,
+        response_handle: ResponseHandle,
         handler: Arc<T>,
     ) -> io::Result<()> {
-        let response_handle = ResponseHandle::new(src_addr, stream_handle);
 
         // TODO: rather than decoding the message here, this RequestStream should instead
         //       forward the request to another sender such that we could pull serialization off
@@ -249,9 +447,11 @@ impl<T: RequestHandler> ServerFuture<T> {
         let mut decoder = BinDecoder::new(&buffer);
         let message = MessageRequest::read(&mut decoder)?;
 
+        let response_handle = response_handle.exempt(&message);
         let request = Request {
             message: message,
             src: src_addr,
+            protocol: protocol,
         };
 
         info!(
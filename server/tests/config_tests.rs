@@ -23,7 +23,7 @@ use std::path::{Path, PathBuf};
 use std::net::{Ipv4Addr, Ipv6Addr};
 use std::time::Duration;
 
-use trust_dns_server::authority::ZoneType;
+use trust_dns_server::authority::{Acl, Network, ZoneType};
 use trust_dns_server::config::*;
 
 #[test]
@@ -248,4 +248,529 @@ tls_listen_port = 8853
         config.get_tls_cert().unwrap().get_path(),
         Path::new("path/to/some.pkcs12")
     );
+    assert_eq!(
+        config.get_tls_cert().unwrap().get_cert_type().unwrap(),
+        CertType::Pkcs12
+    );
+    assert_eq!(config.get_tls_cert().unwrap().get_private_key(), None);
+
+    let config: Config = "
+tls_cert = { path = \"path/to/chain.pem\", cert_type = \"pem\", private_key = \"path/to/key.pem\" }
+  "
+        .parse()
+        .unwrap();
+
+    let tls_cert = config.get_tls_cert().unwrap();
+    assert_eq!(tls_cert.get_cert_type().unwrap(), CertType::Pem);
+    assert_eq!(tls_cert.get_path(), Path::new("path/to/chain.pem"));
+    assert_eq!(tls_cert.get_private_key(), Some(Path::new("path/to/key.pem")));
+
+    let config: Config = "
+tls_cert = { path = \"path/to/some.der\", cert_type = \"der\" }
+  "
+        .parse()
+        .unwrap();
+
+    assert!(config.get_tls_cert().unwrap().get_cert_type().is_err());
+}
+
+#[test]
+fn test_parse_https() {
+    // defaults
+    let config: Config = "".parse().unwrap();
+
+    assert_eq!(config.get_https_listen_port(), None);
+    assert_eq!(config.get_https_endpoint(), "/dns-query");
+
+    let config: Config = "
+https_listen_port = 8443
+https_endpoint = \"/query\"
+  "
+        .parse()
+        .unwrap();
+
+    assert_eq!(config.get_https_listen_port(), Some(8443));
+    assert_eq!(config.get_https_endpoint(), "/query");
+}
+
+#[test]
+fn test_parse_allow_transfer() {
+    use std::net::IpAddr;
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+allow_transfer = [\"127.0.0.1\", \"::1\", \"192.0.2.0/24\"]
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Master\"
+file = \"example.net.zone\"
+"
+        .parse()
+        .unwrap();
+
+    let allow_transfer = config.get_zones()[0].get_allow_transfer().unwrap();
+    assert_eq!(
+        allow_transfer.networks(),
+        &[
+            Network::host(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))),
+            Network::host(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1))),
+            Network::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 0)), 24).unwrap(),
+        ]
+    );
+    assert!(allow_transfer.contains(&IpAddr::V4(Ipv4Addr::new(192, 0, 2, 53))));
+    assert!(!allow_transfer.contains(&IpAddr::V4(Ipv4Addr::new(192, 0, 3, 53))));
+    assert!(config.get_zones()[1].get_allow_transfer().unwrap().is_empty());
+}
+
+#[test]
+fn test_parse_allow_query() {
+    use std::net::IpAddr;
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+allow_query = [\"10.0.0.0/8\", \"2001:db8::/32\"]
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Master\"
+file = \"example.net.zone\"
+"
+        .parse()
+        .unwrap();
+
+    let allow_query = config.get_zones()[0].get_allow_query().unwrap();
+    assert!(allow_query.contains(&IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3))));
+    assert!(allow_query.contains(&IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))));
+    assert!(!allow_query.contains(&IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))));
+    assert!(!allow_query.contains(&IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb9, 0, 0, 0, 0, 0, 1))));
+
+    // all addresses by default
+    let allow_query = config.get_zones()[1].get_allow_query().unwrap();
+    assert!(allow_query.contains(&IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))));
+    assert!(allow_query.contains(&IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1))));
+}
+
+#[test]
+fn test_parse_allow_update() {
+    use std::net::IpAddr;
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+allow_update = [\"192.0.2.0/25\"]
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Master\"
+file = \"example.net.zone\"
+allow_update = true
+
+[[zones]]
+zone = \"example.org\"
+zone_type = \"Master\"
+file = \"example.org.zone\"
+"
+        .parse()
+        .unwrap();
+
+    let zones = config.get_zones();
+    assert!(zones[0].is_update_allowed());
+    let allow_update = zones[0].get_allow_update().unwrap();
+    assert!(allow_update.contains(&IpAddr::V4(Ipv4Addr::new(192, 0, 2, 127))));
+    assert!(!allow_update.contains(&IpAddr::V4(Ipv4Addr::new(192, 0, 2, 128))));
+
+    assert!(zones[1].is_update_allowed());
+    assert_eq!(zones[1].get_allow_update().unwrap(), Acl::any());
+
+    assert!(!zones[2].is_update_allowed());
+}
+
+#[test]
+fn test_parse_allow_notify() {
+    use std::net::IpAddr;
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Slave\"
+file = \"example.com.zone\"
+masters = [\"192.0.2.1\"]
+allow_notify = [\"192.0.2.0/28\"]
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Slave\"
+file = \"example.net.zone\"
+masters = [\"192.0.2.1:5353\"]
+"
+        .parse()
+        .unwrap();
+
+    let allow_notify = config.get_zones()[0].get_allow_notify().unwrap();
+    assert!(allow_notify.contains(&IpAddr::V4(Ipv4Addr::new(192, 0, 2, 15))));
+    assert!(!allow_notify.contains(&IpAddr::V4(Ipv4Addr::new(192, 0, 2, 16))));
+
+    // the masters by default
+    let allow_notify = config.get_zones()[1].get_allow_notify().unwrap();
+    assert_eq!(
+        allow_notify.networks(),
+        &[Network::host(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)))]
+    );
+}
+
+#[test]
+fn test_parse_bad_acl() {
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+allow_query = [\"192.0.2.0/33\"]
+allow_transfer = [\"example.com\"]
+"
+        .parse()
+        .unwrap();
+
+    assert!(config.get_zones()[0].get_allow_query().is_err());
+    assert!(config.get_zones()[0].get_allow_transfer().is_err());
+}
+
+#[test]
+fn test_parse_masters() {
+    use std::net::{IpAddr, SocketAddr};
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Slave\"
+file = \"example.com.zone\"
+masters = [\"127.0.0.1\", \"[::1]:5353\"]
+"
+        .parse()
+        .unwrap();
+
+    assert_eq!(config.get_zones()[0].get_zone_type(), ZoneType::Slave);
+    assert_eq!(
+        config.get_zones()[0].get_masters().unwrap(),
+        vec![
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 53),
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)), 5353),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_also_notify() {
+    use std::net::{IpAddr, SocketAddr};
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+also_notify = [\"127.0.0.1:5300\", \"::1\"]
+"
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        config.get_zones()[0].get_also_notify().unwrap(),
+        vec![
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 5300),
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)), 53),
+        ]
+    );
+    assert!(config.get_zones()[0].get_masters().unwrap().is_empty());
+}
+
+#[test]
+fn test_parse_forwarders() {
+    use std::net::{IpAddr, SocketAddr};
+
+    let config: Config = "
+[[zones]]
+zone = \".\"
+zone_type = \"Forward\"
+forwarders = [\"127.0.0.1\", \"[::1]:5353\"]
+"
+        .parse()
+        .unwrap();
+
+    assert_eq!(config.get_zones()[0].get_zone_type(), ZoneType::Forward);
+    assert_eq!(config.get_zones()[0].get_file(), None);
+    assert_eq!(
+        config.get_zones()[0].get_forwarders().unwrap(),
+        vec![
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 53),
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)), 5353),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_bad_addresses() {
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Slave\"
+file = \"example.com.zone\"
+masters = [\"127.0.0.1\", \"not.an.address\"]
+also_notify = [\"127.0.0.1:port\"]
+forwarders = [\"[::1]:5353\", \"::1:5353:\"]
+"
+        .parse()
+        .unwrap();
+
+    assert!(config.get_zones()[0].get_masters().is_err());
+    assert!(config.get_zones()[0].get_also_notify().is_err());
+    assert!(config.get_zones()[0].get_forwarders().is_err());
+    assert!(config.get_zones()[0].get_allow_notify().is_err());
+}
+
+#[test]
+fn test_parse_tsig_keys() {
+    use trust_dns::rr::Name;
+    use trust_dns::rr::rdata::tsig::TsigAlgorithm;
+
+    let config: Config = "
+[[tsig_keys]]
+name = \"key.example.com.\"
+algorithm = \"hmac-sha256\"
+secret = \"c2VjcmV0\"
+
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+tsig_keys = [\"key.example.com.\"]
+transfer_tsig_keys = [\"key.example.com.\"]
+"
+        .parse()
+        .unwrap();
+
+    let key = &config.get_tsig_keys()[0];
+    assert_eq!(key.get_name().unwrap(), Name::parse("key.example.com.", None).unwrap());
+    assert_eq!(key.get_algorithm().unwrap(), TsigAlgorithm::HmacSha256);
+    assert_eq!(key.get_secret().unwrap(), b"secret".to_vec());
+    assert!(key.to_signer().is_ok());
+
+    assert_eq!(config.get_zones()[0].get_tsig_keys(), &["key.example.com.".to_string()]);
+    assert_eq!(
+        config.get_zones()[0].get_transfer_tsig_keys(),
+        &["key.example.com.".to_string()]
+    );
+}
+
+#[test]
+fn test_parse_nsec3() {
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+enable_dnssec = true
+
+[zones.nsec3]
+salt = \"aabbccdd\"
+iterations = 5
+opt_out = true
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Master\"
+file = \"example.net.zone\"
+enable_dnssec = true
+
+[zones.nsec3]
+salt = \"-\"
+"
+        .parse()
+        .unwrap();
+
+    let nsec3 = config.get_zones()[0].get_nsec3().expect("nsec3 not parsed");
+    assert_eq!(nsec3.get_salt().unwrap(), vec![0xAA, 0xBB, 0xCC, 0xDD]);
+    assert_eq!(nsec3.get_iterations(), 5);
+    assert!(nsec3.is_opt_out());
+
+    let nsec3param = nsec3.to_nsec3param().unwrap();
+    assert_eq!(nsec3param.salt(), &[0xAA, 0xBB, 0xCC, 0xDD]);
+    assert_eq!(nsec3param.iterations(), 5);
+    assert!(nsec3param.opt_out());
+
+    let nsec3 = config.get_zones()[1].get_nsec3().expect("nsec3 not parsed");
+    assert!(nsec3.get_salt().unwrap().is_empty());
+    assert_eq!(nsec3.get_iterations(), 0);
+    assert!(!nsec3.is_opt_out());
+}
+
+#[test]
+fn test_parse_signature_times() {
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+enable_dnssec = true
+signature_validity = 1209600
+signature_inception = 600
+signature_refresh = 259200
+signature_jitter = 7200
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Master\"
+file = \"example.net.zone\"
+enable_dnssec = true
+"
+        .parse()
+        .unwrap();
+
+    let zone = &config.get_zones()[0];
+    assert_eq!(zone.get_signature_validity(), Duration::from_secs(1209600));
+    assert_eq!(zone.get_signature_inception(), Some(Duration::from_secs(600)));
+    assert_eq!(zone.get_signature_refresh(), Some(Duration::from_secs(259200)));
+    assert_eq!(zone.get_signature_jitter(), Some(Duration::from_secs(7200)));
+
+    let zone = &config.get_zones()[1];
+    assert_eq!(zone.get_signature_validity(), Duration::from_secs(30 * 86400));
+    assert_eq!(zone.get_signature_inception(), None);
+    assert_eq!(zone.get_signature_refresh(), None);
+    assert_eq!(zone.get_signature_jitter(), None);
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_parse_key_timing() {
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+enable_dnssec = true
+
+[[zones.keys]]
+key_path = \"zsk.pem\"
+algorithm = \"RSASHA256\"
+is_zone_signing_key = true
+is_key_signing_key = false
+publish = \"1970-01-01T00:16:40Z\"
+activate = \"1970-01-01T00:33:20Z\"
+retire = \"1970-01-01T01:50:00+01:00\"
+remove = \"1970-01-01T01:06:40Z\"
+
+[[zones.keys]]
+key_path = \"ksk.pem\"
+algorithm = \"RSASHA256\"
+is_zone_signing_key = true
+
+[[zones.keys]]
+key_path = \"bad.pem\"
+algorithm = \"RSASHA256\"
+is_zone_signing_key = true
+activate = \"1970-01-01T00:33:20Z\"
+retire = \"1970-01-01T00:16:40Z\"
+
+[[zones.keys]]
+key_path = \"bad.pem\"
+algorithm = \"RSASHA256\"
+is_zone_signing_key = true
+publish = \"yesterday\"
+"
+        .parse()
+        .unwrap();
+
+    let keys = config.get_zones()[0].get_keys();
+    assert!(!keys[0].is_key_signing_key());
+    let timing = keys[0].timing().unwrap();
+    assert_eq!(timing.publish(), Some(1000));
+    assert_eq!(timing.activate(), Some(2000));
+    assert_eq!(timing.retire(), Some(3000));
+    assert_eq!(timing.remove(), Some(4000));
+
+    // the defaults, a KSK which is always published and active
+    assert!(keys[1].is_key_signing_key());
+    let timing = keys[1].timing().unwrap();
+    assert_eq!(timing.publish(), None);
+    assert_eq!(timing.remove(), None);
+
+    assert!(keys[2].timing().is_err());
+    assert!(keys[3].timing().is_err());
+}
+
+#[test]
+fn test_parse_rate_limit() {
+    use trust_dns_server::server::RateLimitOpts;
+
+    let config: Config = "
+[rate_limit]
+responses_per_second = 10
+slip = 0
+log_only = true
+max_table_size = 1000
+"
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        config.get_rate_limit().unwrap().to_opts().unwrap(),
+        RateLimitOpts {
+            responses_per_second: 10,
+            slip: 0,
+            log_only: true,
+            max_table_size: 1000,
+            ..RateLimitOpts::default()
+        }
+    );
+
+    let config: Config = "".parse().unwrap();
+    assert!(config.get_rate_limit().is_none());
+
+    let config: Config = "
+[rate_limit]
+responses_per_second = 0
+"
+        .parse()
+        .unwrap();
+    assert!(config.get_rate_limit().unwrap().to_opts().is_err());
+}
+
+#[test]
+fn test_parse_cookies() {
+    let config: Config = "
+cookie_secret = \"AAECAwQFBgcICQoLDA0ODw==\"
+"
+        .parse()
+        .unwrap();
+
+    // servers sharing the secret accept the cookies of each other
+    let first = config.get_server_cookies().unwrap().unwrap();
+    let second = config.get_server_cookies().unwrap().unwrap();
+    let client_ip = "192.0.2.1".parse().unwrap();
+    let cookie = first.mint(&[1; 8], client_ip, 1_000_000).unwrap();
+    assert!(second.is_valid(&cookie, client_ip, 1_000_000));
+
+    // by default with a random secret
+    let config: Config = "".parse().unwrap();
+    let random = config.get_server_cookies().unwrap().unwrap();
+    assert!(!random.is_valid(&cookie, client_ip, 1_000_000));
+
+    let config: Config = "enable_cookies = false".parse().unwrap();
+    assert!(config.get_server_cookies().unwrap().is_none());
+
+    let config: Config = "cookie_secret = \"AAECAw==\"".parse().unwrap();
+    assert!(config.get_server_cookies().is_err());
+
+    let config: Config = "cookie_secret = \"AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=\""
+        .parse()
+        .unwrap();
+    assert!(config.get_server_cookies().is_err());
 }
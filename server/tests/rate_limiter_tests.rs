@@ -0,0 +1,337 @@
+extern crate trust_dns;
+extern crate trust_dns_server;
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use trust_dns::op::{Edns, Message, MessageType, Query, ResponseCode};
+use trust_dns::rr::{Name, RData, Record, RecordType};
+use trust_dns::rr::rdata::SOA;
+use trust_dns::rr::rdata::opt::{Cookie, EdnsOption};
+use trust_dns::serialize::binary::{BinDecodable, BinEncodable};
+
+use trust_dns_server::authority::MessageRequest;
+use trust_dns_server::server::{RateLimitAction, RateLimitOpts, RateLimiter, ServerCookies};
+use trust_dns_server::server::rate_limiter::truncate;
+
+fn response(name: &str, response_code: ResponseCode) -> Vec<u8> {
+    let name = Name::parse(name, None).unwrap();
+    let mut message = Message::new();
+    message
+        .set_id(10)
+        .set_message_type(MessageType::Response)
+        .set_response_code(response_code)
+        .add_query(Query::query(name.clone(), RecordType::A))
+        .add_answer(Record::from_rdata(
+            name,
+            86400,
+            RecordType::A,
+            RData::A("192.0.2.1".parse().unwrap()),
+        ));
+
+    message.to_bytes().unwrap()
+}
+
+/// A response without answers, with the SOA of the zone in the authorities
+fn negative_response(name: &str, response_code: ResponseCode) -> Vec<u8> {
+    let zone = Name::parse("example.com.", None).unwrap();
+    let mut message = Message::new();
+    message
+        .set_id(10)
+        .set_message_type(MessageType::Response)
+        .set_response_code(response_code)
+        .add_query(Query::query(Name::parse(name, None).unwrap(), RecordType::A))
+        .add_name_server(Record::from_rdata(
+            zone.clone(),
+            3600,
+            RecordType::SOA,
+            RData::SOA(SOA::new(
+                Name::parse("ns.example.com.", None).unwrap(),
+                Name::parse("hostmaster.example.com.", None).unwrap(),
+                1,
+                7200,
+                3600,
+                1209600,
+                3600,
+            )),
+        ));
+
+    message.to_bytes().unwrap()
+}
+
+fn addr(addr: &str) -> IpAddr {
+    addr.parse().unwrap()
+}
+
+fn limiter(responses_per_second: u32, slip: u32) -> RateLimiter {
+    RateLimiter::new(RateLimitOpts {
+        responses_per_second: responses_per_second,
+        slip: slip,
+        ..RateLimitOpts::default()
+    })
+}
+
+#[test]
+fn test_limits_after_burst() {
+    let mut limiter = limiter(2, 0);
+    let response = response("www.example.com.", ResponseCode::NoError);
+    let now = Instant::now();
+
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Send);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Send);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Drop);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Drop);
+
+    // a token is credited every half second
+    let now = now + Duration::from_millis(500);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Send);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Drop);
+
+    // the bucket refills to at most the burst
+    let now = now + Duration::from_secs(60);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Send);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Send);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Drop);
+}
+
+#[test]
+fn test_slip() {
+    let mut limiter = limiter(1, 2);
+    let response = response("www.example.com.", ResponseCode::NoError);
+    let now = Instant::now();
+
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Send);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Drop);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Slip);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Drop);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Slip);
+}
+
+#[test]
+fn test_keyed_by_network() {
+    let mut limiter = limiter(1, 0);
+    let response = response("www.example.com.", ResponseCode::NoError);
+    let now = Instant::now();
+
+    assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Send);
+
+    // the same /24
+    assert_eq!(limiter.check(addr("192.0.2.200"), &response, now), RateLimitAction::Drop);
+
+    // other networks have their own accounts
+    assert_eq!(limiter.check(addr("192.0.3.1"), &response, now), RateLimitAction::Send);
+    assert_eq!(limiter.check(addr("2001:db8::1"), &response, now), RateLimitAction::Send);
+
+    // the same /56
+    assert_eq!(limiter.check(addr("2001:db8:0:ff::1"), &response, now), RateLimitAction::Drop);
+    assert_eq!(limiter.check(addr("2001:db8:0:100::1"), &response, now), RateLimitAction::Send);
+}
+
+#[test]
+fn test_keyed_by_response() {
+    let mut limiter = limiter(1, 0);
+    let now = Instant::now();
+    let www = response("www.example.com.", ResponseCode::NoError);
+
+    assert_eq!(limiter.check(addr("192.0.2.1"), &www, now), RateLimitAction::Send);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &www, now), RateLimitAction::Drop);
+
+    // names are compared case insensitively
+    let upper = response("WWW.example.com.", ResponseCode::NoError);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &upper, now), RateLimitAction::Drop);
+
+    let mail = response("mail.example.com.", ResponseCode::NoError);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &mail, now), RateLimitAction::Send);
+
+    let nx = response("www.example.com.", ResponseCode::NXDomain);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &nx, now), RateLimitAction::Send);
+}
+
+#[test]
+fn test_negative_keyed_by_zone() {
+    let mut limiter = limiter(1, 0);
+    let now = Instant::now();
+
+    // random names of the zone share the account of its NXDOMAIN responses
+    let nx = negative_response("random-1.example.com.", ResponseCode::NXDomain);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &nx, now), RateLimitAction::Send);
+    let nx = negative_response("random-2.example.com.", ResponseCode::NXDomain);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &nx, now), RateLimitAction::Drop);
+
+    // and of its NODATA responses
+    let no_data = negative_response("www.example.com.", ResponseCode::NoError);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &no_data, now), RateLimitAction::Send);
+    let no_data = negative_response("mail.example.com.", ResponseCode::NoError);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &no_data, now), RateLimitAction::Drop);
+
+    // answers are still accounted by name
+    let www = response("www.example.com.", ResponseCode::NoError);
+    assert_eq!(limiter.check(addr("192.0.2.1"), &www, now), RateLimitAction::Send);
+}
+
+#[test]
+fn test_log_only() {
+    let mut limiter = RateLimiter::new(RateLimitOpts {
+        responses_per_second: 1,
+        log_only: true,
+        ..RateLimitOpts::default()
+    });
+    let response = response("www.example.com.", ResponseCode::NoError);
+    let now = Instant::now();
+
+    for _ in 0..10 {
+        assert_eq!(limiter.check(addr("192.0.2.1"), &response, now), RateLimitAction::Send);
+    }
+}
+
+#[test]
+fn test_forgets_after_window() {
+    let mut limiter = RateLimiter::new(RateLimitOpts {
+        window: Duration::from_secs(15),
+        ..RateLimitOpts::default()
+    });
+    let now = Instant::now();
+
+    limiter.check(
+        addr("192.0.2.1"),
+        &response("www.example.com.", ResponseCode::NoError),
+        now,
+    );
+    assert_eq!(limiter.len(), 1);
+
+    limiter.check(
+        addr("192.0.2.1"),
+        &response("mail.example.com.", ResponseCode::NoError),
+        now + Duration::from_secs(16),
+    );
+    assert_eq!(limiter.len(), 1);
+}
+
+#[test]
+fn test_evicts_oldest_when_full() {
+    let mut limiter = RateLimiter::new(RateLimitOpts {
+        max_table_size: 16,
+        ..RateLimitOpts::default()
+    });
+    let now = Instant::now();
+
+    for i in 0..16 {
+        limiter.check(
+            addr("192.0.2.1"),
+            &response(&format!("host-{}.example.com.", i), ResponseCode::NoError),
+            now + Duration::from_millis(i * 10),
+        );
+    }
+    assert_eq!(limiter.len(), 16);
+
+    // all are within the window, the oldest are forgotten to make room
+    let later = now + Duration::from_secs(1);
+    limiter.check(
+        addr("192.0.2.1"),
+        &response("www.example.com.", ResponseCode::NoError),
+        later,
+    );
+    assert!(limiter.len() <= 16);
+    assert!(limiter.len() > 8);
+
+    // the table never grows beyond the limit
+    for i in 0..100 {
+        limiter.check(
+            addr("192.0.2.1"),
+            &response(&format!("other-{}.example.com.", i), ResponseCode::NoError),
+            later,
+        );
+        assert!(limiter.len() <= 16);
+    }
+}
+
+#[test]
+fn test_truncate() {
+    let response = response("www.example.com.", ResponseCode::NoError);
+    let slip = Message::from_bytes(&truncate(&response).unwrap()).unwrap();
+
+    assert_eq!(slip.id(), 10);
+    assert_eq!(slip.message_type(), MessageType::Response);
+    assert!(slip.truncated());
+    assert_eq!(slip.queries().len(), 1);
+    assert_eq!(
+        *slip.queries()[0].name(),
+        Name::parse("www.example.com.", None).unwrap()
+    );
+    assert!(slip.answers().is_empty());
+    assert!(slip.name_servers().is_empty());
+    assert!(slip.additionals().is_empty());
+}
+
+#[test]
+fn test_truncate_keeps_edns() {
+    let name = Name::parse("www.example.com.", None).unwrap();
+    let mut edns = Edns::new();
+    edns.set_max_payload(1232);
+    edns.set_dnssec_ok(true);
+
+    let mut message = Message::new();
+    message
+        .set_id(10)
+        .set_message_type(MessageType::Response)
+        .add_query(Query::query(name.clone(), RecordType::A))
+        .add_answer(Record::from_rdata(
+            name,
+            86400,
+            RecordType::A,
+            RData::A("192.0.2.1".parse().unwrap()),
+        ))
+        .set_edns(edns);
+
+    let slip = Message::from_bytes(&truncate(&message.to_bytes().unwrap()).unwrap()).unwrap();
+    assert!(slip.truncated());
+    assert!(slip.answers().is_empty());
+
+    let edns = slip.edns().expect("the OPT should be kept");
+    assert_eq!(edns.max_payload(), 1232);
+    assert!(edns.dnssec_ok());
+}
+
+fn request(cookie: Option<Cookie>) -> Vec<u8> {
+    let mut message = Message::new();
+    message.add_query(Query::query(
+        Name::parse("www.example.com.", None).unwrap(),
+        RecordType::A,
+    ));
+
+    if let Some(cookie) = cookie {
+        let mut edns = Edns::new();
+        edns.set_option(EdnsOption::Cookie(cookie));
+        message.set_edns(edns);
+    }
+
+    message.to_bytes().unwrap()
+}
+
+#[test]
+fn test_exempt_with_valid_cookie() {
+    let cookies = Arc::new(ServerCookies::new(vec![1; 16]).unwrap());
+    let cookie = cookies.mint(&[7; 8], addr("192.0.2.1"), 1_000_000).unwrap();
+
+    let mut limiter = limiter(1, 0);
+    let valid = request(Some(cookie.clone()));
+    let valid = MessageRequest::from_bytes(&valid).unwrap();
+
+    // without the server cookies nothing is exempt
+    assert!(!limiter.is_exempt(&valid, addr("192.0.2.1"), 1_000_000));
+
+    limiter.set_server_cookies(cookies);
+    assert!(limiter.is_exempt(&valid, addr("192.0.2.1"), 1_000_000));
+
+    // the cookie is for another client
+    assert!(!limiter.is_exempt(&valid, addr("192.0.2.2"), 1_000_000));
+
+    let client_only = request(Some(Cookie::new([7; 8], Vec::new())));
+    let client_only = MessageRequest::from_bytes(&client_only).unwrap();
+    assert!(!limiter.is_exempt(&client_only, addr("192.0.2.1"), 1_000_000));
+
+    let none = request(None);
+    let none = MessageRequest::from_bytes(&none).unwrap();
+    assert!(!limiter.is_exempt(&none, addr("192.0.2.1"), 1_000_000));
+}
@@ -0,0 +1,101 @@
+extern crate trust_dns;
+extern crate trust_dns_server;
+
+use std::net::IpAddr;
+
+use trust_dns::rr::rdata::opt::Cookie;
+use trust_dns_server::server::ServerCookies;
+
+fn addr(addr: &str) -> IpAddr {
+    addr.parse().unwrap()
+}
+
+fn cookies() -> ServerCookies {
+    ServerCookies::new(vec![1; 16]).unwrap()
+}
+
+#[test]
+fn test_mint() {
+    let cookie = cookies().mint(&[7; 8], addr("192.0.2.1"), 1_000_000).unwrap();
+
+    assert_eq!(cookie.client(), &[7; 8]);
+    assert_eq!(cookie.server().len(), 16);
+
+    // version 1, reserved, and the timestamp
+    assert_eq!(&cookie.server()[..8], &[1, 0, 0, 0, 0x00, 0x0F, 0x42, 0x40]);
+}
+
+#[test]
+fn test_is_valid() {
+    let cookies = cookies();
+    let cookie = cookies.mint(&[7; 8], addr("2001:db8::1"), 1_000_000).unwrap();
+
+    assert!(cookies.is_valid(&cookie, addr("2001:db8::1"), 1_000_000));
+
+    // for another client
+    assert!(!cookies.is_valid(&cookie, addr("2001:db8::2"), 1_000_000));
+    let other_client = Cookie::new([8; 8], cookie.server().to_vec());
+    assert!(!cookies.is_valid(&other_client, addr("2001:db8::1"), 1_000_000));
+
+    // minted with another secret
+    let other = ServerCookies::new(vec![2; 16]).unwrap();
+    assert!(!other.is_valid(&cookie, addr("2001:db8::1"), 1_000_000));
+
+    // tampered with
+    let mut server = cookie.server().to_vec();
+    server[15] ^= 1;
+    let tampered = Cookie::new([7; 8], server);
+    assert!(!cookies.is_valid(&tampered, addr("2001:db8::1"), 1_000_000));
+
+    // no server cookie at all
+    let client_only = Cookie::new([7; 8], Vec::new());
+    assert!(!cookies.is_valid(&client_only, addr("2001:db8::1"), 1_000_000));
+}
+
+#[test]
+fn test_expiry() {
+    let cookies = cookies();
+    let cookie = cookies.mint(&[7; 8], addr("192.0.2.1"), 1_000_000).unwrap();
+
+    // valid for an hour
+    assert!(cookies.is_valid(&cookie, addr("192.0.2.1"), 1_000_000 + 3600));
+    assert!(!cookies.is_valid(&cookie, addr("192.0.2.1"), 1_000_000 + 3601));
+
+    // from a clock up to five minutes ahead
+    assert!(cookies.is_valid(&cookie, addr("192.0.2.1"), 1_000_000 - 300));
+    assert!(!cookies.is_valid(&cookie, addr("192.0.2.1"), 1_000_000 - 301));
+}
+
+#[test]
+fn test_rfc9018_vectors() {
+    // RFC 9018 appendix A.1 and A.2, servers sharing the secret with other implementations
+    let cookies = ServerCookies::new(vec![
+        0xe5, 0xe9, 0x73, 0xe5, 0xa6, 0xb2, 0xa4, 0x3f, 0x48, 0xe7, 0xdc, 0x84, 0x9e, 0x37, 0xbf,
+        0xcf,
+    ]).unwrap();
+    let client = [0x24, 0x64, 0xc4, 0xab, 0xcf, 0x10, 0xc9, 0x57];
+
+    let cookie = cookies.mint(&client, addr("198.51.100.100"), 1_559_731_985).unwrap();
+    assert_eq!(
+        cookie.server(),
+        &[
+            0x01, 0x00, 0x00, 0x00, 0x5c, 0xf7, 0x9f, 0x11, 0x1f, 0x81, 0x30, 0xc3, 0xee, 0xe2,
+            0x94, 0x80,
+        ]
+    );
+
+    let cookie = cookies.mint(&client, addr("198.51.100.100"), 1_559_734_385).unwrap();
+    assert_eq!(
+        cookie.server(),
+        &[
+            0x01, 0x00, 0x00, 0x00, 0x5c, 0xf7, 0xa8, 0x71, 0xd4, 0xa5, 0x64, 0xa1, 0x44, 0x2a,
+            0xca, 0x77,
+        ]
+    );
+}
+
+#[test]
+fn test_secret_len() {
+    assert!(ServerCookies::new(vec![1; 15]).is_err());
+    assert!(ServerCookies::new(vec![1; 32]).is_err());
+}
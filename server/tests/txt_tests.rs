@@ -418,3 +418,138 @@ _443._tcp.www.example.com. IN TLSA (
         assert!(false);
     }
 }
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_signed_offline() {
+    use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType};
+
+    let lexer = Lexer::new(
+        "$TTL 3600
+@   IN  SOA     ns1 hostmaster ( 1 7200 600 3600000 60 )
+    RRSIG   SOA 8 2 3600 20300101000000 20180101000000 12345 example.com. AwEAAQ==
+    NS      ns1
+    RRSIG   NS 8 2 3600 20300101000000 20180101000000 12345 example.com. AwEAAQ==
+    DNSKEY  257 3 8 AwEAAQ==
+    RRSIG   DNSKEY 8 2 3600 20300101000000 20180101000000 12345 example.com. AwEAAQ==
+    NSEC    ns1.example.com. NS SOA RRSIG NSEC DNSKEY
+    RRSIG   NSEC 8 2 60 20300101000000 20180101000000 12345 example.com. AwEAAQ==
+ns1 A       192.0.2.53
+    RRSIG   A 8 3 3600 20300101000000 20180101000000 12345 example.com. AwEAAQ==
+    NSEC    example.com. A RRSIG NSEC
+    RRSIG   NSEC 8 3 60 20300101000000 20180101000000 12345 example.com. AwEAAQ==
+",
+    );
+
+    let (origin, records) = Parser::new()
+        .parse(lexer, Some(Name::from_str("example.com.").unwrap()))
+        .expect("failed to parse signed zone");
+
+    // the RRSIGs belong to the record sets they cover
+    assert!(
+        !records
+            .keys()
+            .any(|key| key.record_type == RecordType::DNSSEC(DNSSECRecordType::RRSIG))
+    );
+
+    let authority = Authority::new(origin, records, ZoneType::Master, false, true);
+    assert!(authority.is_signed_offline());
+
+    let name = Name::from_str("ns1.example.com.").unwrap().into();
+    let insecure = authority
+        .lookup(&name, RecordType::A, false, SupportedAlgorithms::all())
+        .unwrap();
+    assert_eq!(insecure.len(), 1);
+
+    let secure = authority
+        .lookup(&name, RecordType::A, true, SupportedAlgorithms::all())
+        .unwrap();
+    assert_eq!(secure.len(), 2);
+    let rrsig = secure
+        .iter()
+        .find(|record| record.rr_type() == RecordType::DNSSEC(DNSSECRecordType::RRSIG))
+        .expect("RRSIG not found");
+    if let RData::DNSSEC(DNSSECRData::SIG(ref sig)) = *rrsig.rdata() {
+        assert_eq!(sig.type_covered(), RecordType::A);
+        assert_eq!(sig.algorithm(), Algorithm::RSASHA256);
+        assert_eq!(sig.key_tag(), 12345);
+    } else {
+        panic!("Not an RRSIG record!!!") // valid panic, test code
+    }
+
+    let dnskeys = authority
+        .lookup(
+            &Name::from_str("example.com.").unwrap().into(),
+            RecordType::DNSSEC(DNSSECRecordType::DNSKEY),
+            true,
+            SupportedAlgorithms::all(),
+        )
+        .unwrap();
+    assert_eq!(dnskeys.len(), 2);
+
+    let nsecs = authority.get_nsec_records(
+        &Name::from_str("nope.example.com.").unwrap().into(),
+        true,
+        SupportedAlgorithms::all(),
+    );
+    assert_eq!(nsecs.len(), 2);
+    assert!(
+        nsecs
+            .iter()
+            .all(|record| record.name() == &Name::from_str("example.com.").unwrap())
+    );
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_signed_offline_algorithm_mnemonics() {
+    use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType};
+
+    let lexer = Lexer::new(
+        "$TTL 3600
+@   IN  SOA     ns1 hostmaster ( 1 7200 600 3600000 60 )
+    DNSKEY  257 3 RSASHA256 AwEAAQ==
+ns1 A       192.0.2.53
+    RRSIG   A ECDSAP256SHA256 3 3600 20300101000000 20180101000000 12345 example.com. AwEAAQ==
+sub DS      12345 RSASHA1-NSEC3-SHA1 1 2BB183AF5F22588179A53B0A98631FAD1A292118
+",
+    );
+
+    let (_, records) = Parser::new()
+        .parse(lexer, Some(Name::from_str("example.com.").unwrap()))
+        .expect("failed to parse signed zone");
+
+    let rdata = |name: &str, record_type: RecordType, is_rrsig: bool| -> RData {
+        let rr_set = records
+            .get(&RrKey::new(Name::from_str(name).unwrap().into(), record_type))
+            .expect("record set not found");
+        if is_rrsig {
+            rr_set.rrsigs()[0].rdata().clone()
+        } else {
+            rr_set.records_without_rrsigs()[0].rdata().clone()
+        }
+    };
+
+    let dnskey = RecordType::DNSSEC(DNSSECRecordType::DNSKEY);
+    match rdata("example.com.", dnskey, false) {
+        RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey)) => {
+            assert_eq!(dnskey.algorithm(), Algorithm::RSASHA256)
+        }
+        rdata => panic!("Not a DNSKEY record: {:?}", rdata), // valid panic, test code
+    }
+
+    match rdata("ns1.example.com.", RecordType::A, true) {
+        RData::DNSSEC(DNSSECRData::SIG(ref sig)) => {
+            assert_eq!(sig.algorithm(), Algorithm::ECDSAP256SHA256)
+        }
+        rdata => panic!("Not an RRSIG record: {:?}", rdata), // valid panic, test code
+    }
+
+    let ds = RecordType::DNSSEC(DNSSECRecordType::DS);
+    match rdata("sub.example.com.", ds, false) {
+        RData::DNSSEC(DNSSECRData::DS(ref ds)) => {
+            assert_eq!(ds.algorithm(), Algorithm::RSASHA1NSEC3SHA1)
+        }
+        rdata => panic!("Not a DS record: {:?}", rdata), // valid panic, test code
+    }
+}
@@ -6,7 +6,7 @@
 // copied, modified, or distributed except according to those terms.
 
 #![cfg(not(windows))]
-#![cfg(feature = "tls")]
+#![cfg(feature = "tls-openssl")]
 
 extern crate chrono;
 extern crate futures;
@@ -13,8 +13,10 @@ extern crate trust_dns;
 extern crate trust_dns_proto;
 extern crate trust_dns_server;
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "tls-openssl")]
 extern crate trust_dns_openssl;
+#[cfg(feature = "tls-ring")]
+extern crate trust_dns_rustls;
 
 mod server_harness;
 
@@ -193,7 +195,7 @@ fn test_nxdomain_where_no_name_exists() {
     })
 }
 
-#[cfg(feature = "tls")]
+#[cfg(feature = "tls-openssl")]
 #[test]
 fn test_example_tls_toml_startup() {
     use std::env;
@@ -245,3 +247,49 @@ fn test_example_tls_toml_startup() {
         assert!(true);
     })
 }
+
+#[cfg(feature = "tls-ring")]
+#[test]
+fn test_example_rustls_toml_startup() {
+    use std::env;
+    use std::fs::File;
+    use std::io::*;
+    use trust_dns_rustls::TlsClientStreamBuilder;
+    use trust_dns_rustls::tls_server::Certificate;
+
+    named_test_harness("dns_over_tls_rustls.toml", move |_, tls_port| {
+        let mut cert_der = vec![];
+        let server_path = env::var("TDNS_SERVER_SRC_ROOT").unwrap_or_else(|_| ".".to_owned());
+        println!("using server src path: {}", server_path);
+
+        File::open(&format!(
+            "{}/tests/named_test_configs/sec/example.cert",
+            server_path
+        )).expect("failed to open cert")
+            .read_to_end(&mut cert_der)
+            .expect("failed to read cert");
+
+        let mut io_loop = Core::new().unwrap();
+        let addr: SocketAddr = ("127.0.0.1", tls_port)
+            .to_socket_addrs()
+            .unwrap()
+            .next()
+            .unwrap();
+        let mut tls_conn_builder = TlsClientStreamBuilder::new();
+        tls_conn_builder.add_ca(Certificate(cert_der.clone()));
+        let (stream, sender) =
+            tls_conn_builder.build(addr, "ns.example.com".to_string(), &io_loop.handle());
+        let mut client = ClientFuture::new(stream, sender, &io_loop.handle(), None);
+
+        query_a(&mut io_loop, &mut client);
+
+        // a second connection, the listener continues after each handshake
+        let mut tls_conn_builder = TlsClientStreamBuilder::new();
+        tls_conn_builder.add_ca(Certificate(cert_der));
+        let (stream, sender) =
+            tls_conn_builder.build(addr, "ns.example.com".to_string(), &io_loop.handle());
+        let mut client = ClientFuture::new(stream, sender, &io_loop.handle(), None);
+
+        query_a(&mut io_loop, &mut client);
+    })
+}